@@ -0,0 +1,3708 @@
+//! Cryptanalysis of Playfair ciphertexts without the key.
+//!
+//! The `crack` subcommand searches the key tables from a shuffled table,
+//! making small changes such as swapping two cells, two rows or two columns
+//! or mirroring the table. The `hill` solver keeps a change when the
+//! decryption reads at least as much like English. The `anneal` solver
+//! (simulated annealing) also keeps worse tables, less often as a
+//! temperature cools, so the search can leave a local optimum; hill climbing
+//! alone rarely gets out of one for Playfair. The `genetic` solver evolves
+//! a population of tables instead, breeding children from the fittest
+//! tables and changing some of them at random. The `beam` solver draws
+//! nothing at random: it keeps the best few tables of every step, so a run
+//! is repeated exactly.
+//!
+//! `hill` and `anneal` walk by the `Solver` trait of the `solver` module,
+//! which further climbing solvers implement to be picked by `--solver`.
+//!
+//! A decryption is scored by the log probabilities of its quadgrams, the
+//! overlapping runs of four letters, counted in running text, or of its
+//! bigrams. Tables for English, French, German, Spanish and Italian are
+//! built into the binary (`ngrams/*.txt.gz`) and picked by `--lang`; the
+//! scoring itself knows nothing of the language. `--stats` reads another
+//! table in the same form, plain or gzipped. Digram ciphers need a few
+//! hundred letters of ciphertext to be cracked reliably.
+//!
+//! The `beam` and `genetic` solvers score whole batches of tables at a time,
+//! which `--gpu` hands to a compute shader (see the `gpu` module).
+//!
+//! With `--crib`, the search starts from the part of the table the cribs
+//! rebuild (see the `cribs` module), and every crib a table breaks costs it
+//! `CRIB_PENALTY`. When the cribs leave at most `MAX_COMPLETIONS` tables,
+//! all of them are scored first and the search only runs if none reads as
+//! the language.
+//!
+//! `--progress` reports the tables tried per second, the best score so far
+//! and the start of its decryption to stderr every `--progress-interval`
+//! seconds, as text or as one JSON object per line.
+//!
+//! `--checkpoint` writes the state of the restarts to a file now and then,
+//! from which `--resume` carries on (see the `checkpoint` module).
+//!
+//! Tables whose rows and columns are cyclic shifts of each other encrypt
+//! alike, so every solver compares tables by their canonical form, shifted
+//! until symbol 0 sits in the top left cell, and never scores a table
+//! equivalent to one it just scored: the change that undoes itself, the
+//! child that copies its parent or the neighbour two tables of a beam
+//! share. Mirrored tables are not equivalent, as they turn the rows and
+//! columns the other way. `crack` reports how many tables it skipped.
+//!
+//! `identify` tells which built-in language a decryption reads like, by
+//! the quadgrams of every language, so `crack` can name the language of
+//! what it found.
+
+use crate::analyze;
+use crate::checkpoint::{self, Checkpoint, Climb, Generation, Restart};
+use crate::compare;
+use crate::config;
+use crate::crackview;
+use crate::cribs::{self, Crib, PartialKeySquare};
+use crate::error::Error;
+use crate::fitness;
+use crate::gpu;
+use crate::keygen::{self, Rng};
+use crate::report::Report;
+use crate::solver;
+use crate::structure;
+use crate::{KeySquare, Variant};
+use clap::ArgMatches;
+use flate2::read::GzDecoder;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// The built-in counts of every language, gzipped: the most frequent
+/// quadgrams, one `TION 145752` per line, and all bigrams. The languages
+/// besides English are built in by their `lang-*` features.
+const NGRAMS: &[(Language, &[u8], &[u8])] = &[
+    (
+        Language::English,
+        include_bytes!("ngrams/en-quadgrams.txt.gz"),
+        include_bytes!("ngrams/en-bigrams.txt.gz"),
+    ),
+    #[cfg(feature = "lang-fr")]
+    (
+        Language::French,
+        include_bytes!("ngrams/fr-quadgrams.txt.gz"),
+        include_bytes!("ngrams/fr-bigrams.txt.gz"),
+    ),
+    #[cfg(feature = "lang-de")]
+    (
+        Language::German,
+        include_bytes!("ngrams/de-quadgrams.txt.gz"),
+        include_bytes!("ngrams/de-bigrams.txt.gz"),
+    ),
+    #[cfg(feature = "lang-es")]
+    (
+        Language::Spanish,
+        include_bytes!("ngrams/es-quadgrams.txt.gz"),
+        include_bytes!("ngrams/es-bigrams.txt.gz"),
+    ),
+    #[cfg(feature = "lang-it")]
+    (
+        Language::Italian,
+        include_bytes!("ngrams/it-quadgrams.txt.gz"),
+        include_bytes!("ngrams/it-bigrams.txt.gz"),
+    ),
+];
+
+/// The built-in English words, gzipped: the most frequent ones, one per line
+const WORDS: &[u8] = include_bytes!("ngrams/en-words.txt.gz");
+
+/// Score per n-gram a decryption made only of words gains at the final
+/// ranking, about what pronounceable gibberish scores above English
+const WORD_WEIGHT: f64 = 1.0;
+
+/// Most of the best tables of a search that are ranked by their words
+const CANDIDATES: usize = 10;
+
+/// The lengths of n-grams a table may count
+const NGRAM_LENGTHS: std::ops::RangeInclusive<usize> = 2..=4;
+
+/// Letter index marking a symbol that is not a letter from A to Z
+const NOT_A_LETTER: u8 = 26;
+
+/// How many letters of the best decryption a progress report shows
+const PREVIEW_LETTERS: usize = 40;
+
+/// Fewest ciphertext letters worth searching
+const MIN_LETTERS: usize = 40;
+
+/// How many changes a restart tries between looks at whether to stop
+const STOP_CHECK_INTERVAL: usize = 4096;
+
+/// Score a table loses for every crib it breaks, about as much as a few
+/// unlikely quadgrams, so the search can still leave a wrong start
+const CRIB_PENALTY: f64 = 20.0;
+
+/// Most tables left by the cribs that are all scored instead of searched
+const MAX_COMPLETIONS: usize = 100_000;
+
+/// How far from the score of the language towards the score of random
+/// letters a decryption may fall and still be taken for the language
+const ENGLISH_MARGIN: f64 = 0.3;
+
+/// Temperature of the search at the first change of a restart, unless
+/// the preset for the length of the ciphertext or `--temperature` sets
+/// another
+pub const START_TEMPERATURE: f64 = 25.0;
+
+/// Temperature the geometric schedule cools down to at the last change,
+/// unless `--cooling-factor` sets how fast it falls
+const END_TEMPERATURE: f64 = 0.1;
+
+/// How many worse neighbours adaptive cooling counts before it adjusts the
+/// temperature
+const ADAPT_INTERVAL: usize = 500;
+
+/// The share of worse neighbours adaptive cooling keeps at the first
+/// change of a restart, falling linearly to none at the last
+const ADAPT_ACCEPTANCE: f64 = 0.2;
+
+/// How much adaptive cooling raises or lowers the temperature at a time
+const ADAPT_STEP: f64 = 1.1;
+
+/// Enum to represent how the temperature falls over a restart
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cooling {
+    /// Falls by the same amount at every change, down to zero
+    Linear,
+    /// Falls by the same factor at every change, down to the end
+    /// temperature
+    Geometric,
+    /// Falls linearly, raised or lowered as it goes so that the share of
+    /// worse neighbours kept falls from `ADAPT_ACCEPTANCE` to none
+    Adaptive,
+}
+
+impl Cooling {
+    /// Parses the name of a cooling, as `--cooling` and the config file
+    /// give it.
+    pub fn parse(name: &str) -> Result<Cooling, Error> {
+        match name {
+            "linear" => Ok(Cooling::Linear),
+            "geometric" => Ok(Cooling::Geometric),
+            "adaptive" => Ok(Cooling::Adaptive),
+            _ => Err(Error::Usage(format!(
+                "unknown cooling '{}': expected linear, geometric or adaptive",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the name `--cooling` picks the cooling by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Cooling::Linear => "linear",
+            Cooling::Geometric => "geometric",
+            Cooling::Adaptive => "adaptive",
+        }
+    }
+}
+
+/// The temperature schedule of simulated annealing
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Temperature at the first change, in log10 probability
+    pub start: f64,
+    /// Temperature at the last change of geometric cooling
+    pub end: f64,
+    /// How the temperature falls
+    pub cooling: Cooling,
+    /// How many changes without a better table start the schedule over
+    /// from its first temperature, cooling over the changes left, if any
+    pub reheat: Option<usize>,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            start: START_TEMPERATURE,
+            end: END_TEMPERATURE,
+            cooling: Cooling::Linear,
+            reheat: None,
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the temperature after a fraction of the changes of a restart.
+    fn temperature(&self, progress: f64) -> f64 {
+        match self.cooling {
+            Cooling::Linear | Cooling::Adaptive => self.start * (1.0 - progress),
+            Cooling::Geometric => self.start * (self.end / self.start).powf(progress),
+        }
+    }
+}
+
+/// Enum to represent the schedules `--schedule` picks for the length of a
+/// ciphertext
+///
+/// A change to the table moves the score by more for a longer ciphertext,
+/// as it changes more of its n-grams, so a longer one is searched hotter.
+/// A short one gets stuck more often, and starts over hot when a restart
+/// stalls.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Preset {
+    /// Fewer than 300 letters
+    Short,
+    /// From 300 to 600 letters
+    Medium,
+    /// More than 600 letters
+    Long,
+}
+
+impl Preset {
+    /// Parses the name of a preset, or `auto` for the preset of the length
+    /// of the ciphertext.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name `--schedule` or the config file gives.
+    /// * `letters` - The letters of the ciphertext.
+    pub fn parse(name: &str, letters: usize) -> Result<Preset, Error> {
+        match name {
+            "auto" => Ok(Preset::of(letters)),
+            "short" => Ok(Preset::Short),
+            "medium" => Ok(Preset::Medium),
+            "long" => Ok(Preset::Long),
+            _ => Err(Error::Usage(format!(
+                "unknown schedule '{}': expected auto, short, medium or long",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the preset for a ciphertext of a number of letters.
+    pub fn of(letters: usize) -> Preset {
+        match letters {
+            0..300 => Preset::Short,
+            300..=600 => Preset::Medium,
+            _ => Preset::Long,
+        }
+    }
+
+    /// Returns the schedule of the preset for restarts of a number of
+    /// changes.
+    pub fn schedule(self, iterations: usize) -> Schedule {
+        match self {
+            Preset::Short => Schedule {
+                start: 12.0,
+                reheat: Some((iterations / 4).max(1)),
+                ..Schedule::default()
+            },
+            Preset::Medium => Schedule {
+                start: 20.0,
+                ..Schedule::default()
+            },
+            Preset::Long => Schedule {
+                start: 40.0,
+                ..Schedule::default()
+            },
+        }
+    }
+}
+
+impl solver::Solver for Schedule {
+    fn name(&self) -> &'static str {
+        "anneal"
+    }
+
+    fn temperature(&self, progress: f64) -> f64 {
+        Schedule::temperature(self, progress)
+    }
+}
+
+/// Enum to represent which changes the search keeps
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Solver {
+    /// Keeps the changes that don't make the decryption worse
+    Hill,
+    /// Also keeps worse changes with a probability falling with the
+    /// temperature
+    Anneal(Schedule),
+    /// Evolves a population of tables
+    Genetic(Genetics),
+    /// Keeps the given number of best tables of every step, without chance
+    Beam(usize),
+    /// Scores every table agreeing with the cribs
+    Exact,
+    /// Climbs by a solver given to `crack_with`, `solver::SOLVERS` by
+    /// default, by its name
+    Custom(&'static str),
+}
+
+impl Solver {
+    /// Returns the name `--solver` picks the solver by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solver::Hill => "hill",
+            Solver::Anneal(_) => "anneal",
+            Solver::Genetic(_) => "genetic",
+            Solver::Beam(_) => "beam",
+            Solver::Exact => "exact",
+            Solver::Custom(name) => name,
+        }
+    }
+
+    /// Returns the climbing solver that `hill`, `anneal` and the custom
+    /// solvers walk by, or `None` for the solvers of whole sets of tables
+    /// and for a custom solver missing from `solvers`.
+    pub fn climber<'a>(
+        &'a self,
+        solvers: &[&'a dyn solver::Solver],
+    ) -> Option<&'a dyn solver::Solver> {
+        match self {
+            Solver::Hill => Some(&solver::Hill),
+            Solver::Anneal(schedule) => Some(schedule),
+            Solver::Custom(name) => solver::find(solvers, name),
+            Solver::Genetic(_) | Solver::Beam(_) | Solver::Exact => None,
+        }
+    }
+}
+
+/// Enum to represent how the progress of a search is reported
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProgressFormat {
+    /// A line of text
+    Text,
+    /// A JSON object per line
+    Json,
+}
+
+/// How often and in which form the progress of a search is reported
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Progress {
+    /// The form of the reports
+    pub format: ProgressFormat,
+    /// The time between two reports
+    pub interval: Duration,
+}
+
+/// The settings of the genetic solver
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Genetics {
+    /// How many tables every generation holds, at least 2
+    pub population: usize,
+    /// Probability that a child mixes two parents instead of copying one
+    pub crossover: f64,
+    /// Probability that a child is changed once more at random
+    pub mutation: f64,
+}
+
+impl Default for Genetics {
+    fn default() -> Self {
+        Genetics {
+            population: 100,
+            crossover: 0.7,
+            mutation: 0.5,
+        }
+    }
+}
+
+/// Enum to represent a kind of change to the table
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Move {
+    /// Swaps two cells
+    Swap,
+    /// Swaps two rows
+    Rows,
+    /// Swaps two columns
+    Columns,
+    /// Mirrors the table top to bottom, left to right or both
+    Reflect,
+}
+
+impl Move {
+    /// Returns the name `--moves` lists the change by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Move::Swap => "swap",
+            Move::Rows => "rows",
+            Move::Columns => "columns",
+            Move::Reflect => "reflect",
+        }
+    }
+
+    /// Returns how often the change is drawn, relative to the others.
+    fn weight(self) -> usize {
+        match self {
+            Move::Swap => 45,
+            Move::Rows | Move::Columns => 1,
+            Move::Reflect => 3,
+        }
+    }
+}
+
+/// Parses a comma-separated list of changes such as `swap,rows`.
+///
+/// # Returns
+///
+/// * The changes, or a usage error for an unknown change.
+pub fn parse_moves(spec: &str) -> Result<Vec<Move>, Error> {
+    let mut moves: Vec<Move> = Vec::new();
+    for name in spec.split(',').map(str::trim) {
+        let change: Move = match name {
+            "swap" => Move::Swap,
+            "rows" => Move::Rows,
+            "columns" => Move::Columns,
+            "reflect" => Move::Reflect,
+            _ => {
+                return Err(Error::Usage(format!(
+                    "invalid move '{}': expected 'swap', 'rows', 'columns' or 'reflect'",
+                    name
+                )));
+            }
+        };
+        if !moves.contains(&change) {
+            moves.push(change);
+        }
+    }
+    Ok(moves)
+}
+
+/// How the search is run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strategy {
+    /// Which changes are kept
+    pub solver: Solver,
+    /// The kinds of changes that are tried
+    pub moves: Vec<Move>,
+    /// How many changes every restart tries, children the genetic solver
+    /// breeds or tables the beam solver scores
+    pub iterations: usize,
+    /// How many times at most the search starts over from a shuffled table
+    pub restarts: usize,
+    /// How many restarts run at the same time
+    pub threads: usize,
+    /// How long the restarts may run in all, if limited
+    pub time_limit: Option<Duration>,
+    /// How many tables the restarts may try in all, if limited
+    pub max_iterations: Option<usize>,
+    /// The score per n-gram that ends the search once a restart reaches
+    /// it, instead of the score of text reading as the language
+    pub target: Option<f64>,
+    /// Whether batches of tables are scored on the GPU
+    pub gpu: bool,
+    /// The known digram pairs every table should agree with
+    pub cribs: Vec<Crib>,
+    /// The part of the table the cribs rebuild, if any
+    pub start: Option<PartialKeySquare>,
+    /// The words the best tables are finally ranked by, if any
+    pub words: Option<Dictionary>,
+    /// How many of the best tables with distinct decryptions are returned
+    pub top: usize,
+    /// The file the state of the restarts is written to, if any
+    pub checkpoint: Option<String>,
+    /// The state an earlier search stopped in, to carry on from
+    pub resume: Option<Checkpoint>,
+    /// How the progress is reported to stderr, if it is
+    pub progress: Option<Progress>,
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Strategy {
+            solver: Solver::Anneal(Schedule::default()),
+            moves: vec![Move::Swap, Move::Rows, Move::Columns, Move::Reflect],
+            iterations: 500_000,
+            restarts: 20,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            time_limit: None,
+            max_iterations: None,
+            target: None,
+            gpu: false,
+            cribs: Vec::new(),
+            start: None,
+            words: None,
+            top: 1,
+            checkpoint: None,
+            resume: None,
+            progress: None,
+        }
+    }
+}
+
+/// Enum to represent a language with built-in n-gram counts
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+    Italian,
+}
+
+impl Language {
+    /// Every language with built-in counts in this build
+    pub const ALL: &[Language] = &[
+        Language::English,
+        #[cfg(feature = "lang-fr")]
+        Language::French,
+        #[cfg(feature = "lang-de")]
+        Language::German,
+        #[cfg(feature = "lang-es")]
+        Language::Spanish,
+        #[cfg(feature = "lang-it")]
+        Language::Italian,
+    ];
+
+    /// Parses a language code such as `de`.
+    ///
+    /// # Returns
+    ///
+    /// * The language, or a usage error for an unknown code or a language
+    ///   whose counts this build leaves out.
+    pub fn parse(code: &str) -> Result<Self, Error> {
+        let language: Language = match code {
+            "en" => Language::English,
+            "fr" => Language::French,
+            "de" => Language::German,
+            "es" => Language::Spanish,
+            "it" => Language::Italian,
+            _ => {
+                return Err(Error::Usage(format!(
+                    "invalid language '{}': expected 'en', 'fr', 'de', 'es' or 'it'",
+                    code
+                )));
+            }
+        };
+        if !Language::ALL.contains(&language) {
+            return Err(Error::Usage(format!(
+                "this build has no {} statistics: build with the feature lang-{}",
+                language.name(),
+                code
+            )));
+        }
+        Ok(language)
+    }
+
+    /// Returns the name of the language, e.g. `German`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "French",
+            Language::German => "German",
+            Language::Spanish => "Spanish",
+            Language::Italian => "Italian",
+        }
+    }
+}
+
+/// A table of n-gram log probabilities
+pub struct Ngrams {
+    /// How many letters every n-gram holds
+    length: usize,
+    /// The log10 probability of every n-gram, by its base-26 index
+    scores: Vec<f64>,
+    /// Score per n-gram above which a decryption is taken for the language
+    threshold: f64,
+}
+
+impl Ngrams {
+    /// Loads the built-in counts of a language.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The language the counts were made in.
+    /// * `length` - 2 for bigrams, otherwise quadgrams.
+    pub fn builtin(language: Language, length: usize) -> Self {
+        let (_, quadgrams, bigrams) = NGRAMS
+            .iter()
+            .find(|(counted, _, _)| *counted == language)
+            .expect("only the languages of the build are parsed");
+        let counts: &[u8] = if length == 2 { bigrams } else { quadgrams };
+        decompress(counts)
+            .and_then(|text| Ngrams::parse(&text))
+            .expect("the built-in statistics are valid")
+    }
+
+    /// Reads a file of n-gram counts, plain or gzipped.
+    ///
+    /// # Returns
+    ///
+    /// * The table, an I/O error if the file can't be read, or an invalid
+    ///   input error if it isn't a valid table.
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let bytes: Vec<u8> = std::fs::read(path)
+            .map_err(|e| Error::Io(format!("cannot read statistics '{}': {}", path, e)))?;
+        decompress(&bytes)
+            .and_then(|text| Ngrams::parse(&text))
+            .map_err(|e| Error::InvalidInput(format!("invalid statistics '{}': {}", path, e)))
+    }
+
+    /// Parses n-gram counts, one `TION 145752` per line.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. N-grams missing
+    /// from the counts get a probability of a hundredth of a single
+    /// occurrence.
+    ///
+    /// # Returns
+    ///
+    /// * The table, or a description of the first invalid line if the
+    ///   n-grams aren't all 2 to 4 letters from A to Z of the same length.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut counts: Vec<(usize, f64)> = Vec::new();
+        let mut length: Option<usize> = None;
+        for (number, line) in text.lines().enumerate() {
+            let line: &str = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| format!("line {}: {}", number + 1, reason);
+            let (ngram, count) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| invalid("expected an n-gram and its count"))?;
+            let ngram: String = ngram.to_ascii_uppercase();
+            if !ngram.bytes().all(|b| b.is_ascii_uppercase()) {
+                return Err(invalid("n-grams hold letters from A to Z only"));
+            }
+            if !NGRAM_LENGTHS.contains(&ngram.len()) {
+                return Err(invalid("n-grams hold 2 to 4 letters"));
+            }
+            if *length.get_or_insert(ngram.len()) != ngram.len() {
+                return Err(invalid("all n-grams must have the same length"));
+            }
+            let count: f64 = match count.trim().parse::<f64>() {
+                Ok(count) if count > 0.0 && count.is_finite() => count,
+                _ => return Err(invalid("the count must be a positive number")),
+            };
+            let index: usize = ngram
+                .bytes()
+                .fold(0, |index, b| index * 26 + usize::from(b - b'A'));
+            counts.push((index, count));
+        }
+        let length: usize = length.ok_or_else(|| String::from("no n-grams counted"))?;
+        let total: f64 = counts.iter().map(|&(_, count)| count).sum();
+        let mut scores: Vec<f64> = vec![(0.01 / total).log10(); 26usize.pow(length as u32)];
+        for (index, count) in counts {
+            scores[index] = (count / total).log10();
+        }
+        // The language scores about the entropy of its n-grams, random
+        // letters the average of all scores
+        let language: f64 = scores.iter().map(|&score| 10f64.powf(score) * score).sum();
+        let random: f64 = scores.iter().sum::<f64>() / scores.len() as f64;
+        Ok(Ngrams {
+            length,
+            scores,
+            threshold: language + (random - language) * ENGLISH_MARGIN,
+        })
+    }
+
+    /// Returns the name of the n-grams, such as `quadgram`.
+    pub fn name(&self) -> &'static str {
+        match self.length {
+            2 => "bigram",
+            3 => "trigram",
+            _ => "quadgram",
+        }
+    }
+
+    /// Returns the score per n-gram above which a decryption is taken for
+    /// the language of the table.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Returns every n-gram with its probability, the most likely first.
+    pub fn ranked(&self) -> Vec<(String, f64)> {
+        let mut ranked: Vec<(String, f64)> = self
+            .scores
+            .iter()
+            .enumerate()
+            .map(|(index, &score)| {
+                let ngram: String = (0..self.length)
+                    .rev()
+                    .map(|place| (b'A' + (index / 26usize.pow(place as u32) % 26) as u8) as char)
+                    .collect();
+                (ngram, 10f64.powf(score))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+
+    /// Scores letters by their n-grams.
+    ///
+    /// # Arguments
+    ///
+    /// * `letters` - Letter indices from 0 (A) to 25 (Z). N-grams holding
+    ///   any other index are skipped.
+    ///
+    /// # Returns
+    ///
+    /// * The sum of the log probabilities of the n-grams.
+    pub fn score(&self, letters: &[u8]) -> f64 {
+        letters
+            .windows(self.length)
+            .filter(|window| window.iter().all(|&l| l < NOT_A_LETTER))
+            .map(|window| {
+                let index: usize = window
+                    .iter()
+                    .fold(0, |index, &l| index * 26 + usize::from(l));
+                self.scores[index]
+            })
+            .sum()
+    }
+}
+
+/// Returns the index `Ngrams::score` takes for a letter from A (0) to Z
+/// (25), or `None` for any other symbol.
+pub fn letter_index(c: char) -> Option<u8> {
+    c.is_ascii_uppercase().then(|| c as u8 - b'A')
+}
+
+/// Tells which built-in language a text reads most like.
+///
+/// Every language scores the letters of the text by its quadgrams, which
+/// are unpacked on the first call only; the text reads like the language
+/// that scores it best, if the score passes the threshold of that language.
+///
+/// # Arguments
+///
+/// * `text` - The text; only the letters from A to Z are scored.
+///
+/// # Returns
+///
+/// * The language the text reads like, if any, and the score per quadgram
+///   of every language, the best first.
+pub fn identify(text: &str) -> (Option<Language>, Vec<(Language, f64)>) {
+    let mut scores: Vec<(Language, f64, f64)> = Language::ALL
+        .iter()
+        .map(|&language| {
+            (
+                language,
+                fitness::score(text, language),
+                fitness::quadgrams(language).threshold(),
+            )
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let language: Option<Language> = scores
+        .first()
+        .filter(|&&(_, score, threshold)| score >= threshold)
+        .map(|&(language, _, _)| language);
+    (
+        language,
+        scores
+            .into_iter()
+            .map(|(language, score, _)| (language, score))
+            .collect(),
+    )
+}
+
+/// A word list decryptions are looked up in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dictionary {
+    /// The words, in table letters
+    words: HashSet<Vec<char>>,
+    /// How many letters the longest word holds
+    longest: usize,
+}
+
+impl Dictionary {
+    /// Loads the built-in English words.
+    ///
+    /// # Arguments
+    ///
+    /// * `variant` - The variant the words are converted to table letters with.
+    pub fn builtin(variant: &Variant) -> Self {
+        Dictionary::parse(
+            &decompress(WORDS).expect("the built-in words are valid"),
+            variant,
+        )
+    }
+
+    /// Reads a word list, one word per line, plain or gzipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the word list.
+    /// * `variant` - The variant the words are converted to table letters with.
+    ///
+    /// # Returns
+    ///
+    /// * The words, an I/O error if the file can't be read, or an invalid
+    ///   input error if it holds no words.
+    pub fn load(path: &str, variant: &Variant) -> Result<Self, Error> {
+        let bytes: Vec<u8> = std::fs::read(path)
+            .map_err(|e| Error::Io(format!("cannot read word list '{}': {}", path, e)))?;
+        let dictionary: Dictionary = decompress(&bytes)
+            .map(|text| Dictionary::parse(&text, variant))
+            .map_err(|e| Error::InvalidInput(format!("invalid word list '{}': {}", path, e)))?;
+        if dictionary.words.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "invalid word list '{}': no words in table letters",
+                path
+            )));
+        }
+        Ok(dictionary)
+    }
+
+    /// Parses a word list, one word per line; lines starting with `#` are
+    /// comments and symbols of no table are dropped.
+    fn parse(text: &str, variant: &Variant) -> Self {
+        let words: HashSet<Vec<char>> = text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|word| crate::table_letters(word, variant))
+            .filter(|word| !word.is_empty())
+            .collect();
+        let longest: usize = words.iter().map(Vec::len).max().unwrap_or(0);
+        Dictionary { words, longest }
+    }
+
+    /// Returns whether a run of table letters is a word of the list.
+    pub fn contains(&self, word: &[char]) -> bool {
+        self.words.contains(word)
+    }
+
+    /// Measures how much of a decryption is spelled by words of the list.
+    ///
+    /// The letters are split into the words that cover most of them. The
+    /// fillers `--strip-filler` would remove may be read or skipped, so the X
+    /// of `LETXTERS` counts as part of `LETTERS` and the one of `EXEMPT` as
+    /// part of the word.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The decryption.
+    /// * `variant` - The variant providing the filler and the strategy.
+    ///
+    /// # Returns
+    ///
+    /// * The share of the letters that are in words, from 0 to 1.
+    pub fn coverage(&self, plaintext: &str, variant: &Variant) -> f64 {
+        let letters: Vec<char> = crate::table_letters(plaintext, variant);
+        let fillers: Vec<bool> = crate::inserted_fillers(&letters, variant, &[]);
+        // The most letters words and fillers cover in every prefix of the text
+        let mut covered: Vec<usize> = vec![0; letters.len() + 1];
+        for start in 0..letters.len() {
+            let before: usize = covered[start];
+            covered[start + 1] = covered[start + 1].max(before + usize::from(fillers[start]));
+            // The ways to read on from `start`, with or without every filler
+            let mut readings: Vec<Vec<char>> = vec![Vec::new()];
+            for end in start..letters.len() {
+                let mut next: Vec<Vec<char>> = Vec::new();
+                for mut reading in readings {
+                    if fillers[end] && !reading.is_empty() {
+                        next.push(reading.clone());
+                    }
+                    reading.push(letters[end]);
+                    if self.words.contains(&reading) {
+                        covered[end + 1] = covered[end + 1].max(before + end + 1 - start);
+                    }
+                    if reading.len() < self.longest {
+                        next.push(reading);
+                    }
+                }
+                readings = next;
+                if readings.is_empty() {
+                    break;
+                }
+            }
+        }
+        covered[letters.len()] as f64 / letters.len().max(1) as f64
+    }
+}
+
+/// Unpacks gzipped counts; other bytes are read as plain text.
+fn decompress(bytes: &[u8]) -> Result<String, String> {
+    let mut text: String = String::new();
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(bytes)
+            .read_to_string(&mut text)
+            .map_err(|e| e.to_string())?;
+    } else {
+        text = String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())?;
+    }
+    Ok(text)
+}
+
+/// The best table found for a ciphertext
+pub struct Solution {
+    /// The key table
+    pub square: KeySquare,
+    /// The ciphertext decrypted with the table
+    pub plaintext: String,
+    /// The average log probability of the n-grams of the plaintext
+    pub score: f64,
+    /// The share of the letters of the plaintext in words, if ranked by a
+    /// word list
+    pub words: Option<f64>,
+    /// What the tables were ranked by: the score with the penalties of the
+    /// broken cribs, plus the share of words
+    pub fitness: f64,
+}
+
+/// The search for the table of one ciphertext
+struct Search<'a> {
+    /// The n-gram scores
+    ngrams: &'a Ngrams,
+    /// The symbols of the table, in their alphabetical fill order
+    symbols: Vec<char>,
+    /// The letter index of every symbol
+    letters: Vec<u8>,
+    /// The ciphertext as symbol indices, of even length, its pairs in order
+    ciphertext: Vec<usize>,
+    /// Where every letter of the decrypted pairs is read from, if the
+    /// ciphertext is seriated
+    reading: Option<Vec<usize>>,
+    /// Rows and columns of the table
+    shape: (usize, usize),
+    /// The cells a pair of cells decrypts to, at the cell of its first
+    /// symbol times the number of cells plus the cell of its second
+    rules: Vec<(usize, usize)>,
+    /// The distinct pairs of the ciphertext
+    digrams: Vec<Digram>,
+    /// Scores batches of tables on the GPU, if opened
+    gpu: Option<gpu::Scorer>,
+    /// Set once the GPU failed, after which batches are scored on the CPU
+    gpu_failed: AtomicBool,
+    /// The cribs and the cells they fill, as in `Strategy`
+    cribs: &'a [Crib],
+    start: Option<&'a PartialKeySquare>,
+    /// How many tables were scored
+    scored: AtomicUsize,
+    /// How many tables were not scored, as they are equivalent to a table
+    /// just scored
+    skipped: AtomicUsize,
+    /// Keeps the checkpoint of the search, if written
+    checkpoint: Option<&'a checkpoint::Writer>,
+    /// Counts the progress of the search, if reported
+    tracker: Option<&'a Tracker>,
+}
+
+/// A distinct pair of the ciphertext of a search
+struct Digram {
+    /// The symbol indices of the pair
+    symbols: (usize, usize),
+    /// Where the two letters of every pair it occurs as are read in the
+    /// decryption
+    occurrences: Vec<(usize, usize)>,
+}
+
+/// The temperature of a climbing restart beyond the schedule of its solver
+struct Thermostat {
+    /// The change the schedule started over at, when the restart last
+    /// reheated
+    since: usize,
+    /// What the temperature of the schedule is multiplied by, which
+    /// adaptive cooling raises and lowers
+    scale: f64,
+    /// The worse neighbours tried since the scale was last adjusted
+    worse: usize,
+    /// How many of them were kept
+    kept: usize,
+}
+
+impl Thermostat {
+    /// Returns how far the schedule got at a change, from 0 when it last
+    /// started to 1 at the last change.
+    fn progress(&self, change: usize, iterations: usize) -> f64 {
+        (change - self.since) as f64 / (iterations - self.since) as f64
+    }
+
+    /// Counts a neighbour tried, and once `ADAPT_INTERVAL` worse ones were,
+    /// raises the temperature if fewer of them were kept than adaptive
+    /// cooling keeps at this point, or lowers it if more were.
+    fn adapt(&mut self, delta: f64, kept: bool, progress: f64) {
+        if delta >= 0.0 {
+            return;
+        }
+        self.worse += 1;
+        self.kept += usize::from(kept);
+        if self.worse < ADAPT_INTERVAL {
+            return;
+        }
+        let target: f64 = ADAPT_ACCEPTANCE * (1.0 - progress);
+        if (self.kept as f64) < target * self.worse as f64 {
+            self.scale *= ADAPT_STEP;
+        } else {
+            self.scale /= ADAPT_STEP;
+        }
+        (self.worse, self.kept) = (0, 0);
+    }
+}
+
+/// The progress of a running search, shared by its restarts
+struct Tracker {
+    /// How and how often it is reported
+    progress: Progress,
+    /// The number of n-grams of the ciphertext, to report scores by
+    ngram_count: f64,
+    /// How many restarts there are
+    restarts: usize,
+    /// When the search started
+    started: Instant,
+    /// How many tables the restarts tried
+    tried: AtomicUsize,
+    /// How many restarts ended
+    ended: AtomicUsize,
+    /// The best table so far and its score
+    best: Mutex<Option<(Vec<usize>, f64)>>,
+    /// When the progress was last reported
+    reported: Mutex<Instant>,
+}
+
+impl<'a> Search<'a> {
+    /// Prepares the search of a ciphertext, scoring on the CPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The ciphertext symbols, of even length.
+    /// * `variant` - The variant deciding the symbols and the shape of the
+    ///   table.
+    /// * `ngrams` - The n-gram scores.
+    /// * `cribs` - The cribs every table should agree with.
+    /// * `start` - The part of the table the cribs rebuild, if any.
+    fn new(
+        ciphertext: &[char],
+        variant: &Variant,
+        ngrams: &'a Ngrams,
+        cribs: &'a [Crib],
+        start: Option<&'a PartialKeySquare>,
+    ) -> Self {
+        let symbols: Vec<char> = variant.symbols();
+        let letters: Vec<u8> = symbols
+            .iter()
+            .map(|&c| letter_index(c).unwrap_or(NOT_A_LETTER))
+            .collect();
+        // A seriated ciphertext is searched with its pairs in order, and the
+        // decrypted pairs are written back into their rows before scoring
+        let ciphertext_symbols: Vec<usize> = crate::pair_order(ciphertext, variant)
+            .iter()
+            .map(|c| symbols.iter().position(|s| s == c).unwrap())
+            .collect();
+        let reading: Option<Vec<usize>> = variant
+            .period
+            .map(|_| crate::unpair_order(&(0..ciphertext.len()).collect::<Vec<usize>>(), variant));
+        let shape: (usize, usize) = variant.alphabet.dimensions();
+        let (rows, cols) = shape;
+        let cells: usize = rows * cols;
+        let rules: Vec<(usize, usize)> = (0..cells * cells)
+            .map(|index| {
+                let (r1, c1) = (index / cells / cols, index / cells % cols);
+                let (r2, c2) = (index % cells / cols, index % cells % cols);
+                // The decryption rules of `transform_pair`
+                if r1 == r2 {
+                    (
+                        r1 * cols + (c1 + cols - 1) % cols,
+                        r2 * cols + (c2 + cols - 1) % cols,
+                    )
+                } else if c1 == c2 {
+                    (
+                        (r1 + rows - 1) % rows * cols + c1,
+                        (r2 + rows - 1) % rows * cols + c2,
+                    )
+                } else {
+                    (r1 * cols + c2, r2 * cols + c1)
+                }
+            })
+            .collect();
+        // Where the letter of every decrypted pair is read
+        let mut place: Vec<usize> = (0..ciphertext_symbols.len()).collect();
+        if let Some(reading) = &reading {
+            for (letter, &i) in reading.iter().enumerate() {
+                place[i] = letter;
+            }
+        }
+        let mut digrams: Vec<Digram> = Vec::new();
+        for (i, pair) in ciphertext_symbols.chunks(2).enumerate() {
+            let occurrence: (usize, usize) = (place[2 * i], place[2 * i + 1]);
+            match digrams
+                .iter_mut()
+                .find(|digram| digram.symbols == (pair[0], pair[1]))
+            {
+                Some(digram) => digram.occurrences.push(occurrence),
+                None => digrams.push(Digram {
+                    symbols: (pair[0], pair[1]),
+                    occurrences: vec![occurrence],
+                }),
+            }
+        }
+        Search {
+            ngrams,
+            letters,
+            ciphertext: ciphertext_symbols,
+            reading,
+            shape,
+            rules,
+            digrams,
+            symbols,
+            gpu: None,
+            gpu_failed: AtomicBool::new(false),
+            cribs,
+            start,
+            scored: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            checkpoint: None,
+            tracker: None,
+        }
+    }
+
+    /// Turns the cells of a table into its square of symbols.
+    fn square(&self, cells: &[usize]) -> KeySquare {
+        KeySquare::from_rows(
+            cells
+                .chunks(self.shape.1)
+                .map(|row| row.iter().map(|&s| self.symbols[s]).collect())
+                .collect(),
+        )
+    }
+
+    /// Shifts the rows and the columns of a table cyclically until symbol
+    /// 0 sits in the top left cell, which gives all tables that encrypt
+    /// alike the same cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - The symbol index of every cell, row by row.
+    /// * `canonical` - Filled with the cells of the shifted table.
+    fn canonical(&self, cells: &[usize], canonical: &mut Vec<usize>) {
+        let (rows, cols) = self.shape;
+        let origin: usize = cells.iter().position(|&symbol| symbol == 0).unwrap();
+        let (top, left) = (origin / cols, origin % cols);
+        canonical.clear();
+        canonical
+            .extend((0..cells.len()).map(|cell| {
+                cells[(cell / cols + top) % rows * cols + (cell % cols + left) % cols]
+            }));
+    }
+
+    /// Records how far a restart got in the checkpoint, if one is written.
+    fn save(&self, restart: usize, now: bool, state: impl FnOnce() -> Restart) {
+        if let Some(writer) = self.checkpoint {
+            writer.update(restart, now, state);
+        }
+    }
+
+    /// Adds the tables a restart tried to the progress, and reports it to
+    /// stderr if it is due.
+    ///
+    /// # Arguments
+    ///
+    /// * `tried` - The tables tried since the last call.
+    /// * `best` - The best table of the restart so far and its score.
+    fn track(&self, tried: usize, best: (&[usize], f64)) {
+        let Some(tracker) = self.tracker else {
+            return;
+        };
+        let tried: usize = tracker.tried.fetch_add(tried, Ordering::Relaxed) + tried;
+        let mut overall = tracker.best.lock().unwrap();
+        if overall.as_ref().is_none_or(|(_, score)| best.1 > *score) {
+            *overall = Some((best.0.to_vec(), best.1));
+        }
+        let mut reported = tracker.reported.lock().unwrap();
+        if reported.elapsed() < tracker.progress.interval {
+            return;
+        }
+        *reported = Instant::now();
+        let (cells, score) = overall.as_ref().unwrap();
+        let mut letters: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        self.score(cells, &mut letters);
+        let preview: String = letters
+            .iter()
+            .take(PREVIEW_LETTERS)
+            .map(|&letter| {
+                if letter < NOT_A_LETTER {
+                    (b'A' + letter) as char
+                } else {
+                    '?'
+                }
+            })
+            .collect();
+        let elapsed: f64 = tracker.started.elapsed().as_secs_f64();
+        let rate: f64 = tried as f64 / elapsed.max(1e-9);
+        let score: f64 = score / tracker.ngram_count;
+        let ended: usize = tracker.ended.load(Ordering::Relaxed);
+        match tracker.progress.format {
+            ProgressFormat::Json => eprintln!(
+                "{}",
+                json!({
+                    "elapsed": elapsed,
+                    "tables": tried,
+                    "tables_per_second": rate,
+                    "restarts_ended": ended,
+                    "restarts": tracker.restarts,
+                    "best_score": score,
+                    "preview": preview,
+                })
+            ),
+            ProgressFormat::Text => eprintln!(
+                "Progress: {:.0} s, {} tables ({:.0}/s), {} of {} restarts ended, best {:.2} per {}: {}",
+                elapsed,
+                tried,
+                rate,
+                ended,
+                tracker.restarts,
+                score,
+                self.ngrams.name(),
+                preview
+            ),
+        }
+    }
+
+    /// Adds to the counts of the tables scored and skipped.
+    fn count(&self, scored: usize, skipped: usize) {
+        self.scored.fetch_add(scored, Ordering::Relaxed);
+        self.skipped.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    /// Decrypts the ciphertext with a table and scores the result.
+    ///
+    /// The climbers score every neighbour whole; incremental rescoring of
+    /// only the n-grams a move changes is not implemented. A swap of two
+    /// cells decrypts about a third of the distinct pairs differently, which
+    /// changes half the n-grams, and a prototype rescoring only those
+    /// measured slower than this.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - The symbol index of every cell, row by row.
+    /// * `plaintext` - Filled with the letter indices of the decryption.
+    ///
+    /// # Returns
+    ///
+    /// * The n-gram score of the decryption.
+    fn score(&self, cells: &[usize], plaintext: &mut Vec<u8>) -> f64 {
+        let mut positions: Vec<usize> = vec![0; cells.len()];
+        for (cell, &symbol) in cells.iter().enumerate() {
+            positions[symbol] = cell;
+        }
+        plaintext.clear();
+        plaintext.resize(self.ciphertext.len(), 0);
+        // Every distinct pair is decrypted once, by the rules of
+        // `transform_pair` worked out for every two cells beforehand
+        for digram in &self.digrams {
+            let (first, second) = digram.symbols;
+            let (a, b) = self.rules[positions[first] * cells.len() + positions[second]];
+            let (a, b) = (self.letters[cells[a]], self.letters[cells[b]]);
+            for &(x, y) in &digram.occurrences {
+                plaintext[x] = a;
+                plaintext[y] = b;
+            }
+        }
+        self.ngrams.score(plaintext) - self.penalty(&positions)
+    }
+
+    /// Returns the score a table loses for the cribs it breaks.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - The cell of every symbol.
+    fn penalty(&self, positions: &[usize]) -> f64 {
+        if self.cribs.is_empty() {
+            return 0.0;
+        }
+        cribs::broken(self.cribs, positions, self.shape) as f64 * CRIB_PENALTY
+    }
+
+    /// Scores a batch of tables, on the GPU if it was opened.
+    ///
+    /// # Arguments
+    ///
+    /// * `tables` - The symbol index of every cell of every table.
+    /// * `plaintext` - Scratch space for the decryptions on the CPU.
+    ///
+    /// # Returns
+    ///
+    /// * The n-gram score of the decryption with every table.
+    fn score_all(&self, tables: &[Vec<usize>], plaintext: &mut Vec<u8>) -> Vec<f64> {
+        if let Some(scorer) = &self.gpu
+            && !self.gpu_failed.load(Ordering::Relaxed)
+        {
+            match scorer.score(tables) {
+                // The shader knows nothing of the cribs
+                Ok(scores) if self.cribs.is_empty() => return scores,
+                Ok(scores) => {
+                    let mut positions: Vec<usize> = vec![0; self.symbols.len()];
+                    return tables
+                        .iter()
+                        .zip(scores)
+                        .map(|(cells, score)| {
+                            for (cell, &symbol) in cells.iter().enumerate() {
+                                positions[symbol] = cell;
+                            }
+                            score - self.penalty(&positions)
+                        })
+                        .collect();
+                }
+                Err(e) => {
+                    if !self.gpu_failed.swap(true, Ordering::Relaxed) {
+                        eprintln!("Warning: {}; scoring on the CPU", e);
+                    }
+                }
+            }
+        }
+        tables
+            .iter()
+            .map(|cells| self.score(cells, plaintext))
+            .collect()
+    }
+
+    /// Lists every table one change away, in a fixed order.
+    ///
+    /// # Arguments
+    ///
+    /// * `moves` - The kinds of changes to make.
+    /// * `cells` - The table to change.
+    fn neighbours(&self, moves: &[Move], cells: &[usize]) -> Vec<Vec<usize>> {
+        let (rows, cols) = self.shape;
+        let mut tables: Vec<Vec<usize>> = Vec::new();
+        let mut push = |change: &dyn Fn(&mut Vec<usize>)| {
+            let mut table: Vec<usize> = cells.to_vec();
+            change(&mut table);
+            tables.push(table);
+        };
+        for change in moves {
+            match change {
+                Move::Swap => {
+                    for a in 0..cells.len() {
+                        for b in a + 1..cells.len() {
+                            push(&|table| table.swap(a, b));
+                        }
+                    }
+                }
+                Move::Rows => {
+                    for a in 0..rows {
+                        for b in a + 1..rows {
+                            push(&|table| {
+                                for col in 0..cols {
+                                    table.swap(a * cols + col, b * cols + col);
+                                }
+                            });
+                        }
+                    }
+                }
+                Move::Columns => {
+                    for a in 0..cols {
+                        for b in a + 1..cols {
+                            push(&|table| {
+                                for row in 0..rows {
+                                    table.swap(row * cols + a, row * cols + b);
+                                }
+                            });
+                        }
+                    }
+                }
+                Move::Reflect => {
+                    for form in 0..3 {
+                        push(&|table| reflect(self.shape, form, table));
+                    }
+                }
+            }
+        }
+        tables
+    }
+
+    /// Searches from a shuffled table, or from where a checkpoint left it.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The generator drawing the changes.
+    /// * `variant` - The variant the shuffled table is drawn for.
+    /// * `strategy` - The solver, the changes and how many are tried.
+    /// * `solver` - The climber of the solver of the strategy.
+    /// * `stop` - Ends the search early when reached.
+    /// * `restart` - The restart, counted from 1, for the checkpoint.
+    /// * `resumed` - Where the restart stopped before, if it did.
+    ///
+    /// # Returns
+    ///
+    /// * The cells of the best table and its score.
+    #[allow(clippy::too_many_arguments)]
+    fn climb(
+        &self,
+        rng: &mut Rng,
+        variant: &Variant,
+        strategy: &Strategy,
+        solver: &dyn solver::Solver,
+        stop: &Stop,
+        restart: usize,
+        resumed: Option<&Climb>,
+    ) -> (Vec<usize>, f64) {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        let (mut cells, mut score, mut best, mut best_score, first) = match resumed {
+            Some(climb) => {
+                *rng = Rng::new(climb.rng);
+                (
+                    climb.cells.clone(),
+                    climb.score,
+                    climb.best.clone(),
+                    climb.best_score,
+                    climb.iteration,
+                )
+            }
+            None => {
+                let cells: Vec<usize> = self.shuffled(rng, variant);
+                let score: f64 = self.score(&cells, &mut plaintext);
+                (cells.clone(), score, cells, score, 0)
+            }
+        };
+        let mut candidate: Vec<usize> = cells.clone();
+        let (mut canonical, mut candidate_canonical) = (Vec::new(), Vec::new());
+        self.canonical(&cells, &mut canonical);
+        let (mut scored, mut skipped): (usize, usize) = (1, 0);
+        // The change the best table was found at, or the restart resumed
+        let mut improved: usize = first;
+        let schedule: Option<&Schedule> = match &strategy.solver {
+            Solver::Anneal(schedule) => Some(schedule),
+            _ => None,
+        };
+        let mut thermostat: Thermostat = Thermostat {
+            since: 0,
+            scale: 1.0,
+            worse: 0,
+            kept: 0,
+        };
+        for i in first..strategy.iterations {
+            if i % STOP_CHECK_INTERVAL == 0 {
+                let tried: usize = if i > first { STOP_CHECK_INTERVAL } else { 0 };
+                if tried > 0 {
+                    self.track(tried, (&best, best_score));
+                }
+                let stopping: bool = stop.check(tried, best_score);
+                self.save(restart, stopping, || {
+                    Restart::Climbing(Climb {
+                        iteration: i,
+                        rng: rng.state(),
+                        cells: cells.clone(),
+                        score,
+                        best: best.clone(),
+                        best_score,
+                    })
+                });
+                if stopping {
+                    self.count(scored, skipped);
+                    return (best, best_score);
+                }
+            }
+            candidate.copy_from_slice(&cells);
+            solver.propose(rng, &strategy.moves, self.shape, &mut candidate);
+            // Such as a swap of a cell with itself, or of the two rows of a
+            // table of two
+            self.canonical(&candidate, &mut candidate_canonical);
+            if candidate_canonical == canonical {
+                skipped += 1;
+                continue;
+            }
+            scored += 1;
+            let candidate_score: f64 = self.score(&candidate, &mut plaintext);
+            let delta: f64 = candidate_score - score;
+            let progress: f64 = thermostat.progress(i, strategy.iterations);
+            let temperature: f64 = solver.temperature(progress) * thermostat.scale;
+            let kept: bool = solver.accept(rng, delta, temperature);
+            if schedule.is_some_and(|schedule| schedule.cooling == Cooling::Adaptive) {
+                thermostat.adapt(delta, kept, progress);
+            }
+            if kept {
+                std::mem::swap(&mut cells, &mut candidate);
+                std::mem::swap(&mut canonical, &mut candidate_canonical);
+                score = candidate_score;
+                if score > best_score {
+                    best.copy_from_slice(&cells);
+                    best_score = score;
+                    improved = i + 1;
+                }
+            }
+            if let Some(reheat) = schedule.and_then(|schedule| schedule.reheat)
+                && i + 1 - improved.max(thermostat.since) >= reheat
+            {
+                thermostat.since = i + 1;
+            }
+            if solver.stop(i + 1, strategy.iterations, i + 1 - improved) {
+                break;
+            }
+        }
+        self.count(scored, skipped);
+        self.save(restart, true, || Restart::Done {
+            cells: best.clone(),
+            score: best_score,
+        });
+        (best, best_score)
+    }
+
+    /// Evolves a population from shuffled tables.
+    ///
+    /// Every generation keeps its best table and breeds the others from
+    /// parents picked by tournament. A crossover copies a run of cells from
+    /// one parent and fills the other cells in the order of the second one.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The generator drawing the tables, parents and changes.
+    /// * `variant` - The variant the shuffled tables are drawn for.
+    /// * `strategy` - The changes and how many children are scored in all.
+    /// * `genetics` - The population size and the rates.
+    /// * `stop` - Ends the search early when reached.
+    /// * `restart` - The restart, counted from 1, for the checkpoint.
+    /// * `resumed` - Where the restart stopped before, if it did.
+    ///
+    /// # Returns
+    ///
+    /// * The cells of the best table and its score.
+    #[allow(clippy::too_many_arguments)]
+    fn evolve(
+        &self,
+        rng: &mut Rng,
+        variant: &Variant,
+        strategy: &Strategy,
+        genetics: &Genetics,
+        stop: &Stop,
+        restart: usize,
+        resumed: Option<&Generation>,
+    ) -> (Vec<usize>, f64) {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        let (mut population, first): (Vec<(Vec<usize>, f64)>, usize) = match resumed {
+            Some(generation) => {
+                *rng = Rng::new(generation.rng);
+                (generation.population.clone(), generation.generation)
+            }
+            None => {
+                let tables: Vec<Vec<usize>> = (0..genetics.population)
+                    .map(|_| self.shuffled(rng, variant))
+                    .collect();
+                let scores: Vec<f64> = self.score_all(&tables, &mut plaintext);
+                (tables.into_iter().zip(scores).collect(), 0)
+            }
+        };
+        let generations: usize = strategy.iterations / genetics.population;
+        let (mut scored, mut skipped): (usize, usize) = (population.len(), 0);
+        let mut canonical: Vec<usize> = Vec::new();
+        let mut stopped: bool = false;
+        for generation in first..generations {
+            let tried: usize = if generation > first {
+                genetics.population - 1
+            } else {
+                0
+            };
+            let mut best_score: f64 = f64::NEG_INFINITY;
+            if let Some((best, score)) = population.iter().max_by(|a, b| a.1.total_cmp(&b.1)) {
+                self.track(tried, (best, *score));
+                best_score = *score;
+            }
+            stopped = stop.check(tried, best_score);
+            self.save(restart, stopped, || {
+                Restart::Evolving(Generation {
+                    generation,
+                    rng: rng.state(),
+                    population: population.clone(),
+                })
+            });
+            if stopped {
+                break;
+            }
+            population.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let mut children: Vec<Vec<usize>> = Vec::with_capacity(genetics.population - 1);
+            while children.len() + 1 < genetics.population {
+                let first: &[usize] = &population[tournament(rng, &population)].0;
+                let mut child: Vec<usize> = if unit(rng) < genetics.crossover {
+                    let second: &[usize] = &population[tournament(rng, &population)].0;
+                    crossover(rng, first, second)
+                } else {
+                    first.to_vec()
+                };
+                if unit(rng) < genetics.mutation {
+                    change(rng, &strategy.moves, self.shape, &mut child);
+                }
+                children.push(child);
+            }
+            // Children equivalent to a table of the population or to an
+            // earlier child, such as copies of their parent, take its score
+            let mut known: HashMap<Vec<usize>, Option<f64>> = HashMap::new();
+            for (cells, score) in &population {
+                self.canonical(cells, &mut canonical);
+                known.insert(canonical.clone(), Some(*score));
+            }
+            let mut twins: Vec<Option<Vec<usize>>> = Vec::with_capacity(children.len());
+            let mut fresh: Vec<Vec<usize>> = Vec::new();
+            for child in &children {
+                self.canonical(child, &mut canonical);
+                if known.contains_key(&canonical) {
+                    twins.push(Some(canonical.clone()));
+                } else {
+                    known.insert(canonical.clone(), None);
+                    twins.push(None);
+                    fresh.push(child.clone());
+                }
+            }
+            scored += fresh.len();
+            skipped += children.len() - fresh.len();
+            let fresh_scores: Vec<f64> = self.score_all(&fresh, &mut plaintext);
+            for (cells, &score) in fresh.iter().zip(&fresh_scores) {
+                self.canonical(cells, &mut canonical);
+                known.insert(canonical.clone(), Some(score));
+            }
+            let mut fresh_scores = fresh_scores.into_iter();
+            let scores: Vec<f64> = twins
+                .iter()
+                .map(|twin| match twin {
+                    Some(canonical) => known[canonical].unwrap(),
+                    None => fresh_scores.next().unwrap(),
+                })
+                .collect();
+            population.truncate(1);
+            population.extend(children.into_iter().zip(scores));
+        }
+        self.count(scored, skipped);
+        let best: (Vec<usize>, f64) = population
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        if !stopped {
+            self.save(restart, true, || Restart::Done {
+                cells: best.0.clone(),
+                score: best.1,
+            });
+        }
+        best
+    }
+
+    /// Searches step by step from the table of an empty key.
+    ///
+    /// Every step scores all tables one change away from the tables kept so
+    /// far and keeps the best `width` of them that were never kept before,
+    /// even if they are worse, so the search doesn't circle around a local
+    /// optimum. Ties go to the table found first, so the search always ends
+    /// the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - The changes and how many tables are scored in all.
+    /// * `width` - How many tables every step keeps.
+    /// * `stop` - Ends the search early when reached.
+    ///
+    /// # Returns
+    ///
+    /// * The cells of the best table and its score.
+    fn beam(&self, strategy: &Strategy, width: usize, stop: &Stop) -> (Vec<usize>, f64) {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        let start: Vec<usize> = self.fill((0..self.symbols.len()).collect());
+        let start_score: f64 = self.score(&start, &mut plaintext);
+        let mut canonical: Vec<usize> = Vec::new();
+        self.canonical(&start, &mut canonical);
+        // The canonical forms of the tables ever kept
+        let mut kept: HashSet<Vec<usize>> = HashSet::from([canonical.clone()]);
+        let mut beam: Vec<(Vec<usize>, f64)> = vec![(start, start_score)];
+        let mut best: (Vec<usize>, f64) = beam[0].clone();
+        let (mut scored, mut skipped): (usize, usize) = (0, 0);
+        let mut tried: usize = 0;
+        while scored < strategy.iterations && !beam.is_empty() && !stop.check(tried, best.1) {
+            // Every table once, however many tables of the beam it neighbours
+            let mut seen: HashSet<Vec<usize>> = HashSet::new();
+            let mut tables: Vec<Vec<usize>> = Vec::new();
+            let mut forms: Vec<Vec<usize>> = Vec::new();
+            for child in beam
+                .iter()
+                .flat_map(|(cells, _)| self.neighbours(&strategy.moves, cells))
+            {
+                self.canonical(&child, &mut canonical);
+                if kept.contains(&canonical) || !seen.insert(canonical.clone()) {
+                    skipped += 1;
+                } else {
+                    tables.push(child);
+                    forms.push(canonical.clone());
+                }
+            }
+            scored += tables.len();
+            let tables_tried: usize = tables.len();
+            let scores: Vec<f64> = self.score_all(&tables, &mut plaintext);
+            let mut children: Vec<(Vec<usize>, f64, Vec<usize>)> = tables
+                .into_iter()
+                .zip(scores)
+                .zip(forms)
+                .map(|((child, score), form)| (child, score, form))
+                .collect();
+            children.sort_by(|a, b| b.1.total_cmp(&a.1));
+            beam.clear();
+            for (child, score, form) in children {
+                if beam.len() == width {
+                    break;
+                }
+                if kept.insert(form) {
+                    beam.push((child, score));
+                }
+            }
+            if let Some(first) = beam.first().filter(|first| first.1 > best.1) {
+                best = first.clone();
+            }
+            self.track(tables_tried, (&best.0, best.1));
+            tried = tables_tried;
+        }
+        self.count(scored + 1, skipped);
+        best
+    }
+
+    /// Draws a shuffled table as the symbol index of every cell, keeping
+    /// the cells the cribs fill.
+    fn shuffled(&self, rng: &mut Rng, variant: &Variant) -> Vec<usize> {
+        let symbols: Vec<usize> = keygen::random_square(rng, variant)
+            .rows()
+            .iter()
+            .flatten()
+            .map(|c| self.symbols.iter().position(|s| s == c).unwrap())
+            .collect();
+        self.fill(symbols)
+    }
+
+    /// Puts the symbols the cribs place in their cells and the others in
+    /// the free cells, in the given order.
+    fn fill(&self, symbols: Vec<usize>) -> Vec<usize> {
+        let Some(start) = self.start else {
+            return symbols;
+        };
+        let mut rest = symbols
+            .into_iter()
+            .filter(|symbol| !start.cells().contains(&Some(*symbol)));
+        start
+            .cells()
+            .iter()
+            .map(|cell| cell.unwrap_or_else(|| rest.next().unwrap()))
+            .collect()
+    }
+
+    /// Scores every table of an enumeration, if there are few enough.
+    ///
+    /// # Arguments
+    ///
+    /// * `tables` - The tables, such as the completions of the part of the
+    ///   table the cribs rebuild.
+    ///
+    /// # Returns
+    ///
+    /// * Every table and its score, or `None` if there are more than
+    ///   `MAX_COMPLETIONS` tables.
+    fn exhaust(&self, tables: impl Iterator<Item = Vec<usize>>) -> Option<Vec<(Vec<usize>, f64)>> {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        let mut scored: Vec<(Vec<usize>, f64)> = Vec::new();
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        let mut canonical: Vec<usize> = Vec::new();
+        let mut skipped: usize = 0;
+        for cells in tables {
+            if scored.len() == MAX_COMPLETIONS {
+                return None;
+            }
+            self.canonical(&cells, &mut canonical);
+            if !seen.insert(canonical.clone()) {
+                skipped += 1;
+                continue;
+            }
+            let score: f64 = self.score(&cells, &mut plaintext);
+            scored.push((cells, score));
+        }
+        self.count(scored.len(), skipped);
+        Some(scored)
+    }
+}
+
+/// Keeps the `CANDIDATES` best of some scored tables, best first; ties
+/// keep their order.
+fn best_of(mut scored: Vec<(Vec<usize>, f64)>) -> Vec<(Vec<usize>, f64)> {
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(CANDIDATES);
+    scored
+}
+
+/// When the restarts of a search stop early
+struct Stop {
+    /// Set once a restart found a decryption that reads as the language,
+    /// or reaches the target score
+    found: AtomicBool,
+    /// When the time limit runs out, if any
+    deadline: Option<Instant>,
+    /// How many tables the restarts may try in all, if limited
+    budget: Option<usize>,
+    /// How many tables the restarts tried so far
+    spent: AtomicUsize,
+    /// The score of `--target-score` over all n-grams, which a restart
+    /// stops at without waiting for its last change
+    target: Option<f64>,
+}
+
+impl Stop {
+    /// Returns whether a decryption was found, the time ran out or the
+    /// tables to try ran out.
+    fn reached(&self) -> bool {
+        self.found.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .budget
+                .is_some_and(|budget| self.spent.load(Ordering::Relaxed) >= budget)
+    }
+
+    /// Counts the tables a restart tried and the best score it reached,
+    /// then returns whether the search stops.
+    fn check(&self, tried: usize, best_score: f64) -> bool {
+        self.spent.fetch_add(tried, Ordering::Relaxed);
+        if self.target.is_some_and(|target| best_score >= target) {
+            self.found.store(true, Ordering::Relaxed);
+        }
+        self.reached()
+    }
+}
+
+/// Changes a table a little, in place.
+///
+/// Most changes swap two cells. The rest swap two rows or two columns
+/// or mirror the table, which keeps the rows and columns of a nearly
+/// found table intact.
+///
+/// # Arguments
+///
+/// * `rng` - The generator drawing the change.
+/// * `moves` - The kinds of changes to draw from; not empty.
+/// * `shape` - Rows and columns of the table.
+/// * `cells` - The table to change.
+pub fn change(rng: &mut Rng, moves: &[Move], shape: (usize, usize), cells: &mut [usize]) {
+    let (rows, cols) = shape;
+    let mut draw: usize = rng.below(moves.iter().map(|m| m.weight()).sum());
+    let change: Move = *moves
+        .iter()
+        .find(|m| {
+            let found: bool = draw < m.weight();
+            draw = draw.saturating_sub(m.weight());
+            found
+        })
+        .unwrap();
+    match change {
+        Move::Swap => cells.swap(rng.below(cells.len()), rng.below(cells.len())),
+        Move::Rows => {
+            let (a, b) = (rng.below(rows), rng.below(rows));
+            for col in 0..cols {
+                cells.swap(a * cols + col, b * cols + col);
+            }
+        }
+        Move::Columns => {
+            let (a, b) = (rng.below(cols), rng.below(cols));
+            for row in 0..rows {
+                cells.swap(row * cols + a, row * cols + b);
+            }
+        }
+        Move::Reflect => reflect(shape, rng.below(3), cells),
+    }
+}
+
+/// Mirrors a table top to bottom (0), left to right (1) or both (2).
+fn reflect(shape: (usize, usize), form: usize, cells: &mut [usize]) {
+    let (rows, cols) = shape;
+    match form {
+        0 => {
+            for row in 0..rows / 2 {
+                for col in 0..cols {
+                    cells.swap(row * cols + col, (rows - 1 - row) * cols + col);
+                }
+            }
+        }
+        1 => {
+            for row in 0..rows {
+                cells[row * cols..(row + 1) * cols].reverse();
+            }
+        }
+        _ => cells.reverse(),
+    }
+}
+
+/// Draws a uniform number in [0, 1) from the top 53 random bits.
+pub fn unit(rng: &mut Rng) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Picks the fitter of two random tables of a population.
+///
+/// # Returns
+///
+/// * The index of the table.
+fn tournament(rng: &mut Rng, population: &[(Vec<usize>, f64)]) -> usize {
+    let (a, b) = (rng.below(population.len()), rng.below(population.len()));
+    if population[a].1 >= population[b].1 {
+        a
+    } else {
+        b
+    }
+}
+
+/// Mixes two tables by order crossover.
+///
+/// # Returns
+///
+/// * A child holding a random run of cells of `first` in place, and the
+///   other symbols in the order they have in `second`.
+fn crossover(rng: &mut Rng, first: &[usize], second: &[usize]) -> Vec<usize> {
+    let (a, b) = (rng.below(first.len()), rng.below(first.len()));
+    let run: std::ops::Range<usize> = a.min(b)..a.max(b) + 1;
+    let mut used: Vec<bool> = vec![false; first.len()];
+    for &symbol in &first[run.clone()] {
+        used[symbol] = true;
+    }
+    let mut rest = second.iter().copied().filter(|&symbol| !used[symbol]);
+    (0..first.len())
+        .map(|cell| {
+            if run.contains(&cell) {
+                first[cell]
+            } else {
+                rest.next().unwrap()
+            }
+        })
+        .collect()
+}
+
+/// Searches the table of a Playfair ciphertext, with the solvers of
+/// `solver::SOLVERS` besides the built-in ones.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext symbols, of even length.
+/// * `variant` - The variant deciding the symbols and the shape of the
+///   table.
+/// * `ngrams` - The n-gram scores.
+/// * `strategy` - How the search is run. The restarts run on
+///   `strategy.threads` threads and all stop once one of them finds a
+///   decryption that reads as the language (or reaches `strategy.target`),
+///   the time limit runs out or `strategy.max_iterations` tables were
+///   tried.
+/// * `rng` - The generator drawing the seed of every restart, so a seed
+///   repeats each restart whatever the number of threads.
+///
+/// # Returns
+///
+/// * The `top` best tables found over all restarts with distinct
+///   decryptions, best first, ranked by their words as well if the strategy
+///   has a word list; ties go to the first restart.
+///   The exact solver fails with an invalid input error if no table agrees
+///   with the cribs, or a usage error if more than `MAX_COMPLETIONS` do.
+pub fn crack(
+    ciphertext: &[char],
+    variant: &Variant,
+    ngrams: &Ngrams,
+    strategy: &Strategy,
+    rng: &mut Rng,
+) -> Result<Vec<Solution>, Error> {
+    crack_with(ciphertext, variant, ngrams, strategy, rng, solver::SOLVERS)
+}
+
+/// Searches the table of a Playfair ciphertext like `crack`, with other
+/// solvers than the defaults of `solver::SOLVERS`.
+///
+/// # Arguments
+///
+/// * `solvers` - The solvers a `Solver::Custom` strategy picks from by
+///   name; the other arguments are those of `crack`.
+///
+/// # Returns
+///
+/// * The tables `crack` returns, or a usage error if the solver of the
+///   strategy is custom and not one of `solvers`.
+pub fn crack_with(
+    ciphertext: &[char],
+    variant: &Variant,
+    ngrams: &Ngrams,
+    strategy: &Strategy,
+    rng: &mut Rng,
+    solvers: &[&dyn solver::Solver],
+) -> Result<Vec<Solution>, Error> {
+    let climber: Option<&dyn solver::Solver> = strategy.solver.climber(solvers);
+    if let Solver::Custom(name) = strategy.solver
+        && climber.is_none()
+    {
+        return Err(Error::Usage(format!("unknown solver '{}'", name)));
+    }
+    let mut search: Search = Search::new(
+        ciphertext,
+        variant,
+        ngrams,
+        &strategy.cribs,
+        strategy.start.as_ref(),
+    );
+    let shape: (usize, usize) = search.shape;
+    search.gpu = if strategy.gpu && search.reading.is_some() {
+        eprintln!("Warning: the GPU only scores ciphertexts without --period; scoring on the CPU");
+        None
+    } else if strategy.gpu {
+        match gpu::Scorer::new(
+            &ngrams.scores,
+            ngrams.length,
+            &search.letters,
+            &search.ciphertext,
+            shape,
+        ) {
+            Ok(scorer) => {
+                eprintln!("Scoring on the GPU: {}", scorer.name);
+                Some(scorer)
+            }
+            Err(e) => {
+                eprintln!("Warning: {}; scoring on the CPU", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let ngram_count: f64 = (ciphertext.len() + 1).saturating_sub(ngrams.length).max(1) as f64;
+    // A beam search would find the same table again
+    let restarts: usize = match strategy.solver {
+        Solver::Beam(_) => 1,
+        _ if strategy.restarts == 0 => {
+            return Err(Error::Usage(String::from(
+                "the search needs at least 1 restart",
+            )));
+        }
+        _ => strategy.restarts,
+    };
+    // A resumed search goes on with the seeds of the checkpoint
+    let text: String = ciphertext.iter().collect();
+    let settings: checkpoint::Settings = checkpoint::Settings {
+        solver: strategy.solver.name().to_string(),
+        schedule: match strategy.solver {
+            Solver::Anneal(schedule) => Some(schedule),
+            _ => None,
+        },
+        genetics: match strategy.solver {
+            Solver::Genetic(genetics) => Some(genetics),
+            _ => None,
+        },
+        moves: strategy.moves.clone(),
+        iterations: strategy.iterations,
+        alphabet: variant.symbols().iter().collect(),
+        period: variant.period,
+        rounds: variant.rounds,
+    };
+    let resumed: Checkpoint = match &strategy.resume {
+        Some(checkpoint) => {
+            checkpoint.check(&text, &settings, restarts)?;
+            checkpoint.clone()
+        }
+        None => Checkpoint {
+            ciphertext: text,
+            settings,
+            seeds: (0..restarts).map(|_| rng.next_u64()).collect(),
+            restarts: vec![Restart::Pending; restarts],
+        },
+    };
+    let seeds: Vec<u64> = resumed.seeds.clone();
+    let writer: Option<checkpoint::Writer> = match &strategy.checkpoint {
+        Some(path) => Some(checkpoint::Writer::new(
+            path,
+            checkpoint::INTERVAL,
+            resumed.clone(),
+        )?),
+        None => None,
+    };
+    let tracker: Option<Tracker> = strategy.progress.map(|progress| Tracker {
+        progress,
+        ngram_count,
+        restarts,
+        started: Instant::now(),
+        tried: AtomicUsize::new(0),
+        ended: AtomicUsize::new(0),
+        best: Mutex::new(None),
+        reported: Mutex::new(Instant::now()),
+    });
+    let search: Search = Search {
+        checkpoint: writer.as_ref(),
+        tracker: tracker.as_ref(),
+        ..search
+    };
+    // A restart done before may already have found the table
+    let target: f64 = strategy.target.unwrap_or(ngrams.threshold());
+    let done: bool = resumed.restarts.iter().any(
+        |restart| matches!(restart, Restart::Done { score, .. } if score / ngram_count >= target),
+    );
+    let stop: Stop = Stop {
+        found: AtomicBool::new(done),
+        deadline: strategy.time_limit.map(|limit| Instant::now() + limit),
+        budget: strategy.max_iterations,
+        spent: AtomicUsize::new(0),
+        target: strategy.target.map(|target| target * ngram_count),
+    };
+    // The best cells of the exhaustive scoring and of every restart, their
+    // score and the restart that found them
+    let candidates: Mutex<Vec<(Vec<usize>, f64, usize)>> = Mutex::new(Vec::new());
+    let exhausted: bool = if matches!(strategy.solver, Solver::Exact) {
+        let scored: Vec<(Vec<usize>, f64)> = search
+            .exhaust(cribs::tables(&strategy.cribs, shape))
+            .ok_or_else(|| {
+                Error::Usage(format!(
+                    "more than {} tables agree with the cribs; give more cribs or another --solver",
+                    MAX_COMPLETIONS
+                ))
+            })?;
+        let count: usize = scored.len();
+        if count == 0 {
+            return Err(Error::InvalidInput(String::from(
+                "no table agrees with all the cribs: check that they line up with the ciphertext",
+            )));
+        }
+        eprintln!(
+            "{} tables agree with the cribs, up to shifts of the rows and columns",
+            count
+        );
+        candidates.lock().unwrap().extend(
+            best_of(scored)
+                .into_iter()
+                .map(|(cells, score)| (cells, score, 0)),
+        );
+        true
+    } else {
+        // Few tables left by the cribs are all scored before any search
+        let scored: Vec<(Vec<usize>, f64)> = search
+            .start
+            .and_then(|start| search.exhaust(start.completions(search.cribs)))
+            .unwrap_or_default();
+        let count: usize = scored.len();
+        match best_of(scored).as_slice() {
+            best @ [(_, score), ..] => {
+                eprintln!(
+                    "Scored all {} tables the cribs leave: {:.2} per {}",
+                    count,
+                    score / ngram_count,
+                    ngrams.name()
+                );
+                let found: bool = score / ngram_count >= target;
+                candidates
+                    .lock()
+                    .unwrap()
+                    .extend(best.iter().map(|(cells, score)| (cells.clone(), *score, 0)));
+                found
+            }
+            [] => false,
+        }
+    };
+    let run = |restart: usize| {
+        let resumed: &Restart = &resumed.restarts[restart - 1];
+        // The first restart always runs, so there is a table to return
+        if restart > 1 && stop.reached() && !matches!(resumed, Restart::Done { .. }) {
+            return;
+        }
+        let mut rng: Rng = Rng::new(seeds[restart - 1]);
+        let (found, found_score) = match (&strategy.solver, resumed) {
+            (_, Restart::Done { cells, score }) => (cells.clone(), *score),
+            (Solver::Genetic(genetics), resumed) => {
+                let resumed: Option<&Generation> = match resumed {
+                    Restart::Evolving(generation) => Some(generation),
+                    _ => None,
+                };
+                search.evolve(
+                    &mut rng, variant, strategy, genetics, &stop, restart, resumed,
+                )
+            }
+            (Solver::Beam(width), _) => search.beam(strategy, *width, &stop),
+            (_, resumed) => {
+                let resumed: Option<&Climb> = match resumed {
+                    Restart::Climbing(climb) => Some(climb),
+                    _ => None,
+                };
+                let climber: &dyn solver::Solver = climber
+                    .expect("the genetic, beam and exact solvers search whole sets of tables");
+                search.climb(
+                    &mut rng, variant, strategy, climber, &stop, restart, resumed,
+                )
+            }
+        };
+        if let Some(tracker) = &tracker {
+            tracker.ended.fetch_add(1, Ordering::Relaxed);
+        }
+        eprintln!(
+            "Restart {}: {:.2} per {}",
+            restart,
+            found_score / ngram_count,
+            ngrams.name()
+        );
+        // Another restart won't read more like the language
+        if found_score / ngram_count >= target {
+            stop.found.store(true, Ordering::Relaxed);
+        }
+        candidates
+            .lock()
+            .unwrap()
+            .push((found, found_score, restart));
+    };
+    if !exhausted {
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(strategy.threads.max(1))
+            .build()
+        {
+            Ok(pool) => pool.install(|| (1..=restarts).into_par_iter().for_each(run)),
+            Err(_) => (1..=restarts).for_each(run),
+        }
+    }
+    let (scored, skipped) = (
+        search.scored.load(Ordering::Relaxed),
+        search.skipped.load(Ordering::Relaxed),
+    );
+    if skipped > 0 {
+        eprintln!(
+            "Skipped {} of {} tables as equivalent to one already scored ({:.1}%)",
+            skipped,
+            scored + skipped,
+            100.0 * skipped as f64 / (scored + skipped) as f64
+        );
+    }
+    let mut candidates: Vec<(Vec<usize>, f64, usize)> = candidates.into_inner().unwrap();
+    let rank = |candidates: &mut Vec<(Vec<usize>, f64, usize)>| {
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.2.cmp(&b.2)));
+        candidates.dedup_by(|a, b| a.0 == b.0);
+    };
+    rank(&mut candidates);
+    // Near-misses of the best table, such as with two rows swapped, are
+    // worth showing as well
+    if strategy.top > 1 {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(ciphertext.len());
+        let near: Vec<Vec<usize>> = search.neighbours(&strategy.moves, &candidates[0].0);
+        candidates.extend(near.into_iter().map(|cells| {
+            let score: f64 = search.score(&cells, &mut plaintext);
+            (cells, score, usize::MAX)
+        }));
+        rank(&mut candidates);
+    }
+    candidates.truncate(CANDIDATES.max(strategy.top));
+    let mut letters: Vec<u8> = Vec::with_capacity(ciphertext.len());
+    let mut solutions: Vec<Solution> = candidates
+        .into_iter()
+        .map(|(cells, found_score, _)| {
+            // The score of the decryption alone, without the cribs it breaks
+            search.score(&cells, &mut letters);
+            let score: f64 = ngrams.score(&letters);
+            let square: KeySquare = search.square(&cells);
+            let plaintext: String = crate::playfair_cipher(
+                &ciphertext.iter().collect::<String>(),
+                square.rows(),
+                variant,
+                crate::CipherMode::Decrypt,
+            );
+            let words: Option<f64> = strategy
+                .words
+                .as_ref()
+                .map(|dictionary| dictionary.coverage(&plaintext, variant));
+            Solution {
+                square,
+                plaintext,
+                score: score / ngram_count,
+                words,
+                fitness: found_score / ngram_count + WORD_WEIGHT * words.unwrap_or(0.0),
+            }
+        })
+        .collect();
+    // The n-gram scores rank the candidates, helped by their words if a
+    // word list is given; ties go to the first restart, and tables that
+    // only shift the rows or columns of a better one are dropped
+    solutions.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+    let mut seen: HashSet<String> = HashSet::new();
+    solutions.retain(|solution| seen.insert(solution.plaintext.clone()));
+    solutions.truncate(strategy.top.max(1));
+    Ok(solutions)
+}
+
+/// A search stepped by hand at a temperature set by hand, with cells
+/// pinned that no change moves, as the full-screen view of `crack` runs it
+pub struct Session<'a> {
+    /// The search of the ciphertext
+    search: Search<'a>,
+    /// The ciphertext, as the table symbols
+    ciphertext: String,
+    /// The variant of the table
+    variant: Variant,
+    /// The generator drawing the changes
+    rng: Rng,
+    /// The kinds of changes tried
+    moves: Vec<Move>,
+    /// The table the search is at and its score
+    current: (Vec<usize>, f64),
+    /// The best table since the last edit and its score
+    best: (Vec<usize>, f64),
+    /// Whether every cell keeps its symbol
+    pinned: Vec<bool>,
+    /// The number of n-grams of the ciphertext, to report scores by
+    ngram_count: f64,
+    /// The temperature worse tables are kept at, 0 for hill climbing
+    pub temperature: f64,
+    /// How many changes were tried
+    pub iterations: usize,
+}
+
+impl<'a> Session<'a> {
+    /// Starts from a shuffled table, with the cells the cribs fill pinned.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The ciphertext symbols, of even length.
+    /// * `variant` - The variant deciding the symbols and the shape of the
+    ///   table.
+    /// * `ngrams` - The n-gram scores.
+    /// * `strategy` - The changes, the temperature of `anneal` (0 for any
+    ///   other solver) and the cribs.
+    /// * `rng` - The generator drawing the table and the changes.
+    pub fn new(
+        ciphertext: &[char],
+        variant: &Variant,
+        ngrams: &'a Ngrams,
+        strategy: &'a Strategy,
+        mut rng: Rng,
+    ) -> Self {
+        let search: Search = Search::new(
+            ciphertext,
+            variant,
+            ngrams,
+            &strategy.cribs,
+            strategy.start.as_ref(),
+        );
+        let cells: Vec<usize> = search.shuffled(&mut rng, variant);
+        let pinned: Vec<bool> = match search.start {
+            Some(start) => start.cells().iter().map(Option::is_some).collect(),
+            None => vec![false; cells.len()],
+        };
+        let mut plaintext: Vec<u8> = Vec::with_capacity(ciphertext.len());
+        let score: f64 = search.score(&cells, &mut plaintext);
+        Session {
+            search,
+            ciphertext: ciphertext.iter().collect(),
+            variant: variant.clone(),
+            rng,
+            moves: strategy.moves.clone(),
+            current: (cells.clone(), score),
+            best: (cells, score),
+            pinned,
+            ngram_count: (ciphertext.len() + 1).saturating_sub(ngrams.length).max(1) as f64,
+            temperature: match strategy.solver {
+                Solver::Anneal(schedule) => schedule.start,
+                _ => 0.0,
+            },
+            iterations: 0,
+        }
+    }
+
+    /// Tries changes to the table, dropping those that move a pinned cell.
+    pub fn step(&mut self, changes: usize) {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        let mut candidate: Vec<usize> = self.current.0.clone();
+        for _ in 0..changes {
+            candidate.copy_from_slice(&self.current.0);
+            change(
+                &mut self.rng,
+                &self.moves,
+                self.search.shape,
+                &mut candidate,
+            );
+            self.iterations += 1;
+            let moved: bool = (0..candidate.len())
+                .any(|cell| self.pinned[cell] && candidate[cell] != self.current.0[cell]);
+            if moved {
+                continue;
+            }
+            let score: f64 = self.search.score(&candidate, &mut plaintext);
+            let delta: f64 = score - self.current.1;
+            let keep: bool = delta >= 0.0
+                || (self.temperature > 0.0
+                    && unit(&mut self.rng) < (delta / self.temperature).exp());
+            if keep {
+                std::mem::swap(&mut self.current.0, &mut candidate);
+                self.current.1 = score;
+                if score > self.best.1 {
+                    self.best = self.current.clone();
+                }
+            }
+        }
+    }
+
+    /// Shuffles the cells that aren't pinned, keeping the best table.
+    pub fn shuffle(&mut self) {
+        let mut cells: Vec<usize> = self.current.0.clone();
+        let free: Vec<usize> = (0..cells.len()).filter(|&c| !self.pinned[c]).collect();
+        for i in (1..free.len()).rev() {
+            let j: usize = self.rng.below(i + 1);
+            cells.swap(free[i], free[j]);
+        }
+        let score: f64 = self.rescore(&cells);
+        self.current = (cells, score);
+    }
+
+    /// Pins the cell of the best table, or unpins it, and goes on from the
+    /// best table.
+    pub fn toggle_pin(&mut self, cell: usize) {
+        self.pinned[cell] = !self.pinned[cell];
+        self.current = self.best.clone();
+    }
+
+    /// Puts a symbol into a cell of the best table by swapping it with the
+    /// symbol there, pins the cell and goes on from the new table, even if
+    /// it scores worse.
+    ///
+    /// # Returns
+    ///
+    /// * `false` if the symbol isn't in the table or sits in another pinned
+    ///   cell.
+    pub fn place(&mut self, cell: usize, symbol: char) -> bool {
+        let Some(index) = self.search.symbols.iter().position(|&s| s == symbol) else {
+            return false;
+        };
+        let mut cells: Vec<usize> = self.best.0.clone();
+        let from: usize = cells.iter().position(|&s| s == index).unwrap();
+        if from != cell && self.pinned[from] {
+            return false;
+        }
+        cells.swap(from, cell);
+        self.pinned[cell] = true;
+        let score: f64 = self.rescore(&cells);
+        self.current = (cells, score);
+        self.best = self.current.clone();
+        true
+    }
+
+    /// Scores a table.
+    fn rescore(&self, cells: &[usize]) -> f64 {
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.ciphertext.len());
+        self.search.score(cells, &mut plaintext)
+    }
+
+    /// Returns whether a cell is pinned.
+    pub fn pinned(&self, cell: usize) -> bool {
+        self.pinned[cell]
+    }
+
+    /// Returns the score per n-gram of the table the search is at.
+    pub fn score(&self) -> f64 {
+        self.current.1 / self.ngram_count
+    }
+
+    /// Returns the score per n-gram of the best table.
+    pub fn best_score(&self) -> f64 {
+        self.best.1 / self.ngram_count
+    }
+
+    /// Returns whether the best decryption reads as the language.
+    pub fn found(&self) -> bool {
+        self.best_score() >= self.search.ngrams.threshold()
+    }
+
+    /// Returns the best table.
+    pub fn square(&self) -> KeySquare {
+        self.search.square(&self.best.0)
+    }
+
+    /// Returns the ciphertext decrypted with the best table.
+    pub fn plaintext(&self) -> String {
+        crate::playfair_cipher(
+            &self.ciphertext,
+            self.square().rows(),
+            &self.variant,
+            crate::CipherMode::Decrypt,
+        )
+    }
+
+    /// Returns the variant of the table.
+    pub fn variant(&self) -> &Variant {
+        &self.variant
+    }
+
+    /// Returns the name of the n-grams scores are counted in.
+    pub fn ngram_name(&self) -> &'static str {
+        self.search.ngrams.name()
+    }
+}
+
+/// Reads the schedule of `--solver anneal` from the arguments, over the
+/// `[anneal]` table of the config file, over the preset for the length of
+/// the ciphertext.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of `crack`.
+/// * `letters` - The letters of the ciphertext.
+/// * `iterations` - The changes of a restart.
+///
+/// # Returns
+///
+/// * The schedule, or a usage error if a setting is out of range.
+fn schedule_from_matches(
+    matches: &ArgMatches,
+    letters: usize,
+    iterations: usize,
+) -> Result<Schedule, Error> {
+    let config: config::Anneal =
+        config::load(matches.get_one::<String>("config").map(String::as_str))?
+            .anneal
+            .unwrap_or_default();
+    let mut schedule: Schedule = match matches
+        .get_one::<String>("schedule")
+        .or(config.schedule.as_ref())
+    {
+        Some(name) => Preset::parse(name, letters)?,
+        None => Preset::of(letters),
+    }
+    .schedule(iterations);
+    if let Some(start) = matches
+        .get_one::<f64>("temperature")
+        .copied()
+        .or(config.temperature)
+    {
+        if !(start > 0.0 && start.is_finite()) {
+            return Err(Error::Usage(format!(
+                "invalid temperature {}: it must be positive",
+                start
+            )));
+        }
+        schedule.start = start;
+    }
+    let cooling: Option<&String> = matches
+        .get_one::<String>("cooling")
+        .or(config.cooling.as_ref());
+    if let Some(name) = cooling {
+        schedule.cooling = Cooling::parse(name)?;
+    }
+    if let Some(factor) = matches
+        .get_one::<f64>("cooling-factor")
+        .copied()
+        .or(config.cooling_factor)
+    {
+        if !(factor > 0.0 && factor < 1.0) {
+            return Err(Error::Usage(format!(
+                "invalid cooling factor {}: it must be between 0 and 1",
+                factor
+            )));
+        }
+        match (cooling, schedule.cooling) {
+            (None, _) => schedule.cooling = Cooling::Geometric,
+            (Some(_), Cooling::Geometric) => {}
+            (Some(_), _) => {
+                return Err(Error::Usage(String::from(
+                    "--cooling-factor only works with geometric cooling",
+                )));
+            }
+        }
+        schedule.end = schedule.start * factor.powf(iterations as f64);
+    }
+    if let Some(reheat) = matches
+        .get_one::<usize>("reheat")
+        .copied()
+        .or(config.reheat)
+    {
+        // 0 turns off the reheat of a preset
+        schedule.reheat = (reheat > 0).then_some(reheat);
+    }
+    Ok(schedule)
+}
+
+/// Reads the solver, the changes and their counts from the arguments.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of `crack`.
+/// * `letters` - The letters of the ciphertext, which the schedule of
+///   `anneal` is preset for.
+/// * `solvers` - The custom solvers `--solver` may name.
+///
+/// # Returns
+///
+/// * The strategy, or a usage error if the flags of a solver are given with
+///   another one, a setting is out of range or a change is unknown.
+fn strategy_from_matches(
+    matches: &ArgMatches,
+    letters: usize,
+    solvers: &[&dyn solver::Solver],
+) -> Result<Strategy, Error> {
+    let name: &str = matches
+        .get_one::<String>("solver")
+        .map_or("anneal", String::as_str);
+    let genetic: bool = ["population", "crossover", "mutation"]
+        .iter()
+        .any(|flag| matches.contains_id(flag));
+    if genetic && name != "genetic" {
+        return Err(Error::Usage(String::from(
+            "--population, --crossover and --mutation only work with --solver genetic",
+        )));
+    }
+    if matches.get_flag("gpu") && !["beam", "genetic"].contains(&name) {
+        return Err(Error::Usage(String::from(
+            "--gpu only works with --solver beam or --solver genetic, which score batches of tables",
+        )));
+    }
+    if matches.contains_id("width") && name != "beam" {
+        return Err(Error::Usage(String::from(
+            "--width only works with --solver beam",
+        )));
+    }
+    let threads: usize = match matches.get_one::<usize>("threads").copied() {
+        Some(0) => {
+            return Err(Error::Usage(String::from(
+                "the search needs at least 1 thread",
+            )));
+        }
+        Some(threads) => threads,
+        None => Strategy::default().threads,
+    };
+    let restarts: usize = *matches.get_one::<usize>("restarts").unwrap();
+    if restarts == 0 {
+        return Err(Error::Usage(String::from(
+            "the search needs at least 1 restart",
+        )));
+    }
+    let time_limit: Option<Duration> = match matches.get_one::<f64>("time-limit").copied() {
+        Some(seconds) if !(seconds > 0.0 && seconds.is_finite()) => {
+            return Err(Error::Usage(format!(
+                "invalid time limit {}: it must be a positive number of seconds",
+                seconds
+            )));
+        }
+        seconds => seconds.map(Duration::from_secs_f64),
+    };
+    let max_iterations: Option<usize> = match matches.get_one::<usize>("max-iterations").copied() {
+        Some(0) => {
+            return Err(Error::Usage(String::from(
+                "--max-iterations needs to try at least 1 table",
+            )));
+        }
+        max_iterations => max_iterations,
+    };
+    let target: Option<f64> = match matches.get_one::<f64>("target-score").copied() {
+        Some(score) if !score.is_finite() => {
+            return Err(Error::Usage(format!(
+                "invalid target score {}: it must be a log10 probability such as -5.2",
+                score
+            )));
+        }
+        target => target,
+    };
+    let top: usize = match matches.get_one::<usize>("top").copied() {
+        Some(0) => {
+            return Err(Error::Usage(String::from(
+                "--top needs to show at least 1 table",
+            )));
+        }
+        top => top.unwrap_or(1),
+    };
+    if ["checkpoint", "resume"]
+        .iter()
+        .any(|flag| matches.contains_id(flag))
+        && ["beam", "exact"].contains(&name)
+    {
+        return Err(Error::Usage(String::from(
+            "--checkpoint and --resume only work with --solver anneal, hill or genetic, which run restarts",
+        )));
+    }
+    let progress: Option<Progress> = match matches.get_one::<String>("progress") {
+        Some(format) => {
+            let seconds: f64 = matches
+                .get_one::<f64>("progress-interval")
+                .copied()
+                .unwrap_or(1.0);
+            if !(seconds > 0.0 && seconds.is_finite()) {
+                return Err(Error::Usage(format!(
+                    "invalid progress interval {}: it must be a positive number of seconds",
+                    seconds
+                )));
+            }
+            Some(Progress {
+                format: match format.as_str() {
+                    "json" => ProgressFormat::Json,
+                    _ => ProgressFormat::Text,
+                },
+                interval: Duration::from_secs_f64(seconds),
+            })
+        }
+        None if matches.contains_id("progress-interval") => {
+            return Err(Error::Usage(String::from(
+                "--progress-interval only works with --progress",
+            )));
+        }
+        None => None,
+    };
+    let resume: Option<Checkpoint> = match matches.get_one::<String>("resume") {
+        Some(path) => Some(checkpoint::read(path)?),
+        None => None,
+    };
+    let annealing: bool = [
+        "temperature",
+        "cooling",
+        "cooling-factor",
+        "reheat",
+        "schedule",
+    ]
+    .iter()
+    .any(|flag| matches.contains_id(flag));
+    let iterations: usize = *matches.get_one::<usize>("iterations").unwrap();
+    let solver: Solver = match name {
+        name if name != "anneal" && annealing => {
+            return Err(Error::Usage(String::from(
+                "--temperature, --cooling, --cooling-factor, --reheat and --schedule only work with --solver anneal",
+            )));
+        }
+        "hill" => Solver::Hill,
+        "exact" => Solver::Exact,
+        "beam" => match matches.get_one::<usize>("width").copied().unwrap_or(10) {
+            0 => {
+                return Err(Error::Usage(String::from(
+                    "the beam must keep at least 1 table",
+                )));
+            }
+            width => Solver::Beam(width),
+        },
+        "genetic" => {
+            let defaults: Genetics = Genetics::default();
+            let genetics: Genetics = Genetics {
+                population: matches
+                    .get_one::<usize>("population")
+                    .copied()
+                    .unwrap_or(defaults.population),
+                crossover: matches
+                    .get_one::<f64>("crossover")
+                    .copied()
+                    .unwrap_or(defaults.crossover),
+                mutation: matches
+                    .get_one::<f64>("mutation")
+                    .copied()
+                    .unwrap_or(defaults.mutation),
+            };
+            if genetics.population < 2 {
+                return Err(Error::Usage(String::from(
+                    "the population must hold at least 2 tables",
+                )));
+            }
+            for rate in [genetics.crossover, genetics.mutation] {
+                if !(0.0..=1.0).contains(&rate) {
+                    return Err(Error::Usage(format!(
+                        "invalid rate {}: it must be between 0 and 1",
+                        rate
+                    )));
+                }
+            }
+            Solver::Genetic(genetics)
+        }
+        name if let Some(solver) = solver::find(solvers, name) => Solver::Custom(solver.name()),
+        _ => Solver::Anneal(schedule_from_matches(matches, letters, iterations)?),
+    };
+    Ok(Strategy {
+        solver,
+        moves: match matches.get_one::<String>("moves") {
+            Some(spec) => parse_moves(spec)?,
+            None => Strategy::default().moves,
+        },
+        iterations,
+        restarts,
+        threads,
+        time_limit,
+        max_iterations,
+        target,
+        gpu: matches.get_flag("gpu"),
+        top,
+        // A resumed search goes on writing to its checkpoint
+        checkpoint: matches
+            .get_one::<String>("checkpoint")
+            .or(matches.get_one::<String>("resume"))
+            .cloned(),
+        resume,
+        progress,
+        ..Strategy::default()
+    })
+}
+
+/// Runs the `crack` subcommand.
+///
+/// The table found and its fingerprint go to stderr, the square in the form
+/// `--key-square` accepts and the decrypted text to stdout.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+/// * `solvers` - The solvers `--solver` offers besides the built-in ones.
+///
+/// # Returns
+///
+/// * An invalid input error if the ciphertext is too short or odd, or a
+///   crack-not-found error if the best decryption doesn't read as English.
+pub(crate) fn run(matches: &ArgMatches, solvers: &[&dyn solver::Solver]) -> Result<(), Error> {
+    let (mut variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    if variant.rounds > 1 {
+        return Err(Error::Usage(String::from(
+            "crack only searches single-round ciphertexts",
+        )));
+    }
+    // Messages under the same key are searched as one ciphertext, each of
+    // them a whole number of digrams
+    let messages: Vec<Vec<char>> = matches
+        .get_many::<String>("ciphertext")
+        .unwrap()
+        .map(|text| crate::table_letters(text, &variant))
+        .collect();
+    if messages.len() > 1 {
+        if variant.period.is_some() || matches.get_flag("find-period") {
+            return Err(Error::Usage(String::from(
+                "several ciphertexts are cracked as adjacent digrams: drop --period and --find-period",
+            )));
+        }
+        if let Some(n) = messages.iter().position(|m| !m.len().is_multiple_of(2)) {
+            return Err(Error::InvalidInput(format!(
+                "ciphertext {} has an odd number of letters, {}",
+                n + 1,
+                messages[n].len()
+            )));
+        }
+    }
+    let ciphertext: Vec<char> = messages.concat();
+    if matches.get_flag("find-period") {
+        let symbols: Vec<char> = variant.symbols();
+        let indices: Vec<usize> = ciphertext
+            .iter()
+            .map(|c| symbols.iter().position(|s| s == c).unwrap())
+            .collect();
+        let periods: Vec<(Option<usize>, f64)> =
+            analyze::periods(&indices, &variant, analyze::MAX_PERIOD);
+        variant.period = analyze::likely_period(&periods);
+        let repeats = |period: Option<usize>| {
+            periods
+                .iter()
+                .find(|&&(p, _)| p == period)
+                .map_or(0.0, |&(_, repeats)| repeats)
+        };
+        match variant.period {
+            Some(period) => eprintln!(
+                "Period: {}, whose pairs repeat {:.2} times as often as random ones ({:.2} without a period)",
+                period,
+                repeats(Some(period)),
+                repeats(None)
+            ),
+            None => eprintln!(
+                "Period: none, the digrams repeat most as they stand ({:.2} times as often as random ones)",
+                repeats(None)
+            ),
+        }
+    }
+    if ciphertext.len() < MIN_LETTERS || !ciphertext.len().is_multiple_of(2) {
+        return Err(Error::InvalidInput(format!(
+            "crack needs an even number of at least {} ciphertext letters, not {}",
+            MIN_LETTERS,
+            ciphertext.len()
+        )));
+    }
+    let seed: u64 = matches
+        .get_one::<u64>("seed")
+        .copied()
+        .unwrap_or_else(keygen::default_seed);
+    let (ngrams, text, language): (Ngrams, String, Option<Language>) =
+        match matches.get_one::<String>("stats") {
+            Some(path) => (
+                Ngrams::load(path)?,
+                String::from("text like the statistics"),
+                None,
+            ),
+            None => {
+                let language: Language = match matches.get_one::<String>("lang") {
+                    Some(code) => Language::parse(code)?,
+                    None => Language::English,
+                };
+                let length: usize = match matches.get_one::<String>("fitness").map(String::as_str) {
+                    Some("bigrams") => 2,
+                    _ => 4,
+                };
+                (
+                    Ngrams::builtin(language, length),
+                    format!("{} text", language.name()),
+                    Some(language),
+                )
+            }
+        };
+    let mut strategy: Strategy = strategy_from_matches(matches, ciphertext.len(), solvers)?;
+    // The built-in words are English only
+    strategy.words = match matches.get_one::<String>("words") {
+        Some(path) => Some(Dictionary::load(path, &variant)?),
+        None if language == Some(Language::English) => Some(Dictionary::builtin(&variant)),
+        None => None,
+    };
+    for spec in matches.get_many::<String>("crib").into_iter().flatten() {
+        strategy.cribs.extend(cribs::parse(spec, &variant)?);
+    }
+    let hints: Vec<&String> = matches
+        .get_many::<String>("hint")
+        .into_iter()
+        .flatten()
+        .collect();
+    if !hints.is_empty() {
+        if variant.period.is_some() {
+            return Err(Error::Usage(String::from(
+                "--hint places words on adjacent digrams: drop --period and --find-period, or give the digrams with --crib",
+            )));
+        }
+        let symbols: Vec<char> = variant.symbols();
+        let indices: Vec<usize> = ciphertext
+            .iter()
+            .map(|c| symbols.iter().position(|s| s == c).unwrap())
+            .collect();
+        for spec in hints {
+            strategy
+                .cribs
+                .extend(cribs::hint(spec, &indices, &variant)?);
+        }
+    }
+    if matches!(strategy.solver, Solver::Exact) {
+        if strategy.cribs.is_empty() {
+            return Err(Error::Usage(String::from(
+                "--solver exact needs the known plaintext of --crib or --hint",
+            )));
+        }
+    } else if !strategy.cribs.is_empty() {
+        let symbols: Vec<char> = variant.symbols();
+        let shape: (usize, usize) = variant.alphabet.dimensions();
+        let start: PartialKeySquare = cribs::solve(&strategy.cribs, shape)?;
+        eprintln!("Crib Table ({} of {} cells):", start.known(), symbols.len());
+        for row in start.cells().chunks(shape.1) {
+            let row: Vec<char> = row
+                .iter()
+                .map(|cell| cell.map_or('.', |symbol| symbols[symbol]))
+                .collect();
+            eprintln!("{:?}", row);
+        }
+        strategy.start = Some(start);
+    }
+    if matches.get_flag("compare") {
+        return compare::run(&ciphertext, &variant, &ngrams, &strategy, seed, solvers);
+    }
+    if matches.get_flag("tui") {
+        if !matches!(strategy.solver, Solver::Anneal(_) | Solver::Hill) {
+            return Err(Error::Usage(String::from(
+                "--tui steps the search one change at a time: use --solver anneal or hill",
+            )));
+        }
+        return crackview::run(&ciphertext, &variant, &ngrams, &strategy, seed);
+    }
+    let started: Instant = Instant::now();
+    let solutions: Vec<Solution> = crack_with(
+        &ciphertext,
+        &variant,
+        &ngrams,
+        &strategy,
+        &mut Rng::new(seed),
+        solvers,
+    )?;
+    let elapsed: Duration = started.elapsed();
+    let solution: &Solution = &solutions[0];
+    // Several ciphertexts are decrypted one by one and shown apart
+    let decrypted = |solution: &Solution| -> Vec<String> {
+        if messages.len() == 1 {
+            return vec![solution.plaintext.clone()];
+        }
+        messages
+            .iter()
+            .map(|message| {
+                crate::playfair_cipher(
+                    &message.iter().collect::<String>(),
+                    solution.square.rows(),
+                    &variant,
+                    crate::CipherMode::Decrypt,
+                )
+            })
+            .collect()
+    };
+    if messages.len() > 1 {
+        eprintln!(
+            "Ciphertexts: {}, {} letters in all",
+            messages.len(),
+            ciphertext.len()
+        );
+    }
+    eprintln!("Best Table Found:");
+    for row in solution.square.rows() {
+        eprintln!("{:?}", row);
+    }
+    eprintln!("Key Fingerprint: {}", solution.square.fingerprint());
+    if let Some(keyword) = structure::keyword(solution.square.rows(), &variant) {
+        eprintln!("Keyword: {} fills an equivalent table", keyword);
+    }
+    eprintln!("Score: {:.2} per {}", solution.score, ngrams.name());
+    if let Some(words) = solution.words {
+        eprintln!("Words: {:.0}% of the letters", words * 100.0);
+    }
+    let (language, scores) = identify(&solution.plaintext);
+    let others: Vec<String> = scores
+        .iter()
+        .skip(1)
+        .map(|(language, score)| format!("{} {:.2}", language.name(), score))
+        .collect();
+    match language {
+        Some(language) => eprintln!(
+            "Language: {} ({:.2} per quadgram; {})",
+            language.name(),
+            scores[0].1,
+            others.join(", ")
+        ),
+        None => eprintln!(
+            "Language: none of the built-in ones ({} scores best, {:.2} per quadgram)",
+            scores[0].0.name(),
+            scores[0].1
+        ),
+    }
+    let found: bool = solution.score >= ngrams.threshold();
+    if let Some(path) = matches.get_one::<String>("report") {
+        let symbols: Vec<char> = variant.symbols();
+        let indices: Vec<usize> = ciphertext
+            .iter()
+            .map(|c| symbols.iter().position(|s| s == c).unwrap())
+            .collect();
+        // The ciphertext is compared with the language of the decryption
+        let compared: Language = language.unwrap_or(Language::English);
+        let report: Report = Report {
+            ciphertext: messages
+                .iter()
+                .map(|message| message.iter().collect::<String>())
+                .collect::<Vec<String>>()
+                .join(" "),
+            statistics: analyze::statistics(&indices, &symbols, compared),
+            strategy: &strategy,
+            seed,
+            scoring: text.clone(),
+            ngram_name: ngrams.name(),
+            solutions: solutions
+                .iter()
+                .map(|solution| (solution, decrypted(solution).join(" ")))
+                .collect(),
+            found,
+            elapsed,
+        };
+        report.write(path)?;
+        eprintln!("Report: {}", path);
+    }
+    let json: bool = matches
+        .get_one::<String>("format")
+        .is_some_and(|f| f == "json");
+    if json {
+        let output = json!({
+            "found": found,
+            "language": language.map(Language::name),
+            "candidates": solutions.iter().map(|solution| json!({
+                "key_square": keygen::square_line(&solution.square),
+                "table": solution.square.rows().iter().map(|row| row.iter().collect::<String>()).collect::<Vec<String>>(),
+                "key_fingerprint": solution.square.fingerprint(),
+                "plaintext": solution.plaintext,
+                "messages": decrypted(solution),
+                "score": solution.score,
+                "words": solution.words,
+                "fitness": solution.fitness,
+            })).collect::<Vec<_>>(),
+        });
+        let json: String =
+            serde_json::to_string_pretty(&output).map_err(|e| Error::Internal(e.to_string()))?;
+        println!("{}", json);
+    } else {
+        for (rank, other) in solutions.iter().enumerate().skip(1) {
+            eprintln!(
+                "Candidate {}: {:.2} per {}{}",
+                rank + 1,
+                other.score,
+                ngrams.name(),
+                other.words.map_or(String::new(), |words| format!(
+                    ", {:.0}% of the letters in words",
+                    words * 100.0
+                ))
+            );
+        }
+    }
+    if !found {
+        if !json {
+            eprintln!("Best Guess: {}", decrypted(solution).join(" "));
+            for (rank, other) in solutions.iter().enumerate().skip(1) {
+                eprintln!("Guess {}: {}", rank + 1, decrypted(other).join(" "));
+            }
+        }
+        return Err(Error::CrackNotFound(format!(
+            "no key found: the best decryption scores {:.2} per {}, {} above {:.2}; try more --iterations or --restarts",
+            solution.score,
+            ngrams.name(),
+            text,
+            ngrams.threshold()
+        )));
+    }
+    if !json {
+        println!("Key Square: {}", keygen::square_line(&solution.square));
+        println!("Decrypted Text: {}", decrypted(solution).join(" "));
+        for (rank, other) in solutions.iter().enumerate().skip(1) {
+            println!(
+                "Key Square {}: {}",
+                rank + 1,
+                keygen::square_line(&other.square)
+            );
+            println!(
+                "Decrypted Text {}: {}",
+                rank + 1,
+                decrypted(other).join(" ")
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ngram_search() {
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let english: Vec<u8> = b"ITWASTHEBESTOFTIMES".iter().map(|b| b - b'A').collect();
+        let noise: Vec<u8> = b"QZXJKQVZXWQJZKXVQZX".iter().map(|b| b - b'A').collect();
+        assert!(ngrams.score(&english) / 16.0 > ngrams.threshold());
+        assert!(ngrams.score(&noise) / 16.0 < ngrams.threshold());
+        let bigrams: Ngrams = Ngrams::builtin(Language::English, 2);
+        assert!(bigrams.score(&english) / 18.0 > bigrams.threshold());
+        assert!(bigrams.score(&noise) / 18.0 < bigrams.threshold());
+        assert_eq!(bigrams.name(), "bigram");
+        let ranked: Vec<(String, f64)> = bigrams.ranked();
+        assert_eq!(ranked.len(), 26 * 26);
+        assert_eq!(ranked[0].0, "TH");
+        assert!(ranked.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        let custom: Ngrams = Ngrams::parse("# counts\nthe 3\nAND 1\n").unwrap();
+        assert_eq!(custom.name(), "trigram");
+        assert!(Ngrams::parse("THE 3\nTH 1").is_err());
+        assert!(Ngrams::parse("TH3 1").is_err());
+        assert!(Ngrams::parse("# no counts").is_err());
+        // Every built-in table tells its own language from noise
+        #[cfg(feature = "lang-de")]
+        {
+            let german: Vec<u8> = b"ESWAREINMALEINKOENIG".iter().map(|b| b - b'A').collect();
+            let table: Ngrams = Ngrams::builtin(Language::parse("de").unwrap(), 4);
+            assert!(table.score(&german) / 17.0 > table.threshold());
+            assert!(table.score(&noise) / 16.0 < table.threshold());
+            let (language, _) = identify("Es war einmal ein Koenig, der hatte eine Tochter");
+            assert_eq!(language, Some(Language::German));
+        }
+        for &language in Language::ALL {
+            let table: Ngrams = Ngrams::builtin(language, 2);
+            assert!(table.score(&noise) / 18.0 < table.threshold());
+        }
+        assert!(Language::parse("xx").is_err());
+        // A language left out of the build is refused like an unknown one
+        #[cfg(not(feature = "lang-it"))]
+        assert!(matches!(Language::parse("it"), Err(Error::Usage(_))));
+        let (_, scores) = identify("It was the best of times");
+        assert_eq!(scores.len(), Language::ALL.len());
+        assert_eq!(
+            identify("It was the best of times").0,
+            Some(Language::English)
+        );
+        assert_eq!(identify("QZXJKQVZXWQJZKXVQZX").0, None);
+
+        // The fast decryption of the search agrees with the cipher
+        let variant: Variant = Variant::default();
+        let square: KeySquare = keygen::random_square(&mut Rng::new(3), &variant);
+        let ciphertext: Vec<char> = crate::playfair_cipher(
+            "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES",
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        )
+        .chars()
+        .collect();
+        let symbols: Vec<char> = variant.symbols();
+        let search: Search = Search::new(&ciphertext, &variant, &ngrams, &[], None);
+        let cells: Vec<usize> = square
+            .rows()
+            .iter()
+            .flatten()
+            .map(|c| symbols.iter().position(|s| s == c).unwrap())
+            .collect();
+        let mut plaintext: Vec<u8> = Vec::new();
+        search.score(&cells, &mut plaintext);
+        let plaintext: String = plaintext.iter().map(|&l| (b'A' + l) as char).collect();
+        assert_eq!(plaintext, "ITWASTHEBESTOFTIMESITWASTHEWORSTOFTIMESX");
+        // Seriated pairs are written back in the order they are read
+        let seriated: Variant = Variant {
+            period: Some(5),
+            ..Variant::default()
+        };
+        let encrypted: Vec<char> = crate::playfair_cipher(
+            &plaintext,
+            square.rows(),
+            &seriated,
+            crate::CipherMode::Encrypt,
+        )
+        .chars()
+        .collect();
+        let mut decrypted: Vec<u8> = Vec::new();
+        Search::new(&encrypted, &seriated, &ngrams, &[], None).score(&cells, &mut decrypted);
+        assert_eq!(
+            decrypted
+                .iter()
+                .map(|&l| (b'A' + l) as char)
+                .collect::<String>(),
+            plaintext
+        );
+        // Shifting the rows and columns changes neither the canonical form
+        // nor the decryption, while mirroring changes both
+        let shifted: Vec<usize> = (0..25)
+            .map(|cell| cells[(cell / 5 + 1) % 5 * 5 + (cell + 2) % 5])
+            .collect();
+        let mirrored: Vec<usize> = cells.iter().rev().copied().collect();
+        let (mut canonical, mut other) = (Vec::new(), Vec::new());
+        search.canonical(&cells, &mut canonical);
+        assert_eq!(canonical[0], 0);
+        search.canonical(&shifted, &mut other);
+        assert_eq!(canonical, other);
+        let mut decrypted: Vec<u8> = Vec::new();
+        search.score(&shifted, &mut decrypted);
+        assert_eq!(
+            decrypted
+                .iter()
+                .map(|&l| (b'A' + l) as char)
+                .collect::<String>(),
+            plaintext
+        );
+        search.canonical(&mirrored, &mut other);
+        assert_ne!(canonical, other);
+
+        let strategy: Strategy = Strategy {
+            iterations: 2000,
+            restarts: 2,
+            ..Strategy::default()
+        };
+        let solution: Solution = crack(&ciphertext, &variant, &ngrams, &strategy, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        let letters: String = keygen::square_line(&solution.square);
+        assert_eq!(
+            KeySquare::from_letters(&letters, &variant),
+            Ok(solution.square)
+        );
+        assert_eq!(solution.plaintext.len(), ciphertext.len());
+
+        let hill: Strategy = Strategy {
+            solver: Solver::Hill,
+            moves: parse_moves("swap, rows,swap").unwrap(),
+            ..strategy
+        };
+        assert_eq!(hill.moves, vec![Move::Swap, Move::Rows]);
+        let solution: Solution = crack(&ciphertext, &variant, &ngrams, &hill, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        assert_eq!(solution.plaintext.len(), ciphertext.len());
+        assert!(parse_moves("swap,spin").is_err());
+        let schedule: Schedule = Schedule {
+            start: 20.0,
+            cooling: Cooling::Geometric,
+            ..Schedule::default()
+        };
+        assert_eq!(schedule.temperature(0.0), 20.0);
+        assert!((schedule.temperature(1.0) - END_TEMPERATURE).abs() < 1e-9);
+
+        let genetic: Strategy = Strategy {
+            solver: Solver::Genetic(Genetics {
+                population: 10,
+                ..Genetics::default()
+            }),
+            ..hill
+        };
+        let solution: Solution = crack(&ciphertext, &variant, &ngrams, &genetic, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        assert_eq!(solution.plaintext.len(), ciphertext.len());
+        let (first, second): (Vec<usize>, Vec<usize>) =
+            ((0..25).collect(), (0..25).rev().collect());
+        let mut child: Vec<usize> = crossover(&mut Rng::new(1), &first, &second);
+        child.sort_unstable();
+        assert_eq!(child, first);
+
+        // The beam search repeats itself whatever the generator
+        let beam: Strategy = Strategy {
+            solver: Solver::Beam(2),
+            moves: Strategy::default().moves,
+            iterations: 2_000,
+            ..Strategy::default()
+        };
+        let first: Solution = crack(&ciphertext, &variant, &ngrams, &beam, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        let second: Solution = crack(&ciphertext, &variant, &ngrams, &beam, &mut Rng::new(2))
+            .unwrap()
+            .remove(0);
+        assert_eq!(first.square, second.square);
+        assert_eq!(first.plaintext, second.plaintext);
+        // Without a GPU the batches are scored on the CPU the same way
+        let batched: Strategy = Strategy { gpu: true, ..beam };
+        let third: Solution = crack(&ciphertext, &variant, &ngrams, &batched, &mut Rng::new(3))
+            .unwrap()
+            .remove(0);
+        assert_eq!(first.square, third.square);
+
+        // A time limit ends restarts that would run for hours
+        let limited: Strategy = Strategy {
+            iterations: usize::MAX,
+            threads: 2,
+            time_limit: Some(Duration::from_millis(100)),
+            ..Strategy::default()
+        };
+        let started: Instant = Instant::now();
+        let solution: Solution = crack(&ciphertext, &variant, &ngrams, &limited, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(solution.plaintext.len(), ciphertext.len());
+
+        // So do a budget of tables and a target score any table reaches
+        for limited in [
+            Strategy {
+                max_iterations: Some(50_000),
+                time_limit: None,
+                ..limited.clone()
+            },
+            Strategy {
+                target: Some(-100.0),
+                time_limit: None,
+                ..limited.clone()
+            },
+        ] {
+            let started: Instant = Instant::now();
+            crack(&ciphertext, &variant, &ngrams, &limited, &mut Rng::new(1)).unwrap();
+            assert!(started.elapsed() < Duration::from_secs(10));
+        }
+
+        // A crib of every letter leaves the exact solver a few tables
+        let pangram: String = crate::playfair_cipher(
+            "THEQUICKBROWNFOXIUMPSOVERTHELAZYDOG",
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        );
+        let exact: Strategy = Strategy {
+            solver: Solver::Exact,
+            cribs: cribs::parse(
+                &format!("THEQUICKBROWNFOXIUMPSOVERTHELAZYDOGX={}", pangram),
+                &variant,
+            )
+            .unwrap(),
+            words: Some(Dictionary::builtin(&variant)),
+            ..Strategy::default()
+        };
+        let pangram: Vec<char> = pangram.chars().collect();
+        let solution: Solution = crack(&pangram, &variant, &ngrams, &exact, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        assert_eq!(solution.plaintext, "THEQUICKBROWNFOXIUMPSOVERTHELAZYDOGX");
+        assert!(solution.words.is_some_and(|words| words > 0.9));
+        // Near-misses come after it, best first, with other decryptions
+        let top: Strategy = Strategy {
+            top: 3,
+            ..exact.clone()
+        };
+        let solutions: Vec<Solution> =
+            crack(&pangram, &variant, &ngrams, &top, &mut Rng::new(1)).unwrap();
+        assert_eq!(solutions.len(), 3);
+        assert_eq!(solutions[0].plaintext, solution.plaintext);
+        assert!(solutions.windows(2).all(
+            |pair| pair[0].fitness >= pair[1].fitness && pair[0].plaintext != pair[1].plaintext
+        ));
+        let wrong: Strategy = Strategy {
+            cribs: cribs::parse("TH=TX", &variant).unwrap(),
+            ..exact
+        };
+        assert!(matches!(
+            crack(&pangram, &variant, &ngrams, &wrong, &mut Rng::new(1)),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_schedule_flags() {
+        let schedule = |flags: &[&str], letters: usize| {
+            let args: Vec<&str> = [
+                &["playfair", "crack", "--iterations", "1000"],
+                flags,
+                &["CIPHERTEXT"],
+            ]
+            .concat();
+            let matches: ArgMatches = crate::cli::build_cli().try_get_matches_from(args).unwrap();
+            strategy_from_matches(
+                matches.subcommand_matches("crack").unwrap(),
+                letters,
+                solver::SOLVERS,
+            )
+            .map(|strategy| strategy.solver)
+        };
+        // The preset follows the length of the ciphertext
+        assert_eq!(Preset::of(100), Preset::Short);
+        assert_eq!(Preset::of(600), Preset::Medium);
+        assert_eq!(
+            schedule(&[], 100).unwrap(),
+            Solver::Anneal(Schedule {
+                start: 12.0,
+                reheat: Some(250),
+                ..Schedule::default()
+            })
+        );
+        assert_eq!(
+            schedule(&["--schedule", "long"], 100).unwrap(),
+            schedule(&[], 1000).unwrap()
+        );
+        // The flags override the preset
+        let Solver::Anneal(geometric) = schedule(
+            &[
+                "--temperature",
+                "10",
+                "--cooling-factor",
+                "0.999",
+                "--reheat",
+                "0",
+            ],
+            100,
+        )
+        .unwrap() else {
+            panic!("not annealing");
+        };
+        assert_eq!(
+            (geometric.start, geometric.cooling, geometric.reheat),
+            (10.0, Cooling::Geometric, None)
+        );
+        assert!((geometric.temperature(1.0) - 10.0 * 0.999f64.powi(1000)).abs() < 1e-9);
+        for flags in [
+            &["--cooling-factor", "1.5"][..],
+            &["--cooling", "linear", "--cooling-factor", "0.9"],
+            &["--temperature=0"],
+            &["--solver", "hill", "--reheat", "100"],
+        ] {
+            assert!(matches!(schedule(flags, 100), Err(Error::Usage(_))));
+        }
+
+        // Adaptive cooling heats up when it keeps too few worse tables
+        let mut thermostat: Thermostat = Thermostat {
+            since: 0,
+            scale: 1.0,
+            worse: 0,
+            kept: 0,
+        };
+        for _ in 0..ADAPT_INTERVAL {
+            thermostat.adapt(-1.0, false, 0.5);
+        }
+        assert_eq!(thermostat.scale, ADAPT_STEP);
+        for _ in 0..ADAPT_INTERVAL {
+            thermostat.adapt(-1.0, true, 0.5);
+        }
+        assert_eq!(thermostat.scale, 1.0);
+        // A reheat starts the schedule over for the changes left
+        thermostat.since = 600;
+        assert_eq!(thermostat.progress(800, 1000), 0.5);
+    }
+
+    #[test]
+    fn test_progress_flags() {
+        let strategy = |flags: &[&str]| {
+            let args: Vec<&str> = [&["playfair", "crack"], flags, &["CIPHERTEXT"]].concat();
+            let matches: ArgMatches = crate::cli::build_cli().try_get_matches_from(args).unwrap();
+            strategy_from_matches(
+                matches.subcommand_matches("crack").unwrap(),
+                100,
+                solver::SOLVERS,
+            )
+        };
+        assert_eq!(strategy(&[]).unwrap().progress, None);
+        assert_eq!(
+            strategy(&["--progress", "json", "--progress-interval", "0.5"])
+                .unwrap()
+                .progress,
+            Some(Progress {
+                format: ProgressFormat::Json,
+                interval: Duration::from_millis(500),
+            })
+        );
+        assert_eq!(
+            strategy(&["--progress", "text"]).unwrap().progress,
+            Some(Progress {
+                format: ProgressFormat::Text,
+                interval: Duration::from_secs(1),
+            })
+        );
+        for flags in [
+            &["--progress-interval", "2"][..],
+            &["--progress", "text", "--progress-interval", "0"],
+        ] {
+            assert!(matches!(strategy(flags), Err(Error::Usage(_))));
+        }
+        // A search needs a thread and a restart to run at all
+        for flags in [&["--threads", "0"], &["--restarts", "0"]] {
+            assert!(matches!(strategy(flags), Err(Error::Usage(_))));
+        }
+
+        // Reporting leaves the search as it was
+        let variant: Variant = Variant::default();
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let square: KeySquare = keygen::random_square(&mut Rng::new(5), &variant);
+        let ciphertext: Vec<char> = crate::playfair_cipher(
+            "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES IT WAS THE AGE OF WISDOM",
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        )
+        .chars()
+        .collect();
+        let quiet: Strategy = Strategy {
+            iterations: 10_000,
+            restarts: 1,
+            ..Strategy::default()
+        };
+        let reported: Strategy = Strategy {
+            progress: Some(Progress {
+                format: ProgressFormat::Json,
+                interval: Duration::ZERO,
+            }),
+            ..quiet.clone()
+        };
+        let solve = |strategy: &Strategy| {
+            crack(&ciphertext, &variant, &ngrams, strategy, &mut Rng::new(1))
+                .unwrap()
+                .remove(0)
+        };
+        assert_eq!(solve(&quiet).square, solve(&reported).square);
+    }
+
+    #[test]
+    fn test_several_ciphertexts() {
+        let crack = |args: &[&str]| {
+            let args: Vec<&str> = [&["playfair", "crack"], args].concat();
+            let matches: ArgMatches = crate::cli::build_cli().try_get_matches_from(args).unwrap();
+            run(
+                matches.subcommand_matches("crack").unwrap(),
+                solver::SOLVERS,
+            )
+        };
+        assert!(matches!(
+            crack(&["--find-period", "ABCD", "EFGH"]),
+            Err(Error::Usage(_))
+        ));
+        assert!(matches!(
+            crack(&["--period", "5", "ABCD", "EFGH"]),
+            Err(Error::Usage(_))
+        ));
+        // Every message is a whole number of digrams, even if they add up
+        assert!(matches!(
+            crack(&["ABCDE", "FGH"]),
+            Err(Error::InvalidInput(e)) if e.contains("ciphertext 1")
+        ));
+    }
+
+    #[test]
+    fn test_resume_checkpoint() {
+        let variant: Variant = Variant::default();
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let square: KeySquare = keygen::random_square(&mut Rng::new(5), &variant);
+        let ciphertext: Vec<char> = crate::playfair_cipher(
+            "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES IT WAS THE AGE OF WISDOM",
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        )
+        .chars()
+        .collect();
+        let path: std::path::PathBuf = std::env::temp_dir().join("playfair_test_resume.json");
+        let path: String = path.to_str().unwrap().to_string();
+        let strategy: Strategy = Strategy {
+            iterations: 10_000,
+            restarts: 2,
+            threads: 1,
+            ..Strategy::default()
+        };
+        let whole: Solution = crack(&ciphertext, &variant, &ngrams, &strategy, &mut Rng::new(1))
+            .unwrap()
+            .remove(0);
+        // A run out of time leaves its first restart climbing and the
+        // second one pending
+        let stopped: Strategy = Strategy {
+            checkpoint: Some(path.clone()),
+            time_limit: Some(Duration::from_nanos(1)),
+            ..strategy.clone()
+        };
+        crack(&ciphertext, &variant, &ngrams, &stopped, &mut Rng::new(1)).unwrap();
+        let checkpoint: Checkpoint = checkpoint::read(&path).unwrap();
+        assert!(matches!(checkpoint.restarts[0], Restart::Climbing(_)));
+        assert_eq!(checkpoint.restarts[1], Restart::Pending);
+        // Carrying on finds what the run that never stopped found, whatever
+        // the generator
+        let resumed: Strategy = Strategy {
+            checkpoint: Some(path.clone()),
+            resume: Some(checkpoint.clone()),
+            ..strategy.clone()
+        };
+        let solution: Solution = crack(&ciphertext, &variant, &ngrams, &resumed, &mut Rng::new(9))
+            .unwrap()
+            .remove(0);
+        assert_eq!(solution.square, whole.square);
+        let done: Checkpoint = checkpoint::read(&path).unwrap();
+        // The first restart found the table, so the second never ran
+        assert!(matches!(done.restarts[0], Restart::Done { .. }));
+        let other: Strategy = Strategy {
+            iterations: 20_000,
+            ..resumed.clone()
+        };
+        assert_eq!(
+            crack(&ciphertext, &variant, &ngrams, &other, &mut Rng::new(1)).err(),
+            Some(Error::Usage(String::from(
+                "the checkpoint is of a search with another number of iterations"
+            )))
+        );
+        // Nor does it carry on under another variant
+        let seriated: Variant = Variant {
+            period: Some(5),
+            ..variant.clone()
+        };
+        assert_eq!(
+            crack(&ciphertext, &seriated, &ngrams, &resumed, &mut Rng::new(1)).err(),
+            Some(Error::Usage(String::from(
+                "the checkpoint is of a search with another period"
+            )))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_dictionary_words() {
+        let variant: Variant = Variant::default();
+        let dictionary: Dictionary = Dictionary::builtin(&variant);
+        // The fillers of doubled letters and of the end count as covered
+        assert_eq!(
+            dictionary.coverage("ITWASTHEBESTOFTIMESITWASTHEWORSTOFTIMESX", &variant),
+            1.0
+        );
+        assert_eq!(dictionary.coverage("LETXTERS", &variant), 1.0);
+        assert_eq!(dictionary.coverage("EXEMPT", &variant), 1.0);
+        assert!(dictionary.coverage("QZVKWQPZXVKQ", &variant) < 0.5);
+        let custom: Dictionary = Dictionary::parse("balloon\n\n", &variant);
+        assert_eq!(custom.coverage("BALXLOONZK", &variant), 0.8);
+        assert_eq!(custom.coverage("", &variant), 0.0);
+    }
+
+    #[test]
+    fn test_crack_english() {
+        // A known text of a few hundred letters is recovered whole from a
+        // fixed seed, with a schedule as `crack` picks for its length
+        let text: &str = "Call me Ishmael. Some years ago, never mind how long precisely, \
+            having little or no money in my purse, and nothing particular to interest me on \
+            shore, I thought I would sail about a little and see the watery part of the world. \
+            It is a way I have of driving off the spleen and regulating the circulation. \
+            Whenever I find myself growing grim about the mouth; whenever it is a damp, \
+            drizzly November in my soul";
+        let variant: Variant = Variant::default();
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let table: Vec<Vec<char>> = crate::generate_playfair_table("ZEBRASTRIPES", &variant);
+        let ciphertext: Vec<char> =
+            crate::playfair_cipher(text, &table, &variant, crate::CipherMode::Encrypt)
+                .chars()
+                .collect();
+        let plaintext: String = crate::prepare_text(text, &variant).0.into_iter().collect();
+        assert!(ciphertext.len() > 300);
+        let strategy: Strategy = Strategy {
+            solver: Solver::Anneal(Preset::of(ciphertext.len()).schedule(100_000)),
+            iterations: 100_000,
+            restarts: 1,
+            threads: 1,
+            ..Strategy::default()
+        };
+        let solution: Solution =
+            crack(&ciphertext, &variant, &ngrams, &strategy, &mut Rng::new(14))
+                .unwrap()
+                .remove(0);
+        assert_eq!(solution.plaintext, plaintext);
+        assert!(solution.score > ngrams.threshold());
+    }
+}
@@ -0,0 +1,1674 @@
+//! The `analyze` subcommand: aids for breaking a ciphertext by hand.
+//!
+//! `analyze freq` counts the digrams of a ciphertext, the first step of
+//! breaking Playfair by hand. The most common ones are shown next to the
+//! most common digrams of the language, leaving out doubled letters, which
+//! Playfair never encrypts. Digrams that also occur reversed stand out too:
+//! a table that turns `ER` into `XY` turns `RE` into `YX`.
+//!
+//! `analyze stats` measures how far the letters and the digrams of a
+//! ciphertext are from the language and from random symbols: the index of
+//! coincidence, the entropy and the chi-squared statistic. Playfair
+//! ciphertexts have letter statistics near random but digram statistics
+//! near those of the language.
+//!
+//! `analyze detect` weighs whether a ciphertext is Playfair at all. The
+//! strongest sign is that no digram doubles a letter: a ciphertext of other
+//! letters with the same index of coincidence `ic` would have no doubled
+//! digram among `d` digrams with a chance of only `(1 - ic)^d`. The
+//! confidence is one minus this chance, lowered if the digrams are less
+//! than half way from random symbols to the language.
+//!
+//! `analyze period` estimates the period of a seriated ciphertext, whose
+//! letters every block of `2 * period` pairs across two rows. Playfair
+//! encrypts a repeated plaintext digram to the same ciphertext digram, so
+//! pairing the letters as the right period does makes the pairs repeat
+//! about as often as the digrams of the language, while any other spacing
+//! mixes up the pairs and they repeat closer to random.
+//!
+//! `analyze same-key A B` weighs whether two ciphertexts share a key.
+//! Under one key a plaintext digram always becomes the same ciphertext
+//! digram, so the digrams of the two ciphertexts coincide about as often
+//! as those of two texts of the language. Under different keys they are
+//! matched up at random, and coincide as often as the digrams of `A`
+//! matched with a random reordering of the digrams of `B`: the mean and the
+//! variance of this permutation test give a z-score, and the normal tail
+//! the chance of so many coincidences under different keys. Tables from
+//! keywords share the letters after their keywords, so different keys
+//! coincide more often than at random: the ciphertexts only likely share a
+//! key if their coincidences are also nearer to those of one key, by the
+//! ratio between them.
+//!
+//! `analyze heatmap` shades the digram counts of a ciphertext on a grid of
+//! first by second letter, which shows at a glance the rows and columns of
+//! letters that pair often and the diagonal Playfair leaves empty. With a
+//! key it also shades how often every cell of the table holds a letter of
+//! the ciphertext or of its decryption, and counts the rules applied.
+//!
+//! `analyze fitness` ranks candidate decryptions by the quadgram score
+//! `crack` ranks its own by, as the `fitness` module computes it.
+//!
+//! `--format json` and `--format csv` print the statistics of `freq`,
+//! `stats`, `detect`, `period`, `same-key` and `fitness` for other
+//! programs instead, from the same structs as the text.
+//!
+//! `--drag-crib WORD` slides a suspected plaintext word along the
+//! ciphertext. Every offset the Playfair rules rule out is shown with the
+//! reason; every other one with the cells of a table agreeing with the
+//! digrams the word covers and the rest of the ciphertext these cells
+//! decrypt. Short words often fit several tables, of which only one is
+//! shown, so the decrypted digrams outside the word are a guess. They are
+//! scored on the bigrams of the language, so the right offset tends to
+//! score highest.
+
+use crate::Variant;
+use crate::analysis::{self, Language, Ngrams};
+use crate::cribs::{self, Drag, PartialKeySquare};
+use crate::error::Error;
+use crate::fitness;
+use clap::ArgMatches;
+use serde::Serialize;
+use std::io::{StdoutLock, Write};
+
+/// Longest period `crack --find-period` tries, as `analyze period` does by
+/// default
+pub const MAX_PERIOD: usize = 20;
+
+/// Runs the `analyze` subcommand.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * A usage error if the variant can't be analyzed or a setting is out of
+///   range, or an invalid input error if the ciphertext is unusable.
+pub(crate) fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("freq", sub)) => print_frequencies(sub),
+        Some(("stats", sub)) => print_statistics(sub),
+        Some(("detect", sub)) => print_detection(sub),
+        Some(("period", sub)) => print_period(sub),
+        Some(("same-key", sub)) => print_same_key(sub),
+        Some(("heatmap", sub)) => print_heatmap(sub),
+        Some(("fitness", sub)) => print_fitness(sub),
+        _ => drag(matches),
+    }
+}
+
+/// Reads the variant and the ciphertext of the arguments.
+///
+/// # Returns
+///
+/// * The variant and the ciphertext as symbol indices, or a usage error if
+///   the variant has several rounds or a period.
+fn read_ciphertext(matches: &ArgMatches) -> Result<(Variant, Vec<usize>), Error> {
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    if variant.rounds > 1 || variant.period.is_some() {
+        return Err(Error::Usage(String::from(
+            "analyze only reads single-round ciphertexts without --period",
+        )));
+    }
+    let ciphertext: Vec<usize> =
+        indices(matches.get_one::<String>("ciphertext").unwrap(), &variant);
+    Ok((variant, ciphertext))
+}
+
+/// Reads the language of the arguments, English by default.
+fn language(matches: &ArgMatches) -> Result<Language, Error> {
+    match matches.get_one::<String>("lang") {
+        Some(code) => Language::parse(code),
+        None => Ok(Language::English),
+    }
+}
+
+/// Enum to represent how the statistics are printed
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+/// Reads the `--format` flag of the arguments, text by default.
+fn format(matches: &ArgMatches) -> Format {
+    match matches
+        .get_one::<String>("stats-format")
+        .map(String::as_str)
+    {
+        Some("json") => Format::Json,
+        Some("csv") => Format::Csv,
+        _ => Format::Text,
+    }
+}
+
+/// Prints statistics as pretty JSON to stdout.
+fn print_json<T: Serialize>(statistics: &T) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let json: String =
+        serde_json::to_string_pretty(statistics).map_err(|e| Error::Internal(e.to_string()))?;
+    writeln!(out, "{}", json)?;
+    Ok(())
+}
+
+/// Prints a table as CSV to stdout, quoting the fields that hold a comma,
+/// a quote or a line break.
+fn print_csv(header: &[&str], rows: &[Vec<String>]) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let quote = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+    writeln!(out, "{}", header.join(","))?;
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| quote(field)).collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+/// A digram of the ciphertext and how often it occurs
+#[derive(Debug, Clone, Serialize)]
+pub struct DigramCount {
+    /// The two symbols
+    pub digram: String,
+    /// How often it occurs
+    pub count: usize,
+    /// Its share of all digrams, from 0 to 1
+    pub share: f64,
+    /// How often it occurs reversed
+    pub reversed_count: usize,
+}
+
+/// A digram of the language and its share of running text
+#[derive(Debug, Clone, Serialize)]
+pub struct DigramShare {
+    /// The two letters
+    pub digram: String,
+    /// Its share of the digrams without doubled letters, from 0 to 1
+    pub share: f64,
+}
+
+/// A digram of the ciphertext that also occurs reversed
+#[derive(Debug, Clone, Serialize)]
+pub struct ReversedPair {
+    /// The more common way round
+    pub digram: String,
+    /// The other way round
+    pub reversed: String,
+    /// How often the digram occurs
+    pub count: usize,
+    /// How often it occurs reversed
+    pub reversed_count: usize,
+}
+
+/// The digram counts of a ciphertext next to those of the language
+#[derive(Debug, Clone, Serialize)]
+pub struct Frequencies {
+    /// How many symbols the ciphertext holds
+    pub letters: usize,
+    /// How many digrams the ciphertext holds
+    pub digrams: usize,
+    /// How many of them are distinct
+    pub distinct: usize,
+    /// The language the digrams are compared with
+    pub language: String,
+    /// The most common digrams of the ciphertext, most common first
+    pub common: Vec<DigramCount>,
+    /// The most common digrams of the language, most common first
+    pub expected: Vec<DigramShare>,
+    /// How many distinct digrams also occur reversed
+    pub reversed_digrams: usize,
+    /// The most common pairs of reversed digrams, each once
+    pub reversed: Vec<ReversedPair>,
+}
+
+/// Counts the digrams of a ciphertext.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext as symbol indices.
+/// * `symbols` - The symbols of the table.
+/// * `language` - The language the digrams are compared with.
+/// * `top` - How many digrams and reversed pairs are kept.
+pub fn frequencies(
+    ciphertext: &[usize],
+    symbols: &[char],
+    language: Language,
+    top: usize,
+) -> Frequencies {
+    let n: usize = symbols.len();
+    let digrams: usize = ciphertext.len() / 2;
+    let counts: Vec<usize> = digram_counts(ciphertext, n);
+    let name =
+        |digram: usize| -> String { [symbols[digram / n], symbols[digram % n]].iter().collect() };
+    let back = |digram: usize| digram % n * n + digram / n;
+    let mut common: Vec<(usize, usize)> = counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(digram, &count)| (digram, count))
+        .collect();
+    common.sort_by_key(|&(digram, count)| (std::cmp::Reverse(count), digram));
+    // Playfair never encrypts a doubled letter, so the language is
+    // compared without them
+    let expected: Vec<(String, f64)> = Ngrams::builtin(language, 2)
+        .ranked()
+        .into_iter()
+        .filter(|(bigram, _)| bigram[..1] != bigram[1..])
+        .collect();
+    let total: f64 = expected.iter().map(|(_, p)| p).sum();
+    // Every pair once, the more common way round first
+    let mut reversed: Vec<(usize, usize, usize, usize)> = common
+        .iter()
+        .filter_map(|&(digram, count)| {
+            let back: usize = back(digram);
+            let back_count: usize = counts[back];
+            let first: bool = count > back_count || (count == back_count && digram < back);
+            (back != digram && back_count > 0 && first).then_some((digram, back, count, back_count))
+        })
+        .collect();
+    reversed.sort_by_key(|&(digram, _, count, back_count)| {
+        (std::cmp::Reverse(count + back_count), digram)
+    });
+    Frequencies {
+        letters: ciphertext.len(),
+        digrams,
+        distinct: common.len(),
+        language: language.name().to_string(),
+        common: common
+            .iter()
+            .take(top)
+            .map(|&(digram, count)| DigramCount {
+                digram: name(digram),
+                count,
+                share: count as f64 / digrams as f64,
+                reversed_count: if back(digram) == digram {
+                    0
+                } else {
+                    counts[back(digram)]
+                },
+            })
+            .collect(),
+        expected: expected
+            .into_iter()
+            .take(top)
+            .map(|(digram, p)| DigramShare {
+                digram,
+                share: p / total,
+            })
+            .collect(),
+        reversed_digrams: 2 * reversed.len(),
+        reversed: reversed
+            .iter()
+            .take(top)
+            .map(|&(digram, back, count, reversed_count)| ReversedPair {
+                digram: name(digram),
+                reversed: name(back),
+                count,
+                reversed_count,
+            })
+            .collect(),
+    }
+}
+
+/// Prints the digram counts of the ciphertext to stdout.
+///
+/// # Returns
+///
+/// * An invalid input error if the ciphertext holds no digram.
+fn print_frequencies(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, ciphertext) = read_ciphertext(matches)?;
+    let top: usize = *matches.get_one::<usize>("top").unwrap();
+    let language: Language = language(matches)?;
+    if ciphertext.len() < 2 {
+        return Err(Error::InvalidInput(String::from(
+            "analyze freq needs at least one ciphertext digram",
+        )));
+    }
+    let frequencies: Frequencies = frequencies(&ciphertext, &variant.symbols(), language, top);
+    match format(matches) {
+        Format::Json => return print_json(&frequencies),
+        Format::Csv => {
+            let rows: Vec<Vec<String>> =
+                (0..frequencies.common.len().max(frequencies.expected.len()))
+                    .map(|rank| {
+                        let common: Option<&DigramCount> = frequencies.common.get(rank);
+                        let expected: Option<&DigramShare> = frequencies.expected.get(rank);
+                        vec![
+                            (rank + 1).to_string(),
+                            common.map_or(String::new(), |c| c.digram.clone()),
+                            common.map_or(String::new(), |c| c.count.to_string()),
+                            common.map_or(String::new(), |c| c.share.to_string()),
+                            common.map_or(String::new(), |c| c.reversed_count.to_string()),
+                            expected.map_or(String::new(), |e| e.digram.clone()),
+                            expected.map_or(String::new(), |e| e.share.to_string()),
+                        ]
+                    })
+                    .collect();
+            print_csv(
+                &[
+                    "rank",
+                    "digram",
+                    "count",
+                    "share",
+                    "reversed_count",
+                    "language_digram",
+                    "language_share",
+                ],
+                &rows,
+            )?;
+            return Ok(());
+        }
+        Format::Text => {}
+    }
+    writeln!(
+        out,
+        "Letters: {} in {} digrams, {} distinct",
+        frequencies.letters, frequencies.digrams, frequencies.distinct
+    )?;
+    writeln!(
+        out,
+        "{:<6} {:>5} {:>6}   {:<7} {:>6}",
+        "Digram",
+        "Count",
+        "Share",
+        language.name(),
+        "Share"
+    )?;
+    for (common, expected) in frequencies.common.iter().zip(&frequencies.expected) {
+        writeln!(
+            out,
+            "{:<6} {:>5} {:>5.1}%   {:<7} {:>5.1}%",
+            common.digram,
+            common.count,
+            100.0 * common.share,
+            expected.digram,
+            100.0 * expected.share
+        )?;
+    }
+    writeln!(
+        out,
+        "Reversed pairs: {} of {} distinct digrams also occur reversed",
+        frequencies.reversed_digrams, frequencies.distinct
+    )?;
+    for pair in &frequencies.reversed {
+        writeln!(
+            out,
+            "{} {} {:>5} {:>5}",
+            pair.digram, pair.reversed, pair.count, pair.reversed_count
+        )?;
+    }
+    Ok(())
+}
+
+/// Statistics of the symbols or digrams of a text
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct Level {
+    /// The probability that two symbols drawn from the text are the same
+    pub coincidence: f64,
+    /// The entropy in bits
+    pub entropy: f64,
+    /// The chi-squared statistic against the language
+    pub chi_squared: f64,
+}
+
+impl Level {
+    /// Measures counts against the probabilities of the language.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - How often every symbol or digram occurs.
+    /// * `expected` - The probabilities of the language, in the same order
+    ///   and adding up to 1. Counts of probability 0 are left out of the
+    ///   chi-squared statistic.
+    fn measure(counts: &[usize], expected: &[f64]) -> Self {
+        let total: f64 = counts.iter().sum::<usize>() as f64;
+        let coincidence: f64 = counts
+            .iter()
+            .map(|&count| (count * count.saturating_sub(1)) as f64)
+            .sum::<f64>()
+            / (total * (total - 1.0)).max(1.0);
+        let entropy: f64 = counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p: f64 = count as f64 / total;
+                -p * p.log2()
+            })
+            .sum();
+        let chi_squared: f64 = counts
+            .iter()
+            .zip(expected)
+            .filter(|&(_, &p)| p > 0.0)
+            .map(|(&count, &p)| (count as f64 - total * p).powi(2) / (total * p))
+            .sum();
+        Level {
+            coincidence,
+            entropy,
+            chi_squared,
+        }
+    }
+
+    /// Returns the statistics of the language itself, whose chi-squared
+    /// statistic is 0.
+    fn expected(expected: &[f64]) -> Self {
+        Level {
+            coincidence: expected.iter().map(|p| p * p).sum(),
+            entropy: expected
+                .iter()
+                .filter(|&&p| p > 0.0)
+                .map(|&p| -p * p.log2())
+                .sum(),
+            chi_squared: 0.0,
+        }
+    }
+
+    /// Returns the statistics of `n` equally likely symbols or digrams.
+    fn random(n: usize) -> Self {
+        Level {
+            coincidence: 1.0 / n as f64,
+            entropy: (n as f64).log2(),
+            chi_squared: 0.0,
+        }
+    }
+}
+
+/// Works out the probabilities of the symbols of the table and of their
+/// digrams in a language, from its built-in bigram counts.
+///
+/// Letters the table lacks and symbols that aren't letters get a
+/// probability of 0, as do doubled letters, which Playfair never encrypts.
+///
+/// # Returns
+///
+/// * The probability of every symbol, and of every digram by first symbol
+///   times the number of symbols plus second, each adding up to 1.
+fn probabilities(symbols: &[char], language: Language) -> (Vec<f64>, Vec<f64>) {
+    let mut bigrams: Vec<f64> = vec![0.0; 26 * 26];
+    for (bigram, p) in Ngrams::builtin(language, 2).ranked() {
+        let letters: Vec<usize> = bigram.bytes().map(|b| usize::from(b - b'A')).collect();
+        bigrams[letters[0] * 26 + letters[1]] = p;
+    }
+    let letters: Vec<Option<usize>> = symbols
+        .iter()
+        .map(|&c| analysis::letter_index(c).map(usize::from))
+        .collect();
+    let n: usize = symbols.len();
+    let mut monograms: Vec<f64> = vec![0.0; n];
+    let mut digrams: Vec<f64> = vec![0.0; n * n];
+    for (first, a) in letters.iter().enumerate() {
+        for (second, b) in letters.iter().enumerate() {
+            if let (Some(a), Some(b)) = (a, b) {
+                // Either letter of a bigram counts, as the counts stop at
+                // the ends of words
+                monograms[first] += bigrams[a * 26 + b];
+                monograms[second] += bigrams[a * 26 + b];
+                if first != second {
+                    digrams[first * n + second] = bigrams[a * 26 + b];
+                }
+            }
+        }
+    }
+    for probabilities in [&mut monograms, &mut digrams] {
+        let total: f64 = probabilities.iter().sum();
+        probabilities.iter_mut().for_each(|p| *p /= total);
+    }
+    (monograms, digrams)
+}
+
+/// Counts every digram of a text, by first symbol times `n` plus second.
+fn digram_counts(text: &[usize], n: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = vec![0; n * n];
+    for pair in text.chunks_exact(2) {
+        counts[pair[0] * n + pair[1]] += 1;
+    }
+    counts
+}
+
+/// The statistics of the letters or the digrams of a ciphertext, of the
+/// language and of random symbols
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Levels {
+    /// Measured on the ciphertext
+    pub ciphertext: Level,
+    /// Of the language, whose chi-squared statistic is 0
+    pub language: Level,
+    /// Of equally likely symbols or digrams
+    pub random: Level,
+}
+
+/// How far the letters and the digrams of a ciphertext are from the
+/// language and from random symbols
+#[derive(Debug, Clone, Serialize)]
+pub struct Statistics {
+    /// The language the ciphertext is compared with
+    pub language: String,
+    /// The statistics of the letters
+    pub letters: Levels,
+    /// The statistics of the digrams
+    pub digrams: Levels,
+}
+
+/// Measures the letters and the digrams of a ciphertext.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext as symbol indices.
+/// * `symbols` - The symbols of the table.
+/// * `language` - The language the ciphertext is compared with.
+pub fn statistics(ciphertext: &[usize], symbols: &[char], language: Language) -> Statistics {
+    let n: usize = symbols.len();
+    let (monograms, digrams) = probabilities(symbols, language);
+    let mut letter_counts: Vec<usize> = vec![0; n];
+    for &symbol in ciphertext {
+        letter_counts[symbol] += 1;
+    }
+    Statistics {
+        language: language.name().to_string(),
+        letters: Levels {
+            ciphertext: Level::measure(&letter_counts, &monograms),
+            language: Level::expected(&monograms),
+            random: Level::random(n),
+        },
+        digrams: Levels {
+            ciphertext: Level::measure(&digram_counts(ciphertext, n), &digrams),
+            language: Level::expected(&digrams),
+            random: Level::random(n * (n - 1)),
+        },
+    }
+}
+
+/// Prints the statistics of the letters and the digrams of the ciphertext
+/// next to those of the language and of random symbols to stdout.
+///
+/// # Returns
+///
+/// * An invalid input error if the ciphertext holds no digram.
+fn print_statistics(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, ciphertext) = read_ciphertext(matches)?;
+    let language: Language = language(matches)?;
+    if ciphertext.len() < 2 {
+        return Err(Error::InvalidInput(String::from(
+            "analyze stats needs at least one ciphertext digram",
+        )));
+    }
+    let statistics: Statistics = statistics(&ciphertext, &variant.symbols(), language);
+    let (letters, digrams) = (&statistics.letters, &statistics.digrams);
+    let levels: [(&str, Level, Level); 3] = [
+        ("ciphertext", letters.ciphertext, digrams.ciphertext),
+        (language.name(), letters.language, digrams.language),
+        ("random", letters.random, digrams.random),
+    ];
+    // The key in CSV, the label in the text, the value and its places
+    type Measure = (&'static str, &'static str, fn(&Level) -> f64, usize);
+    let measures: [Measure; 3] = [
+        ("coincidence", "Index of coincidence", |l| l.coincidence, 4),
+        ("entropy", "Entropy (bits)", |l| l.entropy, 2),
+        ("chi_squared", "Chi-squared", |l| l.chi_squared, 1),
+    ];
+    // The chi-squared statistic is only measured on the ciphertext
+    let shown = |measure: &str, level: usize| measure != "chi_squared" || level == 0;
+    match format(matches) {
+        Format::Json => print_json(&statistics),
+        Format::Csv => {
+            let mut rows: Vec<Vec<String>> = Vec::new();
+            for (measure, _, value, _) in measures {
+                for (level, (name, letters, digrams)) in levels.iter().enumerate() {
+                    if shown(measure, level) {
+                        rows.push(vec![
+                            measure.to_string(),
+                            name.to_string(),
+                            value(letters).to_string(),
+                            value(digrams).to_string(),
+                        ]);
+                    }
+                }
+            }
+            print_csv(&["statistic", "level", "letters", "digrams"], &rows)?;
+            Ok(())
+        }
+        Format::Text => {
+            writeln!(
+                out,
+                "{:<22} {:>12} {:>12}",
+                "Statistic", "Letters", "Digrams"
+            )?;
+            for (measure, label, value, places) in measures {
+                for (level, (name, letters, digrams)) in levels.iter().enumerate() {
+                    if !shown(measure, level) {
+                        continue;
+                    }
+                    let label: String = match level {
+                        0 => label.to_string(),
+                        1 => format!("  {}", name),
+                        _ => String::from("  Random"),
+                    };
+                    writeln!(
+                        out,
+                        "{:<22} {:>12.places$} {:>12.places$}",
+                        label,
+                        value(letters),
+                        value(digrams)
+                    )?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// One sign the detector looks for
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    /// A short name of the check, such as `even`
+    pub name: &'static str,
+    /// Whether the ciphertext passed it
+    pub passed: bool,
+    /// What was checked, with the values found
+    pub detail: String,
+}
+
+/// How sure the detector is that a ciphertext is Playfair
+#[derive(Debug, Clone, Serialize)]
+pub struct Detection {
+    /// Every check made
+    pub checks: Vec<Check>,
+    /// The confidence that the ciphertext is Playfair, from 0 to 1
+    pub confidence: f64,
+    /// The kind of cipher the letters suggest if it isn't Playfair
+    pub otherwise: String,
+}
+
+/// Weighs the signs that a ciphertext is Playfair.
+///
+/// # Arguments
+///
+/// * `raw` - The letters and digits of the ciphertext as given, before any
+///   letters are merged into the table.
+/// * `ciphertext` - The ciphertext as symbol indices of the table.
+/// * `symbols` - How many symbols the table holds.
+/// * `language` - The language of the plaintext.
+pub fn detect(
+    raw: &[char],
+    ciphertext: &[usize],
+    symbols: &[char],
+    language: Language,
+) -> Detection {
+    let n: usize = symbols.len();
+    let (monograms, digram_probabilities) = probabilities(symbols, language);
+    let mut letter_counts: Vec<usize> = vec![0; n];
+    for &symbol in ciphertext {
+        letter_counts[symbol] += 1;
+    }
+    let letters: Level = Level::measure(&letter_counts, &monograms);
+    let digrams: Level = Level::measure(&digram_counts(ciphertext, n), &digram_probabilities);
+    let expected: [Level; 2] = [
+        Level::expected(&monograms),
+        Level::expected(&digram_probabilities),
+    ];
+    let random: [Level; 2] = [Level::random(n), Level::random(n * (n - 1))];
+
+    let pairs: usize = raw.len() / 2;
+    let doubled: usize = raw
+        .chunks_exact(2)
+        .filter(|pair| pair[0] == pair[1])
+        .count();
+    let mut distinct: Vec<char> = raw.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    // How far the digrams are from random symbols towards the language
+    let structure: f64 = (digrams.coincidence - random[1].coincidence)
+        / (expected[1].coincidence - random[1].coincidence);
+    let check = |name: &'static str, passed: bool, detail: String| Check {
+        name,
+        passed,
+        detail,
+    };
+    let checks: Vec<Check> = vec![
+        check(
+            "even",
+            raw.len().is_multiple_of(2),
+            format!("An even number of letters: {}", raw.len()),
+        ),
+        check(
+            "undoubled",
+            doubled == 0,
+            format!("No digram doubles a letter: {} of {}", doubled, pairs),
+        ),
+        check(
+            "alphabet",
+            distinct.len() <= n,
+            format!("At most {} distinct letters: {}", n, distinct.len()),
+        ),
+        check(
+            "structure",
+            structure >= 0.5,
+            format!(
+                "Digrams about as uneven as {}: index of coincidence {:.4}, {:.4} in {}, {:.4} at random",
+                language.name(),
+                digrams.coincidence,
+                expected[1].coincidence,
+                language.name(),
+                random[1].coincidence
+            ),
+        ),
+    ];
+    let confidence: f64 = if checks[..3].iter().all(|check| check.passed) {
+        let chance: f64 = (1.0 - letters.coincidence.max(random[0].coincidence)).powi(pairs as i32);
+        (1.0 - chance) * (structure / 0.5).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    // Transpositions keep the letters of the language, substitutions only
+    // their unevenness
+    let otherwise: String =
+        if letters.coincidence >= (expected[0].coincidence + random[0].coincidence) / 2.0 {
+            if letters.chi_squared < ciphertext.len() as f64 {
+                format!(
+                    "a transposition, whose letters are those of {}",
+                    language.name()
+                )
+            } else {
+                String::from("a monoalphabetic substitution")
+            }
+        } else {
+            String::from("a polyalphabetic or modern cipher")
+        };
+    Detection {
+        checks,
+        confidence,
+        otherwise,
+    }
+}
+
+/// Prints the checks of the detector and its verdict to stdout.
+///
+/// # Returns
+///
+/// * An invalid input error if the ciphertext holds no digram.
+fn print_detection(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, ciphertext) = read_ciphertext(matches)?;
+    if ciphertext.len() < 2 {
+        return Err(Error::InvalidInput(String::from(
+            "analyze detect needs at least one ciphertext digram",
+        )));
+    }
+    let raw: Vec<char> = matches
+        .get_one::<String>("ciphertext")
+        .unwrap()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(crate::to_upper)
+        .collect();
+    let detection: Detection = detect(&raw, &ciphertext, &variant.symbols(), language(matches)?);
+    match format(matches) {
+        Format::Json => return print_json(&detection),
+        Format::Csv => {
+            let mut rows: Vec<Vec<String>> = detection
+                .checks
+                .iter()
+                .map(|check| {
+                    vec![
+                        check.name.to_string(),
+                        check.passed.to_string(),
+                        check.detail.clone(),
+                    ]
+                })
+                .collect();
+            rows.push(vec![
+                String::from("confidence"),
+                detection.confidence.to_string(),
+                String::new(),
+            ]);
+            rows.push(vec![
+                String::from("otherwise"),
+                String::new(),
+                detection.otherwise.clone(),
+            ]);
+            print_csv(&["name", "value", "detail"], &rows)?;
+            return Ok(());
+        }
+        Format::Text => {}
+    }
+    for check in &detection.checks {
+        writeln!(
+            out,
+            "[{}] {}",
+            if check.passed { "pass" } else { "fail" },
+            check.detail
+        )?;
+    }
+    if detection.confidence >= 0.5 {
+        writeln!(
+            out,
+            "Likely Playfair: {:.0}% confidence",
+            100.0 * detection.confidence
+        )?;
+    } else {
+        writeln!(
+            out,
+            "Unlikely Playfair: {:.0}% confidence; the letters suggest {}",
+            100.0 * detection.confidence,
+            detection.otherwise
+        )?;
+    }
+    Ok(())
+}
+
+/// Measures how often the pairs of a ciphertext repeat for every spacing of
+/// a seriation.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext as symbol indices, of even length.
+/// * `variant` - The variant providing the symbols; its period is ignored.
+/// * `max` - The longest period tried; periods pairing the whole
+///   ciphertext as one block are tried once.
+///
+/// # Returns
+///
+/// * How many times as often as random pairs the pairs repeat, without a
+///   period first and then for every period from 2 on.
+pub fn periods(ciphertext: &[usize], variant: &Variant, max: usize) -> Vec<(Option<usize>, f64)> {
+    let n: usize = variant.symbols().len();
+    let repeats = |period: Option<usize>| {
+        let seriated: Variant = Variant {
+            period,
+            ..variant.clone()
+        };
+        let pairs: Vec<usize> = crate::pair_order(ciphertext, &seriated);
+        let counted: usize = pairs.len() / 2;
+        let repeated: usize = digram_counts(&pairs, n)
+            .iter()
+            .map(|&count| count * count.saturating_sub(1))
+            .sum();
+        match counted {
+            0 | 1 => 0.0,
+            _ => (repeated * n * n) as f64 / (counted * (counted - 1)) as f64,
+        }
+    };
+    let longest: usize = max.min(ciphertext.len() / 2);
+    std::iter::once(None)
+        .chain((2..=longest).map(Some))
+        .map(|period| (period, repeats(period)))
+        .collect()
+}
+
+/// How often the pairs of a ciphertext repeat for one way of pairing it
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodRepeats {
+    /// The period of the seriation, `None` for the digrams as they stand
+    pub period: Option<usize>,
+    /// How many times as often as random pairs the pairs repeat
+    pub repeats: f64,
+}
+
+/// The estimate of the period of a seriated ciphertext
+#[derive(Debug, Clone, Serialize)]
+pub struct Seriation {
+    /// The repeats of every way of pairing tried
+    pub periods: Vec<PeriodRepeats>,
+    /// The period whose pairs repeat most, `None` if the digrams do
+    pub likely: Option<usize>,
+}
+
+/// Picks the period whose pairs repeat most, `None` if the unseriated
+/// digrams do; ties go to the shorter one.
+pub fn likely_period(periods: &[(Option<usize>, f64)]) -> Option<usize> {
+    periods
+        .iter()
+        .copied()
+        .reduce(|best, period| if period.1 > best.1 { period } else { best })
+        .and_then(|(period, _)| period)
+}
+
+/// Prints how often the pairs repeat for every period to stdout, and the
+/// likely one.
+///
+/// # Returns
+///
+/// * A usage error if the longest period is below 2, or an invalid input
+///   error if the ciphertext is odd or holds fewer than two digrams.
+fn print_period(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, ciphertext) = read_ciphertext(matches)?;
+    let max: usize = *matches.get_one::<usize>("max-period").unwrap();
+    if max < 2 {
+        return Err(Error::Usage(String::from(
+            "the longest period must be at least 2",
+        )));
+    }
+    if ciphertext.len() < 4 || !ciphertext.len().is_multiple_of(2) {
+        return Err(Error::InvalidInput(format!(
+            "analyze period needs an even number of at least 4 ciphertext letters, not {}",
+            ciphertext.len()
+        )));
+    }
+    let periods: Vec<(Option<usize>, f64)> = periods(&ciphertext, &variant, max);
+    let seriation: Seriation = Seriation {
+        likely: likely_period(&periods),
+        periods: periods
+            .into_iter()
+            .map(|(period, repeats)| PeriodRepeats { period, repeats })
+            .collect(),
+    };
+    let label = |period: Option<usize>| period.map_or(String::from("none"), |p| p.to_string());
+    match format(matches) {
+        Format::Json => return print_json(&seriation),
+        Format::Csv => {
+            let rows: Vec<Vec<String>> = seriation
+                .periods
+                .iter()
+                .map(|repeats| {
+                    vec![
+                        label(repeats.period),
+                        repeats.repeats.to_string(),
+                        (repeats.period == seriation.likely).to_string(),
+                    ]
+                })
+                .collect();
+            print_csv(&["period", "repeats", "likely"], &rows)?;
+            return Ok(());
+        }
+        Format::Text => {}
+    }
+    writeln!(out, "{:<8} {:>8}", "Period", "Repeats")?;
+    for repeats in &seriation.periods {
+        let marker: &str = if repeats.period == seriation.likely {
+            "  <"
+        } else {
+            ""
+        };
+        writeln!(
+            out,
+            "{:<8} {:>8.2}{}",
+            label(repeats.period),
+            repeats.repeats,
+            marker
+        )?;
+    }
+    match seriation.likely {
+        Some(period) => writeln!(
+            out,
+            "Likely period: {}; crack it with --period {} or --find-period",
+            period, period
+        )?,
+        None => writeln!(
+            out,
+            "Likely period: none, the digrams repeat most as they stand"
+        )?,
+    }
+    Ok(())
+}
+
+/// z-score from which two ciphertexts likely share a key, about one chance
+/// in 740 under different keys
+const SAME_KEY_Z: f64 = 3.0;
+
+/// Fewest coincidences expected under the same key for which the agreement
+/// is measured; shorter ciphertexts leave too little to tell
+const SAME_KEY_MIN_EXPECTED: f64 = 5.0;
+
+/// How much the digrams of two ciphertexts agree
+#[derive(Debug, Clone, Serialize)]
+pub struct SameKey {
+    /// The number of digrams of each ciphertext
+    pub digrams: [usize; 2],
+    /// The pairs of a digram of one ciphertext and an equal digram of the
+    /// other
+    pub coincidences: usize,
+    /// The coincidences expected under different keys
+    pub different_keys: f64,
+    /// The coincidences expected under the same key
+    pub same_key: f64,
+    /// How many standard deviations the coincidences are above those under
+    /// different keys
+    pub z: f64,
+    /// The chance of at least as many coincidences under different keys
+    pub chance: f64,
+    /// How far the coincidences are from those under different keys (0) to
+    /// those under the same key (1), on a logarithmic scale and clamped to
+    /// that range, or `None` if too few coincidences are expected to tell
+    pub agreement: Option<f64>,
+    /// Whether the ciphertexts likely share a key
+    pub likely: bool,
+}
+
+/// Weighs whether two ciphertexts were encrypted with the same table.
+///
+/// # Arguments
+///
+/// * `first` - The first ciphertext as symbol indices.
+/// * `second` - The second ciphertext as symbol indices.
+/// * `symbols` - How many symbols the table holds.
+/// * `language` - The language of the plaintexts.
+pub fn same_key(
+    first: &[usize],
+    second: &[usize],
+    symbols: &[char],
+    language: Language,
+) -> SameKey {
+    let n: usize = symbols.len();
+    let (_, digram_probabilities) = probabilities(symbols, language);
+    let (a, b): (Vec<usize>, Vec<usize>) = (digram_counts(first, n), digram_counts(second, n));
+    let (total_a, total_b): (f64, f64) = (first.len() as f64 / 2.0, second.len() as f64 / 2.0);
+    let coincidences: usize = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+    // Ciphertext digrams never double a letter
+    let m: f64 = (n * (n - 1)) as f64;
+    let squares = |counts: &[usize], total: f64| {
+        counts.iter().map(|&c| (c * c) as f64).sum::<f64>() - total * total / m
+    };
+    let different_keys: f64 = total_a * total_b / m;
+    let variance: f64 = squares(&a, total_a) * squares(&b, total_b) / (m - 1.0);
+    let same_key: f64 = total_a * total_b * Level::expected(&digram_probabilities).coincidence;
+    // The coincidences grow by a factor rather than by a difference, and
+    // none at all counts as one
+    let agreement: Option<f64> = (same_key >= SAME_KEY_MIN_EXPECTED).then(|| {
+        ((coincidences.max(1) as f64 / different_keys).ln() / (same_key / different_keys).ln())
+            .clamp(0.0, 1.0)
+    });
+    let z: f64 = if variance > 0.0 {
+        (coincidences as f64 - different_keys) / variance.sqrt()
+    } else {
+        0.0
+    };
+    SameKey {
+        digrams: [a.iter().sum(), b.iter().sum()],
+        coincidences,
+        different_keys,
+        same_key,
+        z,
+        chance: upper_tail(z),
+        agreement,
+        likely: z >= SAME_KEY_Z && agreement.is_some_and(|agreement| agreement >= 0.5),
+    }
+}
+
+/// Returns the chance that a standard normal variable exceeds `z`, from the
+/// approximation of the complementary error function by Abramowitz and
+/// Stegun (7.1.26).
+fn upper_tail(z: f64) -> f64 {
+    let x: f64 = z.abs() / std::f64::consts::SQRT_2;
+    let t: f64 = 1.0 / (1.0 + 0.3275911 * x);
+    let poly: f64 = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let tail: f64 = 0.5 * poly * (-x * x).exp();
+    if z >= 0.0 { tail } else { 1.0 - tail }
+}
+
+/// Prints how much the digrams of two ciphertexts agree to stdout, and
+/// whether they likely share a key.
+///
+/// # Returns
+///
+/// * A usage error if the variant can't be analyzed, or an invalid input
+///   error if a ciphertext holds no digram.
+fn print_same_key(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    if variant.rounds > 1 || variant.period.is_some() {
+        return Err(Error::Usage(String::from(
+            "analyze only reads single-round ciphertexts without --period",
+        )));
+    }
+    let texts: Vec<Vec<usize>> = matches
+        .get_many::<String>("ciphertext")
+        .unwrap()
+        .map(|text| indices(text, &variant))
+        .collect();
+    if let Some(n) = texts.iter().position(|text| text.len() < 2) {
+        return Err(Error::InvalidInput(format!(
+            "analyze same-key needs at least one digram in ciphertext {}",
+            n + 1
+        )));
+    }
+    let language: Language = language(matches)?;
+    let same: SameKey = same_key(&texts[0], &texts[1], &variant.symbols(), language);
+    match format(matches) {
+        Format::Json => return print_json(&same),
+        Format::Csv => {
+            let rows: Vec<Vec<String>> = [
+                ("digrams_first", same.digrams[0].to_string()),
+                ("digrams_second", same.digrams[1].to_string()),
+                ("coincidences", same.coincidences.to_string()),
+                ("different_keys", same.different_keys.to_string()),
+                ("same_key", same.same_key.to_string()),
+                ("z", same.z.to_string()),
+                ("chance", same.chance.to_string()),
+                (
+                    "agreement",
+                    same.agreement.map(|a| a.to_string()).unwrap_or_default(),
+                ),
+                ("likely", same.likely.to_string()),
+            ]
+            .into_iter()
+            .map(|(name, value)| vec![name.to_string(), value])
+            .collect();
+            print_csv(&["name", "value"], &rows)?;
+            return Ok(());
+        }
+        Format::Text => {}
+    }
+    writeln!(out, "Digrams: {} and {}", same.digrams[0], same.digrams[1])?;
+    writeln!(
+        out,
+        "Coincidences: {}, {:.1} expected under different keys, {:.1} under the same key in {}",
+        same.coincidences,
+        same.different_keys,
+        same.same_key,
+        language.name()
+    )?;
+    writeln!(
+        out,
+        "z-score: {:.2}, a chance of {:.2e} under different keys",
+        same.z, same.chance
+    )?;
+    match same.agreement {
+        Some(agreement) => writeln!(
+            out,
+            "Agreement: {:.0}% of the way from different keys to the same key",
+            100.0 * agreement
+        )?,
+        None => writeln!(
+            out,
+            "Agreement: insufficient data, fewer than {} coincidences expected under the same key",
+            SAME_KEY_MIN_EXPECTED
+        )?,
+    }
+    if same.likely {
+        writeln!(
+            out,
+            "Likely the same key: crack them together by giving both ciphertexts to crack"
+        )?;
+    } else if same.agreement.is_none() {
+        writeln!(out, "Too short to tell whether they share a key")?;
+    } else {
+        writeln!(out, "No sign of the same key")?;
+    }
+    Ok(())
+}
+
+/// The score of a candidate decryption
+#[derive(Debug, Clone, Serialize)]
+pub struct Fitness {
+    /// The text as given
+    pub text: String,
+    /// Its average log10 probability per quadgram, `null` in JSON for
+    /// fewer than four letters
+    pub score: f64,
+}
+
+/// Runs `analyze fitness`, ranking texts by how much they read like the
+/// language, the best first.
+fn print_fitness(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let language: Language = language(matches)?;
+    let mut ranked: Vec<Fitness> = matches
+        .get_many::<String>("text")
+        .unwrap()
+        .map(|text| Fitness {
+            text: text.clone(),
+            score: fitness::score(text, language),
+        })
+        .collect();
+    // Ties keep the order they were given in
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    match format(matches) {
+        Format::Json => print_json(&ranked),
+        Format::Csv => {
+            let rows: Vec<Vec<String>> = ranked
+                .iter()
+                .enumerate()
+                .map(|(rank, fitness)| {
+                    vec![
+                        (rank + 1).to_string(),
+                        fitness.score.to_string(),
+                        fitness.text.clone(),
+                    ]
+                })
+                .collect();
+            print_csv(&["rank", "score", "text"], &rows)?;
+            Ok(())
+        }
+        Format::Text => {
+            let threshold: f64 = fitness::quadgrams(language).threshold();
+            for (rank, fitness) in ranked.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{}: {:.2}{} {}",
+                    rank + 1,
+                    fitness.score,
+                    if fitness.score >= threshold { "*" } else { " " },
+                    fitness.text
+                )?;
+            }
+            writeln!(
+                out,
+                "Scores per quadgram of {}; * reads as {} (at least {:.2})",
+                language.name(),
+                language.name(),
+                threshold
+            )?;
+            Ok(())
+        }
+    }
+}
+
+/// Counts how often every cell of a table holds a letter of a ciphertext or
+/// of its decryption, and which rules decrypt its digrams.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext letters, of even length.
+/// * `table` - The Playfair table.
+///
+/// # Returns
+///
+/// * The count of every cell, row by row, and the digrams decrypted by the
+///   row, the column and the rectangle rule.
+pub fn cell_usage(ciphertext: &[char], table: &[Vec<char>]) -> (Vec<Vec<usize>>, [usize; 3]) {
+    let mut cells: Vec<Vec<usize>> = vec![vec![0; table[0].len()]; table.len()];
+    let mut rules: [usize; 3] = [0; 3];
+    let positions: crate::Positions = crate::Positions::new(table);
+    for pair in ciphertext.chunks_exact(2) {
+        let (plain, rule) = crate::transform_pair(
+            table,
+            &positions,
+            (pair[0], pair[1]),
+            crate::CipherMode::Decrypt,
+        );
+        for letter in [pair[0], pair[1], plain.0, plain.1] {
+            let (row, col) = positions.find(letter);
+            cells[row][col] += 1;
+        }
+        rules[match rule {
+            crate::Rule::Row => 0,
+            crate::Rule::Column => 1,
+            crate::Rule::Rectangle => 2,
+        }] += 1;
+    }
+    (cells, rules)
+}
+
+/// Prints the heatmaps of the digrams and the letters of a ciphertext to
+/// stdout, and that of the cells of the table if a key is given.
+///
+/// # Returns
+///
+/// * An invalid input error if the ciphertext is odd or empty, or an
+///   invalid key error if the key fills no table.
+fn print_heatmap(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, ciphertext) = read_ciphertext(matches)?;
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(2) {
+        return Err(Error::InvalidInput(format!(
+            "analyze heatmap needs an even number of ciphertext letters, not {}",
+            ciphertext.len()
+        )));
+    }
+    let symbols: Vec<char> = variant.symbols();
+    let n: usize = symbols.len();
+    let colored: bool = crate::color::stdout_enabled();
+    // Square roots, so the rarer counts still show next to the commonest
+    let levels = |counts: &[usize]| -> Vec<f64> {
+        let max: f64 = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+        counts
+            .iter()
+            .map(|&count| (count as f64 / max).sqrt())
+            .collect()
+    };
+    // Without colors the labels go on a line of their own above the shades
+    let print_line = |out: &mut StdoutLock,
+                      prefix: &str,
+                      labels: &[String],
+                      levels: &[f64]|
+     -> std::io::Result<()> {
+        let cells: String = labels
+            .iter()
+            .zip(levels)
+            .map(|(label, &level)| crate::color::heat_cell(label, level, colored))
+            .collect();
+        if !colored {
+            writeln!(out, "{}{}", " ".repeat(prefix.len()), labels.concat())?;
+        }
+        writeln!(out, "{}{}", prefix, cells)
+    };
+
+    let counts: Vec<usize> = digram_counts(&ciphertext, n);
+    let hottest: usize = (0..counts.len()).max_by_key(|&d| counts[d]).unwrap();
+    writeln!(
+        out,
+        "Digrams, first letter by second, {} in all (most often {}{}, {} time{}):",
+        ciphertext.len() / 2,
+        symbols[hottest / n],
+        symbols[hottest % n],
+        counts[hottest],
+        if counts[hottest] == 1 { "" } else { "s" }
+    )?;
+    writeln!(
+        out,
+        "  {}",
+        symbols
+            .iter()
+            .map(|c| format!(" {}", c))
+            .collect::<String>()
+    )?;
+    let digram_levels: Vec<f64> = levels(&counts);
+    for (first, &symbol) in symbols.iter().enumerate() {
+        let row: std::ops::Range<usize> = first * n..(first + 1) * n;
+        let cells: String = counts[row.clone()]
+            .iter()
+            .zip(&digram_levels[row])
+            .map(|(&count, &level)| {
+                crate::color::heat_cell(&format!("{:>2}", count.min(99)), level, colored)
+            })
+            .collect();
+        writeln!(out, "{} {}", symbol, cells)?;
+    }
+
+    let mut letters: Vec<usize> = vec![0; n];
+    for &symbol in &ciphertext {
+        letters[symbol] += 1;
+    }
+    writeln!(out)?;
+    writeln!(out, "Letters:")?;
+    let labels: Vec<String> = symbols.iter().map(|c| format!(" {}", c)).collect();
+    print_line(&mut out, "  ", &labels, &levels(&letters))?;
+
+    let keyed: bool = ["key", "key-file", "key-square", "key-mnemonic"]
+        .iter()
+        .any(|id| matches.contains_id(id));
+    if keyed {
+        let key: String =
+            crate::cli::key_from_matches(matches, crate::CipherMode::Decrypt, &variant)?;
+        let table: Vec<Vec<char>> = crate::generate_playfair_table(&key, &variant);
+        let text: Vec<char> = ciphertext.iter().map(|&symbol| symbols[symbol]).collect();
+        let (cells, rules) = cell_usage(&text, &table);
+        let flat: Vec<usize> = cells.concat();
+        let cell_levels: Vec<f64> = levels(&flat);
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Table cells holding a letter of the ciphertext or of its decryption:"
+        )?;
+        for (r, row) in table.iter().enumerate() {
+            let labels: Vec<String> = row.iter().map(|c| format!(" {} ", c)).collect();
+            let cols: usize = row.len();
+            print_line(
+                &mut out,
+                "  ",
+                &labels,
+                &cell_levels[r * cols..(r + 1) * cols],
+            )?;
+        }
+        writeln!(
+            out,
+            "Rules: {} same row, {} same column, {} rectangle",
+            rules[0], rules[1], rules[2]
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints every offset the dragged word may take to stdout, and the number
+/// of offsets that fit to stderr.
+///
+/// # Returns
+///
+/// * A usage error if the word holds fewer than 2 letters, or an invalid
+///   input error if the ciphertext is odd.
+fn drag(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, ciphertext) = read_ciphertext(matches)?;
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(2) {
+        return Err(Error::InvalidInput(format!(
+            "analyze needs an even number of ciphertext letters, not {}",
+            ciphertext.len()
+        )));
+    }
+    let word: Vec<usize> = indices(matches.get_one::<String>("drag-crib").unwrap(), &variant);
+    if word.len() < 2 || word.len() > ciphertext.len() {
+        return Err(Error::Usage(format!(
+            "the dragged word needs 2 to {} letters, not {}",
+            ciphertext.len(),
+            word.len()
+        )));
+    }
+    let bigrams: Ngrams = Ngrams::builtin(language(matches)?, 2);
+    let symbols: Vec<char> = variant.symbols();
+    let shape: (usize, usize) = variant.alphabet.dimensions();
+    let drags: Vec<Drag> = cribs::drag(&word, &ciphertext, shape);
+    let mut fits: usize = 0;
+    for drag in &drags {
+        let square: &PartialKeySquare = match &drag.square {
+            Ok(square) => square,
+            Err(reason) => {
+                writeln!(out, "Offset {}: ruled out, {}", drag.offset, reason)?;
+                continue;
+            }
+        };
+        fits += 1;
+        let plaintext: Vec<Option<usize>> = square.decrypt(&ciphertext);
+        // Only the digrams outside the word tell whether it fits
+        let outside = |i: usize| i + 1 < drag.offset || i >= drag.offset + word.len();
+        let scores: Vec<f64> = plaintext
+            .chunks(2)
+            .enumerate()
+            .filter(|&(digram, _)| outside(2 * digram))
+            .filter_map(|(_, pair)| {
+                let letters: Vec<u8> = pair
+                    .iter()
+                    .map(|symbol| analysis::letter_index(symbols[(*symbol)?]))
+                    .collect::<Option<Vec<u8>>>()?;
+                Some(bigrams.score(&letters))
+            })
+            .collect();
+        let preview: String = plaintext
+            .iter()
+            .map(|symbol| symbol.map_or('.', |symbol| symbols[symbol]))
+            .collect();
+        let score: String = if scores.is_empty() {
+            String::from("no other digram decrypts")
+        } else {
+            format!(
+                "{} other digrams at {:.2} per bigram",
+                scores.len(),
+                scores.iter().sum::<f64>() / scores.len() as f64
+            )
+        };
+        writeln!(
+            out,
+            "Offset {}: {} cells, {}: {}",
+            drag.offset,
+            square.known(),
+            score,
+            preview
+        )?;
+    }
+    eprintln!("{} of {} offsets fit the word", fits, drags.len());
+    Ok(())
+}
+
+/// Turns the letters of a text into symbol indices of the table.
+fn indices(text: &str, variant: &Variant) -> Vec<usize> {
+    let symbols: Vec<char> = variant.symbols();
+    crate::table_letters(text, variant)
+        .iter()
+        .map(|c| symbols.iter().position(|s| s == c).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics_of_levels() {
+        let even: Level = Level::measure(&[2, 2, 2, 2], &[0.25; 4]);
+        assert_eq!(even.entropy, 2.0);
+        assert_eq!(even.chi_squared, 0.0);
+        assert!((even.coincidence - 4.0 * 2.0 / (8.0 * 7.0)).abs() < 1e-12);
+        assert_eq!(Level::random(4).entropy, even.entropy);
+        assert_eq!(Level::expected(&[0.25; 4]).coincidence, 0.25);
+        let skewed: Level = Level::measure(&[8, 0, 0, 0], &[0.25; 4]);
+        assert_eq!(skewed.entropy, 0.0);
+        assert_eq!(skewed.chi_squared, 24.0);
+
+        let variant: Variant = Variant::default();
+        let (monograms, digrams) = probabilities(&variant.symbols(), Language::English);
+        assert!((monograms.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((digrams.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        // E is the most common letter, and no digram doubles a letter
+        let e: usize = variant.symbols().iter().position(|&c| c == 'E').unwrap();
+        assert!(monograms.iter().all(|&p| p <= monograms[e]));
+        assert_eq!(digrams[e * 25 + e], 0.0);
+    }
+
+    #[test]
+    fn test_frequencies_and_statistics() {
+        let variant: Variant = Variant::default();
+        let symbols: Vec<char> = variant.symbols();
+        let ciphertext: Vec<usize> = indices("ABBAABCDAB", &variant);
+        let frequencies: Frequencies = frequencies(&ciphertext, &symbols, Language::English, 2);
+        assert_eq!((frequencies.letters, frequencies.digrams), (10, 5));
+        assert_eq!(frequencies.distinct, 3);
+        assert_eq!(frequencies.common[0].digram, "AB");
+        assert_eq!(frequencies.common[0].count, 3);
+        assert_eq!(frequencies.common[0].share, 0.6);
+        assert_eq!(frequencies.common[0].reversed_count, 1);
+        assert_eq!(frequencies.expected[0].digram, "TH");
+        assert_eq!(frequencies.reversed_digrams, 2);
+        assert_eq!(frequencies.reversed[0].reversed, "BA");
+
+        let statistics: Statistics = statistics(&ciphertext, &symbols, Language::English);
+        assert_eq!(statistics.digrams.random, Level::random(25 * 24));
+        let json: serde_json::Value = serde_json::to_value(&statistics).unwrap();
+        assert_eq!(json["language"], "English");
+        assert_eq!(
+            json["letters"]["ciphertext"]["entropy"],
+            statistics.letters.ciphertext.entropy
+        );
+    }
+
+    #[test]
+    fn test_detect_playfair() {
+        let variant: Variant = Variant::default();
+        let plaintext: String =
+            "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES IT WAS THE AGE OF \
+            WISDOM IT WAS THE AGE OF FOOLISHNESS IT WAS THE EPOCH OF BELIEF IT WAS THE EPOCH OF \
+            INCREDULITY IT WAS THE SEASON OF LIGHT IT WAS THE SEASON OF DARKNESS"
+                .replace(' ', "");
+        let square: crate::KeySquare = crate::KeySquare::from_key("DICKENS", &variant);
+        let ciphertext: String = crate::playfair_cipher(
+            &plaintext,
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        );
+        let symbols: Vec<char> = variant.symbols();
+        let detected = |text: &str| {
+            let raw: Vec<char> = text.chars().collect();
+            let indices: Vec<usize> = indices(text, &variant);
+            detect(&raw, &indices, &symbols, Language::English)
+        };
+        let playfair: Detection = detected(&ciphertext);
+        assert!(playfair.checks.iter().all(|check| check.passed));
+        assert!(playfair.confidence > 0.9, "{}", playfair.confidence);
+        // The plaintext doubles letters in its digrams, as in FO OL
+        let english: Detection = detected(&plaintext);
+        assert_eq!(english.confidence, 0.0);
+        assert!(english.otherwise.starts_with("a transposition"));
+    }
+
+    #[test]
+    fn test_same_key() {
+        let variant: Variant = Variant::default();
+        let encrypt = |plaintext: &str, key: &str| {
+            let square: crate::KeySquare = crate::KeySquare::from_key(key, &variant);
+            let ciphertext: String = crate::playfair_cipher(
+                plaintext,
+                square.rows(),
+                &variant,
+                crate::CipherMode::Encrypt,
+            );
+            indices(&ciphertext, &variant)
+        };
+        let first: &str = "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES IT WAS THE AGE OF \
+            WISDOM IT WAS THE AGE OF FOOLISHNESS IT WAS THE EPOCH OF BELIEF";
+        let second: &str = "IT WAS THE EPOCH OF INCREDULITY IT WAS THE SEASON OF LIGHT IT WAS \
+            THE SEASON OF DARKNESS IT WAS THE SPRING OF HOPE IT WAS THE WINTER OF DESPAIR";
+        let symbols: Vec<char> = variant.symbols();
+        let same: SameKey = same_key(
+            &encrypt(first, "DICKENS"),
+            &encrypt(second, "DICKENS"),
+            &symbols,
+            Language::English,
+        );
+        assert!(same.likely, "{:?}", same);
+        assert!(same.chance < 0.001);
+        let different: SameKey = same_key(
+            &encrypt(first, "DICKENS"),
+            &encrypt(second, "TWOCITIES"),
+            &symbols,
+            Language::English,
+        );
+        assert!(!different.likely, "{:?}", different);
+        assert!(different.coincidences < same.coincidences);
+        for agreement in [same.agreement, different.agreement] {
+            assert!(agreement.is_some_and(|a| (0.0..=1.0).contains(&a)));
+        }
+        // A few digrams tell nothing, even when they are all the same
+        let short: Vec<usize> = encrypt("HELLO WORLD", "DICKENS");
+        let tiny: SameKey = same_key(&short, &short, &symbols, Language::English);
+        assert_eq!(tiny.agreement, None);
+        assert!(!tiny.likely);
+        assert!((upper_tail(0.0) - 0.5).abs() < 1e-6);
+        assert!((upper_tail(1.96) - 0.025).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_cell_usage() {
+        let variant: Variant = Variant::default();
+        let table: Vec<Vec<char>> = crate::generate_playfair_table("PLAYFAIREXAMPLE", &variant);
+        // HIDETHEG of the Wikipedia example, whose B is only in BM and ZB
+        let ciphertext: Vec<char> = "BMODZBXD".chars().collect();
+        let (cells, rules) = cell_usage(&ciphertext, &table);
+        assert_eq!(cells.concat().iter().sum::<usize>(), 16);
+        assert_eq!(rules.iter().sum::<usize>(), 4);
+        let (row, col) = crate::Positions::new(&table).find('B');
+        assert_eq!(cells[row][col], 2);
+    }
+
+    #[test]
+    fn test_seriation_period() {
+        let plaintext: &str = "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES IT WAS THE AGE \
+            OF WISDOM IT WAS THE AGE OF FOOLISHNESS IT WAS THE EPOCH OF BELIEF IT WAS THE EPOCH \
+            OF INCREDULITY IT WAS THE SEASON OF LIGHT IT WAS THE SEASON OF DARKNESS";
+        for period in [None, Some(4), Some(7)] {
+            let variant: Variant = Variant {
+                period,
+                ..Variant::default()
+            };
+            let square: crate::KeySquare = crate::KeySquare::from_key("DICKENS", &variant);
+            let ciphertext: String = crate::playfair_cipher(
+                plaintext,
+                square.rows(),
+                &variant,
+                crate::CipherMode::Encrypt,
+            );
+            let periods: Vec<(Option<usize>, f64)> =
+                periods(&indices(&ciphertext, &variant), &variant, MAX_PERIOD);
+            assert_eq!(periods.len(), MAX_PERIOD);
+            assert_eq!(likely_period(&periods), period);
+        }
+    }
+}
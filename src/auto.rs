@@ -0,0 +1,142 @@
+//! Guessing whether a text is to be encrypted or decrypted.
+//!
+//! Encryption turns text that reads like a language into letters that
+//! don't, so of a text and its decryption, the plaintext is the one that
+//! scores better on the quadgrams of the built-in languages. A plaintext is
+//! encrypted and a ciphertext decrypted.
+
+use crate::analysis::{self, Language};
+use crate::error::Error;
+use crate::{CipherMode, Variant};
+use clap::ArgMatches;
+
+/// How a text and its decryption read
+pub struct Guess {
+    /// The direction the text is taken to go
+    pub mode: CipherMode,
+    /// The score per quadgram of the text in the language it reads most like
+    pub input: f64,
+    /// The language the text reads like, if any
+    pub input_language: Option<Language>,
+    /// The score per quadgram of the decryption
+    pub decryption: f64,
+    /// The language the decryption reads like, if any
+    pub decryption_language: Option<Language>,
+    /// The result in the guessed direction
+    pub result: String,
+    /// The result in the other direction
+    pub other: String,
+}
+
+/// Tries both directions on a text.
+///
+/// # Arguments
+///
+/// * `text` - The text of unknown direction.
+/// * `table` - The Playfair table of the key.
+/// * `variant` - The variant the table was generated with.
+///
+/// # Returns
+///
+/// * The guess: decryption if the decryption scores better than the text
+///   itself, encryption otherwise.
+pub fn guess(text: &str, table: &[Vec<char>], variant: &Variant) -> Guess {
+    let encrypted: String = crate::playfair_cipher(text, table, variant, CipherMode::Encrypt);
+    let decrypted: String = crate::playfair_cipher(text, table, variant, CipherMode::Decrypt);
+    let (input_language, input) = analysis::identify(text);
+    let (decryption_language, decryption) = analysis::identify(&decrypted);
+    let (input, decryption): (f64, f64) = (input[0].1, decryption[0].1);
+    let (mode, result, other) = if decryption > input {
+        (CipherMode::Decrypt, decrypted, encrypted)
+    } else {
+        (CipherMode::Encrypt, encrypted, decrypted)
+    };
+    Guess {
+        mode,
+        input,
+        input_language,
+        decryption,
+        decryption_language,
+        result,
+        other,
+    }
+}
+
+/// Names the language a text reads like for the report.
+fn reads_like(language: Option<Language>) -> String {
+    match language {
+        Some(language) => format!("reads like {}", language.name()),
+        None => String::from("reads like none of the built-in languages"),
+    }
+}
+
+/// Runs the `auto` subcommand.
+///
+/// The scores and the chosen direction go to stderr, the result to stdout
+/// as the main command prints it.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the key can't be used.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let (variant, style, _) = crate::cli::settings_from_matches(matches)?;
+    let key: String = crate::cli::key_from_matches(matches, CipherMode::Decrypt, &variant)?;
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(&key, &variant);
+    let text: &String = matches.get_one::<String>("text").unwrap();
+    let guess: Guess = guess(text, &table, &variant);
+    eprintln!(
+        "Input: {:.2} per quadgram, {}",
+        guess.input,
+        reads_like(guess.input_language)
+    );
+    eprintln!(
+        "Decryption: {:.2} per quadgram, {}",
+        guess.decryption,
+        reads_like(guess.decryption_language)
+    );
+    if guess.input_language.is_none() && guess.decryption_language.is_none() {
+        eprintln!("Warning: neither reads like a language; guessing from the scores");
+    }
+    let (direction, other) = match guess.mode {
+        CipherMode::Encrypt => ("encrypt, the input reads more like a language", "Decrypted"),
+        CipherMode::Decrypt => (
+            "decrypt, the decryption reads more like a language",
+            "Encrypted",
+        ),
+    };
+    eprintln!("Direction: {}", direction);
+    eprintln!("Otherwise {} Text: {}", other, style.apply(&guess.other));
+    match guess.mode {
+        CipherMode::Encrypt => println!("Encrypted Text: {}", style.apply(&guess.result)),
+        CipherMode::Decrypt => println!("Decrypted Text: {}", style.apply(&guess.result)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_direction() {
+        let variant: Variant = Variant::default();
+        let table: Vec<Vec<char>> = crate::generate_playfair_table("MONARCHY", &variant);
+        let plaintext: &str = "Meet me by the old bridge after the market closes tonight";
+        let guess: Guess = guess(plaintext, &table, &variant);
+        assert!(matches!(guess.mode, CipherMode::Encrypt));
+        assert_eq!(guess.input_language, Some(Language::English));
+        let ciphertext: String = guess.result;
+        let guess: Guess = super::guess(&ciphertext, &table, &variant);
+        assert!(matches!(guess.mode, CipherMode::Decrypt));
+        assert!(guess.decryption > guess.input);
+        assert_eq!(
+            guess.other,
+            crate::playfair_cipher(&ciphertext, &table, &variant, CipherMode::Encrypt)
+        );
+        assert!(guess.result.starts_with("MEETMEBYTHEOLDBRIDGE"));
+    }
+}
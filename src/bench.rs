@@ -0,0 +1,139 @@
+//! Throughput benchmark of encryption and decryption.
+//!
+//! The input is a deterministic stream of pseudo-random letters, so results
+//! are comparable between runs and builds. Only build with `--release` when
+//! comparing numbers.
+
+use crate::error::Error;
+use crate::{CipherMode, Variant};
+use clap::ArgMatches;
+use std::time::{Duration, Instant};
+
+/// Key of the table used for every run
+const BENCH_KEY: &str = "PLAYFAIR EXAMPLE";
+
+/// Generates `size` pseudo-random uppercase letters.
+///
+/// A fixed linear congruential generator keeps the input identical between
+/// runs without pulling in a random number crate.
+fn synthetic_text(size: usize) -> String {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..size)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (b'A' + ((state >> 33) % 26) as u8) as char
+        })
+        .collect()
+}
+
+/// The measurements of one mode
+struct Measurement {
+    /// Total time spent over all iterations
+    elapsed: Duration,
+    /// Bytes of input processed over all iterations
+    bytes: usize,
+    /// Digrams transformed over all iterations
+    digrams: usize,
+}
+
+impl Measurement {
+    /// Throughput in megabytes (10^6 bytes) of input per second
+    fn megabytes_per_second(&self) -> f64 {
+        self.bytes as f64 / 1e6 / self.elapsed.as_secs_f64()
+    }
+
+    /// Average time spent on one digram, in nanoseconds
+    fn nanoseconds_per_digram(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.digrams as f64
+    }
+}
+
+/// Runs the cipher over a text a number of times.
+///
+/// # Arguments
+///
+/// * `text` - The input text.
+/// * `iterations` - How many times to process it.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * The measurement, and the output of the last iteration.
+fn measure(text: &str, iterations: usize, mode: CipherMode) -> (Measurement, String) {
+    let variant: Variant = Variant::default();
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(BENCH_KEY, &variant);
+    let mut output: String = String::new();
+    let start: Instant = Instant::now();
+    for _ in 0..iterations {
+        output = crate::playfair_cipher(std::hint::black_box(text), &table, &variant, mode);
+    }
+    let elapsed: Duration = start.elapsed();
+    let measurement: Measurement = Measurement {
+        elapsed,
+        bytes: text.len() * iterations,
+        digrams: output.len() / 2 * iterations,
+    };
+    (measurement, output)
+}
+
+/// Runs the `bench` subcommand, printing a line per mode.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * A usage error if the size or the iteration count is zero.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let size: usize = *matches.get_one::<usize>("size").unwrap();
+    let iterations: usize = *matches.get_one::<usize>("iterations").unwrap();
+    if size == 0 || iterations == 0 {
+        return Err(Error::Usage(String::from(
+            "the size and the number of iterations must be at least 1",
+        )));
+    }
+
+    let plaintext: String = synthetic_text(size);
+    eprintln!(
+        "Benchmarking {} bytes x {} iterations{}",
+        size,
+        iterations,
+        if cfg!(debug_assertions) {
+            " (debug build, use --release for real numbers)"
+        } else {
+            ""
+        }
+    );
+    let (encryption, ciphertext) = measure(&plaintext, iterations, CipherMode::Encrypt);
+    let (decryption, _) = measure(&ciphertext, iterations, CipherMode::Decrypt);
+    for (name, measurement) in [("encrypt", encryption), ("decrypt", decryption)] {
+        println!(
+            "{}: {:.2} MB/s, {:.1} ns/digram ({:.3} s total)",
+            name,
+            measurement.megabytes_per_second(),
+            measurement.nanoseconds_per_digram(),
+            measurement.elapsed.as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_text_and_measure() {
+        let text: String = synthetic_text(1001);
+        assert_eq!(text.len(), 1001);
+        assert!(text.chars().all(|c| c.is_ascii_uppercase()));
+        assert_eq!(text, synthetic_text(1001));
+
+        let (measurement, output) = measure(&text, 2, CipherMode::Encrypt);
+        assert_eq!(measurement.bytes, 2002);
+        assert_eq!(measurement.digrams, output.len());
+    }
+}
@@ -0,0 +1,144 @@
+//! Encoding of arbitrary bytes as table letters for `--binary`.
+//!
+//! Every byte becomes one digram. Its high nibble picks a symbol of the
+//! table and its low nibble the symbol 1 to 16 places after it, so no
+//! digram repeats a letter and the text always has an even length. The
+//! cipher then inserts no fillers, and decryption gives back exactly the
+//! encoded letters. Any table of at least 17 symbols works, such as the
+//! 25 letters of a 5x5 table or the 36 symbols of a 6x6 table.
+
+use crate::error::Error;
+use crate::{CipherMode, Variant};
+use std::io::Read;
+
+/// Encodes bytes as letters of the table.
+///
+/// # Arguments
+///
+/// * `bytes` - The data to encode.
+/// * `variant` - The variant providing the symbols of the table.
+///
+/// # Returns
+///
+/// * Two letters per byte, or a usage error if the table is too small.
+pub fn encode(bytes: &[u8], variant: &Variant) -> Result<String, Error> {
+    let symbols: Vec<char> = symbols(variant)?;
+    Ok(bytes
+        .iter()
+        .flat_map(|&byte| {
+            let high: usize = usize::from(byte >> 4);
+            let low: usize = (high + 1 + usize::from(byte & 0x0f)) % symbols.len();
+            [symbols[high], symbols[low]]
+        })
+        .collect())
+}
+
+/// Decodes letters produced by `encode`.
+///
+/// Characters that are not table symbols, such as group separators, are
+/// skipped.
+///
+/// # Arguments
+///
+/// * `text` - The encoded letters.
+/// * `variant` - The variant providing the symbols of the table.
+///
+/// # Returns
+///
+/// * The decoded bytes, or an invalid input error if the letters can't
+///   come from `encode`.
+pub fn decode(text: &str, variant: &Variant) -> Result<Vec<u8>, Error> {
+    let symbols: Vec<char> = symbols(variant)?;
+    let letters: Vec<char> = crate::table_letters(text, variant);
+    if !letters.len().is_multiple_of(2) {
+        return Err(Error::InvalidInput(String::from(
+            "the text is not binary data: it has an odd number of letters",
+        )));
+    }
+    let index = |c: char| symbols.iter().position(|&s| s == c).unwrap();
+    letters
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let (high, low): (usize, usize) = (index(pair[0]), index(pair[1]));
+            let offset: usize = (low + symbols.len() - high - 1) % symbols.len();
+            if high > 0x0f || offset > 0x0f {
+                return Err(Error::InvalidInput(format!(
+                    "the text is not binary data: digram {} is {}{}",
+                    i + 1,
+                    pair[0],
+                    pair[1]
+                )));
+            }
+            Ok((high << 4 | offset) as u8)
+        })
+        .collect()
+}
+
+/// Reads the input of `--binary`.
+///
+/// # Arguments
+///
+/// * `path` - The file to read, or `-` for stdin.
+/// * `variant` - The variant providing the symbols of the table.
+/// * `mode` - Whether the input is encrypted or decrypted.
+///
+/// # Returns
+///
+/// * The encoded bytes of the file when encrypting, or its text when
+///   decrypting, or an I/O error if the file can't be read.
+pub fn read_input(path: &str, variant: &Variant, mode: CipherMode) -> Result<String, Error> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let read = if path == "-" {
+        std::io::stdin().read_to_end(&mut bytes)
+    } else {
+        std::fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes))
+    };
+    read.map_err(|e| Error::Io(format!("cannot read '{}': {}", path, e)))?;
+    match mode {
+        CipherMode::Encrypt => encode(&bytes, variant),
+        CipherMode::Decrypt => String::from_utf8(bytes).map_err(|_| {
+            Error::InvalidInput(format!("'{}' does not hold encrypted letters", path))
+        }),
+    }
+}
+
+/// Returns the symbols of the table, checking there are enough of them.
+fn symbols(variant: &Variant) -> Result<Vec<char>, Error> {
+    let symbols: Vec<char> = variant.symbols();
+    if symbols.len() < 17 {
+        return Err(Error::Usage(format!(
+            "binary mode needs a table of at least 17 symbols, not {}",
+            symbols.len()
+        )));
+    }
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_round_trip() {
+        let variant: Variant = Variant::default();
+        let bytes: Vec<u8> = (0..=255).collect();
+        let encoded: String = encode(&bytes, &variant).unwrap();
+        assert_eq!(encoded.len(), 512);
+        assert!(encoded.as_bytes().chunks(2).all(|pair| pair[0] != pair[1]));
+        assert_eq!(decode(&encoded, &variant).unwrap(), bytes);
+        assert_eq!(encode(&[0x00, 0x4f, 0xff], &variant).unwrap(), "ABEVQG");
+        assert_eq!(
+            decode("ab ev-qg", &variant).unwrap(),
+            vec![0x00, 0x4f, 0xff]
+        );
+
+        assert!(decode("ABE", &variant).is_err());
+        assert!(decode("ZA", &variant).is_err());
+        let small: Variant = Variant {
+            alphabet: crate::parse_alphabet("ABCDEFGHIJKLMNOP").unwrap(),
+            ..Variant::default()
+        };
+        assert!(encode(b"x", &small).is_err());
+    }
+}
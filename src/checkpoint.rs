@@ -0,0 +1,342 @@
+//! Checkpoints of long `crack` runs.
+//!
+//! With `--checkpoint PATH`, the search writes the state of every restart
+//! to a JSON file at most every `INTERVAL`, when a restart ends and when
+//! the search stops early: the seeds drawn, the table, score and generator
+//! of every restart still climbing or evolving, and the best table of every
+//! restart done. `--resume PATH` reads it back and carries on from there,
+//! writing to the same file, so a run survives a reboot or moves to another
+//! machine. The generators pick up where they were, so a resumed run finds
+//! the same tables as one that never stopped.
+//!
+//! The file is written next to its path and then renamed over it, so a
+//! crash while writing leaves the previous checkpoint.
+
+use crate::analysis::{Genetics, Move, Schedule};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest time between two writes of a checkpoint while restarts run
+pub const INTERVAL: Duration = Duration::from_secs(60);
+
+/// The settings a resumed search needs to share with the search that wrote
+/// the checkpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// The name of the solver, as `--solver` gives it
+    pub solver: String,
+    /// The temperature schedule, if the solver anneals
+    pub schedule: Option<Schedule>,
+    /// The settings of the genetic solver, if it is that one
+    pub genetics: Option<Genetics>,
+    /// The kinds of changes tried
+    pub moves: Vec<Move>,
+    /// How many changes every restart tries or children it breeds
+    pub iterations: usize,
+    /// The symbols of the table
+    pub alphabet: String,
+    /// The length of the rows of a seriated ciphertext, if it is seriated
+    pub period: Option<usize>,
+    /// How many times the cipher was applied
+    pub rounds: usize,
+}
+
+impl Settings {
+    /// Returns what differs between two settings.
+    ///
+    /// # Returns
+    ///
+    /// * The first setting that differs, as in "another period", or `None`
+    ///   if they are the same.
+    fn difference(&self, other: &Settings) -> Option<&'static str> {
+        let same: [(bool, &'static str); 6] = [
+            (
+                self.solver == other.solver
+                    && self.schedule == other.schedule
+                    && self.genetics == other.genetics,
+                "another solver",
+            ),
+            (self.moves == other.moves, "other moves"),
+            (
+                self.iterations == other.iterations,
+                "another number of iterations",
+            ),
+            (self.alphabet == other.alphabet, "another alphabet"),
+            (self.period == other.period, "another period"),
+            (self.rounds == other.rounds, "another number of rounds"),
+        ];
+        same.iter()
+            .find(|(same, _)| !same)
+            .map(|&(_, difference)| difference)
+    }
+}
+
+/// Where a climbing restart is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Climb {
+    /// The change the restart tries next
+    pub iteration: usize,
+    /// The state of its generator
+    pub rng: u64,
+    /// The table it is at
+    pub cells: Vec<usize>,
+    /// The score of that table
+    pub score: f64,
+    /// The best table it found
+    pub best: Vec<usize>,
+    /// The score of the best table
+    pub best_score: f64,
+}
+
+/// Where an evolving restart is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Generation {
+    /// The generation the restart breeds next
+    pub generation: usize,
+    /// The state of its generator
+    pub rng: u64,
+    /// The tables of the population and their scores
+    pub population: Vec<(Vec<usize>, f64)>,
+}
+
+/// Enum to represent how far a restart got
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Restart {
+    /// Not started yet
+    Pending,
+    /// Stopped while climbing from a table
+    Climbing(Climb),
+    /// Stopped while evolving a population
+    Evolving(Generation),
+    /// Ended with its best table
+    Done {
+        /// The cells of the table
+        cells: Vec<usize>,
+        /// Its score
+        score: f64,
+    },
+}
+
+/// The state of a search, as written to a checkpoint file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The ciphertext searched, as the symbols of the table
+    pub ciphertext: String,
+    /// The solver, its changes and the variant, which a resumed search
+    /// needs to share
+    pub settings: Settings,
+    /// The seed of every restart
+    pub seeds: Vec<u64>,
+    /// How far every restart got
+    pub restarts: Vec<Restart>,
+}
+
+impl Checkpoint {
+    /// Checks that a checkpoint belongs to a search.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The ciphertext of the search.
+    /// * `settings` - The settings of the search.
+    /// * `restarts` - How many restarts the search runs.
+    ///
+    /// # Returns
+    ///
+    /// * A usage error naming what differs.
+    pub fn check(
+        &self,
+        ciphertext: &str,
+        settings: &Settings,
+        restarts: usize,
+    ) -> Result<(), Error> {
+        if self.ciphertext != ciphertext {
+            return Err(Error::Usage(String::from(
+                "the checkpoint is of another ciphertext",
+            )));
+        }
+        if let Some(difference) = self.settings.difference(settings) {
+            return Err(Error::Usage(format!(
+                "the checkpoint is of a search with {}",
+                difference
+            )));
+        }
+        if self.seeds.len() != restarts || self.restarts.len() != restarts {
+            return Err(Error::Usage(format!(
+                "the checkpoint has {} restarts, not {}",
+                self.seeds.len(),
+                restarts
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reads a checkpoint file.
+///
+/// # Returns
+///
+/// * The checkpoint, an I/O error if the file can't be read, or an invalid
+///   input error if it isn't a checkpoint.
+pub fn read(path: &str) -> Result<Checkpoint, Error> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read checkpoint '{}': {}", path, e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| Error::InvalidInput(format!("invalid checkpoint '{}': {}", path, e)))
+}
+
+/// Writes a checkpoint file through a temporary file next to it.
+///
+/// # Returns
+///
+/// * An I/O error if the file can't be written.
+pub fn write(path: &str, checkpoint: &Checkpoint) -> Result<(), Error> {
+    let contents: String =
+        serde_json::to_string(checkpoint).map_err(|e| Error::Internal(e.to_string()))?;
+    let temporary: String = format!("{}.tmp", path);
+    std::fs::write(&temporary, contents)
+        .and_then(|_| std::fs::rename(&temporary, path))
+        .map_err(|e| Error::Io(format!("cannot write checkpoint '{}': {}", path, e)))
+}
+
+/// Keeps the checkpoint of a running search and writes it now and then
+pub struct Writer {
+    /// The file written
+    path: String,
+    /// Longest time between two writes while restarts run
+    interval: Duration,
+    /// The state of the search and when it was last written
+    state: Mutex<(Checkpoint, Instant)>,
+}
+
+impl Writer {
+    /// Creates a writer, writing the checkpoint once.
+    ///
+    /// # Returns
+    ///
+    /// * The writer, or an I/O error if the file can't be written.
+    pub fn new(path: &str, interval: Duration, checkpoint: Checkpoint) -> Result<Self, Error> {
+        write(path, &checkpoint)?;
+        Ok(Writer {
+            path: path.to_string(),
+            interval,
+            state: Mutex::new((checkpoint, Instant::now())),
+        })
+    }
+
+    /// Records how far a restart got, and writes the checkpoint if it is
+    /// due or `now` is set.
+    ///
+    /// Failing writes are warned about and the search goes on.
+    ///
+    /// # Arguments
+    ///
+    /// * `restart` - The restart, counted from 1.
+    /// * `now` - Whether to write the checkpoint even if it isn't due.
+    /// * `state` - Builds the state of the restart, only called if the
+    ///   checkpoint is written.
+    pub fn update(&self, restart: usize, now: bool, state: impl FnOnce() -> Restart) {
+        let mut guard = self.state.lock().unwrap();
+        let (checkpoint, written) = &mut *guard;
+        if !now && written.elapsed() < self.interval {
+            return;
+        }
+        checkpoint.restarts[restart - 1] = state();
+        if let Err(e) = write(&self.path, checkpoint) {
+            eprintln!("Warning: {}", e);
+        }
+        *written = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The settings of a hill climb of the default variant
+    fn hill() -> Settings {
+        Settings {
+            solver: String::from("hill"),
+            schedule: None,
+            genetics: None,
+            moves: vec![Move::Swap, Move::Rows],
+            iterations: 1000,
+            alphabet: String::from("ABCDEFGHIKLMNOPQRSTUVWXYZ"),
+            period: None,
+            rounds: 1,
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_file() {
+        let path: std::path::PathBuf = std::env::temp_dir().join("playfair_test_checkpoint.json");
+        let path: &str = path.to_str().unwrap();
+        let checkpoint: Checkpoint = Checkpoint {
+            ciphertext: String::from("ABCD"),
+            settings: hill(),
+            seeds: vec![1, u64::MAX],
+            restarts: vec![Restart::Pending, Restart::Pending],
+        };
+        let writer: Writer = Writer::new(path, Duration::from_secs(3600), checkpoint).unwrap();
+        let climb: Climb = Climb {
+            iteration: 4096,
+            rng: 7,
+            cells: vec![1, 0],
+            score: -1.25,
+            best: vec![0, 1],
+            best_score: -0.1,
+        };
+        // Not due yet, so nothing is recorded until it is forced
+        writer.update(1, false, || unreachable!());
+        assert_eq!(read(path).unwrap().restarts[0], Restart::Pending);
+        writer.update(1, true, || Restart::Climbing(climb.clone()));
+        let read: Checkpoint = read(path).unwrap();
+        assert_eq!(read.restarts[0], Restart::Climbing(climb));
+        assert_eq!(read.seeds, vec![1, u64::MAX]);
+        assert!(read.check("ABCD", &hill(), 2).is_ok());
+        assert!(matches!(
+            read.check("ABCE", &hill(), 2),
+            Err(Error::Usage(_))
+        ));
+        assert!(matches!(
+            read.check("ABCD", &hill(), 3),
+            Err(Error::Usage(_))
+        ));
+        let annealing: Settings = Settings {
+            solver: String::from("anneal"),
+            schedule: Some(Schedule::default()),
+            ..hill()
+        };
+        let rounds: Settings = Settings {
+            rounds: 2,
+            ..hill()
+        };
+        let alphabet: Settings = Settings {
+            alphabet: String::from("ABCDEFGHIJKLMNOPQRSTUVWXY"),
+            ..hill()
+        };
+        assert_eq!(
+            read.check("ABCD", &annealing, 2),
+            Err(Error::Usage(String::from(
+                "the checkpoint is of a search with another solver"
+            )))
+        );
+        assert_eq!(
+            read.check("ABCD", &rounds, 2),
+            Err(Error::Usage(String::from(
+                "the checkpoint is of a search with another number of rounds"
+            )))
+        );
+        assert_eq!(
+            read.check("ABCD", &alphabet, 2),
+            Err(Error::Usage(String::from(
+                "the checkpoint is of a search with another alphabet"
+            )))
+        );
+        std::fs::write(path, "{}").unwrap();
+        assert!(matches!(super::read(path), Err(Error::InvalidInput(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,2249 @@
+//! The command line of `playfair`: its flags and subcommands, and the
+//! encryption and decryption they run.
+//!
+//! `build_cli` describes the interface and `run` dispatches the parsed
+//! arguments to the subcommands, the cipher commands being handled here.
+//! The helpers reading the key, the variant and the output settings from
+//! the arguments are shared by the subcommands of the other modules.
+
+use crate::config::Config;
+#[cfg(unix)]
+use crate::daemon;
+use crate::encoding::Encoding;
+use crate::envelope::Envelope;
+use crate::error::Error;
+use crate::mask::Mask;
+use crate::squares::DigramCipher;
+use crate::{
+    CipherMode, CipherSettings, Doubles, KEY_ENV_VAR, KeySquare, OutputFormat, OutputStyle,
+    Variant, analysis, analyze, auto, bench, binary, clipboard, color, config,
+    config_from_settings, dropped_chars, encoding, envelope, export, filter, info, keycheck,
+    keygen, mnemonic, morse, nato, normalize, pair_order, polybius, prepare_letters, prompt_key,
+    qr, read_dictionary, read_key_list, repl, resolve_key, round_inputs, selftest, serve,
+    settings_from_config, skipped_letters, solver, squares, table_fingerprint, table_letters,
+    trace_pairs, transposition, tui, vectors, watch,
+};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use clap_complete::Shell;
+use serde_json::json;
+use std::io::Write;
+
+/// Builds the command-line interface.
+///
+/// # Returns
+///
+/// * The clap command describing every flag and subcommand.
+pub fn build_cli() -> Command {
+    build_cli_with(solver::SOLVERS)
+}
+
+/// Builds the command-line interface with other custom solvers than the
+/// defaults of `solver::SOLVERS`.
+///
+/// # Arguments
+///
+/// * `solvers` - The solvers `crack --solver` offers besides the built-in
+///   ones.
+///
+/// # Returns
+///
+/// * The clap command describing every flag and subcommand.
+pub fn build_cli_with(solvers: &[&dyn solver::Solver]) -> Command {
+    Command::new("Playfair")
+        .bin_name("playfair")
+        .version("1.0")
+        .author("martian58")
+        .about("Encrypts or decrypts text using the Playfair cipher")
+        .args(key_args())
+        .args(key_list_args())
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("TEXT")
+                .help("The text to encrypt or decrypt")
+                // Morse code starts with dashes
+                .allow_hyphen_values(true)
+                .required_unless_present_any(["paste", "binary", "envelope"]),
+        )
+        .arg(
+            Arg::new("binary")
+                .long("binary")
+                .value_name("FILE")
+                .help("Encrypts the bytes of FILE (- for stdin) as letters, or decrypts them back to raw bytes on stdout")
+                .conflicts_with_all(["input", "paste", "keep-layout", "preserve-case", "strip-filler", "normalize"]),
+        )
+        .arg(
+            Arg::new("envelope")
+                .long("envelope")
+                .value_name("FILE")
+                .help("Writes the ciphertext and its settings to a .pfr envelope, or decrypts one with its settings")
+                .conflicts_with_all(["paste", "binary"]),
+        )
+        .arg(
+            Arg::new("mask")
+                .long("mask")
+                .value_name("FILE")
+                .help("Writes the case, punctuation and fillers of the plaintext to FILE, or restores the exact text from it")
+                .conflicts_with_all(["binary", "keep-layout", "preserve-case", "strip-filler", "normalize"]),
+        )
+        .arg(
+            Arg::new("with-mask")
+                .long("with-mask")
+                .help("Also stores the mask in the envelope when encrypting")
+                .requires("envelope")
+                .conflicts_with("mask")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("in-encoding")
+                .long("in-encoding")
+                .value_name("ENCODING")
+                .help("Decodes the input from hex or base64 before processing it [default: plain]")
+                .value_parser(["plain", "hex", "base64"])
+                .conflicts_with("binary"),
+        )
+        .arg(
+            Arg::new("out-encoding")
+                .long("out-encoding")
+                .value_name("ENCODING")
+                .help("Encodes the result as hex or base64, e.g. for JSON or URLs [default: plain]")
+                .value_parser(["plain", "hex", "base64"]),
+        )
+        .arg(
+            Arg::new("paste")
+                .long("paste")
+                .help("Takes the text to encrypt or decrypt from the clipboard")
+                .conflicts_with("input")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("copy")
+                .long("copy")
+                .help("Also places the result on the clipboard")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("qr")
+                .long("qr")
+                .value_name("PATH")
+                .help("Also writes the ciphertext as a QR code to a PNG file, or draws it on stderr with -"),
+        )
+        .arg(decrypt_arg())
+        .arg(
+            Arg::new("preserve-case")
+                .long("preserve-case")
+                .help("Writes each letter of the result in the case of the input letter at its position")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("keep-layout")
+                .long("keep-layout")
+                .help("Keeps punctuation, digits and whitespace in place and only enciphers the letters")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strip-filler")
+                .long("strip-filler")
+                .help("Removes the fillers a decrypted text most likely contains")
+                .requires("decrypt")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dictionary")
+                .long("dictionary")
+                .value_name("PATH")
+                .help("Keeps the fillers of the words in this list, one per line, with --strip-filler")
+                .requires("strip-filler"),
+        )
+        .arg(
+            Arg::new("normalize")
+                .long("normalize")
+                .help("Replaces accented letters by their base letter (é as E) and spells out ligatures")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .help("Rejects input with characters the cipher would drop instead of skipping them")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .help("Decrypts the result again and reports what the reader must undo")
+                .conflicts_with("decrypt")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("highlight-last")
+                .long("highlight-last")
+                .help("Highlights the cells of the last digram in the colored table")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("trace")
+                .long("trace")
+                .help("Prints each digram, the rule applied and the coordinates involved to stderr")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .args(output_args())
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format of the result [default: text]")
+                .value_parser(["text", "json", "nato", "morse"]),
+        )
+        .arg(
+            Arg::new("letter-separator")
+                .long("letter-separator")
+                .value_name("TEXT")
+                .help("Writes TEXT between the Morse codes of a letter group [default: \" \"]"),
+        )
+        .arg(
+            Arg::new("word-separator")
+                .long("word-separator")
+                .value_name("TEXT")
+                .help("Writes TEXT between the Morse letter groups [default: \" / \"]"),
+        )
+        .args(cipher_args())
+        .arg(config_arg())
+        .args(variant_args())
+        .subcommand(
+            Command::new("completions")
+                .about("Prints a shell completion script to stdout")
+                .arg(
+                    Arg::new("shell")
+                        .value_name("SHELL")
+                        .help("The shell to generate completions for")
+                        .value_parser(["bash", "zsh", "fish", "powershell"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Encrypts files as they appear or change in a directory")
+                .args(key_args())
+                .arg(decrypt_arg())
+                .arg(config_arg())
+                .args(variant_args())
+                .args(output_args())
+                .arg(
+                    Arg::new("in")
+                        .long("in")
+                        .value_name("DIR")
+                        .help("The directory to watch for new or changed files")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .value_name("DIR")
+                        .help("The directory the processed files are written to")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("repl")
+                .about("Starts an interactive session encrypting or decrypting each line typed")
+                .args(key_args())
+                .args(key_list_args())
+                .arg(decrypt_arg())
+                .arg(config_arg())
+                .args(variant_args())
+                .args(output_args()),
+        )
+        .subcommand(
+            Command::new("tui")
+                .about("Opens a full-screen view highlighting how each digram is transformed")
+                .args(key_args())
+                .arg(decrypt_arg())
+                .arg(config_arg())
+                .args(variant_args()),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Serves /encrypt and /decrypt JSON endpoints over HTTP")
+                .args(key_args())
+                .arg(config_arg())
+                .args(variant_args())
+                .args(output_args())
+                .arg(
+                    Arg::new("bind")
+                        .long("bind")
+                        .value_name("ADDR")
+                        .help("The address to listen on")
+                        .default_value("127.0.0.1:8080"),
+                ),
+        )
+        .subcommands(daemon_command())
+        .subcommand(
+            Command::new("filter")
+                .about("Git clean/smudge filter storing files encrypted in the repository")
+                .args(key_args())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("clean")
+                        .long("clean")
+                        .help("Encrypts stdin for storage in the repository")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("smudge")
+                        .long("smudge")
+                        .help("Restores stdin to the working tree file")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .group(
+                    ArgGroup::new("direction")
+                        .args(["clean", "smudge"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("table")
+                .about("Prints the table of a key, or exports it as LaTeX, HTML or SVG")
+                .args(key_args())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("export")
+                        .long("export")
+                        .value_name("FORMAT")
+                        .help("Writes the table as a LaTeX tabular, an HTML table or an SVG image")
+                        .value_parser(["latex", "html", "svg"]),
+                )
+                .arg(
+                    Arg::new("stats")
+                        .long("stats")
+                        .help("Prints the structure of the table: displacement from alphabetical order, runs of consecutive symbols, permutation entropy and the shortest keyword that fills it")
+                        .conflicts_with("export")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Reports the keyspace of the cipher and the unicity distance, how much ciphertext pins down a key")
+                .args(cipher_args())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("lang")
+                        .long("lang")
+                        .value_name("LANG")
+                        .help("Sets the language of the plaintext: en, fr, de, es or it [default: en]")
+                        .value_parser(["en", "fr", "de", "es", "it"]),
+                ),
+        )
+        .subcommand(
+            Command::new("auto")
+                .about("Encrypts or decrypts a text, whichever way its result reads less like a ciphertext")
+                .args(key_args())
+                .arg(config_arg())
+                .args(variant_args())
+                .args(output_args())
+                .arg(
+                    Arg::new("text")
+                        .value_name("TEXT")
+                        .help("The plaintext or ciphertext")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("crack")
+                .about("Searches the key table of a ciphertext by scoring its decryptions on n-grams of a language")
+                .arg(
+                    Arg::new("ciphertext")
+                        .value_name("CIPHERTEXT")
+                        .help("The ciphertext to crack, best a few hundred letters long; several ciphertexts under the same key are cracked together")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("iterations")
+                        .long("iterations")
+                        .value_name("N")
+                        .help("How many tables every restart scores: changes tried, children bred by --solver genetic, or tables scored by --solver beam")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("500000"),
+                )
+                .arg(
+                    Arg::new("restarts")
+                        .long("restarts")
+                        .value_name("N")
+                        .help("How many times at most the search starts over from a shuffled table")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("20"),
+                )
+                .arg(
+                    Arg::new("solver")
+                        .long("solver")
+                        .value_name("SOLVER")
+                        .help("Keeps only better tables (hill), sometimes worse ones too while cooling (anneal), evolves a population (genetic), keeps the best tables of every step without chance (beam), scores every table agreeing with --crib (exact), or climbs by a solver listed in the solver module, such as threshold accepting (threshold) [default: anneal]")
+                        .value_parser(clap::builder::PossibleValuesParser::new(
+                            ["hill", "anneal", "genetic", "beam", "exact"]
+                                .into_iter()
+                                .chain(solvers.iter().map(|solver| solver.name())),
+                        )),
+                )
+                .arg(
+                    Arg::new("temperature")
+                        .long("temperature")
+                        .value_name("T")
+                        .help("Sets the starting temperature of --solver anneal [default: set by --schedule]")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("cooling")
+                        .long("cooling")
+                        .value_name("SCHEDULE")
+                        .help("Lowers the temperature by the same amount (linear) or factor (geometric) at every change, or linearly while keeping a falling share of worse tables (adaptive) [default: linear]")
+                        .value_parser(["linear", "geometric", "adaptive"]),
+                )
+                .arg(
+                    Arg::new("cooling-factor")
+                        .long("cooling-factor")
+                        .value_name("F")
+                        .help("Multiplies the temperature by F, between 0 and 1, at every change of geometric cooling, e.g. 0.99999 [default: down to 0.1 at the last change]")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("reheat")
+                        .long("reheat")
+                        .value_name("N")
+                        .help("Starts the schedule over from its first temperature after N changes without a better table, 0 for never [default: set by --schedule]")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("schedule")
+                        .long("schedule")
+                        .value_name("PRESET")
+                        .help("Picks the temperature and reheat of --solver anneal for a short (under 300 letters), medium or long (over 600) ciphertext, or by its length (auto) [default: auto]")
+                        .value_parser(["auto", "short", "medium", "long"]),
+                )
+                .arg(
+                    Arg::new("moves")
+                        .long("moves")
+                        .value_name("LIST")
+                        .help("Changes tried on the table: swap, rows, columns and reflect, comma-separated [default: all]"),
+                )
+                .arg(
+                    Arg::new("population")
+                        .long("population")
+                        .value_name("N")
+                        .help("Sets how many tables every generation of --solver genetic holds [default: 100]")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("crossover")
+                        .long("crossover")
+                        .value_name("RATE")
+                        .help("Sets the probability that a child mixes two parents [default: 0.7]")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("mutation")
+                        .long("mutation")
+                        .value_name("RATE")
+                        .help("Sets the probability that a child is changed at random [default: 0.5]")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("width")
+                        .long("width")
+                        .value_name("K")
+                        .help("Sets how many tables every step of --solver beam keeps [default: 10]")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("threads")
+                        .long("threads")
+                        .value_name("N")
+                        .help("Runs N restarts at the same time [default: the number of cores]")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("time-limit")
+                        .long("time-limit")
+                        .visible_alias("max-time")
+                        .value_name("SECONDS")
+                        .help("Stops all restarts after SECONDS and shows the best table so far")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("max-iterations")
+                        .long("max-iterations")
+                        .value_name("N")
+                        .help("Stops all restarts once they tried N tables in all and shows the best table so far")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("target-score")
+                        .long("target-score")
+                        .value_name("SCORE")
+                        .help("Stops all restarts once one of them reaches SCORE per n-gram, such as -5.2, instead of the score of text reading as the language")
+                        .allow_hyphen_values(true)
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("gpu")
+                        .long("gpu")
+                        .help("Scores the batches of --solver beam or genetic on the GPU, falling back to the CPU (needs a build with --features gpu)")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("crib")
+                        .long("crib")
+                        .value_name("PLAIN=CIPHER")
+                        .help("Starts from the table rebuilt from known plaintext digrams and the ciphertext digrams they encrypt to, e.g. THEM=XPEF; may be repeated")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("hint")
+                        .long("hint")
+                        .value_name("OFFSET:WORD")
+                        .help("Like --crib, with a word known to start at a letter of the plaintext, counted from the end if negative, e.g. 0:DEAR or -7:REGARDS; may be repeated")
+                        .allow_hyphen_values(true)
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("fitness")
+                        .long("fitness")
+                        .value_name("NGRAMS")
+                        .help("Scores decryptions by the built-in quadgrams or bigrams of the language [default: quadgrams]")
+                        .value_parser(["quadgrams", "bigrams"])
+                        .conflicts_with("stats"),
+                )
+                .arg(
+                    Arg::new("lang")
+                        .long("lang")
+                        .value_name("LANG")
+                        .help("Sets the language of the plaintext: en, fr, de, es or it [default: en]")
+                        .value_parser(["en", "fr", "de", "es", "it"])
+                        .conflicts_with("stats"),
+                )
+                .arg(
+                    Arg::new("stats")
+                        .long("stats")
+                        .value_name("FILE")
+                        .help("Scores decryptions by the n-gram counts of a file, one 'TION 145752' per line, plain or gzipped"),
+                )
+                .arg(
+                    Arg::new("words")
+                        .long("words")
+                        .value_name("FILE")
+                        .help("Ranks the best tables by how much of their decryption is spelled by the words of this list, one per line [default: built-in words for English]"),
+                )
+                .arg(
+                    Arg::new("find-period")
+                        .long("find-period")
+                        .help("Estimates the period of a seriated ciphertext as analyze period does, and searches with it")
+                        .conflicts_with("period")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("top")
+                        .long("top")
+                        .value_name("N")
+                        .help("Shows the N best tables with distinct decryptions and their scores [default: 1]")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format of the tables found [default: text]")
+                        .value_parser(["text", "json"]),
+                )
+                .arg(
+                    Arg::new("seed")
+                        .long("seed")
+                        .value_name("N")
+                        .help("Seeds the search so the same tables are tried again")
+                        .value_parser(clap::value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("progress")
+                        .long("progress")
+                        .value_name("FORMAT")
+                        .help("Reports the tables tried per second, the best score and the start of its decryption to stderr as text or json lines")
+                        .value_parser(["text", "json"]),
+                )
+                .arg(
+                    Arg::new("progress-interval")
+                        .long("progress-interval")
+                        .value_name("SECONDS")
+                        .help("Sets the time between two progress reports [default: 1]")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("checkpoint")
+                        .long("checkpoint")
+                        .value_name("PATH")
+                        .help("Writes the state of the restarts to PATH every minute, when a restart ends and when the search stops"),
+                )
+                .arg(
+                    Arg::new("resume")
+                        .long("resume")
+                        .value_name("PATH")
+                        .help("Carries on from the checkpoint at PATH, with the same ciphertext and solver flags, writing to it as it goes")
+                        .conflicts_with("seed"),
+                )
+                .arg(
+                    Arg::new("tui")
+                        .long("tui")
+                        .help("Runs the search in a full-screen view of the best table, its score and its decryption, where cells can be pinned")
+                        .conflicts_with_all(["gpu", "top", "format", "threads", "time-limit", "max-iterations", "target-score", "progress", "checkpoint", "resume", "report"])
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_name("PATH")
+                        .help("Writes a write-up of the run to PATH: the ciphertext, its statistics, the settings, the best tables and their decryptions, as HTML if PATH ends in .html and Markdown otherwise"),
+                )
+                .arg(
+                    Arg::new("compare")
+                        .long("compare")
+                        .help("Runs every solver in turn for an even share of --time-limit (default 60 s) and prints how often each solved the ciphertext, its best score and when it first solved it")
+                        .conflicts_with_all(["solver", "top", "format", "tui", "report", "progress", "checkpoint", "resume"])
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(analyze_command())
+        .subcommand(
+            Command::new("keygen")
+                .about("Generates random key squares or pronounceable keywords")
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("keyword")
+                        .long("keyword")
+                        .help("Generates pronounceable keywords and the squares they fill instead")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("mnemonic")
+                        .long("mnemonic")
+                        .help("Prints each square as mnemonic words for --key-mnemonic")
+                        .conflicts_with("keyword")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("length")
+                        .long("length")
+                        .value_name("N")
+                        .help("The number of letters of each keyword")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("8"),
+                )
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .value_name("N")
+                        .help("How many keys to generate")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::new("seed")
+                        .long("seed")
+                        .value_name("N")
+                        .help("Seeds the generator so the same keys are generated again")
+                        .value_parser(clap::value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            Command::new("keycheck")
+                .about("Warns about weak keywords: short, leaving the table in alphabetical order, dictionary words or repeated letters")
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("key")
+                        .value_name("KEY")
+                        .help("The keyword to check")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("words")
+                        .long("words")
+                        .value_name("FILE")
+                        .help("Checks the key against the words of FILE, one per line, plain or gzipped, instead of the built-in English words"),
+                ),
+        )
+        .subcommand(
+            Command::new("selftest")
+                .about("Checks the cipher against embedded known-answer vectors"),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Measures encryption and decryption throughput on synthetic input")
+                .arg(
+                    Arg::new("size")
+                        .long("size")
+                        .value_name("BYTES")
+                        .help("The number of letters in the synthetic input")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("100000"),
+                )
+                .arg(
+                    Arg::new("iterations")
+                        .long("iterations")
+                        .value_name("N")
+                        .help("How many times the input is encrypted and decrypted")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("vectors")
+                .about("Checks or regenerates a TOML or JSON file of test vectors")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about("Compares the ciphertext of every vector with this implementation")
+                        .arg(vector_file_arg()),
+                )
+                .subcommand(
+                    Command::new("generate")
+                        .about(
+                            "Prints the file with every ciphertext computed by this implementation",
+                        )
+                        .arg(vector_file_arg()),
+                ),
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+}
+
+/// Builds the `analyze` subcommand and its subcommands, which all read a
+/// ciphertext in the table of the variant.
+fn analyze_command() -> Command {
+    let ciphertext = || {
+        Arg::new("ciphertext")
+            .value_name("CIPHERTEXT")
+            .help("The ciphertext to analyze")
+            .required(true)
+    };
+    let lang = |help: &'static str| {
+        Arg::new("lang")
+            .long("lang")
+            .value_name("LANG")
+            .help(help)
+            .value_parser(["en", "fr", "de", "es", "it"])
+    };
+    // Its own id, as `--format` of the other commands is read as the output
+    // format of the settings, which has no csv
+    let format = || {
+        Arg::new("stats-format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Output format of the statistics: text, json or csv [default: text]")
+            .value_parser(["text", "json", "csv"])
+    };
+    Command::new("analyze")
+        .about("Helps break a ciphertext by hand: detects Playfair, counts digrams, measures statistics or slides a suspected word along it")
+        .arg(ciphertext())
+        .arg(config_arg())
+        .args(variant_args())
+        .arg(
+            Arg::new("drag-crib")
+                .long("drag-crib")
+                .value_name("WORD")
+                .help("Shows every offset of the plaintext WORD may take, with the table cells and the decrypted digrams it gives")
+                .required(true),
+        )
+        .arg(lang("Sets the language the decrypted digrams are scored in: en, fr, de, es or it [default: en]"))
+        .subcommand(
+            Command::new("freq")
+                .about("Counts the digrams of a ciphertext, the pairs that also occur reversed, and compares them with the language")
+                .arg(ciphertext())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("top")
+                        .long("top")
+                        .value_name("N")
+                        .help("Shows the N most common digrams and reversed pairs")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                )
+                .arg(lang("Sets the language the digrams are compared with: en, fr, de, es or it [default: en]"))
+                .arg(format()),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Measures the index of coincidence, entropy and chi-squared statistic of the letters and digrams of a ciphertext")
+                .arg(ciphertext())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(lang("Sets the language the statistics are compared with: en, fr, de, es or it [default: en]"))
+                .arg(format()),
+        )
+        .subcommand(
+            Command::new("period")
+                .about("Estimates the period of a seriated ciphertext from how often its pairs repeat at every spacing")
+                .arg(ciphertext())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(
+                    Arg::new("max-period")
+                        .long("max-period")
+                        .value_name("N")
+                        .help("Tries the periods up to N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("20"),
+                )
+                .arg(format()),
+        )
+        .subcommand(
+            Command::new("heatmap")
+                .about("Shades the digram and letter counts of a ciphertext in the terminal, and with a key how often every cell of the table is used")
+                .arg(ciphertext())
+                .arg(config_arg())
+                .args(variant_args())
+                .args(key_args()),
+        )
+        .subcommand(
+            Command::new("fitness")
+                .about("Ranks candidate decryptions by how much they read like the language, by the quadgram scores of crack")
+                .arg(
+                    Arg::new("text")
+                        .value_name("TEXT")
+                        .help("The texts to rank")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(lang("Sets the language the texts are scored in: en, fr, de, es or it [default: en]"))
+                .arg(format()),
+        )
+        .subcommand(
+            Command::new("same-key")
+                .about("Weighs whether two ciphertexts share a key from how often their digrams coincide, with the chance under different keys")
+                .arg(
+                    Arg::new("ciphertext")
+                        .value_name("CIPHERTEXT")
+                        .help("The two ciphertexts to compare")
+                        .num_args(2)
+                        .required(true),
+                )
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(lang("Sets the language of the plaintexts: en, fr, de, es or it [default: en]"))
+                .arg(format()),
+        )
+        .subcommand(
+            Command::new("detect")
+                .about("Weighs whether a ciphertext is Playfair or another classical cipher, with a confidence")
+                .arg(ciphertext())
+                .arg(config_arg())
+                .args(variant_args())
+                .arg(lang("Sets the language of the plaintext: en, fr, de, es or it [default: en]"))
+                .arg(format()),
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+}
+
+/// Builds the `daemon` subcommand, which needs unix domain sockets.
+fn daemon_command() -> Option<Command> {
+    if cfg!(unix) {
+        Some(
+            Command::new("daemon")
+                .about("Answers newline-delimited JSON requests on a unix domain socket")
+                .args(key_args())
+                .arg(config_arg())
+                .args(variant_args())
+                .args(output_args())
+                .arg(
+                    Arg::new("socket")
+                        .long("socket")
+                        .value_name("PATH")
+                        .help("The socket to listen on")
+                        .required(true),
+                ),
+        )
+    } else {
+        None
+    }
+}
+
+/// Builds the arguments selecting the key.
+fn key_args() -> [Arg; 5] {
+    [
+        Arg::new("key")
+            .short('k')
+            .long("key")
+            .value_name("KEY")
+            .help("Sets the encryption/decryption key (defaults to $PLAYFAIR_KEY)"),
+        Arg::new("key-file")
+            .long("key-file")
+            .value_name("PATH")
+            .help("Reads the key (a keyword or a 25-letter square) from a file"),
+        Arg::new("key-square")
+            .long("key-square")
+            .value_name("SYMBOLS")
+            .help(
+                "Uses this pre-arranged table, every symbol once row by row, instead of a keyword",
+            )
+            .conflicts_with_all(["key", "key-file"]),
+        Arg::new("key-mnemonic")
+            .long("key-mnemonic")
+            .value_name("WORDS")
+            .help("Uses the table encoded by these mnemonic words, as printed by keygen --mnemonic")
+            .conflicts_with_all(["key", "key-file", "key-square"]),
+        Arg::new("derive-square")
+            .long("derive-square")
+            .help(
+                "Treats the key as a passphrase and derives a shuffled table from its SHA-256 hash",
+            )
+            .conflicts_with_all(["key-square", "key-mnemonic"])
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+/// Builds the arguments rotating the key through a list, one key per message.
+fn key_list_args() -> [Arg; 2] {
+    [
+        Arg::new("key-list")
+            .long("key-list")
+            .value_name("PATH")
+            .help("Reads a key schedule, one key per line, and uses its keys in turn for each message")
+            .conflicts_with_all(["key", "key-file", "key-square", "key-mnemonic"]),
+        Arg::new("message-index")
+            .long("message-index")
+            .value_name("N")
+            .help("Uses key N of the --key-list, counting from 1 and wrapping around [default: 1]")
+            .value_parser(clap::value_parser!(usize))
+            .requires("key-list"),
+    ]
+}
+
+/// Builds the argument naming a vector file.
+fn vector_file_arg() -> Arg {
+    Arg::new("file")
+        .value_name("FILE")
+        .help("The vector file, read as JSON if it ends in .json and as TOML otherwise")
+        .required(true)
+}
+
+/// Builds the flag switching to decryption.
+fn decrypt_arg() -> Arg {
+    Arg::new("decrypt")
+        .short('d')
+        .long("decrypt")
+        .help("Decrypt the input text instead of encrypting")
+        .action(clap::ArgAction::SetTrue)
+}
+
+/// Builds the arguments formatting the results, which override the config file.
+fn output_args() -> [Arg; 3] {
+    [
+        Arg::new("group")
+            .long("group")
+            .value_name("N")
+            .help("Splits the result into groups of N letters, e.g. the classic 5")
+            .value_parser(clap::value_parser!(usize)),
+        Arg::new("separator")
+            .long("separator")
+            .value_name("CHAR")
+            .help(
+                "Writes CHAR between the groups and skips it in decrypted input [default: space]",
+            ),
+        Arg::new("lower")
+            .long("lower")
+            .help("Writes the result in lowercase")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+/// Builds the arguments choosing the cipher and its second key.
+fn cipher_args() -> [Arg; 3] {
+    [
+        Arg::new("cipher")
+            .long("variant")
+            .value_name("CIPHER")
+            .help("Uses the playfair, two-square, four-square, double-playfair, playfair-columnar, polybius or adfgvx cipher [default: playfair]")
+            .value_parser([
+                "playfair",
+                "two-square",
+                "four-square",
+                "double-playfair",
+                "playfair-columnar",
+                "polybius",
+                "adfgvx",
+            ]),
+        Arg::new("second-key")
+            .short('K')
+            .long("second-key")
+            .value_name("KEY")
+            .help("Sets the second key: of the second table, or of the playfair-columnar, polybius or adfgvx transposition"),
+        Arg::new("arrangement")
+            .long("arrangement")
+            .value_name("LAYOUT")
+            .help("Places the two-square tables above or beside each other [default: vertical]")
+            .value_parser(["vertical", "horizontal"]),
+    ]
+}
+
+/// Builds the arguments selecting the variant, which override the config file.
+fn variant_args() -> [Arg; 12] {
+    [
+        Arg::new("compat")
+            .long("compat")
+            .value_name("TOOL")
+            .help("Follows the merge, filler and padding conventions of dCode, CrypTool or practicalcryptography.com")
+            .value_parser(["dcode", "cryptool", "practicalcryptography"]),
+        Arg::new("filler")
+            .long("filler")
+            .value_name("LETTER")
+            .help("Inserts LETTER between repeated letters and pads odd-length texts with it [default: X]"),
+        Arg::new("alt-filler")
+            .long("alt-filler")
+            .value_name("LETTER")
+            .help("Inserts LETTER instead of the filler after a filler, e.g. Q between two Xs"),
+        Arg::new("grid")
+            .long("grid")
+            .value_name("SIZE")
+            .help("Uses a 5x5 table of letters or a 6x6 table of letters and digits [default: 5x5]")
+            .value_parser(["5x5", "6x6"]),
+        Arg::new("alphabet")
+            .long("alphabet")
+            .value_name("SYMBOLS")
+            .help("Builds the table from these symbols, e.g. 25 for 5x5 or 30 for 5x6, or the Cyrillic ru or uk 6x6 table")
+            .conflicts_with("grid"),
+        Arg::new("locale")
+            .long("locale")
+            .value_name("LANG")
+            .help("Spells out letters missing from the table the way LANG does, e.g. ß as SS")
+            .value_parser(["de", "tr", "es"]),
+        Arg::new("route")
+            .long("route")
+            .value_name("ROUTE")
+            .help("Fills the table from the key by rows, columns, spiral, zigzag or reversed [default: rows]")
+            .value_parser(["rows", "columns", "spiral", "zigzag", "reversed"]),
+        Arg::new("rounds")
+            .long("rounds")
+            .value_name("N")
+            .help("Applies the cipher N times, shifting the pairs by one letter between rounds [default: 1]")
+            .value_parser(clap::value_parser!(usize)),
+        Arg::new("period")
+            .long("period")
+            .value_name("N")
+            .help("Seriates the text: writes it in two rows of N letters and pairs them by columns")
+            .value_parser(clap::value_parser!(usize)),
+        Arg::new("doubles")
+            .long("doubles")
+            .value_name("STRATEGY")
+            .help("Splits doubled digrams with the filler, puts it between all doubles, skips the repeat or fails [default: digram]")
+            .value_parser(["digram", "all", "skip", "error"]),
+        Arg::new("merge")
+            .long("merge")
+            .value_name("FROM=INTO")
+            .help("Leaves FROM out of the table and reads it as INTO [default: J=I]"),
+        Arg::new("omit")
+            .long("omit")
+            .value_name("LETTER")
+            .help("Leaves LETTER out of the table and drops it from texts, e.g. Q")
+            .conflicts_with("merge"),
+    ]
+}
+
+/// Builds the argument pointing at an explicit config file.
+fn config_arg() -> Arg {
+    Arg::new("config")
+        .long("config")
+        .value_name("PATH")
+        .help("Reads default settings from this file instead of ~/.config/playfair/config.toml")
+}
+
+/// Writes the completion script for a shell to stdout.
+///
+/// The script is generated in memory first, as the generator panics on a
+/// failed write.
+///
+/// # Arguments
+///
+/// * `shell` - One of `bash`, `zsh`, `fish` or `powershell`.
+/// * `solvers` - The solvers `crack --solver` offers besides the built-in
+///   ones.
+fn print_completions(shell: &str, solvers: &[&dyn solver::Solver]) -> Result<(), Error> {
+    let shell: Shell = match shell {
+        "bash" => Shell::Bash,
+        "zsh" => Shell::Zsh,
+        "fish" => Shell::Fish,
+        _ => Shell::PowerShell,
+    };
+    let mut cli: Command = build_cli_with(solvers);
+    let mut script: Vec<u8> = Vec::new();
+    clap_complete::generate(shell, &mut cli, "playfair", &mut script);
+    std::io::stdout().lock().write_all(&script)?;
+    Ok(())
+}
+
+/// Runs the command selected on the command line.
+///
+/// Results are written to stdout; the generated table and other diagnostics
+/// go to stderr.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed command-line arguments.
+///
+/// # Returns
+///
+/// * An error describing why the command failed.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    run_with(matches, solver::SOLVERS)
+}
+
+/// Runs the command selected on the command line of `build_cli_with`.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed command-line arguments.
+/// * `solvers` - The solvers `crack --solver` picks from besides the
+///   built-in ones, the same as given to `build_cli_with`.
+///
+/// # Returns
+///
+/// * An error describing why the command failed.
+pub fn run_with(matches: &ArgMatches, solvers: &[&dyn solver::Solver]) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("completions", sub)) => {
+            print_completions(sub.get_one::<String>("shell").unwrap(), solvers)
+        }
+        Some(("watch", sub)) => watch::run(sub),
+        Some(("repl", sub)) => repl::run(sub),
+        Some(("tui", sub)) => tui::run(sub),
+        Some(("serve", sub)) => serve::run(sub),
+        #[cfg(unix)]
+        Some(("daemon", sub)) => daemon::run(sub),
+        Some(("filter", sub)) => filter::run(sub),
+        Some(("table", sub)) => export::run(sub),
+        Some(("keygen", sub)) => keygen::run(sub),
+        Some(("keycheck", sub)) => keycheck::run(sub),
+        Some(("info", sub)) => info::run(sub),
+        Some(("auto", sub)) => auto::run(sub),
+        Some(("crack", sub)) => analysis::run(sub, solvers),
+        Some(("analyze", sub)) => analyze::run(sub),
+        Some(("selftest", _)) => selftest::run(),
+        Some(("bench", sub)) => bench::run(sub),
+        Some(("vectors", sub)) => vectors::run(sub),
+        _ => run_cipher(matches),
+    }
+}
+
+/// Determines the mode selected by the `--decrypt` flag.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the command.
+///
+/// # Returns
+///
+/// * The encryption or decryption mode.
+pub fn mode_from_matches(matches: &ArgMatches) -> CipherMode {
+    if matches.get_flag("decrypt") {
+        CipherMode::Decrypt
+    } else {
+        CipherMode::Encrypt
+    }
+}
+
+/// Reads the `--in-encoding` or `--out-encoding` flag of the command.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the command.
+/// * `id` - The id of the flag.
+///
+/// # Returns
+///
+/// * The encoding, plain when the flag is not given.
+fn encoding_from_matches(matches: &ArgMatches, id: &str) -> Result<Encoding, Error> {
+    matches
+        .get_one::<String>(id)
+        .map_or(Ok(Encoding::Plain), |spec| encoding::parse(spec))
+}
+
+/// Loads the config file and combines it with the flags of the command.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the command.
+///
+/// # Returns
+///
+/// * The variant, the output style and the output format.
+pub fn settings_from_matches(
+    matches: &ArgMatches,
+) -> Result<(Variant, OutputStyle, OutputFormat), Error> {
+    let mut config: Config = config::load(matches.get_one::<String>("config").map(String::as_str))?;
+    // Variant flags override the config file
+    if let Some(grid) = matches.get_one::<String>("grid") {
+        (config.grid, config.alphabet) = (Some(grid.clone()), None);
+    }
+    if let Some(alphabet) = matches.get_one::<String>("alphabet") {
+        (config.grid, config.alphabet) = (None, Some(alphabet.clone()));
+    }
+    if let Some(locale) = matches.get_one::<String>("locale") {
+        config.locale = Some(locale.clone());
+    }
+    if let Some(route) = matches.get_one::<String>("route") {
+        config.route = Some(route.clone());
+    }
+    if let Some(&rounds) = matches.get_one::<usize>("rounds") {
+        config.rounds = Some(rounds);
+    }
+    if let Some(&period) = matches.get_one::<usize>("period") {
+        config.period = Some(period);
+    }
+    if let Some(doubles) = matches.get_one::<String>("doubles") {
+        config.doubles = Some(doubles.clone());
+    }
+    if let Some(filler) = matches.get_one::<String>("filler") {
+        let mut chars = filler.chars();
+        config.filler = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => {
+                return Err(Error::Usage(format!(
+                    "invalid filler '{}': expected a single letter",
+                    filler
+                )));
+            }
+        };
+    }
+    if let Some(alt) = matches.get_one::<String>("alt-filler") {
+        let mut chars = alt.chars();
+        config.alt_filler = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => {
+                return Err(Error::Usage(format!(
+                    "invalid alternative filler '{}': expected a single letter",
+                    alt
+                )));
+            }
+        };
+    }
+    if let Some(compat) = matches.get_one::<String>("compat") {
+        config.compat = Some(compat.clone());
+    }
+    if let Some(merge) = matches.get_one::<String>("merge") {
+        (config.merge, config.omit) = (Some(merge.clone()), None);
+    }
+    if let Some(omit) = matches.get_one::<String>("omit") {
+        (config.merge, config.omit) = (None, Some(omit.clone()));
+    }
+    if let Some(&group) = matches.try_get_one::<usize>("group").ok().flatten() {
+        config.group = Some(group);
+    }
+    if let Some(separator) = matches.try_get_one::<String>("separator").ok().flatten() {
+        let mut chars = separator.chars();
+        config.separator = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => {
+                return Err(Error::Usage(format!(
+                    "invalid separator '{}': expected a single character",
+                    separator
+                )));
+            }
+        };
+    }
+    if matches.try_get_one::<bool>("lower").ok().flatten() == Some(&true) {
+        config.lower = Some(true);
+    }
+    let format_flag: Option<&String> = matches.try_get_one::<String>("format").ok().flatten();
+    settings_from_config(&config, format_flag)
+}
+
+/// Reads the `--key-square` and `--key-mnemonic` flags of the command.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the command.
+/// * `variant` - The variant the square is checked against.
+///
+/// # Returns
+///
+/// * The symbols of the square as a key generating exactly that table,
+///   `None` if neither flag is given, or an invalid key error if the square
+///   or the mnemonic is malformed.
+pub fn square_from_matches(
+    matches: &ArgMatches,
+    variant: &Variant,
+) -> Result<Option<String>, Error> {
+    let square: Option<KeySquare> = match (
+        matches.get_one::<String>("key-square"),
+        matches.get_one::<String>("key-mnemonic"),
+    ) {
+        (Some(letters), _) => Some(KeySquare::from_letters(letters, variant)?),
+        (None, Some(words)) => Some(mnemonic::decode(words, variant)?),
+        (None, None) => None,
+    };
+    Ok(square.map(|square| square.key(variant.route)))
+}
+
+/// Applies `--derive-square` to a key of the command.
+///
+/// # Returns
+///
+/// * The key of the square derived from the passphrase if the flag is given,
+///   otherwise the key unchanged.
+pub fn derive_from_matches(matches: &ArgMatches, key: String, variant: &Variant) -> String {
+    if matches.get_flag("derive-square") {
+        KeySquare::from_passphrase(&key, variant).key(variant.route)
+    } else {
+        key
+    }
+}
+
+/// Reads the `--key-list` flag of the command.
+///
+/// Every key is passed through `--derive-square` and checked.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the command.
+/// * `variant` - The variant the keys are checked against.
+///
+/// # Returns
+///
+/// * The keys, `None` if the flag isn't given or the command has none, or
+///   an error if the list can't be read or a key contains no letters.
+pub fn key_list_from_matches(
+    matches: &ArgMatches,
+    variant: &Variant,
+) -> Result<Option<Vec<String>>, Error> {
+    let Some(path) = matches.try_get_one::<String>("key-list").ok().flatten() else {
+        return Ok(None);
+    };
+    let mut keys: Vec<String> = Vec::new();
+    for (number, key) in read_key_list(path)?.into_iter().enumerate() {
+        let key: String = derive_from_matches(matches, key, variant);
+        validate_key(&key, variant)
+            .map_err(|e| Error::InvalidKey(format!("key {} of '{}': {}", number + 1, path, e)))?;
+        keys.push(key);
+    }
+    Ok(Some(keys))
+}
+
+/// Reads the `--message-index` flag of the command.
+///
+/// # Returns
+///
+/// * The index of the first message counted from 0, 0 if the flag isn't
+///   given, or a usage error if it is 0.
+pub fn message_index_from_matches(matches: &ArgMatches) -> Result<usize, Error> {
+    match matches.get_one::<usize>("message-index") {
+        Some(0) => Err(Error::Usage(String::from(
+            "invalid message index 0: messages are counted from 1",
+        ))),
+        Some(&index) => Ok(index - 1),
+        None => Ok(0),
+    }
+}
+
+/// Finds the key of the command, prompting for it if no source provides one.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the command.
+/// * `mode` - The mode, deciding whether the prompt asks for confirmation.
+/// * `variant` - The variant the key is checked against.
+///
+/// # Returns
+///
+/// * The key, or an invalid key error if it contains no letters.
+pub fn key_from_matches(
+    matches: &ArgMatches,
+    mode: CipherMode,
+    variant: &Variant,
+) -> Result<String, Error> {
+    if let Some(key) = square_from_matches(matches, variant)? {
+        return Ok(key);
+    }
+    if let Some(keys) = key_list_from_matches(matches, variant)? {
+        let index: usize = message_index_from_matches(matches)?;
+        return Ok(keys[index % keys.len()].clone());
+    }
+    let key: String = match resolve_key(
+        matches.get_one::<String>("key"),
+        matches.get_one::<String>("key-file"),
+        std::env::var(KEY_ENV_VAR).ok(),
+    )? {
+        Some(key) => key,
+        None => prompt_key(matches!(mode, CipherMode::Encrypt))?,
+    };
+    let key: String = derive_from_matches(matches, key, variant);
+    validate_key(&key, variant)?;
+    Ok(key)
+}
+
+/// Checks that a key can generate a table.
+///
+/// # Arguments
+///
+/// * `key` - The key to check.
+/// * `variant` - The variant whose table the key fills.
+///
+/// # Returns
+///
+/// * An invalid key error if the key contains no symbols of the table.
+pub fn validate_key(key: &str, variant: &Variant) -> Result<(), Error> {
+    if table_letters(key, variant).is_empty() {
+        return Err(Error::InvalidKey(String::from(
+            "the key contains no letters of the table",
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the `--letter-separator` and `--word-separator` flags of the command.
+///
+/// # Returns
+///
+/// * The Morse separators, or a usage error if they are invalid or given
+///   without `--format morse`.
+fn separators_from_matches(
+    matches: &ArgMatches,
+    format: OutputFormat,
+) -> Result<morse::Separators, Error> {
+    let mut separators: morse::Separators = morse::Separators::default();
+    let (letter, word) = (
+        matches.get_one::<String>("letter-separator"),
+        matches.get_one::<String>("word-separator"),
+    );
+    if (letter.is_some() || word.is_some()) && format != OutputFormat::Morse {
+        return Err(Error::Usage(String::from(
+            "--letter-separator and --word-separator only work with --format morse",
+        )));
+    }
+    if let Some(letter) = letter {
+        separators.letter = letter.clone();
+    }
+    if let Some(word) = word {
+        separators.word = word.clone();
+    }
+    separators.check()?;
+    Ok(separators)
+}
+
+/// Encrypts or decrypts the `--input` text and prints the result.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed command-line arguments.
+///
+/// # Returns
+///
+/// * An error describing why the command failed.
+fn run_cipher(matches: &ArgMatches) -> Result<(), Error> {
+    let mode: CipherMode = mode_from_matches(matches);
+    let (mut variant, mut style, format) = settings_from_matches(matches)?;
+    // Decrypting an envelope takes every setting from it
+    let opened: Option<Envelope> = match (mode, matches.get_one::<String>("envelope")) {
+        (CipherMode::Decrypt, Some(path)) => {
+            if matches.contains_id("input") {
+                return Err(Error::Usage(String::from(
+                    "the envelope holds the ciphertext, so --input can't be given with it",
+                )));
+            }
+            let opened: Envelope = envelope::read(path)?;
+            if !opened.cipher.starts_with("playfair") {
+                return Err(Error::InvalidInput(format!(
+                    "unsupported envelope cipher '{}': expected playfair or playfair-columnar",
+                    opened.cipher
+                )));
+            }
+            (variant, style, _) = settings_from_config(&opened.settings, None)?;
+            Some(opened)
+        }
+        _ => None,
+    };
+    let binary: Option<&String> = matches.get_one::<String>("binary");
+    if binary.is_some() {
+        if matches!((mode, format), (CipherMode::Decrypt, OutputFormat::Json)) {
+            return Err(Error::Usage(String::from(
+                "--binary decryption writes raw bytes and has no JSON output",
+            )));
+        }
+        // Encoded bytes never double a digram, but may double across digrams
+        if variant.doubles == Doubles::All {
+            variant.doubles = Doubles::Digram;
+        }
+    }
+    let input_text: &String = &match (matches.get_one::<String>("input"), binary, &opened) {
+        (_, _, Some(opened)) => opened.ciphertext.clone(),
+        (Some(text), _, _) => text.clone(),
+        (None, Some(path), _) => binary::read_input(path, &variant, mode)?,
+        (None, None, None) if matches.contains_id("envelope") => {
+            return Err(Error::Usage(String::from(
+                "--envelope needs the text to encrypt: use -i",
+            )));
+        }
+        (None, None, None) => clipboard::paste()?,
+    };
+    let input_text: &String = &match encoding_from_matches(matches, "in-encoding")? {
+        Encoding::Plain => input_text.clone(),
+        encoding => String::from_utf8(encoding.decode(input_text)?).map_err(|_| {
+            Error::InvalidInput(String::from("the decoded input is not UTF-8 text"))
+        })?,
+    };
+    let out_encoding: Encoding = encoding_from_matches(matches, "out-encoding")?;
+    let separators: morse::Separators = separators_from_matches(matches, format)?;
+    if matches.contains_id("qr") && matches!(mode, CipherMode::Decrypt) {
+        return Err(Error::Usage(String::from(
+            "--qr encodes the ciphertext, so it only works when encrypting",
+        )));
+    }
+    if matches!(format, OutputFormat::Nato | OutputFormat::Morse)
+        && (matches.contains_id("in-encoding") || matches.contains_id("out-encoding"))
+    {
+        return Err(Error::Usage(String::from(
+            "--format nato and morse can't be combined with --in-encoding or --out-encoding",
+        )));
+    }
+    // The ciphertext to decrypt is spelled with code words or in Morse
+    let input_text: &String = &match (format, mode, &opened) {
+        (OutputFormat::Nato, CipherMode::Decrypt, None) => nato::decode(input_text)?,
+        (OutputFormat::Morse, CipherMode::Decrypt, None) => morse::decode(input_text, &separators)?,
+        _ => input_text.clone(),
+    };
+    let key: String = key_from_matches(matches, mode, &variant)?;
+
+    let input_text: &String = &if matches.get_flag("normalize") {
+        let (text, changed) = normalize::normalize(input_text, &variant);
+        if changed > 0 {
+            eprintln!(
+                "Normalized {} {}",
+                changed,
+                if changed == 1 {
+                    "character"
+                } else {
+                    "characters"
+                }
+            );
+        }
+        text
+    } else {
+        input_text.clone()
+    };
+
+    // Coordinates are labels rather than symbols of the table
+    let coordinates: bool = matches!(mode, CipherMode::Decrypt)
+        && matches!(
+            matches.get_one::<String>("cipher").map(String::as_str),
+            Some("polybius" | "adfgvx")
+        );
+    if matches.get_flag("strict") && !coordinates {
+        // The separator of grouped ciphertext is skipped like whitespace
+        let dropped: Vec<(usize, char)> =
+            dropped_chars(input_text, &variant, matches.get_flag("keep-layout"))
+                .into_iter()
+                .filter(|&(_, c)| c != style.separator)
+                .collect();
+        if !dropped.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "the input has {} {} outside the table: {}",
+                dropped.len(),
+                if dropped.len() == 1 {
+                    "character"
+                } else {
+                    "characters"
+                },
+                dropped
+                    .iter()
+                    .map(|(i, c)| format!("{:?} at {}", c, i))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )));
+        }
+    }
+
+    let skipped: Vec<char> = skipped_letters(input_text, &variant);
+    if !skipped.is_empty() {
+        eprintln!(
+            "Warning: skipped letters that are not in the table: {}{}",
+            skipped
+                .iter()
+                .map(char::to_string)
+                .collect::<Vec<String>>()
+                .join(" "),
+            if variant.locale.is_none() {
+                " (see --locale)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let cipher: &str = match &opened {
+        Some(opened) => &opened.cipher,
+        None => matches
+            .get_one::<String>("cipher")
+            .map_or("playfair", String::as_str),
+    };
+    if !cipher.starts_with("playfair") {
+        return run_squares(matches, input_text, &key, &variant, mode, format, &style);
+    }
+
+    // Generate the Playfair table and process the text
+    let mut settings: CipherSettings = CipherSettings::new(&key, variant, mode, style);
+    if cipher == "playfair-columnar" {
+        let Some(second) = matches.get_one::<String>("second-key") else {
+            return Err(Error::Usage(String::from(
+                "the playfair-columnar variant needs a transposition key: use -K",
+            )));
+        };
+        settings.transposition = Some(transposition::column_order(second)?);
+    }
+    settings.preserve_case = matches.get_flag("preserve-case");
+    settings.keep_layout = matches.get_flag("keep-layout");
+    if matches.get_flag("strip-filler") {
+        let words: Vec<Vec<char>> = match matches.get_one::<String>("dictionary") {
+            Some(path) => read_dictionary(path, &settings.variant)?,
+            None => Vec::new(),
+        };
+        settings.strip_filler = Some(words);
+    }
+    if let Some(opened) = &opened
+        && settings.cipher.square().fingerprint() != opened.key
+    {
+        return Err(Error::InvalidKey(format!(
+            "the key doesn't match the envelope: its table has fingerprint {}, not {}",
+            settings.cipher.square().fingerprint(),
+            opened.key
+        )));
+    }
+    let result: String = settings.process(input_text)?;
+    // A broken round trip fails before anything is printed
+    if matches.get_flag("verify") {
+        let notes: Vec<String> = settings.verify(input_text, &result)?;
+        eprintln!("Verify: decryption restores the prepared text");
+        for note in notes {
+            eprintln!("Verify: {}", note);
+        }
+    }
+    let (playfair, variant) = (&settings.cipher, &settings.variant);
+    let table: &[Vec<char>] = playfair.square().rows();
+    // The text entering the Playfair stage, after undoing any transposition
+    let stage_text: String = settings.playfair_input(input_text);
+
+    if let Some(opened) = &opened {
+        envelope::verify(opened, &playfair.process(&stage_text, variant, mode))?;
+    }
+
+    let mask_path: Option<&String> = matches.get_one::<String>("mask");
+    let mask: Option<Mask> = match (mode, mask_path, &opened) {
+        (CipherMode::Decrypt, Some(path), _) => Some(Mask::read(path)?),
+        (CipherMode::Decrypt, None, Some(opened)) => opened.mask.clone(),
+        (CipherMode::Decrypt, None, None) => None,
+        (CipherMode::Encrypt, None, _) if !matches.get_flag("with-mask") => None,
+        (CipherMode::Encrypt, _, _) => {
+            let (_, fillers) = prepare_letters(&stage_text, variant, mode);
+            Some(Mask::record(input_text, variant, fillers)?)
+        }
+    };
+    // Decryption with a mask restores the plaintext exactly
+    let restored: Option<String> = match (mode, &mask) {
+        (CipherMode::Decrypt, Some(mask)) => Some(mask.apply(&result, variant)?),
+        _ => None,
+    };
+    let output: String = out_encoding.encode(restored.as_ref().unwrap_or(&result).as_bytes());
+    let output: String = match (format, mode) {
+        (OutputFormat::Nato, CipherMode::Encrypt) => nato::encode(&output),
+        (OutputFormat::Morse, CipherMode::Encrypt) => morse::encode(&output, &separators)?,
+        _ => output,
+    };
+    if let (CipherMode::Encrypt, Some(path), Some(mask)) = (mode, mask_path, &mask) {
+        mask.write(path)?;
+        eprintln!("Wrote mask '{}'", path);
+    }
+    if let (CipherMode::Encrypt, Some(path)) = (mode, matches.get_one::<String>("envelope")) {
+        let sealed: Envelope = Envelope {
+            version: envelope::VERSION,
+            cipher: cipher.to_string(),
+            key: table_fingerprint(table),
+            checksum: Some(envelope::checksum(
+                &prepare_letters(&stage_text, variant, mode)
+                    .0
+                    .into_iter()
+                    .collect::<String>(),
+            )),
+            ciphertext: result.clone(),
+            settings: config_from_settings(variant, &settings.style),
+            mask: mask.filter(|_| matches.get_flag("with-mask")),
+        };
+        envelope::write(path, &sealed)?;
+        eprintln!("Wrote envelope '{}'", path);
+    }
+    let print_trace = || {
+        if matches.get_flag("trace") {
+            let chars: Vec<char> =
+                pair_order(&prepare_letters(&stage_text, variant, mode).0, variant);
+            eprintln!("Trace:");
+            let inputs: Vec<Vec<char>> = round_inputs(&chars, table, variant.rounds, mode);
+            for (round, input) in inputs[..variant.rounds].iter().enumerate() {
+                if variant.rounds > 1 {
+                    eprintln!("Round {}:", round + 1);
+                }
+                for line in trace_pairs(input, table, mode) {
+                    eprintln!("{}", line);
+                }
+            }
+        }
+    };
+
+    match format {
+        OutputFormat::Text | OutputFormat::Nato | OutputFormat::Morse => {
+            eprintln!("Generated Playfair Table:");
+            if color::enabled() {
+                let last = matches.get_flag("highlight-last").then(|| {
+                    let chars: Vec<char> =
+                        pair_order(&prepare_letters(&stage_text, variant, mode).0, variant);
+                    let pair: (char, char) = (chars[chars.len() - 2], chars[chars.len() - 1]);
+                    (pair, playfair.transform_pair(pair, mode))
+                });
+                for row in color::table_rows(table, &color::key_cells(&key, variant), last) {
+                    eprintln!("{}", row);
+                }
+            } else {
+                for row in table {
+                    eprintln!("{:?}", row);
+                }
+            }
+            eprintln!("Key Fingerprint: {}", playfair.square().fingerprint());
+            print_trace();
+            match mode {
+                CipherMode::Encrypt => println!("Encrypted Text: {}", output),
+                CipherMode::Decrypt if binary.is_some() => {
+                    let bytes: Vec<u8> = binary::decode(&result, variant)?;
+                    if out_encoding == Encoding::Plain {
+                        std::io::stdout().write_all(&bytes).map_err(|e| {
+                            Error::Io(format!("cannot write the decrypted bytes: {}", e))
+                        })?;
+                    } else {
+                        println!("{}", out_encoding.encode(&bytes));
+                    }
+                }
+                // The restored text is written as it was, byte for byte
+                CipherMode::Decrypt if restored.is_some() && out_encoding == Encoding::Plain => {
+                    print!("{}", output)
+                }
+                CipherMode::Decrypt => println!("Decrypted Text: {}", output),
+            }
+        }
+        OutputFormat::Json => {
+            print_trace();
+            let (_, fillers) = prepare_letters(&stage_text, variant, mode);
+            let mut output = json!({
+                "key_fingerprint": table_fingerprint(table),
+                "variant": {
+                    "cipher": cipher,
+                    "mode": match mode {
+                        CipherMode::Encrypt => "encrypt",
+                        CipherMode::Decrypt => "decrypt",
+                    },
+                    "grid": variant.grid(),
+                    "route": variant.route.name(),
+                    "rounds": variant.rounds,
+                    "period": variant.period,
+                    "doubles": variant.doubles.name(),
+                    "alphabet": variant.symbols().into_iter().collect::<String>(),
+                    "filler": variant.filler.to_string(),
+                    "alt_filler": variant.alt_filler.map(String::from),
+                },
+                "table": table.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<String>>(),
+                "input_length": input_text.chars().count(),
+                "filler_positions": fillers,
+                "result": output,
+            });
+            if let Some((name, letters)) = variant.applied_reduction() {
+                output["variant"][name] = json!(letters);
+            }
+            let json: String = serde_json::to_string_pretty(&output)
+                .map_err(|e| Error::Internal(e.to_string()))?;
+            println!("{}", json);
+        }
+    }
+
+    if let Some(path) = matches.get_one::<String>("qr") {
+        qr::write(path, &output)?;
+    }
+
+    // Without a clipboard the result is still printed, so only warn
+    if matches.get_flag("copy")
+        && let Err(e) = clipboard::copy(&output)
+    {
+        eprintln!("Warning: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Encrypts or decrypts the `--input` text with a cipher of several tables.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed command-line arguments.
+/// * `input_text` - The text to process.
+/// * `key` - The key of the first keyed table.
+/// * `variant` - The variant every table is generated with.
+/// * `mode` - The encryption or decryption mode.
+/// * `format` - How the result is printed.
+/// * `style` - How the result is grouped.
+///
+/// # Returns
+///
+/// * A usage error if the second key is missing or a Playfair-only flag is
+///   given, or another error describing why the command failed.
+fn run_squares(
+    matches: &ArgMatches,
+    input_text: &str,
+    key: &str,
+    variant: &Variant,
+    mode: CipherMode,
+    format: OutputFormat,
+    style: &OutputStyle,
+) -> Result<(), Error> {
+    let name: &str = matches.get_one::<String>("cipher").unwrap();
+    if [
+        "trace",
+        "verify",
+        "highlight-last",
+        "preserve-case",
+        "keep-layout",
+        "strip-filler",
+    ]
+    .iter()
+    .any(|flag| matches.get_flag(flag))
+        || matches.contains_id("binary")
+        || matches.contains_id("envelope")
+        || matches.contains_id("mask")
+        || variant.rounds > 1
+        || variant.period.is_some()
+    {
+        return Err(Error::Usage(String::from(
+            "--trace, --verify, --highlight-last, --preserve-case, --keep-layout, --strip-filler, --binary, --envelope, --mask, --rounds and --period only work with the playfair variant",
+        )));
+    }
+    // Coordinates are labels, so their input is checked by the square
+    if name == "polybius" || name == "adfgvx" {
+        return run_polybius(matches, input_text, key, variant, mode, format, style);
+    }
+    if table_letters(input_text, variant).is_empty() {
+        return Err(Error::InvalidInput(String::from(
+            "the input contains no letters to process",
+        )));
+    }
+    let second: String = match matches.get_one::<String>("second-key") {
+        Some(second) => derive_from_matches(matches, second.clone(), variant),
+        None => {
+            return Err(Error::Usage(format!(
+                "the {} variant needs a second key: use -K",
+                name
+            )));
+        }
+    };
+    validate_key(&second, variant)?;
+    let arrangement: Option<squares::Arrangement> = matches
+        .get_one::<String>("arrangement")
+        .map(|spec| squares::parse_arrangement(spec))
+        .transpose()?;
+
+    let cipher: Box<dyn DigramCipher> = match name {
+        "two-square" => Box::new(squares::TwoSquare::new(
+            key,
+            &second,
+            variant,
+            arrangement.unwrap_or(squares::Arrangement::Vertical),
+        )),
+        _ if arrangement.is_some() => {
+            return Err(Error::Usage(String::from(
+                "--arrangement only works with the two-square variant",
+            )));
+        }
+        "four-square" => Box::new(squares::FourSquare::new(key, &second, variant)),
+        _ => Box::new(squares::DoublePlayfair::new(key, &second, variant)),
+    };
+    let result: String = finish_result(
+        matches,
+        &cipher.process(input_text, variant, mode),
+        mode,
+        format,
+        style,
+    )?;
+    match format {
+        OutputFormat::Text | OutputFormat::Nato | OutputFormat::Morse => {
+            eprintln!(
+                "Generated {} Tables:",
+                match cipher.name() {
+                    "two-square" => "Two-Square",
+                    "four-square" => "Four-Square",
+                    _ => "Double Playfair",
+                }
+            );
+            for row in cipher.rows() {
+                eprintln!("{}", row);
+            }
+            let fingerprints: Vec<String> = cipher
+                .keyed_squares()
+                .iter()
+                .map(|square| square.fingerprint())
+                .collect();
+            eprintln!("Key Fingerprints: {}", fingerprints.join(", "));
+            match mode {
+                CipherMode::Encrypt => println!("Encrypted Text: {}", result),
+                CipherMode::Decrypt => println!("Decrypted Text: {}", result),
+            }
+        }
+        OutputFormat::Json => {
+            let squares: Vec<&KeySquare> = cipher.keyed_squares();
+            let mut output = json!({
+                "key_fingerprints": squares.iter().map(|square| square.fingerprint()).collect::<Vec<String>>(),
+                "variant": {
+                    "cipher": cipher.name(),
+                    "mode": match mode {
+                        CipherMode::Encrypt => "encrypt",
+                        CipherMode::Decrypt => "decrypt",
+                    },
+                    "grid": variant.grid(),
+                    "alphabet": variant.symbols().into_iter().collect::<String>(),
+                    "filler": variant.filler.to_string(),
+                    "alt_filler": variant.alt_filler.map(String::from),
+                },
+                "tables": squares.iter().map(|square| {
+                    square.rows().iter().map(|row| row.iter().collect::<String>()).collect::<Vec<String>>()
+                }).collect::<Vec<Vec<String>>>(),
+                "input_length": input_text.chars().count(),
+                "result": result,
+            });
+            if let Some((name, letters)) = variant.applied_reduction() {
+                output["variant"][name] = json!(letters);
+            }
+            if cipher.name() == "two-square" {
+                output["variant"]["arrangement"] =
+                    json!(arrangement.unwrap_or(squares::Arrangement::Vertical).name());
+            }
+            let json: String = serde_json::to_string_pretty(&output)
+                .map_err(|e| Error::Internal(e.to_string()))?;
+            println!("{}", json);
+        }
+    }
+    share_result(matches, &result)
+}
+
+/// Encrypts or decrypts the `--input` text with the key table as a Polybius
+/// square, for the polybius and adfgvx variants.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed command-line arguments.
+/// * `input_text` - The text to process.
+/// * `key` - The key of the table.
+/// * `variant` - The variant the table is generated with.
+/// * `mode` - The encryption or decryption mode.
+/// * `format` - How the result is printed.
+/// * `style` - How the result is grouped.
+///
+/// # Returns
+///
+/// * A usage error if the table can't be labelled or adfgvx has no
+///   transposition key, or another error describing why the command failed.
+fn run_polybius(
+    matches: &ArgMatches,
+    input_text: &str,
+    key: &str,
+    variant: &Variant,
+    mode: CipherMode,
+    format: OutputFormat,
+    style: &OutputStyle,
+) -> Result<(), Error> {
+    let name: &str = matches.get_one::<String>("cipher").unwrap();
+    if matches.contains_id("arrangement") {
+        return Err(Error::Usage(String::from(
+            "--arrangement only works with the two-square variant",
+        )));
+    }
+    let transposition: Option<Vec<usize>> = matches
+        .get_one::<String>("second-key")
+        .map(|second| transposition::column_order(second))
+        .transpose()?;
+    let square: polybius::Polybius = polybius::Polybius::new(name, key, variant, transposition)?;
+    let result: String = match mode {
+        CipherMode::Encrypt if table_letters(input_text, variant).is_empty() => {
+            return Err(Error::InvalidInput(String::from(
+                "the input contains no letters to process",
+            )));
+        }
+        CipherMode::Encrypt => square.encrypt(input_text, variant),
+        CipherMode::Decrypt => square.decrypt(input_text)?,
+    };
+    let result: String = finish_result(matches, &result, mode, format, style)?;
+    match format {
+        OutputFormat::Text | OutputFormat::Nato | OutputFormat::Morse => {
+            eprintln!(
+                "Generated {} Square:",
+                match square.name() {
+                    "adfgvx" => "ADFGVX",
+                    _ => "Polybius",
+                }
+            );
+            for row in square.rows() {
+                eprintln!("{}", row);
+            }
+            eprintln!("Key Fingerprint: {}", square.square.fingerprint());
+            match mode {
+                CipherMode::Encrypt => println!("Encrypted Text: {}", result),
+                CipherMode::Decrypt => println!("Decrypted Text: {}", result),
+            }
+        }
+        OutputFormat::Json => {
+            let mut output = json!({
+                "key_fingerprint": square.square.fingerprint(),
+                "variant": {
+                    "cipher": square.name(),
+                    "mode": match mode {
+                        CipherMode::Encrypt => "encrypt",
+                        CipherMode::Decrypt => "decrypt",
+                    },
+                    "grid": variant.grid(),
+                    "alphabet": variant.symbols().into_iter().collect::<String>(),
+                    "labels": square.labels.iter().collect::<String>(),
+                    "transposed": square.transposition.is_some(),
+                },
+                "table": square.square.rows().iter().map(|row| row.iter().collect::<String>()).collect::<Vec<String>>(),
+                "input_length": input_text.chars().count(),
+                "result": result,
+            });
+            if let Some((name, letters)) = variant.applied_reduction() {
+                output["variant"][name] = json!(letters);
+            }
+            let json: String = serde_json::to_string_pretty(&output)
+                .map_err(|e| Error::Internal(e.to_string()))?;
+            println!("{}", json);
+        }
+    }
+    share_result(matches, &result)
+}
+
+/// Groups and encodes the result of a cipher of several tables for output.
+///
+/// # Returns
+///
+/// * The result as printed: grouped, in the `--out-encoding`, and spelled out
+///   for `--format nato` or `morse` when encrypting.
+fn finish_result(
+    matches: &ArgMatches,
+    result: &str,
+    mode: CipherMode,
+    format: OutputFormat,
+    style: &OutputStyle,
+) -> Result<String, Error> {
+    let result: String =
+        encoding_from_matches(matches, "out-encoding")?.encode(style.apply(result).as_bytes());
+    Ok(match (format, mode) {
+        (OutputFormat::Nato, CipherMode::Encrypt) => nato::encode(&result),
+        (OutputFormat::Morse, CipherMode::Encrypt) => {
+            morse::encode(&result, &separators_from_matches(matches, format)?)?
+        }
+        _ => result,
+    })
+}
+
+/// Writes the printed result to `--qr` and `--copy`, if given.
+fn share_result(matches: &ArgMatches, result: &str) -> Result<(), Error> {
+    if let Some(path) = matches.get_one::<String>("qr") {
+        qr::write(path, result)?;
+    }
+
+    // Without a clipboard the result is still printed, so only warn
+    if matches.get_flag("copy")
+        && let Err(e) = clipboard::copy(result)
+    {
+        eprintln!("Warning: {}", e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{group_text, parse_compat, prepare_text, strip_fillers, validate_variant};
+
+    #[test]
+    fn test_compat_presets() {
+        let cryptool: Variant = parse_compat("cryptool").unwrap();
+        assert_eq!((cryptool.filler, cryptool.alt_filler), ('X', Some('Y')));
+        assert!(parse_compat("enigma").is_err());
+
+        // The alternative filler splits doubled fillers and pads after a final one
+        let (chars, fillers) = prepare_text("xxa box", &cryptool);
+        assert_eq!(chars.iter().collect::<String>(), "XYXABOXY");
+        assert_eq!(fillers, vec![1, 7]);
+        let (chars, _) = prepare_text("xxa box", &Variant::default());
+        assert_eq!(chars.iter().collect::<String>(), "XXXABOXX");
+        assert_eq!(strip_fillers("XYXABOXY", &cryptool, &[]), "XXABOX");
+
+        // Explicit settings override the preset
+        let matches: ArgMatches = build_cli()
+            .try_get_matches_from([
+                "playfair", "-k", "K", "-i", "x", "--compat", "dcode", "--filler", "Z",
+            ])
+            .unwrap();
+        let (variant, _, _) = settings_from_matches(&matches).unwrap();
+        assert_eq!((variant.filler, variant.alt_filler), ('Z', Some('Q')));
+        let config: Config = config::parse("compat = \"dcode\"\nfiller = \"q\"\n").unwrap();
+        let (variant, _, _) = settings_from_config(&config, None).unwrap();
+        assert_eq!((variant.filler, variant.alt_filler), ('Q', Some('X')));
+        let twice: Variant = Variant {
+            alt_filler: Some('X'),
+            ..Variant::default()
+        };
+        assert!(validate_variant(&twice).is_err());
+    }
+
+    #[test]
+    fn test_output_style() {
+        assert_eq!(group_text("GYIZSCOKCFBU", 5, ' '), "GYIZS COKCF BU");
+        assert_eq!(group_text("GYIZSCOKCFBU", 4, '-'), "GYIZ-SCOK-CFBU");
+        let style: OutputStyle = OutputStyle {
+            group: Some(5),
+            separator: '/',
+            lower: false,
+        };
+        assert_eq!(style.apply("GYIZSCOKCFBU"), "GYIZS/COKCF/BU");
+        assert_eq!(OutputStyle::default().apply("GYIZSC"), "GYIZSC");
+        let lower: OutputStyle = OutputStyle {
+            lower: true,
+            ..style.clone()
+        };
+        assert_eq!(lower.apply("GYIZSCOKCFBU"), "gyizs/cokcf/bu");
+
+        // Grouped ciphertext decrypts as it is
+        let mut settings: CipherSettings =
+            CipherSettings::new("KEYWORD", Variant::default(), CipherMode::Decrypt, style);
+        assert_eq!(
+            settings.process("GYIZS/COKCF/BU").unwrap(),
+            "HELXL/OWORL/DX"
+        );
+        settings.style = OutputStyle::default();
+        assert_eq!(settings.process("GYIZS/COKCF/BU").unwrap(), "HELXLOWORLDX");
+        assert_eq!(settings.process("gyizs cokcf bu").unwrap(), "HELXLOWORLDX");
+
+        let matches: ArgMatches = build_cli()
+            .try_get_matches_from([
+                "playfair",
+                "-k",
+                "K",
+                "-i",
+                "x",
+                "--group",
+                "5",
+                "--separator",
+                "-",
+            ])
+            .unwrap();
+        let (_, style, _) = settings_from_matches(&matches).unwrap();
+        assert_eq!((style.group, style.separator), (Some(5), '-'));
+        let digits: ArgMatches = build_cli()
+            .try_get_matches_from([
+                "playfair",
+                "-k",
+                "K",
+                "-i",
+                "x",
+                "--grid",
+                "6x6",
+                "--separator",
+                "0",
+            ])
+            .unwrap();
+        assert!(settings_from_matches(&digits).is_err());
+    }
+
+    #[test]
+    fn test_build_cli() {
+        build_cli().debug_assert();
+        let matches = build_cli()
+            .try_get_matches_from(["playfair", "completions", "zsh"])
+            .unwrap();
+        assert!(matches.subcommand_matches("completions").is_some());
+        assert!(
+            build_cli()
+                .try_get_matches_from(["playfair", "-k", "KEY"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_variant_flags_override_config() {
+        let path: std::path::PathBuf = std::env::temp_dir().join("playfair_test_flags.toml");
+        std::fs::write(&path, "filler = \"Z\"\nmerge = \"V=U\"\n").unwrap();
+        let config: &str = path.to_str().unwrap();
+        let matches: ArgMatches = build_cli()
+            .try_get_matches_from(["playfair", "-i", "T", "--config", config, "--filler", "q"])
+            .unwrap();
+        let (variant, _, _) = settings_from_matches(&matches).unwrap();
+        assert_eq!(variant.filler, 'Q');
+        assert_eq!(variant.merge, ('V', Some('U')));
+
+        let matches: ArgMatches = build_cli()
+            .try_get_matches_from(["playfair", "-i", "T", "--config", config, "--omit", "j"])
+            .unwrap();
+        let (variant, _, _) = settings_from_matches(&matches).unwrap();
+        assert_eq!(variant.filler, 'Z');
+        assert_eq!(variant.merge, ('J', None));
+
+        let matches: ArgMatches = build_cli()
+            .try_get_matches_from(["playfair", "-i", "T", "--config", config, "--filler", "V"])
+            .unwrap();
+        assert!(settings_from_matches(&matches).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_key_list_message_index() {
+        let path: std::path::PathBuf = std::env::temp_dir().join("playfair_test_key_list.txt");
+        std::fs::write(&path, "# day keys\nKEYWORD\n\n  PLAYFAIR \nMONARCHY\n").unwrap();
+        let file: &str = path.to_str().unwrap();
+        assert_eq!(
+            read_key_list(file).unwrap(),
+            vec!["KEYWORD", "PLAYFAIR", "MONARCHY"]
+        );
+
+        let key = |index: &str| {
+            let matches: ArgMatches = build_cli()
+                .try_get_matches_from([
+                    "playfair",
+                    "-i",
+                    "x",
+                    "--key-list",
+                    file,
+                    "--message-index",
+                    index,
+                ])
+                .unwrap();
+            key_from_matches(&matches, CipherMode::Encrypt, &Variant::default())
+        };
+        assert_eq!(key("1").unwrap(), "KEYWORD");
+        assert_eq!(key("3").unwrap(), "MONARCHY");
+        assert_eq!(key("5").unwrap(), "PLAYFAIR");
+        assert!(key("0").is_err());
+        assert!(
+            build_cli()
+                .try_get_matches_from(["playfair", "-i", "x", "-k", "K", "--key-list", file])
+                .is_err()
+        );
+
+        std::fs::write(&path, "# nothing but comments\n\n").unwrap();
+        assert!(read_key_list(file).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
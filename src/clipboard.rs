@@ -0,0 +1,35 @@
+//! System clipboard access for `--copy` and `--paste`.
+//!
+//! On headless systems there is no clipboard to talk to; callers decide
+//! whether that is fatal (`--paste`) or only worth a warning (`--copy`).
+//! On X11 the copied text is served by this process, so it only outlives
+//! the command when a clipboard manager picks it up.
+
+use crate::error::Error;
+
+/// Places a text on the system clipboard.
+///
+/// # Arguments
+///
+/// * `text` - The text to copy.
+///
+/// # Returns
+///
+/// * An I/O error if the clipboard is unavailable.
+pub fn copy(text: &str) -> Result<(), Error> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| Error::Io(format!("cannot copy to the clipboard: {}", e)))
+}
+
+/// Reads the text currently on the system clipboard.
+///
+/// # Returns
+///
+/// * The clipboard text, or an I/O error if the clipboard is unavailable or
+///   holds no text.
+pub fn paste() -> Result<String, Error> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| Error::Io(format!("cannot read from the clipboard: {}", e)))
+}
@@ -0,0 +1,159 @@
+//! Colorized rendering of the table for terminals.
+//!
+//! Letters contributed by the key are shown in bold cyan and the alphabetical
+//! fill in the default color. The cells of a highlighted digram are shown
+//! with a yellow (input) and green (output) background, matching the
+//! full-screen mode. Colors are only used when stderr is a terminal and
+//! `NO_COLOR` is not set.
+//!
+//! Heatmaps, such as those of `analyze heatmap`, shade every cell on a scale
+//! of 256-color backgrounds from dark blue to red, or with characters from
+//! `.` to `#` when stdout isn't a terminal.
+
+use crate::Variant;
+use std::io::IsTerminal;
+
+/// Escape sequence of the key letters
+const KEY_STYLE: &str = "\x1b[1;36m";
+/// Escape sequence of the input cells of the highlighted digram
+const INPUT_STYLE: &str = "\x1b[30;43m";
+/// Escape sequence of the output cells of the highlighted digram
+const OUTPUT_STYLE: &str = "\x1b[30;42m";
+/// Escape sequence restoring the default style
+const RESET: &str = "\x1b[0m";
+/// 256-color backgrounds of the heat scale, from cold to hot
+const HEAT: [u8; 8] = [17, 19, 25, 31, 37, 178, 208, 196];
+/// Characters of the heat scale without colors, from cold to hot
+const SHADES: [char; 8] = ['.', ':', '-', '=', '+', '*', '%', '#'];
+
+/// Whether the table printed to stderr should be colored.
+pub fn enabled() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Whether the heatmaps printed to stdout should be colored.
+pub fn stdout_enabled() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Renders a cell of a heatmap.
+///
+/// # Arguments
+///
+/// * `text` - What the cell shows when colored, such as a letter.
+/// * `level` - How hot the cell is, from 0 to 1; 0 leaves it blank.
+/// * `colored` - Whether to shade the background or to draw shade
+///   characters in place of the text.
+///
+/// # Returns
+///
+/// * The cell, as wide as `text`.
+pub fn heat_cell(text: &str, level: f64, colored: bool) -> String {
+    let width: usize = text.chars().count();
+    if level <= 0.0 {
+        return " ".repeat(width);
+    }
+    let step: usize = ((level * HEAT.len() as f64).ceil() as usize).clamp(1, HEAT.len()) - 1;
+    if colored {
+        format!("\x1b[38;5;15;48;5;{}m{}{}", HEAT[step], text, RESET)
+    } else {
+        SHADES[step].to_string().repeat(width)
+    }
+}
+
+/// Finds the table cells filled from the key rather than the alphabet.
+///
+/// # Arguments
+///
+/// * `key` - The key the table was generated from.
+/// * `variant` - The variant the table was generated with.
+///
+/// # Returns
+///
+/// * The `(row, col)` of the cells holding the distinct table letters of the
+///   key, which are the first cells along the fill route.
+pub fn key_cells(key: &str, variant: &Variant) -> Vec<(usize, usize)> {
+    let mut seen: Vec<char> = Vec::new();
+    for c in crate::table_letters(key, variant) {
+        if !seen.contains(&c) {
+            seen.push(c);
+        }
+    }
+    let mut cells: Vec<(usize, usize)> = variant.route.cells(variant.alphabet.dimensions());
+    cells.truncate(seen.len());
+    cells
+}
+
+/// Renders the rows of a table with colors.
+///
+/// # Arguments
+///
+/// * `table` - The Playfair table.
+/// * `key_cells` - The cells filled from the key.
+/// * `digram` - The input and output pair to highlight, if any.
+///
+/// # Returns
+///
+/// * One line per row, laid out like the plain `['K', 'E', ...]` rows.
+pub fn table_rows(
+    table: &[Vec<char>],
+    key_cells: &[(usize, usize)],
+    digram: Option<((char, char), (char, char))>,
+) -> Vec<String> {
+    table
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(j, &c)| {
+                    let style: Option<&str> = match digram {
+                        Some((input, _)) if c == input.0 || c == input.1 => Some(INPUT_STYLE),
+                        Some((_, output)) if c == output.0 || c == output.1 => Some(OUTPUT_STYLE),
+                        _ if key_cells.contains(&(i, j)) => Some(KEY_STYLE),
+                        _ => None,
+                    };
+                    match style {
+                        Some(style) => format!("'{}{}{}'", style, c, RESET),
+                        None => format!("'{}'", c),
+                    }
+                })
+                .collect();
+            format!("[{}]", cells.join(", "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_rows() {
+        let variant: Variant = Variant::default();
+        let cells: Vec<(usize, usize)> = key_cells("Keyword key", &variant);
+        assert_eq!(cells.len(), 7);
+        assert_eq!(cells[6], (1, 1));
+        let table: Vec<Vec<char>> = crate::generate_playfair_table("KEYWORD", &variant);
+        let rows: Vec<String> = table_rows(&table, &cells, Some((('L', 'O'), ('S', 'C'))));
+        assert_eq!(
+            rows[0],
+            "['\x1b[1;36mK\x1b[0m', '\x1b[1;36mE\x1b[0m', '\x1b[1;36mY\x1b[0m', \
+             '\x1b[1;36mW\x1b[0m', '\x1b[30;43mO\x1b[0m']"
+        );
+        assert_eq!(
+            rows[1],
+            "['\x1b[1;36mR\x1b[0m', '\x1b[1;36mD\x1b[0m', 'A', 'B', '\x1b[30;42mC\x1b[0m']"
+        );
+        assert_eq!(rows[4], "['T', 'U', 'V', 'X', 'Z']");
+    }
+
+    #[test]
+    fn test_heat_cell() {
+        assert_eq!(heat_cell("AB", 0.0, true), "  ");
+        assert_eq!(heat_cell("AB", 0.01, false), "..");
+        assert_eq!(heat_cell("AB", 1.0, false), "##");
+        assert_eq!(heat_cell("A", 1.0, true), "\x1b[38;5;15;48;5;196mA\x1b[0m");
+    }
+}
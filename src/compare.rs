@@ -0,0 +1,253 @@
+//! Comparison of the solvers of `crack` on one ciphertext.
+//!
+//! `crack --compare` splits the time of `--time-limit` evenly between
+//! `anneal`, `hill`, `genetic`, `beam`, the custom solvers, by default
+//! those of `solver::SOLVERS`, and, given cribs, `exact`. Every solver runs trials of one restart from
+//! the same seeds, one after the other, until its share runs out; a trial
+//! stops as soon as it solves the ciphertext, and the last one is cut off
+//! at the end of the share. `beam` and `exact` draw
+//! nothing at random and run a single trial.
+//!
+//! A trial solves the ciphertext when its decryption reads as the language,
+//! or reaches `--target-score`. The other flags of `crack`, such as
+//! `--iterations`, `--moves` and `--crib`, apply to every solver.
+
+use crate::Variant;
+use crate::analysis::{self, Genetics, Ngrams, Schedule, Solver, Strategy};
+use crate::error::Error;
+use crate::keygen::Rng;
+use crate::solver;
+use std::time::{Duration, Instant};
+
+/// Time shared by the solvers without `--time-limit`
+const COMPARE_SECONDS: u64 = 60;
+
+/// Tables `beam` keeps at every step in the comparison, as `--width` does
+/// by default
+const BEAM_WIDTH: usize = 10;
+
+/// How one solver did in the comparison
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trials {
+    /// The name `--solver` picks the solver by
+    pub name: &'static str,
+    /// How many trials ran
+    pub trials: usize,
+    /// How many of them solved the ciphertext
+    pub solved: usize,
+    /// The best score of a trial, per n-gram
+    pub best: f64,
+    /// How long after its start the solver first solved the ciphertext, if
+    /// it did
+    pub first: Option<Duration>,
+}
+
+impl Trials {
+    /// Returns the share of the trials that solved the ciphertext, from 0
+    /// to 1.
+    pub fn rate(&self) -> f64 {
+        self.solved as f64 / self.trials.max(1) as f64
+    }
+}
+
+/// Returns the solvers compared for a strategy, `anneal` with its
+/// schedule first.
+fn solvers(strategy: &Strategy, custom: &[&dyn solver::Solver]) -> Vec<Solver> {
+    let mut solvers: Vec<Solver> = vec![
+        match strategy.solver {
+            Solver::Anneal(schedule) => Solver::Anneal(schedule),
+            _ => Solver::Anneal(Schedule::default()),
+        },
+        Solver::Hill,
+        Solver::Genetic(Genetics::default()),
+        Solver::Beam(BEAM_WIDTH),
+    ];
+    solvers.extend(custom.iter().map(|solver| Solver::Custom(solver.name())));
+    if !strategy.cribs.is_empty() {
+        solvers.push(Solver::Exact);
+    }
+    solvers
+}
+
+/// Runs every solver on a ciphertext for its share of a time budget.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext symbols, of even length.
+/// * `variant` - The variant deciding the symbols and the shape of the
+///   table.
+/// * `ngrams` - The n-gram scores.
+/// * `strategy` - The settings every solver runs with; its solver only
+///   gives the schedule of `anneal`, and its time limit is the budget.
+/// * `seed` - The seed the trials of every solver are drawn from.
+/// * `custom` - The custom solvers compared after the built-in ones.
+///
+/// # Returns
+///
+/// * How every solver did, in the order they ran, or the error of a search
+///   that failed. `exact` is left out if the cribs leave too many tables.
+pub fn compare(
+    ciphertext: &[char],
+    variant: &Variant,
+    ngrams: &Ngrams,
+    strategy: &Strategy,
+    seed: u64,
+    custom: &[&dyn solver::Solver],
+) -> Result<Vec<Trials>, Error> {
+    let solvers: Vec<Solver> = solvers(strategy, custom);
+    let budget: Duration = strategy
+        .time_limit
+        .unwrap_or(Duration::from_secs(COMPARE_SECONDS));
+    let share: Duration = budget / solvers.len() as u32;
+    let target: f64 = strategy.target.unwrap_or(ngrams.threshold());
+    let mut results: Vec<Trials> = Vec::new();
+    for solver in solvers {
+        eprintln!(
+            "Comparing {} for {:.1} s",
+            solver.name(),
+            share.as_secs_f64()
+        );
+        let mut trials: Trials = Trials {
+            name: solver.name(),
+            trials: 0,
+            solved: 0,
+            best: f64::NEG_INFINITY,
+            first: None,
+        };
+        let mut rng: Rng = Rng::new(seed);
+        let started: Instant = Instant::now();
+        while let Some(left) = share.checked_sub(started.elapsed()) {
+            let trial: Strategy = Strategy {
+                solver,
+                restarts: 1,
+                time_limit: Some(left),
+                target: Some(target),
+                top: 1,
+                checkpoint: None,
+                resume: None,
+                progress: None,
+                ..strategy.clone()
+            };
+            let solution: analysis::Solution =
+                match analysis::crack_with(ciphertext, variant, ngrams, &trial, &mut rng, custom) {
+                    Ok(mut solutions) => solutions.remove(0),
+                    Err(Error::Usage(e)) if solver == Solver::Exact => {
+                        eprintln!("Warning: {}; leaving out exact", e);
+                        break;
+                    }
+                    Err(e) => return Err(e),
+                };
+            trials.trials += 1;
+            trials.best = trials.best.max(solution.score);
+            if solution.score >= target {
+                trials.solved += 1;
+                trials.first.get_or_insert(started.elapsed());
+            }
+            // Another trial would find the same table
+            if matches!(solver, Solver::Beam(_) | Solver::Exact) {
+                break;
+            }
+        }
+        if trials.trials > 0 {
+            results.push(trials);
+        }
+    }
+    Ok(results)
+}
+
+/// Runs `crack --compare`, printing a line per solver to stdout.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext symbols, of even length.
+/// * `variant` - The variant of the table.
+/// * `ngrams` - The n-gram scores.
+/// * `strategy` - The settings every solver runs with.
+/// * `seed` - The seed the trials are drawn from.
+/// * `custom` - The custom solvers compared after the built-in ones.
+///
+/// # Returns
+///
+/// * The error of a search that failed.
+pub fn run(
+    ciphertext: &[char],
+    variant: &Variant,
+    ngrams: &Ngrams,
+    strategy: &Strategy,
+    seed: u64,
+    custom: &[&dyn solver::Solver],
+) -> Result<(), Error> {
+    for trials in compare(ciphertext, variant, ngrams, strategy, seed, custom)? {
+        println!(
+            "{}: {} of {} trials solved ({:.0}%), best {:.2} per {}, {}",
+            trials.name,
+            trials.solved,
+            trials.trials,
+            trials.rate() * 100.0,
+            trials.best,
+            ngrams.name(),
+            match trials.first {
+                Some(first) => format!("first solved after {:.1} s", first.as_secs_f64()),
+                None => String::from("never solved"),
+            }
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Language;
+    use crate::keygen;
+
+    #[test]
+    fn test_compare() {
+        let variant: Variant = Variant::default();
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let square = keygen::random_square(&mut Rng::new(5), &variant);
+        let ciphertext: Vec<char> = crate::playfair_cipher(
+            "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES IT WAS THE AGE OF WISDOM",
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        )
+        .chars()
+        .collect();
+        let strategy: Strategy = Strategy {
+            iterations: 2000,
+            threads: 1,
+            time_limit: Some(Duration::from_millis(500)),
+            ..Strategy::default()
+        };
+        let results: Vec<Trials> = compare(
+            &ciphertext,
+            &variant,
+            &ngrams,
+            &strategy,
+            1,
+            solver::SOLVERS,
+        )
+        .unwrap();
+        let names: Vec<&str> = results.iter().map(|trials| trials.name).collect();
+        assert_eq!(
+            names,
+            vec!["anneal", "hill", "genetic", "beam", "threshold"]
+        );
+        for trials in &results {
+            assert!(trials.trials >= 1 && trials.solved <= trials.trials);
+            assert!(trials.best.is_finite());
+            assert_eq!(trials.first.is_some(), trials.solved > 0);
+        }
+        assert_eq!(results[3].trials, 1);
+        assert_eq!(
+            Trials {
+                trials: 4,
+                solved: 3,
+                ..results[0].clone()
+            }
+            .rate(),
+            0.75
+        );
+    }
+}
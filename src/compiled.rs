@@ -0,0 +1,119 @@
+//! A Playfair table compiled into a digram substitution table.
+//!
+//! `transform_pair` works out the rule of every pair it is given from where
+//! its letters are. A `CompiledCipher` does so once for every pair of cells
+//! of a table, 625 of them for a 5x5 table, and then transforms a pair by a
+//! lookup. That pays off on texts with more digrams than the table has
+//! pairs, which `process_pairs` compiles its table for first.
+//!
+//! The solvers of `crack` change the table at every step, so compiling each
+//! candidate would cost more than it saves; they look up the decryption
+//! rules of every two cells instead, which hold for any table of the shape.
+
+use crate::{CipherMode, Positions};
+
+/// A table with the result of every pair of its symbols worked out
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledCipher {
+    /// The positions of the symbols of the table
+    positions: Positions,
+    /// The columns of the table
+    cols: usize,
+    /// The cells of the table
+    cells: usize,
+    /// The result of every pair of cells, the first row by row times the
+    /// number of cells plus the second
+    pairs: Vec<(char, char)>,
+}
+
+impl CompiledCipher {
+    /// Works out the result of every pair of symbols of a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The Playfair table.
+    /// * `mode` - Whether the pairs are encrypted or decrypted.
+    pub fn new(table: &[Vec<char>], mode: CipherMode) -> Self {
+        let positions: Positions = Positions::new(table);
+        let symbols: Vec<char> = table.iter().flatten().copied().collect();
+        let pairs: Vec<(char, char)> = symbols
+            .iter()
+            .flat_map(|&a| symbols.iter().map(move |&b| (a, b)))
+            .map(|pair| crate::transform_pair(table, &positions, pair, mode).0)
+            .collect();
+        CompiledCipher {
+            positions,
+            cols: table[0].len(),
+            cells: symbols.len(),
+            pairs,
+        }
+    }
+
+    /// Looks up the result of a pair; both letters must be in the table.
+    pub fn transform(&self, pair: (char, char)) -> (char, char) {
+        let cell = |c: char| {
+            let (row, col) = self.positions.find(c);
+            row * self.cols + col
+        };
+        self.pairs[cell(pair.0) * self.cells + cell(pair.1)]
+    }
+
+    /// Encrypts or decrypts prepared letters pair by pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `chars` - The even-length letters produced by `prepare_text`.
+    ///
+    /// # Returns
+    ///
+    /// * The encrypted or decrypted text.
+    pub fn process(&self, chars: &[char]) -> String {
+        let mut result: String = String::with_capacity(chars.len());
+        for chunk in chars.chunks(2) {
+            let (a, b) = self.transform((chunk[0], chunk[1]));
+            result.push(a);
+            result.push(b);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+
+    #[test]
+    fn test_compiled_cipher() {
+        for grid in ["5x5", "6x6"] {
+            let variant: Variant = crate::parse_grid(grid)
+                .map(|alphabet| Variant {
+                    alphabet,
+                    ..Variant::default()
+                })
+                .unwrap();
+            let table: Vec<Vec<char>> = crate::generate_playfair_table("PLAYFAIR2024", &variant);
+            let positions: Positions = Positions::new(&table);
+            let symbols: Vec<char> = table.concat();
+            for mode in [CipherMode::Encrypt, CipherMode::Decrypt] {
+                let compiled: CompiledCipher = CompiledCipher::new(&table, mode);
+                assert_eq!(compiled.pairs.len(), symbols.len() * symbols.len());
+                for &a in &symbols {
+                    for &b in &symbols {
+                        assert_eq!(
+                            compiled.transform((a, b)),
+                            crate::transform_pair(&table, &positions, (a, b), mode).0
+                        );
+                    }
+                }
+            }
+        }
+        let variant: Variant = Variant::default();
+        let table: Vec<Vec<char>> = crate::generate_playfair_table("PLAYFAIREXAMPLE", &variant);
+        let letters: Vec<char> = "HIDETHEGOLDINTHETREXESTUMP".chars().collect();
+        assert_eq!(
+            CompiledCipher::new(&table, CipherMode::Encrypt).process(&letters),
+            "BMODZBXDNABEKUDMUIXMMOUVIF"
+        );
+    }
+}
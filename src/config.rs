@@ -0,0 +1,184 @@
+//! Loading of default settings from a TOML config file.
+//!
+//! The file lives at `~/.config/playfair/config.toml` (or under
+//! `$XDG_CONFIG_HOME` when set) unless `--config PATH` points elsewhere.
+//! Every key is optional; command-line flags override the file.
+//!
+//! ```toml
+//! filler = "Q"
+//! merge = "J=I"
+//! grid = "5x5"
+//! group = 5
+//! format = "json"
+//!
+//! [anneal]
+//! schedule = "auto"
+//! cooling = "geometric"
+//! cooling_factor = 0.99999
+//! ```
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Default settings read from the config file, also the settings of envelopes
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Conventions of another implementation, e.g. `cryptool`, which the
+    /// other keys override
+    pub compat: Option<String>,
+    /// Letter inserted between repeated characters and used as padding
+    pub filler: Option<char>,
+    /// Letter inserted instead of the filler after a filler
+    pub alt_filler: Option<char>,
+    /// Letter merged into another one, written as `J=I`
+    pub merge: Option<String>,
+    /// Letter left out of the table and dropped from texts, e.g. `Q`
+    pub omit: Option<String>,
+    /// Table size, `5x5` or `6x6` for letters and digits
+    pub grid: Option<String>,
+    /// The symbols of the table, e.g. 25 for 5x5 or 30 for 5x6, instead of a grid
+    pub alphabet: Option<String>,
+    /// Locale spelling out letters missing from the table, `de`, `tr` or `es`
+    pub locale: Option<String>,
+    /// Order in which the table is filled, e.g. `rows` or `spiral`
+    pub route: Option<String>,
+    /// How many times the cipher is applied
+    pub rounds: Option<usize>,
+    /// Row length of the seriated text, if pairs are taken vertically
+    pub period: Option<usize>,
+    /// How repeated letters in a digram are handled, e.g. `digram` or `skip`
+    pub doubles: Option<String>,
+    /// Size of the letter groups in the output
+    pub group: Option<usize>,
+    /// Character written between the letter groups
+    pub separator: Option<char>,
+    /// Whether results are written in lowercase
+    pub lower: Option<bool>,
+    /// Output format, `text`, `json`, `nato` or `morse`
+    pub format: Option<String>,
+    /// The schedule of `crack --solver anneal`
+    pub anneal: Option<Anneal>,
+}
+
+/// The schedule of `crack --solver anneal`, under `[anneal]`, which the
+/// flags of the same names override
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Anneal {
+    /// Preset for the length of the ciphertext, `auto`, `short`, `medium`
+    /// or `long`
+    pub schedule: Option<String>,
+    /// Temperature at the first change
+    pub temperature: Option<f64>,
+    /// How the temperature falls, `linear`, `geometric` or `adaptive`
+    pub cooling: Option<String>,
+    /// Factor geometric cooling multiplies the temperature by at every
+    /// change
+    pub cooling_factor: Option<f64>,
+    /// Changes without a better table after which the schedule starts over,
+    /// 0 for never
+    pub reheat: Option<usize>,
+}
+
+/// Returns the path of the default config file.
+///
+/// # Returns
+///
+/// * `$XDG_CONFIG_HOME/playfair/config.toml` or `$HOME/.config/playfair/config.toml`,
+///   or `None` if neither variable is set.
+pub fn default_path() -> Option<PathBuf> {
+    let base: PathBuf = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("playfair").join("config.toml"))
+}
+
+/// Parses the contents of a config file.
+///
+/// # Arguments
+///
+/// * `contents` - The TOML text of the config file.
+///
+/// # Returns
+///
+/// * The parsed config, or an error message for invalid TOML or unknown keys.
+pub fn parse(contents: &str) -> Result<Config, String> {
+    toml::from_str(contents).map_err(|e| e.to_string())
+}
+
+/// Loads the config file.
+///
+/// # Arguments
+///
+/// * `path` - The path given to `--config`, if any. An explicit path must
+///   exist, while a missing default config file simply yields the defaults.
+///
+/// # Returns
+///
+/// * The config, an I/O error if the file can't be read, or a usage error if
+///   it can't be parsed.
+pub fn load(path: Option<&str>) -> Result<Config, Error> {
+    let (path, explicit): (PathBuf, bool) = match path {
+        Some(path) => (PathBuf::from(path), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    let contents: String = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Config::default());
+        }
+        Err(e) => {
+            return Err(Error::Io(format!(
+                "cannot read config file '{}': {}",
+                path.display(),
+                e
+            )));
+        }
+    };
+    parse(&contents)
+        .map_err(|e| Error::Usage(format!("invalid config file '{}': {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config =
+            parse("filler = \"Q\"\nmerge = \"J=I\"\ngroup = 5\nformat = \"json\"\n").unwrap();
+        assert_eq!(config.filler, Some('Q'));
+        assert_eq!(config.merge.as_deref(), Some("J=I"));
+        assert_eq!(config.group, Some(5));
+        assert_eq!(config.format.as_deref(), Some("json"));
+        assert_eq!(parse("").unwrap(), Config::default());
+        let anneal: Anneal = parse("[anneal]\ncooling = \"adaptive\"\nreheat = 1000\n")
+            .unwrap()
+            .anneal
+            .unwrap();
+        assert_eq!(anneal.cooling.as_deref(), Some("adaptive"));
+        assert_eq!((anneal.reheat, anneal.temperature), (Some(1000), None));
+    }
+
+    #[test]
+    fn test_parse_config_rejects_unknown_keys() {
+        assert!(parse("colour = \"red\"\n").is_err());
+        assert!(parse("filler = \"QQ\"\n").is_err());
+        assert!(parse("[anneal]\nheat = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_load_missing_explicit_config() {
+        let path: PathBuf = std::env::temp_dir().join("playfair_test_missing_config.toml");
+        assert!(matches!(
+            load(Some(path.to_str().unwrap())),
+            Err(Error::Io(_))
+        ));
+    }
+}
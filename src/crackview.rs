@@ -0,0 +1,412 @@
+//! Full-screen view of `crack --tui`.
+//!
+//! The search runs between frames and draws the best table found so far,
+//! a graph of the score of the table it is at and the decryption of the
+//! best table. Cells believed right can be pinned, or filled with a symbol
+//! and pinned, and the search goes on around them.
+//!
+//! Keys: arrows move the cursor, typing a symbol puts it into the cell under
+//! the cursor and pins it, `Ctrl-P` pins or unpins the cell as it is,
+//! `Backspace` unpins it, `Space` pauses, `+`/`-` double or halve the
+//! temperature, `]`/`[` double or halve the changes tried per frame,
+//! `Ctrl-R` shuffles the cells not pinned, `Enter` prints the best table and
+//! its decryption and quits and `Esc` quits without them.
+
+use crate::Variant;
+use crate::analysis::{Ngrams, Session, Strategy};
+use crate::error::Error;
+use crate::keygen::{self, Rng};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Sparkline, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::time::{Duration, Instant};
+
+/// Style of the pinned cells
+const PINNED_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+/// Style of the cell under the cursor
+const CURSOR_STYLE: Style = Style::new().add_modifier(Modifier::REVERSED);
+/// Changes tried between two frames at first
+const CHANGES_PER_FRAME: usize = 20_000;
+/// Factor the temperature falls by every frame
+const COOLING: f64 = 0.98;
+/// Temperature the cooling stops at, so the search never freezes
+const MIN_TEMPERATURE: f64 = 0.1;
+/// Number of frames whose score the graph shows
+const HISTORY: usize = 200;
+/// Time between two looks at the keyboard while paused
+const PAUSED_POLL: Duration = Duration::from_millis(250);
+
+/// Enum to represent how the view was closed
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Exit {
+    /// The best table is taken as the key
+    Accept,
+    /// The view was closed without a key
+    Quit,
+}
+
+/// The state of the view
+struct App<'a> {
+    /// The search being run
+    session: Session<'a>,
+    /// The number of rows and columns of the table
+    shape: (usize, usize),
+    /// The cell under the cursor
+    cursor: usize,
+    /// Whether the search is paused
+    paused: bool,
+    /// How many changes are tried between two frames
+    changes: usize,
+    /// The score per n-gram of the current table at the last frames
+    history: Vec<f64>,
+    /// The changes tried per second over the last frame
+    rate: f64,
+    /// What the last key did, if it needs saying
+    message: String,
+}
+
+impl<'a> App<'a> {
+    /// Creates the state for a session.
+    fn new(session: Session<'a>, variant: &Variant) -> Self {
+        App {
+            session,
+            shape: variant.alphabet.dimensions(),
+            cursor: 0,
+            paused: false,
+            changes: CHANGES_PER_FRAME,
+            history: Vec::with_capacity(HISTORY),
+            rate: 0.0,
+            message: String::new(),
+        }
+    }
+
+    /// Runs the search for a frame, cools it and records its score.
+    fn advance(&mut self) {
+        let started: Instant = Instant::now();
+        self.session.step(self.changes);
+        let elapsed: f64 = started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.rate = self.changes as f64 / elapsed;
+        }
+        if self.session.temperature > 0.0 {
+            self.session.temperature = (self.session.temperature * COOLING).max(MIN_TEMPERATURE);
+        }
+        if self.history.len() == HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(self.session.score());
+    }
+
+    /// Applies a key press.
+    ///
+    /// # Returns
+    ///
+    /// * How the view closes, or `None` if it stays open.
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Exit> {
+        let ctrl: bool = key.modifiers.contains(KeyModifiers::CONTROL);
+        let (rows, cols) = self.shape;
+        let (row, col) = (self.cursor / cols, self.cursor % cols);
+        self.message.clear();
+        match key.code {
+            KeyCode::Esc => return Some(Exit::Quit),
+            KeyCode::Char('c') if ctrl => return Some(Exit::Quit),
+            KeyCode::Enter => return Some(Exit::Accept),
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Up => self.cursor = (row + rows - 1) % rows * cols + col,
+            KeyCode::Down => self.cursor = (row + 1) % rows * cols + col,
+            KeyCode::Left => self.cursor = row * cols + (col + cols - 1) % cols,
+            KeyCode::Right => self.cursor = row * cols + (col + 1) % cols,
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.session.temperature = if self.session.temperature > 0.0 {
+                    self.session.temperature * 2.0
+                } else {
+                    MIN_TEMPERATURE
+                };
+            }
+            KeyCode::Char('-') => {
+                // Below the floor the search only climbs
+                self.session.temperature /= 2.0;
+                if self.session.temperature < MIN_TEMPERATURE {
+                    self.session.temperature = 0.0;
+                }
+            }
+            KeyCode::Char(']') => self.changes *= 2,
+            KeyCode::Char('[') => self.changes = (self.changes / 2).max(1),
+            KeyCode::Char('r') if ctrl => self.session.shuffle(),
+            KeyCode::Char('p') if ctrl => self.session.toggle_pin(self.cursor),
+            KeyCode::Backspace | KeyCode::Delete if self.session.pinned(self.cursor) => {
+                self.session.toggle_pin(self.cursor)
+            }
+            KeyCode::Char(c) if !ctrl => {
+                let symbol: Option<char> =
+                    crate::table_letters(&c.to_string(), self.session.variant())
+                        .first()
+                        .copied();
+                let placed: bool = symbol.is_some_and(|s| self.session.place(self.cursor, s));
+                if !placed {
+                    self.message = format!("Cannot put '{}' here", c);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Draws the whole view.
+    fn draw(&self, frame: &mut Frame) {
+        let (rows, cols): (u16, u16) = (self.shape.0 as u16, self.shape.1 as u16);
+        let [top, graph, plaintext, help] = Layout::vertical([
+            Constraint::Length(rows.max(7) + 2),
+            Constraint::Length(8),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [square, info] =
+            Layout::horizontal([Constraint::Length(cols * 3 + 2), Constraint::Min(20)]).areas(top);
+
+        self.draw_square(frame, square);
+        self.draw_info(frame, info);
+        self.draw_graph(frame, graph);
+        frame.render_widget(
+            Paragraph::new(self.session.plaintext())
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Best Decryption ")),
+            plaintext,
+        );
+        frame.render_widget(
+            Paragraph::new(
+                "Arrows: move   Symbol: place and pin   Ctrl-P: pin   Space: pause   +/-: temperature   [/]: speed   Ctrl-R: shuffle   Enter: accept   Esc: quit",
+            )
+            .style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+    }
+
+    /// Draws the best table, marking the pinned cells and the cursor.
+    fn draw_square(&self, frame: &mut Frame, area: Rect) {
+        let rows: Vec<Vec<char>> = self.session.square().rows().to_vec();
+        let lines: Vec<Line> = rows
+            .iter()
+            .enumerate()
+            .map(|(r, row)| {
+                Line::from(
+                    row.iter()
+                        .enumerate()
+                        .map(|(c, &symbol)| {
+                            let cell: usize = r * self.shape.1 + c;
+                            let mut style: Style = if self.session.pinned(cell) {
+                                PINNED_STYLE
+                            } else {
+                                Style::new()
+                            };
+                            if cell == self.cursor {
+                                style = style.patch(CURSOR_STYLE);
+                            }
+                            Span::styled(format!(" {} ", symbol), style)
+                        })
+                        .collect::<Vec<Span>>(),
+                )
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Best Table ")),
+            area,
+        );
+    }
+
+    /// Draws the state of the search.
+    fn draw_info(&self, frame: &mut Frame, area: Rect) {
+        let name: &str = self.session.ngram_name();
+        let pinned: usize = (0..self.shape.0 * self.shape.1)
+            .filter(|&cell| self.session.pinned(cell))
+            .count();
+        let mut lines: Vec<Line> = vec![
+            Line::from(if self.paused { "Paused" } else { "Running" }),
+            Line::from(format!(
+                "Changes: {} ({:.0}/s, {} per frame)",
+                self.session.iterations, self.rate, self.changes
+            )),
+            Line::from(format!("Temperature: {:.2}", self.session.temperature)),
+            Line::from(format!("Score: {:.2} per {}", self.session.score(), name)),
+            Line::from(format!(
+                "Best: {:.2} per {}{}",
+                self.session.best_score(),
+                name,
+                if self.session.found() {
+                    ", reads as the language"
+                } else {
+                    ""
+                }
+            )),
+            Line::from(format!("Pinned: {} cells", pinned)),
+        ];
+        if !self.message.is_empty() {
+            lines.push(Line::styled(
+                self.message.clone(),
+                Style::new().fg(Color::Red),
+            ));
+        }
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Search ")),
+            area,
+        );
+    }
+
+    /// Draws the scores of the last frames above the lowest of them.
+    fn draw_graph(&self, frame: &mut Frame, area: Rect) {
+        let lowest: f64 = self.history.iter().copied().fold(f64::INFINITY, f64::min);
+        // Hundredths of a point per n-gram, so small gains still show
+        let data: Vec<u64> = self
+            .history
+            .iter()
+            .map(|score| ((score - lowest) * 100.0).round() as u64 + 1)
+            .collect();
+        frame.render_widget(
+            Sparkline::default()
+                .data(&data)
+                .style(Style::new().fg(Color::Green))
+                .block(Block::bordered().title(" Fitness ")),
+            area,
+        );
+    }
+}
+
+/// Runs the search of `crack` in the full-screen view until the user quits.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext symbols, of even length.
+/// * `variant` - The variant deciding the symbols and the shape of the table.
+/// * `ngrams` - The n-gram scores.
+/// * `strategy` - The changes, the temperature and the cribs of the search.
+/// * `seed` - The seed of the search.
+///
+/// # Returns
+///
+/// * An error if the terminal can't be used.
+pub fn run(
+    ciphertext: &[char],
+    variant: &Variant,
+    ngrams: &Ngrams,
+    strategy: &Strategy,
+    seed: u64,
+) -> Result<(), Error> {
+    let session: Session = Session::new(ciphertext, variant, ngrams, strategy, Rng::new(seed));
+    let mut app: App = App::new(session, variant);
+
+    let mut terminal: DefaultTerminal = ratatui::try_init()
+        .map_err(|e| Error::Io(format!("cannot start the terminal interface: {}", e)))?;
+    let result: Result<Exit, Error> = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    if result? == Exit::Accept {
+        let session: &Session = &app.session;
+        eprintln!("Key Fingerprint: {}", session.square().fingerprint());
+        eprintln!(
+            "Score: {:.2} per {}",
+            session.best_score(),
+            session.ngram_name()
+        );
+        println!("Key Square: {}", keygen::square_line(&session.square()));
+        println!("Decrypted Text: {}", session.plaintext());
+    }
+    Ok(())
+}
+
+/// Runs the search, draws the view and applies key presses until the user
+/// quits.
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<Exit, Error> {
+    loop {
+        if !app.paused {
+            app.advance();
+        }
+        terminal
+            .draw(|frame| app.draw(frame))
+            .map_err(|e| Error::Io(e.to_string()))?;
+        let wait: Duration = if app.paused {
+            PAUSED_POLL
+        } else {
+            Duration::ZERO
+        };
+        if event::poll(wait).map_err(|e| Error::Io(e.to_string()))?
+            && let Event::Key(key) = event::read().map_err(|e| Error::Io(e.to_string()))?
+            && key.kind == KeyEventKind::Press
+            && let Some(exit) = app.handle_key(key)
+        {
+            return Ok(exit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Language;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    /// Builds a key press without modifiers
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_app_keys_and_draw() {
+        let variant: Variant = Variant::default();
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let strategy: Strategy = Strategy::default();
+        let ciphertext: Vec<char> = "BMODZBXDNABEKUDMUIXMMOUVIF".chars().collect();
+        let session: Session = Session::new(&ciphertext, &variant, &ngrams, &strategy, Rng::new(1));
+        let mut app: App = App::new(session, &variant);
+        app.advance();
+        assert_eq!(app.history.len(), 1);
+        assert_eq!(app.session.iterations, CHANGES_PER_FRAME);
+
+        // Placing pins the cell, and the search leaves it alone
+        app.handle_key(press(KeyCode::Right));
+        app.handle_key(press(KeyCode::Down));
+        assert_eq!(app.cursor, 6);
+        app.handle_key(press(KeyCode::Char('q')));
+        assert!(app.session.pinned(6));
+        assert_eq!(app.session.square().rows()[1][1], 'Q');
+        app.advance();
+        assert_eq!(app.session.square().rows()[1][1], 'Q');
+        // Q is pinned where it is, so it can't go elsewhere
+        app.handle_key(press(KeyCode::Left));
+        app.handle_key(press(KeyCode::Char('q')));
+        assert!(!app.session.pinned(5));
+        assert!(!app.message.is_empty());
+        app.handle_key(press(KeyCode::Right));
+        app.handle_key(press(KeyCode::Backspace));
+        assert!(!app.session.pinned(6));
+
+        app.handle_key(press(KeyCode::Char(' ')));
+        assert!(app.paused);
+        app.handle_key(press(KeyCode::Char(']')));
+        assert_eq!(app.changes, CHANGES_PER_FRAME * 2);
+        app.handle_key(press(KeyCode::Up));
+        app.handle_key(press(KeyCode::Up));
+        assert_eq!(app.cursor, 21);
+
+        let mut terminal: Terminal<TestBackend> = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Best Table"));
+        assert!(screen.contains("Paused"));
+        assert!(screen.contains(&format!("Changes: {}", 2 * CHANGES_PER_FRAME)));
+        assert_eq!(app.handle_key(press(KeyCode::Enter)), Some(Exit::Accept));
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Exit::Quit)
+        );
+    }
+}
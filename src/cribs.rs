@@ -0,0 +1,668 @@
+//! Known-plaintext attack on Playfair from cribs.
+//!
+//! A crib pairs plaintext digrams with the ciphertext digrams they encrypt
+//! to, such as `--crib THEM=XPEF`. Every pair pins down where its four
+//! letters sit relative to each other: once both plaintext letters of a
+//! digram are placed, the cipher rules place both ciphertext letters, and
+//! the other way around. Placing the most constrained letter in every free
+//! cell in turn and propagating these rules rebuilds the part of the table
+//! the cribs cover, the way Playfair was broken by hand. The first letter
+//! always goes in the top left cell, as shifting the rows or the columns of
+//! a table doesn't change the cipher.
+//!
+//! The search of the `crack` subcommand then starts from this partial table
+//! and counts every crib the table breaks against its score.
+
+use crate::Variant;
+use crate::error::Error;
+
+/// Most placements tried before the cribs are given up on
+const MAX_STEPS: usize = 1_000_000;
+
+/// One plaintext digram and the ciphertext digram it encrypts to, as
+/// symbol indices
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Crib {
+    /// The plaintext digram
+    pub plain: [usize; 2],
+    /// The ciphertext digram
+    pub cipher: [usize; 2],
+}
+
+/// Parses a crib such as `THEM=XPEF` into its digrams.
+///
+/// # Arguments
+///
+/// * `spec` - The plaintext, split into digrams as it was encrypted, an
+///   `=` and the ciphertext. Characters outside the table are skipped.
+/// * `variant` - The variant deciding the symbols of the table.
+///
+/// # Returns
+///
+/// * The digrams, or a usage error if the halves differ in length, are odd
+///   or a plaintext digram repeats a letter.
+pub fn parse(spec: &str, variant: &Variant) -> Result<Vec<Crib>, Error> {
+    let Some((plain, cipher)) = spec.split_once('=') else {
+        return Err(Error::Usage(format!(
+            "invalid crib '{}': expected PLAINTEXT=CIPHERTEXT",
+            spec
+        )));
+    };
+    let symbols: Vec<char> = variant.symbols();
+    let index = |text: &str| -> Vec<usize> {
+        crate::table_letters(text, variant)
+            .iter()
+            .map(|c| symbols.iter().position(|s| s == c).unwrap())
+            .collect()
+    };
+    let (plain, cipher): (Vec<usize>, Vec<usize>) = (index(plain), index(cipher));
+    if plain.is_empty() || plain.len() != cipher.len() || !plain.len().is_multiple_of(2) {
+        return Err(Error::Usage(format!(
+            "invalid crib '{}': both halves need the same even number of letters",
+            spec
+        )));
+    }
+    let mut cribs: Vec<Crib> = Vec::with_capacity(plain.len() / 2);
+    for (plain, cipher) in plain.chunks(2).zip(cipher.chunks(2)) {
+        if plain[0] == plain[1] || cipher[0] == cipher[1] {
+            return Err(Error::Usage(format!(
+                "invalid crib '{}': a digram never repeats a letter",
+                spec
+            )));
+        }
+        cribs.push(Crib {
+            plain: [plain[0], plain[1]],
+            cipher: [cipher[0], cipher[1]],
+        });
+    }
+    Ok(cribs)
+}
+
+/// Finds the cells a digram turns into under the cipher rules.
+///
+/// # Arguments
+///
+/// * `cells` - The cells of the two letters, row by row.
+/// * `shape` - Rows and columns of the table.
+/// * `step` - 1 to encrypt, `-1` to decrypt.
+///
+/// # Returns
+///
+/// * The cells of the two letters of the result.
+pub fn transform(cells: [usize; 2], shape: (usize, usize), step: isize) -> [usize; 2] {
+    let (rows, cols) = shape;
+    let (r1, c1) = (cells[0] / cols, cells[0] % cols);
+    let (r2, c2) = (cells[1] / cols, cells[1] % cols);
+    let shift = |i: usize, n: usize| (i as isize + step).rem_euclid(n as isize) as usize;
+    if r1 == r2 {
+        [r1 * cols + shift(c1, cols), r2 * cols + shift(c2, cols)]
+    } else if c1 == c2 {
+        [shift(r1, rows) * cols + c1, shift(r2, rows) * cols + c2]
+    } else {
+        [r1 * cols + c2, r2 * cols + c1]
+    }
+}
+
+/// A key square of which only some cells are known, as symbol indices
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialKeySquare {
+    /// Rows and columns of the table
+    shape: (usize, usize),
+    /// The symbol in every cell, row by row, if known
+    symbol_at: Vec<Option<usize>>,
+    /// The cell of every symbol, if known
+    cell_of: Vec<Option<usize>>,
+}
+
+impl PartialKeySquare {
+    /// Creates a square of which no cell is known.
+    pub fn new(shape: (usize, usize)) -> Self {
+        PartialKeySquare {
+            shape,
+            symbol_at: vec![None; shape.0 * shape.1],
+            cell_of: vec![None; shape.0 * shape.1],
+        }
+    }
+
+    /// Returns the symbol in every cell, row by row, `None` where unknown.
+    pub fn cells(&self) -> &[Option<usize>] {
+        &self.symbol_at
+    }
+
+    /// Returns how many cells are known.
+    pub fn known(&self) -> usize {
+        self.symbol_at.iter().filter(|cell| cell.is_some()).count()
+    }
+
+    /// Puts a symbol in a cell.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the symbol was placed, `Some(false)` if it already sat
+    ///   there, or `None` if it sits elsewhere or the cell is taken.
+    pub fn place(&mut self, symbol: usize, cell: usize) -> Option<bool> {
+        match (self.cell_of[symbol], self.symbol_at[cell]) {
+            (Some(placed), _) => (placed == cell).then_some(false),
+            (None, Some(_)) => None,
+            (None, None) => {
+                self.cell_of[symbol] = Some(cell);
+                self.symbol_at[cell] = Some(symbol);
+                Some(true)
+            }
+        }
+    }
+
+    /// Checks the known cells against cribs, leaving the square as it is.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the letters the cribs force can all be placed without
+    ///   taking a cell twice or moving a known letter.
+    pub fn consistent(&self, cribs: &[Crib]) -> bool {
+        self.clone().propagate(cribs)
+    }
+
+    /// Places every letter the cribs force, until nothing changes.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the square still agrees with all cribs.
+    pub fn propagate(&mut self, cribs: &[Crib]) -> bool {
+        let mut changed: bool = true;
+        while changed {
+            changed = false;
+            for crib in cribs {
+                for (from, to, step) in
+                    [(crib.plain, crib.cipher, 1), (crib.cipher, crib.plain, -1)]
+                {
+                    let (Some(a), Some(b)) = (self.cell_of[from[0]], self.cell_of[from[1]]) else {
+                        continue;
+                    };
+                    let cells: [usize; 2] = transform([a, b], self.shape, step);
+                    for (symbol, cell) in to.into_iter().zip(cells) {
+                        match self.place(symbol, cell) {
+                            Some(placed) => changed |= placed,
+                            None => return false,
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Decrypts the digrams of a ciphertext the known cells cover.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The ciphertext, as symbol indices.
+    ///
+    /// # Returns
+    ///
+    /// * The plaintext symbol at every position, `None` where the known
+    ///   cells don't decrypt the digram.
+    pub fn decrypt(&self, ciphertext: &[usize]) -> Vec<Option<usize>> {
+        let mut plaintext: Vec<Option<usize>> = Vec::with_capacity(ciphertext.len());
+        for digram in ciphertext.chunks(2) {
+            let decrypted: Option<[usize; 2]> = match digram {
+                &[first, second] => {
+                    self.cell_of[first]
+                        .zip(self.cell_of[second])
+                        .and_then(|(a, b)| {
+                            let [a, b] = transform([a, b], self.shape, -1);
+                            Some([self.symbol_at[a]?, self.symbol_at[b]?])
+                        })
+                }
+                _ => None,
+            };
+            match decrypted {
+                Some(pair) => plaintext.extend(pair.map(Some)),
+                None => plaintext.extend(vec![None; digram.len()]),
+            }
+        }
+        plaintext
+    }
+
+    /// Enumerates every full square that keeps the known cells and agrees
+    /// with the cribs, one at a time.
+    ///
+    /// A square with `n` unknown cells and no cribs on them has `n!`
+    /// completions, so callers usually take only as many as they can
+    /// score.
+    pub fn completions<'a>(&self, cribs: &'a [Crib]) -> Completions<'a> {
+        let mut order: Vec<usize> = crib_letters(cribs);
+        let rest: Vec<usize> = (0..self.cell_of.len())
+            .filter(|symbol| !order.contains(symbol))
+            .collect();
+        order.extend(rest);
+        Completions {
+            cribs,
+            order,
+            stack: if self.consistent(cribs) {
+                vec![self.clone()]
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
+/// The completions of a partial key square, found depth first
+pub struct Completions<'a> {
+    /// The cribs every completion agrees with
+    cribs: &'a [Crib],
+    /// The symbols in the order they are placed, those of the cribs first
+    order: Vec<usize>,
+    /// The squares left to fill in, the next one last
+    stack: Vec<PartialKeySquare>,
+}
+
+impl Iterator for Completions<'_> {
+    /// The symbol in every cell, row by row
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(square) = self.stack.pop() {
+            let Some(&symbol) = self.order.iter().find(|&&s| square.cell_of[s].is_none()) else {
+                return Some(square.symbol_at.iter().flatten().copied().collect());
+            };
+            // Pushed in reverse, so the lowest free cell is tried first
+            for cell in (0..square.symbol_at.len()).rev() {
+                if square.symbol_at[cell].is_some() {
+                    continue;
+                }
+                let mut next: PartialKeySquare = square.clone();
+                next.place(symbol, cell);
+                if next.propagate(self.cribs) {
+                    self.stack.push(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Rebuilds the part of the table the cribs cover.
+///
+/// # Arguments
+///
+/// * `cribs` - The digram pairs, as symbol indices.
+/// * `shape` - Rows and columns of the table.
+///
+/// # Returns
+///
+/// * A square in which the letters of all cribs are known, or an invalid
+///   input error if no table agrees with all cribs.
+pub fn solve(cribs: &[Crib], shape: (usize, usize)) -> Result<PartialKeySquare, Error> {
+    let mut square: PartialKeySquare = PartialKeySquare::new(shape);
+    let order: Vec<usize> = crib_letters(cribs);
+    let mut steps: usize = 0;
+    if search(&mut square, cribs, &order, &mut steps) {
+        Ok(square)
+    } else if steps >= MAX_STEPS {
+        Err(Error::InvalidInput(format!(
+            "the cribs can't be placed within {} steps; give fewer or longer cribs",
+            MAX_STEPS
+        )))
+    } else {
+        Err(Error::InvalidInput(String::from(
+            "no table agrees with all the cribs: check that they line up with the ciphertext",
+        )))
+    }
+}
+
+/// Enumerates every table agreeing with the cribs, up to shifts of its rows
+/// and columns: the first letter of the cribs always sits in the top left
+/// cell.
+///
+/// Unlike `solve`, this proves that no other table exists, but a table the
+/// cribs leave `n` cells of has `n!` completions.
+pub fn tables(cribs: &[Crib], shape: (usize, usize)) -> Completions<'_> {
+    let mut square: PartialKeySquare = PartialKeySquare::new(shape);
+    square.place(crib_letters(cribs).first().copied().unwrap_or(0), 0);
+    square.completions(cribs)
+}
+
+/// Returns the letters of the cribs, those of the most cribs first.
+fn crib_letters(cribs: &[Crib]) -> Vec<usize> {
+    let mut letters: Vec<(usize, usize)> = Vec::new();
+    for &symbol in cribs
+        .iter()
+        .flat_map(|crib| crib.plain.iter().chain(&crib.cipher))
+    {
+        match letters.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, count)) => *count += 1,
+            None => letters.push((symbol, 1)),
+        }
+    }
+    letters.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    letters.into_iter().map(|(symbol, _)| symbol).collect()
+}
+
+/// Places the first unplaced letter in every free cell in turn.
+///
+/// # Returns
+///
+/// * Whether a placement agreeing with all cribs was found; `square` holds
+///   it.
+fn search(
+    square: &mut PartialKeySquare,
+    cribs: &[Crib],
+    order: &[usize],
+    steps: &mut usize,
+) -> bool {
+    let Some(&symbol) = order.iter().find(|&&s| square.cell_of[s].is_none()) else {
+        return true;
+    };
+    // Shifting the rows or columns keeps the cipher, so the first letter
+    // only needs the top left cell
+    let cells: Vec<usize> = if square.known() == 0 {
+        vec![0]
+    } else {
+        (0..square.symbol_at.len())
+            .filter(|&cell| square.symbol_at[cell].is_none())
+            .collect()
+    };
+    for cell in cells {
+        *steps += 1;
+        if *steps >= MAX_STEPS {
+            return false;
+        }
+        let saved: PartialKeySquare = square.clone();
+        square.place(symbol, cell);
+        if square.propagate(cribs) && search(square, cribs, order, steps) {
+            return true;
+        }
+        *square = saved;
+    }
+    false
+}
+
+/// Counts the cribs a table breaks.
+///
+/// # Arguments
+///
+/// * `cribs` - The digram pairs, as symbol indices.
+/// * `positions` - The cell of every symbol.
+/// * `shape` - Rows and columns of the table.
+pub fn broken(cribs: &[Crib], positions: &[usize], shape: (usize, usize)) -> usize {
+    cribs
+        .iter()
+        .filter(|crib| {
+            let cells: [usize; 2] = [positions[crib.plain[0]], positions[crib.plain[1]]];
+            transform(cells, shape, 1) != [positions[crib.cipher[0]], positions[crib.cipher[1]]]
+        })
+        .count()
+}
+
+/// A place a suspected word may take in the plaintext
+pub struct Drag {
+    /// The letter of the plaintext the word starts at
+    pub offset: usize,
+    /// A square agreeing with the digrams the word covers, or why the word
+    /// can't sit here
+    pub square: Result<PartialKeySquare, String>,
+}
+
+/// Slides a suspected word along the ciphertext (crib dragging).
+///
+/// Every offset at which the word covers at least one whole digram is
+/// checked: the word can't split a doubled letter into one digram, no
+/// letter encrypts to itself, and a table must agree with all the digrams
+/// the word covers.
+///
+/// # Arguments
+///
+/// * `word` - The suspected plaintext word, as symbol indices.
+/// * `ciphertext` - The ciphertext, as symbol indices.
+/// * `shape` - Rows and columns of the table.
+///
+/// # Returns
+///
+/// * One entry per offset, in order.
+pub fn drag(word: &[usize], ciphertext: &[usize], shape: (usize, usize)) -> Vec<Drag> {
+    let mut drags: Vec<Drag> = Vec::new();
+    for offset in 0..(ciphertext.len() + 1).saturating_sub(word.len()) {
+        let square: Result<PartialKeySquare, String> = match word_cribs(word, ciphertext, offset) {
+            None => continue,
+            Some(Err(reason)) => Err(reason),
+            Some(Ok(cribs)) => {
+                solve(&cribs, shape).map_err(|_| String::from("no table agrees with the digrams"))
+            }
+        };
+        drags.push(Drag { offset, square });
+    }
+    drags
+}
+
+/// Pairs the whole digrams a word covers at an offset of the plaintext with
+/// the ciphertext digrams under them.
+///
+/// # Arguments
+///
+/// * `word` - The plaintext word, as symbol indices.
+/// * `ciphertext` - The ciphertext, as symbol indices.
+/// * `offset` - The letter of the plaintext the word starts at, leaving room
+///   for the word.
+///
+/// # Returns
+///
+/// * The digrams as cribs, why the word can't sit there if a digram would
+///   hold a doubled letter or a letter would encrypt to itself, or `None`
+///   if the word covers no whole digram.
+fn word_cribs(
+    word: &[usize],
+    ciphertext: &[usize],
+    offset: usize,
+) -> Option<Result<Vec<Crib>, String>> {
+    // The whole digrams the word covers, by their first letter
+    let first: usize = offset.next_multiple_of(2);
+    let digrams: Vec<usize> = (first..offset + word.len().saturating_sub(1))
+        .step_by(2)
+        .collect();
+    if digrams.is_empty() {
+        return None;
+    }
+    if let Some(i) = digrams
+        .iter()
+        .find(|&&i| word[i - offset] == word[i + 1 - offset])
+    {
+        return Some(Err(format!(
+            "a digram would hold a doubled letter at {}",
+            i
+        )));
+    }
+    if let Some(i) = (0..word.len()).find(|&i| word[i] == ciphertext[offset + i]) {
+        return Some(Err(format!(
+            "a letter would encrypt to itself at {}",
+            offset + i
+        )));
+    }
+    Some(Ok(digrams
+        .iter()
+        .map(|&i| Crib {
+            plain: [word[i - offset], word[i + 1 - offset]],
+            cipher: [ciphertext[i], ciphertext[i + 1]],
+        })
+        .collect()))
+}
+
+/// Parses a hint such as `0:DEAR` or `-7:REGARDS`, a word known to sit at
+/// an offset of the plaintext, into the digrams it covers.
+///
+/// # Arguments
+///
+/// * `spec` - The letter of the plaintext the word starts at, counted from
+///   the end if negative, a `:` and the word. Characters outside the table
+///   are skipped.
+/// * `ciphertext` - The ciphertext, as symbol indices.
+/// * `variant` - The variant deciding the symbols of the table.
+///
+/// # Returns
+///
+/// * The digrams, or a usage error if the hint is malformed, runs past the
+///   ciphertext, covers no whole digram or contradicts the ciphertext.
+pub fn hint(spec: &str, ciphertext: &[usize], variant: &Variant) -> Result<Vec<Crib>, Error> {
+    let invalid = |reason: &str| Error::Usage(format!("invalid hint '{}': {}", spec, reason));
+    let Some((offset, word)) = spec.split_once(':') else {
+        return Err(invalid("expected OFFSET:WORD"));
+    };
+    let offset: isize = offset
+        .trim()
+        .parse()
+        .map_err(|_| invalid("the offset is not a whole number"))?;
+    let symbols: Vec<char> = variant.symbols();
+    let word: Vec<usize> = crate::table_letters(word, variant)
+        .iter()
+        .map(|c| symbols.iter().position(|s| s == c).unwrap())
+        .collect();
+    let start: Option<usize> = if offset < 0 {
+        ciphertext.len().checked_sub(offset.unsigned_abs())
+    } else {
+        Some(offset as usize)
+    };
+    let Some(start) = start.filter(|&start| start + word.len() <= ciphertext.len()) else {
+        return Err(invalid(&format!(
+            "the word doesn't fit in {} letters of ciphertext",
+            ciphertext.len()
+        )));
+    };
+    match word_cribs(&word, ciphertext, start) {
+        None => Err(invalid("the word covers no whole digram there")),
+        Some(Err(reason)) => Err(invalid(&reason)),
+        Some(Ok(cribs)) => Ok(cribs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeySquare;
+
+    /// Turns letters into symbol indices of the standard table.
+    fn index(text: &str) -> Vec<usize> {
+        let symbols: Vec<char> = Variant::default().symbols();
+        text.chars()
+            .map(|c| symbols.iter().position(|&s| s == c).unwrap())
+            .collect()
+    }
+
+    /// Encrypts a text with the key of the Wikipedia example.
+    fn encrypt(plaintext: &str) -> String {
+        let variant: Variant = Variant::default();
+        let square: KeySquare = KeySquare::from_key("PLAYFAIREXAMPLE", &variant);
+        crate::playfair_cipher(
+            plaintext,
+            square.rows(),
+            &variant,
+            crate::CipherMode::Encrypt,
+        )
+    }
+
+    #[test]
+    fn test_cribs_rebuild_the_table() {
+        let variant: Variant = Variant::default();
+        let plaintext: &str = "HIDETHEGOLDINTHETREXESTUMP";
+        let ciphertext: String = encrypt(plaintext);
+        let cribs: Vec<Crib> = parse(&format!("{}={}", plaintext, ciphertext), &variant).unwrap();
+        assert_eq!(cribs.len(), 13);
+        let square: PartialKeySquare = solve(&cribs, (5, 5)).unwrap();
+        assert!(square.known() >= 10);
+        assert!(square.consistent(&cribs));
+        let cells: Vec<usize> = square.completions(&cribs).next().unwrap();
+        let mut positions: Vec<usize> = vec![0; 25];
+        for (cell, &symbol) in cells.iter().enumerate() {
+            positions[symbol] = cell;
+        }
+        assert_eq!(broken(&cribs, &positions, (5, 5)), 0);
+        let all: Vec<Vec<usize>> = tables(&cribs, (5, 5)).collect();
+        assert!(all.contains(&cells));
+        for cells in &all {
+            for (cell, &symbol) in cells.iter().enumerate() {
+                positions[symbol] = cell;
+            }
+            assert_eq!(broken(&cribs, &positions, (5, 5)), 0);
+        }
+
+        assert!(parse("THEM", &variant).is_err());
+        assert!(parse("THE=XPE", &variant).is_err());
+        assert!(parse("EE=XP", &variant).is_err());
+        // A letter never encrypts to itself
+        let contradiction: Vec<Crib> = parse("TH=TX", &variant).unwrap();
+        assert!(solve(&contradiction, (5, 5)).is_err());
+        assert_eq!(tables(&contradiction, (5, 5)).count(), 0);
+    }
+
+    #[test]
+    fn test_partial_square_completions() {
+        let mut square: PartialKeySquare = PartialKeySquare::new((5, 5));
+        for symbol in 0..22 {
+            assert_eq!(square.place(symbol, symbol), Some(true));
+        }
+        assert_eq!(square.place(0, 0), Some(false));
+        assert_eq!(square.place(0, 1), None);
+        assert_eq!(square.place(22, 0), None);
+        assert_eq!(square.known(), 22);
+        assert_eq!(square.completions(&[]).count(), 6);
+        // X shares the last row with W, so WX turning into XY puts X, Y
+        // and Z in order
+        let crib: Crib = Crib {
+            plain: [21, 22],
+            cipher: [22, 23],
+        };
+        let completions: Vec<Vec<usize>> = square.completions(&[crib]).collect();
+        assert_eq!(completions, vec![(0..25).collect::<Vec<usize>>()]);
+        let wrong: Crib = Crib {
+            plain: [0, 1],
+            cipher: [3, 4],
+        };
+        assert!(!square.consistent(&[wrong]));
+        assert_eq!(square.completions(&[wrong]).count(), 0);
+    }
+
+    #[test]
+    fn test_drag_finds_the_word() {
+        let ciphertext: Vec<usize> = index(&encrypt("HIDETHEGOLDINTHETREXESTUMP"));
+        let drags: Vec<Drag> = drag(&index("GOLDIN"), &ciphertext, (5, 5));
+        assert_eq!(drags.len(), ciphertext.len() - 5);
+        let found: &Drag = drags.iter().find(|d| d.offset == 7).unwrap();
+        let plaintext: Vec<Option<usize>> = found.square.as_ref().unwrap().decrypt(&ciphertext);
+        assert_eq!(
+            &plaintext[8..12],
+            &index("OLDI").into_iter().map(Some).collect::<Vec<_>>()
+        );
+        // The D of HIDE sits where GOLDIN would put its D
+        assert!(drags[0].square.is_err());
+        // A word of two letters covers no whole digram at odd offsets
+        assert_eq!(drag(&index("GO"), &ciphertext, (5, 5)).len(), 13);
+    }
+
+    #[test]
+    fn test_hints_at_offsets() {
+        let variant: Variant = Variant::default();
+        let ciphertext: Vec<usize> = index(&encrypt("HIDETHEGOLDINTHETREXESTUMP"));
+        let opening: Vec<Crib> = hint("0:HIDE", &ciphertext, &variant).unwrap();
+        assert_eq!(opening.len(), 2);
+        assert_eq!(opening[0].plain, [index("H")[0], index("I")[0]]);
+        assert_eq!(opening[1].cipher, [ciphertext[2], ciphertext[3]]);
+        // From the end, and at an odd offset covering one whole digram
+        let closing: Vec<Crib> = hint("-6:estump", &ciphertext, &variant).unwrap();
+        assert_eq!(closing.len(), 3);
+        assert_eq!(hint("7:GOLD", &ciphertext, &variant).unwrap().len(), 1);
+        for spec in [
+            "DEAR",
+            "x:DEAR",
+            "24:GOLD",
+            "-30:HIDE",
+            "1:HI",
+            "0:LLAMA",
+            "-5:ESTUMP",
+        ] {
+            assert!(
+                matches!(hint(spec, &ciphertext, &variant), Err(Error::Usage(_))),
+                "{}",
+                spec
+            );
+        }
+    }
+}
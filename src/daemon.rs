@@ -0,0 +1,268 @@
+//! Long-running daemon answering requests on a unix domain socket.
+//!
+//! Each line sent to the socket is a JSON request such as
+//! `{"op": "encrypt", "text": "HELLO", "key": "KEYWORD", "id": 7}` and is
+//! answered with one line, e.g. `{"id": 7, "result": "GYIZSC",
+//! "key_fingerprint": "..."}` or `{"id": 7, "error": "..."}`. The `key` may be
+//! left out when one was preloaded at startup and `id` is echoed back
+//! unchanged when present. A connection stays open for any number of requests;
+//! a line longer than `MAX_LINE` bytes is answered with an error and closes
+//! it.
+//!
+//! The daemon removes its socket when it is stopped by a signal, and only
+//! ever replaces a socket, never another kind of file.
+
+use crate::CipherMode;
+use crate::error::Error;
+use crate::serve::{self, Service};
+use clap::ArgMatches;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Longest request line accepted, in bytes, with its newline
+const MAX_LINE: u64 = 1 << 20;
+
+/// One request line sent to the daemon
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DaemonRequest {
+    /// Either `encrypt` or `decrypt`
+    op: String,
+    /// The text to encrypt or decrypt
+    text: String,
+    /// The key, required unless one was preloaded
+    key: Option<String>,
+    /// An identifier echoed back in the response
+    id: Option<Value>,
+}
+
+/// Answers one request line.
+///
+/// # Arguments
+///
+/// * `service` - The shared cipher settings.
+/// * `line` - The JSON request.
+///
+/// # Returns
+///
+/// * The JSON response.
+pub fn respond(service: &Service, line: &str) -> Value {
+    let request: DaemonRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return json!({ "error": format!("invalid request: {}", e) }),
+    };
+    let id: Value = request.id.unwrap_or(Value::Null);
+    let mode: CipherMode = match request.op.as_str() {
+        "encrypt" => CipherMode::Encrypt,
+        "decrypt" => CipherMode::Decrypt,
+        other => {
+            return json!({
+                "id": id,
+                "error": format!("unknown op '{}': expected 'encrypt' or 'decrypt'", other),
+            });
+        }
+    };
+    match service.process_text(&request.text, request.key.as_deref(), mode) {
+        Ok(mut response) => {
+            response["id"] = id;
+            response
+        }
+        Err(e) => json!({ "id": id, "error": e.to_string() }),
+    }
+}
+
+/// Runs the `daemon` subcommand until the process is stopped.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings are invalid or the socket can't be bound.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let service: Arc<Service> = Arc::new(serve::service_from_matches(matches)?);
+    let path: &Path = Path::new(matches.get_one::<String>("socket").unwrap());
+
+    remove_stale_socket(path)?;
+    let listener: UnixListener = UnixListener::bind(path)
+        .map_err(|e| Error::Io(format!("cannot listen on '{}': {}", path.display(), e)))?;
+    let socket: PathBuf = path.to_path_buf();
+    ctrlc::set_handler(move || {
+        let _ = std::fs::remove_file(&socket);
+        std::process::exit(0);
+    })
+    .map_err(|e| Error::Io(format!("cannot handle signals: {}", e)))?;
+    eprintln!(
+        "Listening on {} ({})",
+        path.display(),
+        serve::key_source(&service)
+    );
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let service: Arc<Service> = Arc::clone(&service);
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &service) {
+                        eprintln!("Error: connection: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("Error: cannot accept connection: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the socket a previous daemon left behind.
+///
+/// # Arguments
+///
+/// * `path` - The socket path.
+///
+/// # Returns
+///
+/// * An I/O error if a daemon still listens on the socket, or the path is
+///   some other kind of file, which is left alone.
+fn remove_stale_socket(path: &Path) -> Result<(), Error> {
+    let metadata: std::fs::Metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(Error::Io(format!(
+                "cannot read '{}': {}",
+                path.display(),
+                e
+            )));
+        }
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(Error::Io(format!(
+            "'{}' exists and is not a socket",
+            path.display()
+        )));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(Error::Io(format!(
+            "a daemon is already listening on '{}'",
+            path.display()
+        )));
+    }
+    std::fs::remove_file(path).map_err(|e| {
+        Error::Io(format!(
+            "cannot remove stale socket '{}': {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Answers the requests of one connection until the client hangs up or
+/// sends a line longer than `MAX_LINE`.
+fn handle_connection(stream: UnixStream, service: &Service) -> std::io::Result<()> {
+    let mut writer: UnixStream = stream.try_clone()?;
+    let mut reader: BufReader<UnixStream> = BufReader::new(stream);
+    let mut line: String = String::new();
+    loop {
+        line.clear();
+        if (&mut reader).take(MAX_LINE + 1).read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.len() as u64 > MAX_LINE {
+            writeln!(writer, "{}", json!({ "error": "request line too long" }))?;
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", respond(service, line.trim_end()))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutputStyle, Variant};
+
+    #[test]
+    fn test_respond() {
+        let service: Service =
+            Service::new(Variant::default(), OutputStyle::default(), Some("KEYWORD"));
+        let response: Value = respond(&service, r#"{"op": "encrypt", "text": "hello", "id": 7}"#);
+        assert_eq!(response["result"], "GYIZSC");
+        assert_eq!(response["id"], 7);
+
+        let response: Value = respond(&service, r#"{"op": "shuffle", "text": "hello"}"#);
+        assert!(response["error"].as_str().unwrap().contains("unknown op"));
+        assert!(respond(&service, "{").get("error").is_some());
+    }
+
+    #[test]
+    fn test_socket_round_trip() {
+        let path: std::path::PathBuf = std::env::temp_dir().join("playfair_test_daemon.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener: UnixListener = UnixListener::bind(&path).unwrap();
+        let service: Service = Service::new(Variant::default(), OutputStyle::default(), None);
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle_connection(stream, &service).unwrap();
+        });
+
+        let mut client: UnixStream = UnixStream::connect(&path).unwrap();
+        writeln!(
+            client,
+            r#"{{"op": "decrypt", "text": "GYIZSC", "key": "KEYWORD"}}"#
+        )
+        .unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response: String = String::new();
+        BufReader::new(&client).read_line(&mut response).unwrap();
+        server.join().unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["result"], "HELXLO");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stale_socket() {
+        let dir: PathBuf = std::env::temp_dir().join("playfair_test_daemon_stale");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // A regular file at the path is kept
+        let file: PathBuf = dir.join("notes.txt");
+        std::fs::write(&file, "keep me").unwrap();
+        assert!(matches!(remove_stale_socket(&file), Err(Error::Io(_))));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+        // A socket nobody listens on is removed, a live one is not
+        let socket: PathBuf = dir.join("daemon.sock");
+        let listener: UnixListener = UnixListener::bind(&socket).unwrap();
+        assert!(matches!(remove_stale_socket(&socket), Err(Error::Io(_))));
+        drop(listener);
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+        remove_stale_socket(&socket).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_long_line() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let service: Service = Service::new(Variant::default(), OutputStyle::default(), None);
+        let server = std::thread::spawn(move || handle_connection(server, &service).unwrap());
+        let mut writer: UnixStream = client.try_clone().unwrap();
+        // The server stops reading at the limit, so the rest may not fit
+        let _ = writer.write_all(&vec![b'A'; MAX_LINE as usize + 10]);
+        let mut response: String = String::new();
+        BufReader::new(&client).read_line(&mut response).unwrap();
+        server.join().unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["error"], "request line too long");
+    }
+}
@@ -0,0 +1,150 @@
+//! Hex and base64 framing of the input and the result.
+//!
+//! `--in-encoding` decodes the input before it is processed and
+//! `--out-encoding` encodes the result, so that ciphertexts can travel in
+//! JSON strings or URLs. Base64 is written with the standard alphabet and
+//! padding; decoding also accepts the URL-safe alphabet and missing padding.
+
+use crate::error::Error;
+
+/// The standard base64 alphabet
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Enum to represent how a text is framed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Encoding {
+    /// The text as it is
+    Plain,
+    /// Two lowercase hex digits per byte
+    Hex,
+    /// Base64 with padding
+    Base64,
+}
+
+/// Parses an encoding setting such as `hex`.
+///
+/// # Arguments
+///
+/// * `spec` - One of `plain`, `hex` or `base64`.
+///
+/// # Returns
+///
+/// * The encoding, or a usage error for any other value.
+pub fn parse(spec: &str) -> Result<Encoding, Error> {
+    match spec {
+        "plain" => Ok(Encoding::Plain),
+        "hex" => Ok(Encoding::Hex),
+        "base64" => Ok(Encoding::Base64),
+        _ => Err(Error::Usage(format!(
+            "invalid encoding '{}': expected 'plain', 'hex' or 'base64'",
+            spec
+        ))),
+    }
+}
+
+impl Encoding {
+    /// Encodes bytes in the encoding.
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Plain => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+            Encoding::Base64 => bytes
+                .chunks(3)
+                .flat_map(|chunk| {
+                    let n: u32 = chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+                    // One letter per 6 bits, padded to 4 letters
+                    (0..4).map(move |i| {
+                        if i <= chunk.len() {
+                            char::from(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize])
+                        } else {
+                            '='
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Decodes a text in the encoding. Whitespace is ignored by hex and base64.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded bytes, or an invalid input error if the text is not
+    ///   valid in the encoding.
+    pub fn decode(self, text: &str) -> Result<Vec<u8>, Error> {
+        let digits = text.chars().filter(|c| !c.is_whitespace());
+        match self {
+            Encoding::Plain => Ok(text.as_bytes().to_vec()),
+            Encoding::Hex => {
+                let values: Vec<u32> = digits
+                    .map(|c| c.to_digit(16))
+                    .collect::<Option<Vec<u32>>>()
+                    .filter(|values| values.len().is_multiple_of(2))
+                    .ok_or_else(|| {
+                        Error::InvalidInput(String::from("the input is not valid hex"))
+                    })?;
+                Ok(values
+                    .chunks(2)
+                    .map(|pair| (pair[0] << 4 | pair[1]) as u8)
+                    .collect())
+            }
+            Encoding::Base64 => {
+                let invalid = || Error::InvalidInput(String::from("the input is not valid base64"));
+                let values: Vec<u32> = digits
+                    .collect::<String>()
+                    .trim_end_matches('=')
+                    .chars()
+                    .map(|c| match c {
+                        '-' => Some(62),
+                        '_' => Some(63),
+                        _ => BASE64
+                            .iter()
+                            .position(|&b| char::from(b) == c)
+                            .map(|i| i as u32),
+                    })
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(invalid)?;
+                if values.len() % 4 == 1 {
+                    return Err(invalid());
+                }
+                Ok(values
+                    .chunks(4)
+                    .flat_map(|chunk| {
+                        let n: u32 = chunk
+                            .iter()
+                            .enumerate()
+                            .fold(0, |n, (i, &v)| n | v << (18 - 6 * i));
+                        (0..chunk.len() - 1).map(move |i| (n >> (16 - 8 * i)) as u8)
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_and_base64() {
+        assert_eq!(Encoding::Hex.encode(b"GYIZ"), "4759495a");
+        assert_eq!(Encoding::Hex.decode("47 59 49 5A").unwrap(), b"GYIZ");
+        assert!(Encoding::Hex.decode("475").is_err());
+        assert!(Encoding::Hex.decode("4g").is_err());
+
+        for (text, encoded) in [("", ""), ("G", "Rw=="), ("GY", "R1k="), ("GYI", "R1lJ")] {
+            assert_eq!(Encoding::Base64.encode(text.as_bytes()), encoded);
+            assert_eq!(Encoding::Base64.decode(encoded).unwrap(), text.as_bytes());
+        }
+        assert_eq!(Encoding::Base64.decode("R1k").unwrap(), b"GY");
+        assert_eq!(Encoding::Base64.decode("-_8=").unwrap(), [0xfb, 0xff]);
+        assert_eq!(Encoding::Base64.encode(&[0xfb, 0xff]), "+/8=");
+        assert!(Encoding::Base64.decode("R").is_err());
+        assert!(Encoding::Base64.decode("R1k*").is_err());
+        assert!(parse("rot13").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! The `.pfr` envelope holding a ciphertext together with its settings.
+//!
+//! An envelope is a small TOML document. The header names the format
+//! version, the cipher, the fingerprint of the key table and the SHA-256
+//! checksum of the prepared plaintext, and the `[settings]` table holds
+//! every setting that decryption depends on, written with the keys of the
+//! config file:
+//!
+//! ```toml
+//! version = 1
+//! cipher = "playfair"
+//! key = "f0b7d3c4f5bd1e9d"
+//! checksum = "41cb8cd18bf97706cb3d05afbc73d33fc3779490cd5a8f0503afd3906e25f11c"
+//! ciphertext = "GYIZS COKCF BU"
+//!
+//! [settings]
+//! filler = "X"
+//! merge = "J=I"
+//! grid = "5x5"
+//! group = 5
+//! ```
+//!
+//! Decrypting an envelope takes the settings from it, so only the key has
+//! to be remembered, and checks the decrypted letters against the checksum.
+//! Envelopes written with `--with-mask` also hold a `[mask]` table restoring
+//! the plaintext exactly, see the `mask` module.
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::mask::Mask;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The envelope version this build writes and reads
+pub const VERSION: u32 = 1;
+
+/// A ciphertext with the settings it was encrypted with
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Envelope {
+    /// The version of the envelope format
+    pub version: u32,
+    /// The cipher variant, e.g. `playfair-columnar`
+    pub cipher: String,
+    /// The fingerprint of the key table
+    pub key: String,
+    /// The SHA-256 digest of the prepared plaintext, fillers included, as hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// The encrypted text, grouped as it was written
+    pub ciphertext: String,
+    /// The settings decryption needs, as in the config file
+    pub settings: Config,
+    /// The case, punctuation and fillers of the plaintext, if recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<Mask>,
+}
+
+/// Computes the checksum of prepared letters.
+///
+/// # Returns
+///
+/// * The SHA-256 digest of the letters, as hex.
+pub fn checksum(letters: &str) -> String {
+    Sha256::digest(letters.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Checks decrypted letters against the checksum of an envelope.
+///
+/// # Arguments
+///
+/// * `envelope` - The envelope that was decrypted.
+/// * `letters` - The decrypted letters, fillers included.
+///
+/// # Returns
+///
+/// * An invalid input error if the envelope has a checksum and the letters
+///   don't match it.
+pub fn verify(envelope: &Envelope, letters: &str) -> Result<(), Error> {
+    match &envelope.checksum {
+        Some(expected) if *expected != checksum(letters) => Err(Error::InvalidInput(String::from(
+            "the decrypted text doesn't match the envelope checksum: \
+             the ciphertext is corrupt or the transposition key is wrong",
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Parses the contents of an envelope.
+///
+/// # Arguments
+///
+/// * `contents` - The TOML text of the envelope.
+///
+/// # Returns
+///
+/// * The envelope, or an invalid input error if it can't be parsed or comes
+///   from another version of the format.
+pub fn parse(contents: &str) -> Result<Envelope, Error> {
+    let envelope: Envelope = toml::from_str(contents)
+        .map_err(|e| Error::InvalidInput(format!("invalid envelope: {}", e)))?;
+    if envelope.version != VERSION {
+        return Err(Error::InvalidInput(format!(
+            "unsupported envelope version {}: expected {}",
+            envelope.version, VERSION
+        )));
+    }
+    Ok(envelope)
+}
+
+/// Reads an envelope file.
+///
+/// # Returns
+///
+/// * The envelope, an I/O error if the file can't be read, or an invalid
+///   input error if it isn't a valid envelope.
+pub fn read(path: &str) -> Result<Envelope, Error> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read envelope '{}': {}", path, e)))?;
+    parse(&contents)
+}
+
+/// Writes an envelope file, replacing any previous one.
+///
+/// # Returns
+///
+/// * An I/O error if the file can't be written.
+pub fn write(path: &str, envelope: &Envelope) -> Result<(), Error> {
+    let contents: String = toml::to_string(envelope).map_err(|e| Error::Internal(e.to_string()))?;
+    std::fs::write(path, contents)
+        .map_err(|e| Error::Io(format!("cannot write envelope '{}': {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let envelope: Envelope = Envelope {
+            version: VERSION,
+            cipher: String::from("playfair"),
+            key: String::from("f0b7d3c4f5bd1e9d"),
+            checksum: Some(checksum("HELXLOWORLDX")),
+            ciphertext: String::from("GYIZS COKCF BU"),
+            settings: Config {
+                filler: Some('X'),
+                merge: Some(String::from("J=I")),
+                group: Some(5),
+                ..Config::default()
+            },
+            mask: None,
+        };
+        let contents: String = toml::to_string(&envelope).unwrap();
+        assert!(
+            contents.contains("\n[settings]\nfiller = \"X\"\n"),
+            "{}",
+            contents
+        );
+        assert_eq!(parse(&contents), Ok(envelope));
+        let envelope: Envelope = parse(&contents).unwrap();
+        assert_eq!(
+            envelope.checksum.as_deref(),
+            Some("41cb8cd18bf97706cb3d05afbc73d33fc3779490cd5a8f0503afd3906e25f11c")
+        );
+        assert!(verify(&envelope, "HELXLOWORLDX").is_ok());
+        assert!(verify(&envelope, "HELXLOWORLDQ").is_err());
+
+        let newer: String = contents.replace("version = 1", "version = 2");
+        assert!(parse(&newer).is_err());
+        assert!(parse("version = 1\n").is_err());
+    }
+}
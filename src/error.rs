@@ -0,0 +1,118 @@
+//! Error type of the command-line tool and the exit code of each failure.
+//!
+//! | Code | Meaning                                         |
+//! |------|-------------------------------------------------|
+//! | 0    | Success                                         |
+//! | 1    | Internal error (a bug in playfair)              |
+//! | 2    | Usage error: bad flags, settings or config file |
+//! | 3    | Invalid key                                     |
+//! | 4    | Invalid input text                              |
+//! | 5    | I/O error reading or writing a file             |
+//! | 6    | Cracking finished without a key                 |
+//!
+//! Output cut short by its reader, as in `playfair keygen | head -1`, is not
+//! a failure: the tool stops quietly with code 0.
+
+use std::fmt;
+
+/// Exit code for internal errors
+pub const EXIT_INTERNAL: i32 = 1;
+/// Exit code for invalid flags, settings or config files
+pub const EXIT_USAGE: i32 = 2;
+/// Exit code for an unusable key
+pub const EXIT_INVALID_KEY: i32 = 3;
+/// Exit code for unusable input text
+pub const EXIT_INVALID_INPUT: i32 = 4;
+/// Exit code for I/O failures
+pub const EXIT_IO: i32 = 5;
+/// Exit code for a crack that found no key
+pub const EXIT_CRACK_NOT_FOUND: i32 = 6;
+
+/// Enum to represent the ways the tool can fail
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A bug: something that should not be possible happened
+    Internal(String),
+    /// Invalid flags, settings or config file
+    Usage(String),
+    /// The key is missing or unusable
+    InvalidKey(String),
+    /// The input text can't be processed
+    InvalidInput(String),
+    /// Reading or writing a file failed
+    Io(String),
+    /// Cracking finished without a key that decrypts to English
+    CrackNotFound(String),
+    /// The reader of standard output closed it before the output ended
+    BrokenPipe,
+}
+
+impl Error {
+    /// Returns the process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Internal(_) => EXIT_INTERNAL,
+            Error::Usage(_) => EXIT_USAGE,
+            Error::InvalidKey(_) => EXIT_INVALID_KEY,
+            Error::InvalidInput(_) => EXIT_INVALID_INPUT,
+            Error::Io(_) => EXIT_IO,
+            Error::CrackNotFound(_) => EXIT_CRACK_NOT_FOUND,
+            Error::BrokenPipe => 0,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    /// Converts a failed write to standard output.
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::BrokenPipe => Error::BrokenPipe,
+            _ => Error::Io(format!("cannot write output: {}", e)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Internal(message) => write!(f, "internal error: {}", message),
+            Error::Usage(message)
+            | Error::InvalidKey(message)
+            | Error::InvalidInput(message)
+            | Error::Io(message)
+            | Error::CrackNotFound(message) => write!(f, "{}", message),
+            Error::BrokenPipe => write!(f, "output closed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors: Vec<Error> = vec![
+            Error::Internal(String::new()),
+            Error::Usage(String::new()),
+            Error::InvalidKey(String::new()),
+            Error::InvalidInput(String::new()),
+            Error::Io(String::new()),
+            Error::CrackNotFound(String::new()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(Error::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0));
+    }
+
+    #[test]
+    fn test_broken_pipe_is_quiet() {
+        let closed: Error = std::io::Error::from(std::io::ErrorKind::BrokenPipe).into();
+        assert_eq!(closed, Error::BrokenPipe);
+        assert_eq!(closed.exit_code(), 0);
+        let full: Error = std::io::Error::from(std::io::ErrorKind::StorageFull).into();
+        assert!(matches!(full, Error::Io(_)));
+    }
+}
@@ -0,0 +1,213 @@
+//! The `table` subcommand, printing the key table or exporting it for
+//! handouts.
+//!
+//! `--export latex` writes a `tabular` environment, `--export html` a
+//! styled `<table>` and `--export svg` a standalone image, each with one
+//! cell per symbol of the table. Characters with a meaning in the format,
+//! such as `&` or `<`, are escaped.
+//!
+//! `--stats` adds the structure of the table below it: how far its symbols
+//! sit from alphabetical order, its runs of consecutive symbols, its
+//! permutation entropy and the shortest keyword that fills it.
+
+use crate::error::Error;
+use crate::structure::{self, Structure};
+use clap::ArgMatches;
+
+/// Side of an SVG cell, in pixels
+const SVG_CELL: usize = 48;
+
+/// Enum to represent the format a table is exported in
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Export {
+    Latex,
+    Html,
+    Svg,
+}
+
+/// Parses an export format such as `svg`.
+///
+/// # Returns
+///
+/// * The format, or a usage error for any other value.
+pub fn parse(spec: &str) -> Result<Export, Error> {
+    match spec {
+        "latex" => Ok(Export::Latex),
+        "html" => Ok(Export::Html),
+        "svg" => Ok(Export::Svg),
+        _ => Err(Error::Usage(format!(
+            "invalid export format '{}': expected 'latex', 'html' or 'svg'",
+            spec
+        ))),
+    }
+}
+
+/// Escapes a symbol for LaTeX.
+fn latex_escape(c: char) -> String {
+    match c {
+        '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{}", c),
+        '~' => String::from("\\textasciitilde{}"),
+        '^' => String::from("\\textasciicircum{}"),
+        '\\' => String::from("\\textbackslash{}"),
+        '<' => String::from("\\textless{}"),
+        '>' => String::from("\\textgreater{}"),
+        '|' => String::from("\\textbar{}"),
+        _ => c.to_string(),
+    }
+}
+
+/// Escapes a symbol for HTML and SVG.
+pub fn xml_escape(c: char) -> String {
+    match c {
+        '&' => String::from("&amp;"),
+        '<' => String::from("&lt;"),
+        '>' => String::from("&gt;"),
+        '"' => String::from("&quot;"),
+        _ => c.to_string(),
+    }
+}
+
+/// Writes a table as a LaTeX `tabular` with ruled cells.
+pub fn latex(table: &[Vec<char>]) -> String {
+    let columns: usize = table[0].len();
+    let mut latex: String = format!("\\begin{{tabular}}{{|{}}}\n\\hline\n", "c|".repeat(columns));
+    for row in table {
+        let cells: Vec<String> = row.iter().map(|&c| latex_escape(c)).collect();
+        latex.push_str(&format!("{} \\\\ \\hline\n", cells.join(" & ")));
+    }
+    latex.push_str("\\end{tabular}\n");
+    latex
+}
+
+/// Writes a table as an HTML `<table>` preceded by its style.
+pub fn html(table: &[Vec<char>]) -> String {
+    let mut html: String = String::from(
+        "<style>\n\
+         table.playfair { border-collapse: collapse; font: 1.5em monospace; }\n\
+         table.playfair td { border: 1px solid #000; width: 2em; height: 2em; text-align: center; }\n\
+         </style>\n\
+         <table class=\"playfair\">\n",
+    );
+    for row in table {
+        let cells: String = row
+            .iter()
+            .map(|&c| format!("<td>{}</td>", xml_escape(c)))
+            .collect();
+        html.push_str(&format!("  <tr>{}</tr>\n", cells));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Writes a table as a standalone SVG image.
+pub fn svg(table: &[Vec<char>]) -> String {
+    let (width, height) = (table[0].len() * SVG_CELL, table.len() * SVG_CELL);
+    // One pixel of margin keeps the outer lines whole
+    let mut svg: String = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"-1 -1 {w} {h}\">\n\
+         <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"#fff\" stroke=\"#000\" stroke-width=\"2\"/>\n",
+        width,
+        height,
+        w = width + 2,
+        h = height + 2
+    );
+    for (i, row) in table.iter().enumerate() {
+        for (j, &c) in row.iter().enumerate() {
+            let (x, y) = (j * SVG_CELL, i * SVG_CELL);
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"none\" stroke=\"#000\"/>\n\
+                 <text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"28\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                x,
+                y,
+                x + SVG_CELL / 2,
+                y + SVG_CELL / 2,
+                xml_escape(c),
+                s = SVG_CELL
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Runs the `table` subcommand.
+///
+/// Without `--export` the table is printed row by row, and its fingerprint
+/// goes to stderr.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the key or the variant is invalid.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    let key: String = crate::cli::key_from_matches(matches, crate::CipherMode::Encrypt, &variant)?;
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(&key, &variant);
+    match matches
+        .get_one::<String>("export")
+        .map(|spec| parse(spec))
+        .transpose()?
+    {
+        Some(Export::Latex) => print!("{}", latex(&table)),
+        Some(Export::Html) => print!("{}", html(&table)),
+        Some(Export::Svg) => print!("{}", svg(&table)),
+        None => {
+            for row in &table {
+                let cells: Vec<String> = row.iter().map(char::to_string).collect();
+                println!("{}", cells.join(" "));
+            }
+            eprintln!("Key Fingerprint: {}", crate::table_fingerprint(&table));
+            if matches.get_flag("stats") {
+                print_structure(&structure::measure(&table, &variant));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the structure of a table to stdout.
+fn print_structure(structure: &Structure) {
+    println!(
+        "Displacement: {:.2} cells from alphabetical order on average ({:.2} for a shuffled table), {} in place",
+        structure.displacement, structure.random_displacement, structure.fixed
+    );
+    let runs: Vec<String> = structure.runs.iter().map(usize::to_string).collect();
+    println!(
+        "Runs of consecutive symbols: {} (longest {})",
+        if runs.is_empty() {
+            String::from("none")
+        } else {
+            runs.join(", ")
+        },
+        structure.longest_run()
+    );
+    println!(
+        "Permutation entropy: {:.3} (0 in alphabetical order, near 1 shuffled)",
+        structure.entropy
+    );
+    println!("Alphabetical tail: {} cells", structure.tail);
+    println!("Shortest keyword: {}", structure.keyword);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exports() {
+        let table: Vec<Vec<char>> = vec![vec!['K', '&'], vec!['<', '_']];
+        assert_eq!(
+            latex(&table),
+            "\\begin{tabular}{|c|c|}\n\\hline\nK & \\& \\\\ \\hline\n\\textless{} & \\_ \\\\ \\hline\n\\end{tabular}\n"
+        );
+        assert!(html(&table).contains("  <tr><td>&lt;</td><td>_</td></tr>\n"));
+        let svg: String = svg(&table);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"98\""));
+        assert_eq!(svg.matches("<text").count(), 4);
+        assert!(svg.contains(">&amp;</text>"));
+        assert!(parse("pdf").is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Git clean/smudge filter storing files encrypted in the repository.
+//!
+//! Wired up through `.gitattributes`, `--clean` encrypts the working tree
+//! file on its way into the repository and `--smudge` restores it on
+//! checkout. The Playfair cipher itself loses case, punctuation and the
+//! positions of inserted fillers, so the cleaned form carries what is needed
+//! to restore the file byte for byte:
+//!
+//! ```text
+//! playfair-filter 1 key=f0b7d3c4f5bd1e9d merge=J=I filler=X fillers=3,11
+//! GYIZSCOKCFBU
+//! Aaaaa aaaaa!
+//! ```
+//!
+//! The first line names the format, the key fingerprint, the variant and the
+//! filler positions; the second holds the ciphertext; the rest is the layout
+//! of the original text, see the `mask` module. Only the layouts of custom
+//! alphabets, which may not contain the placeholder letters, are escaped.
+//! The output is deterministic, so unchanged files never show up as modified,
+//! and cleaning a file already in the cleaned form leaves it as it is.
+
+use crate::error::Error;
+use crate::mask;
+use crate::{Alphabet, CipherMode, Variant};
+use clap::ArgMatches;
+use std::io::{Read, Write};
+
+/// First word of every cleaned file
+const MAGIC: &str = "playfair-filter";
+/// Version of the cleaned format
+const VERSION: &str = "1";
+
+/// Checks whether a file is already in the cleaned form, by its header.
+fn is_cleaned(text: &str) -> bool {
+    let header: &str = text.lines().next().unwrap_or("");
+    let mut words = header.split(' ');
+    words.next() == Some(MAGIC)
+        && words.next() == Some(VERSION)
+        && header.contains(" key=")
+        && header.contains(" fillers=")
+}
+
+/// Encrypts a working tree file into its repository form.
+///
+/// A file already in that form, as git may hand over when a file was
+/// committed cleaned, is returned unchanged.
+///
+/// # Arguments
+///
+/// * `text` - The file contents.
+/// * `key` - The key to encrypt with.
+/// * `variant` - The variant to encrypt with, recorded in the header.
+///
+/// # Returns
+///
+/// * The cleaned file contents.
+pub fn clean(text: &str, key: &str, variant: &Variant) -> String {
+    if is_cleaned(text) {
+        return text.to_string();
+    }
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(key, variant);
+    let (chars, fillers) = crate::prepare_letters(text, variant, CipherMode::Encrypt);
+    let ciphertext: String = crate::process_letters(&chars, &table, variant, CipherMode::Encrypt);
+    // A custom alphabet may leave out the placeholder letters themselves
+    let escaping: bool = matches!(variant.alphabet, Alphabet::Custom(_));
+    let layout: String = mask::layout(text, variant, escaping);
+
+    // Files cleaned with the default grid keep the header of older versions
+    let grid: String = match &variant.alphabet {
+        Alphabet::Letters => String::new(),
+        Alphabet::Alphanumeric => format!(" grid={}", variant.grid()),
+        Alphabet::Custom(symbols) => format!(" alphabet={}", symbols.iter().collect::<String>()),
+    };
+    let route: String = match variant.route {
+        crate::Route::Rows => String::new(),
+        route => format!(" route={}", route.name()),
+    };
+    let rounds: String = match variant.rounds {
+        1 => String::new(),
+        rounds => format!(" rounds={}", rounds),
+    };
+    let period: String = match variant.period {
+        None => String::new(),
+        Some(period) => format!(" period={}", period),
+    };
+    let (reduction, value) = variant.reduction();
+    format!(
+        "{} {} key={} {}={}{}{}{}{} filler={} fillers={}\n{}\n{}",
+        MAGIC,
+        VERSION,
+        crate::table_fingerprint(&table),
+        reduction,
+        value,
+        grid,
+        route,
+        rounds,
+        period,
+        variant.filler,
+        fillers
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<String>>()
+            .join(","),
+        ciphertext,
+        layout
+    )
+}
+
+/// Restores a working tree file from its repository form.
+///
+/// Files that are not in the cleaned format, such as files committed before
+/// the filter was set up, are returned unchanged.
+///
+/// # Arguments
+///
+/// * `data` - The repository contents.
+/// * `key` - The key to decrypt with.
+///
+/// # Returns
+///
+/// * The original file contents, an invalid key error if the key fingerprint
+///   doesn't match, or an invalid input error if the contents are corrupt.
+pub fn smudge(data: &str, key: &str) -> Result<String, Error> {
+    if !data.starts_with(MAGIC) {
+        return Ok(data.to_string());
+    }
+    let corrupt = |what: &str| Error::InvalidInput(format!("corrupt filtered file: {}", what));
+    let (header, rest) = data
+        .split_once('\n')
+        .ok_or_else(|| corrupt("no ciphertext"))?;
+    let (ciphertext, layout) = rest.split_once('\n').ok_or_else(|| corrupt("no layout"))?;
+
+    let mut words = header.split(' ');
+    if words.next() != Some(MAGIC) || words.next() != Some(VERSION) {
+        return Err(corrupt("unsupported header"));
+    }
+    let (mut fingerprint, mut fillers) = (None, Vec::new());
+    let mut variant: Variant = Variant::default();
+    for word in words {
+        match word.split_once('=') {
+            Some(("key", value)) => fingerprint = Some(value),
+            Some(("merge", value)) => variant.merge = crate::parse_merge(value)?,
+            Some(("omit", value)) => variant.merge = crate::parse_omit(value)?,
+            Some(("grid", value)) => variant.alphabet = crate::parse_grid(value)?,
+            Some(("alphabet", value)) => variant.alphabet = crate::parse_alphabet(value)?,
+            Some(("route", value)) => variant.route = crate::parse_route(value)?,
+            Some(("period", value)) => {
+                variant.period = Some(
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| corrupt("bad period"))?,
+                );
+            }
+            Some(("rounds", value)) => {
+                variant.rounds = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| corrupt("bad rounds"))?;
+            }
+            Some(("filler", value)) => {
+                variant.filler = value.chars().next().ok_or_else(|| corrupt("no filler"))?;
+            }
+            Some(("fillers", "")) => {}
+            Some(("fillers", value)) => {
+                fillers = value
+                    .split(',')
+                    .map(|n| {
+                        n.parse::<usize>()
+                            .map_err(|_| corrupt("bad filler position"))
+                    })
+                    .collect::<Result<Vec<usize>, Error>>()?;
+            }
+            _ => return Err(corrupt("unknown header field")),
+        }
+    }
+
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(key, &variant);
+    if fingerprint != Some(crate::table_fingerprint(&table).as_str()) {
+        return Err(Error::InvalidKey(String::from(
+            "the key does not match the one the file was encrypted with",
+        )));
+    }
+    let cipher_chars: Vec<char> = ciphertext.chars().collect();
+    if !cipher_chars.len().is_multiple_of(2)
+        || cipher_chars
+            .iter()
+            .any(|&c| crate::normalize_char(c, &variant) != Some(c))
+    {
+        return Err(corrupt("bad ciphertext"));
+    }
+    let prepared: Vec<char> =
+        crate::process_letters(&cipher_chars, &table, &variant, CipherMode::Decrypt)
+            .chars()
+            .collect();
+    let letters: Vec<char> = mask::without_fillers(&prepared, &fillers).map_err(corrupt)?;
+    let escaping: bool = matches!(variant.alphabet, Alphabet::Custom(_));
+    mask::restore(layout, letters, &variant, escaping).map_err(corrupt)
+}
+
+/// Runs the `filter` subcommand, filtering stdin to stdout.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the key is missing or the input can't be filtered.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let smudging: bool = matches.get_flag("smudge");
+    let (mut variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    // Spelled-out letters (e.g. ß as SS) and skipped repeats could not be
+    // restored on checkout
+    variant.locale = None;
+    if variant.doubles == crate::Doubles::Skip {
+        variant.doubles = crate::Doubles::Digram;
+    }
+    let mode: CipherMode = if smudging {
+        CipherMode::Decrypt
+    } else {
+        CipherMode::Encrypt
+    };
+    let key: String = crate::cli::key_from_matches(matches, mode, &variant)?;
+
+    let mut input: String = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| Error::Io(format!("cannot read stdin: {}", e)))?;
+    let output: String = if smudging {
+        smudge(&input, &key)?
+    } else {
+        if !is_cleaned(&input) {
+            crate::check_doubles(&input, &variant)?;
+        }
+        clean(&input, &key, &variant)
+    };
+    std::io::stdout()
+        .write_all(output.as_bytes())
+        .map_err(|e| Error::Io(format!("cannot write stdout: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_format() {
+        let cleaned: String = clean("Hello world!", "KEYWORD", &Variant::default());
+        assert_eq!(
+            cleaned,
+            "playfair-filter 1 key=f0b7d3c4f5bd1e9d merge=J=I filler=X fillers=3,11\nGYIZSCOKCFBU\nAaaaa aaaaa!"
+        );
+    }
+
+    #[test]
+    fn test_clean_smudge_round_trip() {
+        let variant: Variant = Variant::default();
+        for text in [
+            "Hello world!\n",
+            "Jolly JJ balloon\n\tX marks the spot\n",
+            "",
+            "1234 - no letters\n",
+            "Ünïcode stays\n",
+        ] {
+            let cleaned: String = clean(text, "KEYWORD", &variant);
+            assert_eq!(cleaned, clean(text, "KEYWORD", &variant));
+            assert_eq!(clean(&cleaned, "KEYWORD", &variant), cleaned);
+            assert_eq!(smudge(&cleaned, "KEYWORD").unwrap(), text);
+        }
+
+        let variant: Variant = Variant {
+            alphabet: Alphabet::Alphanumeric,
+            ..Variant::default()
+        };
+        let cleaned: String = clean("Jam at 10pm\n", "KEYWORD", &variant);
+        assert!(cleaned.contains(" grid=6x6 "));
+        assert_eq!(smudge(&cleaned, "KEYWORD").unwrap(), "Jam at 10pm\n");
+
+        let variant: Variant = Variant {
+            route: crate::Route::Spiral,
+            rounds: 2,
+            period: Some(4),
+            ..Variant::default()
+        };
+        let cleaned: String = clean("Jam at 10pm\n", "KEYWORD", &variant);
+        assert!(cleaned.contains(" route=spiral rounds=2 period=4 "));
+        assert_eq!(smudge(&cleaned, "KEYWORD").unwrap(), "Jam at 10pm\n");
+
+        let variant: Variant = Variant {
+            alphabet: crate::parse_alphabet("BCDEFGHIJKLNOPQRSTUVWXYZÄ").unwrap(),
+            ..Variant::default()
+        };
+        let text: &str = "Märchen am Main \\o/\n";
+        let cleaned: String = clean(text, "KÄSE", &variant);
+        assert!(cleaned.contains(" alphabet=BCDEFGHIJKLNOPQRSTUVWXYZÄ "));
+        assert_eq!(smudge(&cleaned, "KÄSE").unwrap(), text);
+    }
+
+    #[test]
+    fn test_clean_is_idempotent() {
+        let variant: Variant = Variant {
+            route: crate::Route::Spiral,
+            period: Some(4),
+            ..Variant::default()
+        };
+        let cleaned: String = clean("Jolly JJ balloon\n", "KEYWORD", &variant);
+        let twice: String = clean(&cleaned, "KEYWORD", &variant);
+        assert_eq!(twice, cleaned);
+        assert_eq!(smudge(&twice, "KEYWORD").unwrap(), "Jolly JJ balloon\n");
+        // Text that only mentions the format is still encrypted
+        let prose: &str = "playfair-filter 1 is the format of cleaned files\n";
+        assert_ne!(clean(prose, "KEYWORD", &variant), prose);
+    }
+
+    #[test]
+    fn test_smudge_rejects_wrong_key_and_passes_plain_files() {
+        let cleaned: String = clean("Secret", "KEYWORD", &Variant::default());
+        assert!(matches!(
+            smudge(&cleaned, "OTHER"),
+            Err(Error::InvalidKey(_))
+        ));
+        assert_eq!(
+            smudge("not filtered\n", "KEYWORD").unwrap(),
+            "not filtered\n"
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Scoring of key tables on the GPU, for the `crack` solvers that score
+//! whole batches of tables at once.
+//!
+//! Built with `--features gpu`, a wgpu compute shader decrypts the
+//! ciphertext with every table of a batch and sums the n-gram scores of the
+//! decryption, one table per invocation. Without the feature, or without a
+//! usable adapter, [`Scorer::new`] fails and the search scores on the CPU.
+//! The shader adds up `f32` scores, so its sums may differ from the `f64`
+//! ones of the CPU in the last digits.
+
+/// The compute shader, mirroring `Search::score` of the analysis module
+#[cfg(feature = "gpu")]
+const SHADER: &str = r#"
+@group(0) @binding(0) var<storage, read> params: array<u32>;
+@group(0) @binding(1) var<storage, read> scores: array<f32>;
+@group(0) @binding(2) var<storage, read> letters: array<u32>;
+@group(0) @binding(3) var<storage, read> ciphertext: array<u32>;
+@group(0) @binding(4) var<storage, read> tables: array<u32>;
+@group(0) @binding(5) var<storage, read_write> results: array<f32>;
+
+@compute @workgroup_size(64)
+fn score(@builtin(global_invocation_id) id: vec3<u32>) {
+    let table = id.x;
+    if (table >= params[0]) {
+        return;
+    }
+    let cells = params[1];
+    let rows = params[2];
+    let cols = params[3];
+    let pairs = params[4];
+    let length = params[5];
+    let base = table * cells;
+    var positions: array<u32, 64>;
+    for (var cell = 0u; cell < cells; cell++) {
+        positions[tables[base + cell]] = cell;
+    }
+    var modulus = 1u;
+    for (var i = 1u; i < length; i++) {
+        modulus = modulus * 26u;
+    }
+    var index = 0u;
+    var run = 0u;
+    var total = 0.0;
+    for (var pair = 0u; pair < pairs; pair++) {
+        let p1 = positions[ciphertext[2u * pair]];
+        let p2 = positions[ciphertext[2u * pair + 1u]];
+        let r1 = p1 / cols;
+        let c1 = p1 % cols;
+        let r2 = p2 / cols;
+        let c2 = p2 % cols;
+        var a: u32;
+        var b: u32;
+        if (r1 == r2) {
+            a = r1 * cols + (c1 + cols - 1u) % cols;
+            b = r2 * cols + (c2 + cols - 1u) % cols;
+        } else if (c1 == c2) {
+            a = (r1 + rows - 1u) % rows * cols + c1;
+            b = (r2 + rows - 1u) % rows * cols + c2;
+        } else {
+            a = r1 * cols + c2;
+            b = r2 * cols + c1;
+        }
+        for (var k = 0u; k < 2u; k++) {
+            let letter = letters[tables[base + select(b, a, k == 0u)]];
+            if (letter >= 26u) {
+                run = 0u;
+                index = 0u;
+            } else {
+                index = index % modulus * 26u + letter;
+                run = run + 1u;
+                if (run >= length) {
+                    total = total + scores[index];
+                }
+            }
+        }
+    }
+    results[table] = total;
+}
+"#;
+
+/// Most cells a table may have on the GPU, the size of the shader's
+/// position array
+#[cfg(feature = "gpu")]
+const MAX_CELLS: usize = 64;
+
+/// Most tables scored by one dispatch
+#[cfg(feature = "gpu")]
+const BATCH: usize = 65_536;
+
+/// A GPU device ready to score tables for one ciphertext
+#[cfg(feature = "gpu")]
+pub struct Scorer {
+    /// The device and its queue
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    /// The compute pipeline of the shader
+    pipeline: wgpu::ComputePipeline,
+    /// The n-gram scores, the letter of every symbol and the ciphertext
+    scores: wgpu::Buffer,
+    letters: wgpu::Buffer,
+    ciphertext: wgpu::Buffer,
+    /// Cells, rows, columns, digrams and n-gram length
+    shape: [u32; 5],
+    /// The name of the adapter, for the user
+    pub name: String,
+}
+
+/// Stands in for the GPU scorer when the `gpu` feature is off
+#[cfg(not(feature = "gpu"))]
+pub struct Scorer {
+    /// The name of the adapter, for the user
+    pub name: String,
+    /// Makes the stand-in impossible to build
+    never: std::convert::Infallible,
+}
+
+/// Converts numbers to the little-endian bytes of a buffer.
+#[cfg(feature = "gpu")]
+fn bytes<T: Copy, const N: usize>(values: &[T], to_bytes: fn(T) -> [u8; N]) -> Vec<u8> {
+    values.iter().flat_map(|&value| to_bytes(value)).collect()
+}
+
+impl Scorer {
+    /// Opens the GPU and uploads the ciphertext and the n-gram scores.
+    ///
+    /// # Arguments
+    ///
+    /// * `scores` - The log probability of every n-gram, by base-26 index.
+    /// * `length` - How many letters every n-gram holds.
+    /// * `letters` - The letter index of every symbol, 26 if it is no letter.
+    /// * `ciphertext` - The ciphertext as symbol indices, of even length.
+    /// * `shape` - Rows and columns of the table.
+    ///
+    /// # Returns
+    ///
+    /// * The scorer, or the reason the GPU can't be used.
+    #[cfg(feature = "gpu")]
+    pub fn new(
+        scores: &[f64],
+        length: usize,
+        letters: &[u8],
+        ciphertext: &[usize],
+        shape: (usize, usize),
+    ) -> Result<Self, String> {
+        use wgpu::util::DeviceExt;
+
+        let (rows, cols) = shape;
+        if rows * cols > MAX_CELLS {
+            return Err(format!(
+                "the GPU scores tables of at most {} cells, not {}",
+                MAX_CELLS,
+                rows * cols
+            ));
+        }
+        let instance: wgpu::Instance = wgpu::Instance::default();
+        let adapter: wgpu::Adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            }))
+            .map_err(|e| format!("no GPU adapter found: {}", e))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("playfair"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .map_err(|e| format!("cannot open the GPU: {}", e))?;
+        let module: wgpu::ShaderModule =
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("score"),
+                source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            });
+        let pipeline: wgpu::ComputePipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("score"),
+                layout: None,
+                module: &module,
+                entry_point: Some("score"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+        let upload = |label: &str, contents: &[u8]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        };
+        let scores_f32: Vec<f32> = scores.iter().map(|&score| score as f32).collect();
+        let letters_u32: Vec<u32> = letters.iter().map(|&letter| u32::from(letter)).collect();
+        let ciphertext_u32: Vec<u32> = ciphertext.iter().map(|&symbol| symbol as u32).collect();
+        Ok(Scorer {
+            scores: upload("scores", &bytes(&scores_f32, f32::to_le_bytes)),
+            letters: upload("letters", &bytes(&letters_u32, u32::to_le_bytes)),
+            ciphertext: upload("ciphertext", &bytes(&ciphertext_u32, u32::to_le_bytes)),
+            shape: [
+                (rows * cols) as u32,
+                rows as u32,
+                cols as u32,
+                (ciphertext.len() / 2) as u32,
+                length as u32,
+            ],
+            name: adapter.get_info().name,
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Stands in for opening the GPU without the `gpu` feature.
+    ///
+    /// # Returns
+    ///
+    /// * Always an error asking for a build with the feature.
+    #[cfg(not(feature = "gpu"))]
+    pub fn new(
+        _scores: &[f64],
+        _length: usize,
+        _letters: &[u8],
+        _ciphertext: &[usize],
+        _shape: (usize, usize),
+    ) -> Result<Self, String> {
+        Err(String::from(
+            "playfair was built without GPU support; rebuild it with --features gpu",
+        ))
+    }
+
+    /// Scores a batch of tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `tables` - The symbol index of every cell of every table.
+    ///
+    /// # Returns
+    ///
+    /// * The n-gram score of the decryption with every table, or the reason
+    ///   the GPU failed.
+    #[cfg(feature = "gpu")]
+    pub fn score(&self, tables: &[Vec<usize>]) -> Result<Vec<f64>, String> {
+        let mut results: Vec<f64> = Vec::with_capacity(tables.len());
+        for batch in tables.chunks(BATCH) {
+            results.extend(self.dispatch(batch)?);
+        }
+        Ok(results)
+    }
+
+    /// Stands in for scoring without the `gpu` feature; never called.
+    #[cfg(not(feature = "gpu"))]
+    pub fn score(&self, _tables: &[Vec<usize>]) -> Result<Vec<f64>, String> {
+        match self.never {}
+    }
+
+    /// Scores one dispatch of at most `BATCH` tables.
+    #[cfg(feature = "gpu")]
+    fn dispatch(&self, tables: &[Vec<usize>]) -> Result<Vec<f64>, String> {
+        use wgpu::util::DeviceExt;
+
+        let [cells, rows, cols, pairs, length] = self.shape;
+        let params: [u32; 6] = [tables.len() as u32, cells, rows, cols, pairs, length];
+        let cells_u32: Vec<u32> = tables.iter().flatten().map(|&s| s as u32).collect();
+        let upload = |label: &str, contents: &[u8]| {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents,
+                    usage: wgpu::BufferUsages::STORAGE,
+                })
+        };
+        let params: wgpu::Buffer = upload("params", &bytes(&params, u32::to_le_bytes));
+        let cells: wgpu::Buffer = upload("tables", &bytes(&cells_u32, u32::to_le_bytes));
+        let size: u64 = (tables.len() * 4) as u64;
+        let results: wgpu::Buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("results"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging: wgpu::Buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let buffers: [&wgpu::Buffer; 6] = [
+            &params,
+            &self.scores,
+            &self.letters,
+            &self.ciphertext,
+            &cells,
+            &results,
+        ];
+        let entries: Vec<wgpu::BindGroupEntry> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group: wgpu::BindGroup =
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("score"),
+                layout: &self.pipeline.get_bind_group_layout(0),
+                entries: &entries,
+            });
+        let mut encoder: wgpu::CommandEncoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("score"),
+                });
+        {
+            let mut pass: wgpu::ComputePass =
+                encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("score"),
+                    timestamp_writes: None,
+                });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(tables.len().div_ceil(64) as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&results, 0, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        staging.map_async(wgpu::MapMode::Read, .., move |mapped| {
+            let _ = sender.send(mapped);
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| format!("the GPU failed: {}", e))?;
+        receiver
+            .recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("cannot read the scores from the GPU: {}", e))?;
+        let scores: Vec<f64> = {
+            let view = staging
+                .get_mapped_range(..)
+                .map_err(|e| format!("cannot read the scores from the GPU: {}", e))?;
+            view.chunks_exact(4)
+                .map(|b| f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+                .collect()
+        };
+        staging.unmap();
+        Ok(scores)
+    }
+}
@@ -0,0 +1,376 @@
+//! How large the keyspace of a cipher is and how much ciphertext pins
+//! down its key.
+//!
+//! A table is an arrangement of the symbols of the variant, but every
+//! cyclic shift of its rows or of its columns encrypts exactly like it in
+//! Playfair: the neighbours of a symbol to the right and below, and the
+//! corners of every rectangle, stay the same. Transposing a table is not
+//! equivalent, as it swaps the two letters a rectangle gives.
+//!
+//! The ciphers of two tables only look up rows and columns across them.
+//! The two-square cipher keeps the row of each letter in its own table and
+//! swaps columns above each other, or the other way round beside each
+//! other, so the rows of either table and the columns of both reorder
+//! freely. The double Playfair needs the neighbours in a row, leaving the
+//! cyclic shifts of the columns of either table and any order of the rows
+//! of both. The four-square cipher reads its keyed tables through fixed
+//! plain ones, and the labels of a Polybius square fix every cell, so no
+//! two of their tables are alike. A columnar transposition adds every
+//! order of its columns.
+//!
+//! The unicity distance is Shannon's estimate of the ciphertext length
+//! past which only one key decrypts to a plausible text: the bits of the
+//! keyspace divided by the redundancy of the language, the bits per letter
+//! its text wastes. The entropy of the language is read from its quadgrams,
+//! which know less of it than a reader does, so the estimate is on the
+//! cautious side.
+
+use crate::analysis::{Language, Ngrams};
+use crate::error::Error;
+use crate::squares::{self, Arrangement};
+use crate::{Variant, polybius, transposition};
+use clap::ArgMatches;
+
+/// The size of the keyspace of a cipher
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyspace {
+    /// How many symbols a table holds
+    pub symbols: usize,
+    /// The number of rows and columns of a table
+    pub shape: (usize, usize),
+    /// How many keyed tables the cipher uses
+    pub squares: usize,
+    /// The number of ways to fill them in bits, `squares` times the base 2
+    /// logarithm of `symbols!`
+    pub tables: f64,
+    /// The number of columns of the transposition, if the cipher has one
+    pub columns: Option<usize>,
+    /// The number of orders of those columns in bits
+    pub transposition: f64,
+    /// How many keys encrypt alike
+    pub equivalent: f64,
+    /// The number of distinct ciphers in bits
+    pub ciphers: f64,
+}
+
+/// Returns the base 2 logarithm of `n!`.
+fn factorial_bits(n: usize) -> f64 {
+    (2..=n).map(|k| (k as f64).log2()).sum()
+}
+
+/// Measures the keyspace of a cipher.
+///
+/// # Arguments
+///
+/// * `variant` - The variant deciding which symbols make up the tables.
+/// * `cipher` - The name of the cipher, as `--variant` takes it.
+/// * `arrangement` - How the two-square tables are placed.
+/// * `columns` - The number of columns of the transposition, if any.
+pub fn keyspace(
+    variant: &Variant,
+    cipher: &str,
+    arrangement: Arrangement,
+    columns: Option<usize>,
+) -> Keyspace {
+    let symbols: usize = variant.symbols().len();
+    let shape: (usize, usize) = variant.alphabet.dimensions();
+    let (rows, cols) = (shape.0 as f64, shape.1 as f64);
+    let orders = |n: usize| (1..=n).map(|k| k as f64).product::<f64>();
+    let (squares, equivalent): (usize, f64) = match (cipher, arrangement) {
+        ("two-square", Arrangement::Vertical) => (2, orders(shape.0).powi(2) * orders(shape.1)),
+        ("two-square", Arrangement::Horizontal) => (2, orders(shape.0) * orders(shape.1).powi(2)),
+        ("four-square", _) => (2, 1.0),
+        ("double-playfair", _) => (2, orders(shape.0) * cols * cols),
+        ("polybius" | "adfgvx", _) => (1, 1.0),
+        _ => (1, rows * cols),
+    };
+    let tables: f64 = squares as f64 * factorial_bits(symbols);
+    let transposition: f64 = columns.map_or(0.0, factorial_bits);
+    Keyspace {
+        symbols,
+        shape,
+        squares,
+        tables,
+        columns,
+        transposition,
+        equivalent,
+        ciphers: tables + transposition - equivalent.log2(),
+    }
+}
+
+/// Estimates the entropy of a language in bits per letter as the entropy
+/// of the last letter of its quadgrams given the other three.
+pub fn letter_entropy(language: Language) -> f64 {
+    let quadgrams: Vec<(String, f64)> = Ngrams::builtin(language, 4).ranked();
+    let total: f64 = quadgrams.iter().map(|(_, p)| p).sum();
+    let mut trigrams: Vec<f64> = vec![0.0; 26 * 26 * 26];
+    let mut entropy: f64 = 0.0;
+    for (quadgram, p) in &quadgrams {
+        let p: f64 = p / total;
+        let trigram: usize = quadgram
+            .bytes()
+            .take(3)
+            .fold(0, |index, letter| index * 26 + (letter - b'A') as usize);
+        trigrams[trigram] += p;
+        entropy -= p * p.log2();
+    }
+    let prefixes: f64 = trigrams
+        .iter()
+        .filter(|&&p| p > 0.0)
+        .map(|&p| -p * p.log2())
+        .sum();
+    entropy - prefixes
+}
+
+/// Estimates the unicity distance of a keyspace.
+///
+/// # Arguments
+///
+/// * `keyspace` - The keyspace of the variant.
+/// * `entropy` - The entropy of the language in bits per letter.
+///
+/// # Returns
+///
+/// * How many letters of ciphertext leave one plausible key, or `None` if
+///   the language has no redundancy over the symbols of the table.
+pub fn unicity_distance(keyspace: &Keyspace, entropy: f64) -> Option<f64> {
+    let redundancy: f64 = (keyspace.symbols as f64).log2() - entropy;
+    (redundancy > 0.0).then(|| keyspace.ciphers / redundancy)
+}
+
+/// Writes a number of bits as a power of ten, such as `1.55e25`.
+fn power(bits: f64) -> String {
+    let exponent: f64 = bits * 2f64.log10();
+    format!(
+        "{:.2}e{}",
+        10f64.powf(exponent.fract()),
+        exponent.trunc() as i64
+    )
+}
+
+/// Explains which keys of a cipher encrypt alike.
+fn equivalents(cipher: &str, arrangement: Arrangement, (rows, columns): (usize, usize)) -> String {
+    match (cipher, arrangement) {
+        ("two-square", Arrangement::Vertical) => format!(
+            "the {}! orders of the rows of either table times the {}! of the columns of both",
+            rows, columns
+        ),
+        ("two-square", Arrangement::Horizontal) => format!(
+            "the {}! orders of the rows of both tables times the {}! of the columns of either",
+            rows, columns
+        ),
+        ("four-square", _) => String::from("the plain tables tell every keyed table apart"),
+        ("double-playfair", _) => format!(
+            "the {}! orders of the rows of both tables times the {} cyclic shifts of the columns of either",
+            rows, columns
+        ),
+        ("polybius" | "adfgvx", _) => String::from("the labels tell every table apart"),
+        _ => format!(
+            "the {} cyclic shifts of the rows times the {} of the columns",
+            rows, columns
+        ),
+    }
+}
+
+/// Runs the `info` subcommand, printing the keyspace of the cipher and the
+/// unicity distance in the language to stdout.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings, the transposition key or the language are
+///   invalid.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    let language: Language = match matches.get_one::<String>("lang") {
+        Some(code) => Language::parse(code)?,
+        None => Language::English,
+    };
+    let cipher: &str = matches
+        .get_one::<String>("cipher")
+        .map_or("playfair", String::as_str);
+    let arrangement: Arrangement = match matches.get_one::<String>("arrangement") {
+        Some(_) if cipher != "two-square" => {
+            return Err(Error::Usage(String::from(
+                "--arrangement only works with the two-square variant",
+            )));
+        }
+        Some(spec) => squares::parse_arrangement(spec)?,
+        None => Arrangement::Vertical,
+    };
+    // Only the transposition key changes the size of the keyspace
+    let order: Option<Vec<usize>> = match matches.get_one::<String>("second-key") {
+        Some(second) if matches!(cipher, "playfair-columnar" | "polybius" | "adfgvx") => {
+            Some(transposition::column_order(second)?)
+        }
+        _ => None,
+    };
+    match cipher {
+        "playfair-columnar" if order.is_none() => {
+            return Err(Error::Usage(String::from(
+                "the playfair-columnar variant needs a transposition key: use -K",
+            )));
+        }
+        "polybius" | "adfgvx" => {
+            polybius::Polybius::new(cipher, "", &variant, order.clone())?;
+        }
+        _ => {}
+    }
+    let keyspace: Keyspace = keyspace(
+        &variant,
+        cipher,
+        arrangement,
+        order.map(|order| order.len()),
+    );
+    let (rows, columns) = keyspace.shape;
+    println!(
+        "Table: {} symbols in {} rows of {}{}",
+        keyspace.symbols,
+        rows,
+        columns,
+        if keyspace.squares > 1 {
+            format!(", {} keyed tables", keyspace.squares)
+        } else {
+            String::new()
+        }
+    );
+    println!(
+        "Tables: {}!{} = {} ({:.1} bits)",
+        keyspace.symbols,
+        if keyspace.squares > 1 {
+            format!("^{}", keyspace.squares)
+        } else {
+            String::new()
+        },
+        power(keyspace.tables),
+        keyspace.tables
+    );
+    if let Some(columns) = keyspace.columns {
+        println!(
+            "Transposition: {}! = {} orders of its columns ({:.1} bits)",
+            columns,
+            power(keyspace.transposition),
+            keyspace.transposition
+        );
+    }
+    if keyspace.equivalent > 1.0 {
+        println!(
+            "Equivalent tables: {} each, {}",
+            keyspace.equivalent,
+            equivalents(cipher, arrangement, keyspace.shape)
+        );
+    } else {
+        println!(
+            "Equivalent tables: none, {}",
+            equivalents(cipher, arrangement, keyspace.shape)
+        );
+    }
+    println!(
+        "Distinct keys: {} ({:.1} bits)",
+        power(keyspace.ciphers),
+        keyspace.ciphers
+    );
+    let entropy: f64 = letter_entropy(language);
+    let redundancy: f64 = (keyspace.symbols as f64).log2() - entropy;
+    println!(
+        "{}: {:.2} bits per letter from its quadgrams, {:.2} bits of redundancy",
+        language.name(),
+        entropy,
+        redundancy
+    );
+    match unicity_distance(&keyspace, entropy) {
+        // A letter becomes two labels of the ciphertext
+        Some(distance) if matches!(cipher, "polybius" | "adfgvx") => println!(
+            "Unicity distance: about {:.0} letters of plaintext, {:.0} labels of ciphertext",
+            distance.ceil(),
+            2.0 * distance.ceil()
+        ),
+        Some(distance) => {
+            println!(
+                "Unicity distance: about {:.0} letters of ciphertext",
+                distance.ceil()
+            );
+            if cipher == "playfair" {
+                println!("crack usually needs several times as many, a few hundred letters");
+            }
+        }
+        None => println!("Unicity distance: none, the language has no redundancy in this table"),
+    }
+    if cipher.starts_with("playfair") && (variant.rounds > 1 || variant.period.is_some()) {
+        println!(
+            "Rounds and periods don't change the keyspace, but a period to guess adds to the search"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyspace_and_unicity_distance() {
+        let variant: Variant = Variant::default();
+        let keyspace: Keyspace = keyspace(&variant, "playfair", Arrangement::Vertical, None);
+        assert_eq!((keyspace.symbols, keyspace.shape), (25, (5, 5)));
+        assert_eq!(keyspace.equivalent, 25.0);
+        // 25! is about 1.55e25, or 83.68 bits
+        assert!(
+            (keyspace.tables - 83.68).abs() < 0.01,
+            "{}",
+            keyspace.tables
+        );
+        assert!((keyspace.ciphers - keyspace.tables + 25f64.log2()).abs() < 1e-9);
+        assert_eq!(power(keyspace.tables), "1.55e25");
+
+        let entropy: f64 = letter_entropy(Language::English);
+        assert!(entropy > 1.0 && entropy < 4.0, "{}", entropy);
+        let distance: f64 = unicity_distance(&keyspace, entropy).unwrap();
+        assert!(distance > 15.0 && distance < 100.0, "{}", distance);
+        assert_eq!(unicity_distance(&keyspace, 25f64.log2()), None);
+    }
+
+    #[test]
+    fn test_keyspace_of_every_cipher() {
+        let variant: Variant = Variant::default();
+        let playfair: Keyspace = keyspace(&variant, "playfair", Arrangement::Vertical, None);
+        let measure = |cipher: &str, columns: Option<usize>| {
+            keyspace(&variant, cipher, Arrangement::Vertical, columns)
+        };
+
+        for cipher in ["two-square", "four-square", "double-playfair"] {
+            let keyspace: Keyspace = measure(cipher, None);
+            assert_eq!(keyspace.squares, 2);
+            assert!((keyspace.tables - 2.0 * playfair.tables).abs() < 1e-9);
+        }
+        assert_eq!(
+            measure("two-square", None).equivalent,
+            120.0 * 120.0 * 120.0
+        );
+        assert_eq!(measure("four-square", None).equivalent, 1.0);
+        assert_eq!(measure("double-playfair", None).equivalent, 120.0 * 25.0);
+
+        // The columns of the transposition add 5! orders
+        let columnar: Keyspace = measure("playfair-columnar", Some(5));
+        assert!((columnar.ciphers - playfair.ciphers - 120f64.log2()).abs() < 1e-9);
+        let polybius: Keyspace = measure("polybius", None);
+        assert_eq!((polybius.squares, polybius.equivalent), (1, 1.0));
+        assert_eq!(polybius.ciphers, playfair.tables);
+        let adfgvx: Keyspace = measure("adfgvx", Some(5));
+        assert!((adfgvx.ciphers - playfair.tables - 120f64.log2()).abs() < 1e-9);
+
+        // Beside each other, the rows of both tables move together instead
+        let variant: Variant = Variant {
+            alphabet: crate::parse_alphabet("ABCDEFGHIJKLMNOPQRSTUVWXYZ0123").unwrap(),
+            ..Variant::default()
+        };
+        assert_eq!(variant.alphabet.dimensions(), (5, 6));
+        let vertical: Keyspace = keyspace(&variant, "two-square", Arrangement::Vertical, None);
+        let horizontal: Keyspace = keyspace(&variant, "two-square", Arrangement::Horizontal, None);
+        assert_eq!(vertical.equivalent, 120.0 * 120.0 * 720.0);
+        assert_eq!(horizontal.equivalent, 120.0 * 720.0 * 720.0);
+    }
+}
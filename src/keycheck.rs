@@ -0,0 +1,273 @@
+//! Weaknesses of a keyword.
+//!
+//! A keyword fills the first cells of the table with its letters, without
+//! repeats, and the alphabet fills the rest in order. `keycheck` measures
+//! how much of the table that leaves to the alphabet, how many of its cells
+//! stay where the table of no key has them, whether the keyword is spelled
+//! by the built-in English words a wordlist attack would try first, and how
+//! many of its letters repeat. Every finding takes points off a score of
+//! 100 and comes with a suggestion.
+//!
+//! Even a strong keyword only slows down a guess of the key: `crack` finds
+//! any table from a few hundred letters of ciphertext.
+
+use crate::Variant;
+use crate::analysis::Dictionary;
+use crate::error::Error;
+use crate::structure::{self, Structure};
+use clap::ArgMatches;
+
+/// Fewer distinct letters than this make a very short keyword
+const VERY_SHORT: usize = 6;
+
+/// Fewer distinct letters than this make a short keyword
+const SHORT: usize = 12;
+
+/// The score from which a keyword is fair
+const FAIR: usize = 50;
+
+/// The score from which a keyword is strong
+const STRONG: usize = 85;
+
+/// Enum to represent how a keyword reads as English words
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Words {
+    /// The keyword is one word of the list
+    Word,
+    /// The keyword is spelled by several words of the list
+    Phrase,
+}
+
+/// A weakness of a keyword with what to do about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// What is weak
+    pub warning: String,
+    /// What makes it stronger
+    pub suggestion: String,
+    /// The points it takes off the score
+    pub penalty: usize,
+}
+
+/// What `keycheck` found about a keyword
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    /// The table letters of the keyword
+    pub letters: usize,
+    /// The distinct table letters of the keyword, which fill the first cells
+    pub distinct: usize,
+    /// The cells at the end of the table in alphabetical order, filled by
+    /// the alphabet
+    pub alphabetical: usize,
+    /// The cells of the table holding the symbol of the table of no key
+    pub unchanged: usize,
+    /// The cells of the table
+    pub cells: usize,
+    /// How the keyword reads as words of the list, if it does
+    pub words: Option<Words>,
+    /// The weaknesses found, worst first
+    pub findings: Vec<Finding>,
+    /// The score from 0 to 100
+    pub score: usize,
+}
+
+impl Strength {
+    /// Returns the word for the score: weak, fair or strong.
+    pub fn rating(&self) -> &'static str {
+        if self.score >= STRONG {
+            "strong"
+        } else if self.score >= FAIR {
+            "fair"
+        } else {
+            "weak"
+        }
+    }
+}
+
+/// Checks a keyword for weaknesses.
+///
+/// # Arguments
+///
+/// * `key` - The keyword.
+/// * `variant` - The variant deciding which symbols make up the table.
+/// * `dictionary` - The words a wordlist attack would try.
+///
+/// # Returns
+///
+/// * What was found, or an invalid key error if the keyword holds no
+///   letters of the table.
+pub fn check(key: &str, variant: &Variant, dictionary: &Dictionary) -> Result<Strength, Error> {
+    let letters: Vec<char> = crate::table_letters(key, variant);
+    if letters.is_empty() {
+        return Err(Error::InvalidKey(String::from(
+            "the key holds no letters of the table",
+        )));
+    }
+    let mut distinct: Vec<char> = Vec::new();
+    for &c in &letters {
+        if !distinct.contains(&c) {
+            distinct.push(c);
+        }
+    }
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(key, variant);
+    let structure: Structure = structure::measure(&table, variant);
+    let cells: usize = table.len() * table[0].len();
+    let (alphabetical, unchanged): (usize, usize) = (structure.tail, structure.fixed);
+    let words: Option<Words> = if dictionary.contains(&letters) {
+        Some(Words::Word)
+    } else if dictionary.coverage(key, variant) >= 1.0 {
+        Some(Words::Phrase)
+    } else {
+        None
+    };
+
+    let mut findings: Vec<Finding> = Vec::new();
+    if distinct.len() < SHORT {
+        findings.push(Finding {
+            warning: format!("the key has only {} distinct letters", distinct.len()),
+            suggestion: format!("use a key of at least {} distinct letters", SHORT),
+            penalty: if distinct.len() < VERY_SHORT { 40 } else { 25 },
+        });
+    }
+    match words {
+        Some(Words::Word) => findings.push(Finding {
+            warning: String::from(
+                "the key is a dictionary word, one of the first a wordlist attack tries",
+            ),
+            suggestion: String::from(
+                "misspell it, join unrelated words or use a random square of keygen",
+            ),
+            penalty: 30,
+        }),
+        Some(Words::Phrase) => findings.push(Finding {
+            warning: String::from(
+                "the key is made of dictionary words, which a wordlist attack combines",
+            ),
+            suggestion: String::from(
+                "add letters that spell no word, or use a random square of keygen",
+            ),
+            penalty: 15,
+        }),
+        None => {}
+    }
+    if 2 * alphabetical > cells {
+        findings.push(Finding {
+            warning: format!(
+                "{} of the {} cells hold the rest of the alphabet in order, {} of them where the table of no key has them",
+                alphabetical, cells, unchanged
+            ),
+            suggestion: String::from(
+                "use more distinct letters, including late ones such as V, W, X, Y and Z",
+            ),
+            penalty: if 4 * alphabetical > 3 * cells { 25 } else { 15 },
+        });
+    }
+    let repeated: usize = letters.len() - distinct.len();
+    if letters.len() >= 4 && 3 * repeated >= letters.len() {
+        findings.push(Finding {
+            warning: format!(
+                "{} of the {} letters of the key repeat earlier ones and add nothing to the table",
+                repeated,
+                letters.len()
+            ),
+            suggestion: String::from("choose a key with fewer repeated letters"),
+            penalty: if 2 * repeated >= letters.len() {
+                15
+            } else {
+                10
+            },
+        });
+    }
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.penalty));
+    let score: usize = 100usize.saturating_sub(findings.iter().map(|f| f.penalty).sum());
+    Ok(Strength {
+        letters: letters.len(),
+        distinct: distinct.len(),
+        alphabetical,
+        unchanged,
+        cells,
+        words,
+        findings,
+        score,
+    })
+}
+
+/// Runs the `keycheck` subcommand, printing the findings and the score to
+/// stdout.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings are invalid or the key holds no letters of
+///   the table.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    let key: &String = matches.get_one::<String>("key").unwrap();
+    let dictionary: Dictionary = match matches.get_one::<String>("words") {
+        Some(path) => Dictionary::load(path, &variant)?,
+        None => Dictionary::builtin(&variant),
+    };
+    let strength: Strength = check(key, &variant, &dictionary)?;
+    println!(
+        "Key: {} letters, {} distinct",
+        strength.letters, strength.distinct
+    );
+    println!(
+        "Table: {} of {} cells in alphabetical order, {} where the table of no key has them",
+        strength.alphabetical, strength.cells, strength.unchanged
+    );
+    for finding in &strength.findings {
+        println!("Warning: {}", finding.warning);
+        println!("  Suggestion: {}", finding.suggestion);
+    }
+    println!("Strength: {} ({}/100)", strength.rating(), strength.score);
+    println!("Any keyword falls to crack given a few hundred letters of ciphertext");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keycheck() {
+        let variant: Variant = Variant::default();
+        let dictionary: Dictionary = Dictionary::builtin(&variant);
+        let strength = |key: &str| check(key, &variant, &dictionary).unwrap();
+
+        let short: Strength = strength("the");
+        assert_eq!((short.letters, short.distinct), (3, 3));
+        assert_eq!(short.alphabetical, 22);
+        assert_eq!(short.words, Some(Words::Word));
+        assert_eq!((short.score, short.rating()), (5, "weak"));
+        assert_eq!(short.findings[0].penalty, 40);
+
+        let phrase: Strength = strength("PLAYFAIR");
+        assert_eq!(phrase.words, Some(Words::Phrase));
+        assert_eq!(phrase.score, 45);
+
+        let repeats: Strength = strength("AAAABBBB");
+        assert_eq!(repeats.distinct, 2);
+        assert!(
+            repeats
+                .findings
+                .iter()
+                .any(|f| f.warning.contains("6 of the 8"))
+        );
+        // A and B come first anyway, so the table is the one of no key
+        assert_eq!(repeats.unchanged, 25);
+
+        let strong: Strength = strength("QZVHWKJDMXUYBOFL");
+        assert_eq!(strong.words, None);
+        assert!(strong.findings.is_empty());
+        assert_eq!((strong.score, strong.rating()), (100, "strong"));
+
+        assert!(matches!(
+            check("1234", &variant, &dictionary),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! Generation of random keys for puzzles and exercises.
+//!
+//! Keys are either a shuffled square, which no keyword structure constrains,
+//! or a pronounceable keyword of alternating consonants and vowels together
+//! with the square it generates. The same `--seed` always yields the same
+//! keys. The generator is not cryptographically secure, which matches the
+//! strength of the cipher itself.
+
+use crate::error::Error;
+use crate::{KeySquare, Variant};
+use clap::ArgMatches;
+use std::io::{StdoutLock, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Vowels of the supported alphabets, used for pronounceable keywords
+const VOWELS: &str = "AEIOUYÄÖÜАЕЁИОУЫЭЮЯЄІЇ";
+
+/// The SplitMix64 generator, small and good enough for shuffling
+pub struct Rng {
+    /// The current state, advanced on every draw
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Returns the state of the generator, from which `new` carries on.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Draws the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z: u64 = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Draws an index below `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// Picks a seed from the clock and the process id when none is given.
+pub fn default_seed() -> u64 {
+    let nanos: u128 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    (nanos as u64) ^ (u64::from(std::process::id()) << 32)
+}
+
+/// Shuffles the symbols of the table into a square (Fisher-Yates).
+///
+/// # Arguments
+///
+/// * `rng` - The generator to draw from.
+/// * `variant` - The variant deciding which symbols make up the table.
+///
+/// # Returns
+///
+/// * The shuffled square.
+pub fn random_square(rng: &mut Rng, variant: &Variant) -> KeySquare {
+    let mut symbols: Vec<char> = variant.symbols();
+    for i in (1..symbols.len()).rev() {
+        symbols.swap(i, rng.below(i + 1));
+    }
+    let letters: String = symbols.into_iter().collect();
+    KeySquare::from_letters(&letters, variant).expect("a permutation of the symbols is a square")
+}
+
+/// Generates a keyword of alternating consonants and vowels.
+///
+/// # Arguments
+///
+/// * `rng` - The generator to draw from.
+/// * `variant` - The variant deciding which letters are available.
+/// * `length` - The number of letters of the keyword.
+///
+/// # Returns
+///
+/// * The keyword, or a usage error if the table lacks vowels or consonants.
+fn random_keyword(rng: &mut Rng, variant: &Variant, length: usize) -> Result<String, Error> {
+    let (vowels, consonants): (Vec<char>, Vec<char>) = variant
+        .symbols()
+        .into_iter()
+        .filter(|c| c.is_alphabetic())
+        .partition(|&c| VOWELS.contains(c));
+    if vowels.is_empty() || consonants.is_empty() {
+        return Err(Error::Usage(String::from(
+            "the table has no vowels or no consonants to build a keyword from",
+        )));
+    }
+    Ok((0..length)
+        .map(|i| {
+            let letters: &[char] = if i % 2 == 0 { &consonants } else { &vowels };
+            letters[rng.below(letters.len())]
+        })
+        .collect())
+}
+
+/// Formats a square row by row, in the form `--key-square` accepts.
+pub fn square_line(square: &KeySquare) -> String {
+    square
+        .rows()
+        .iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Runs the `keygen` subcommand, printing one key per line.
+///
+/// Shuffled squares are printed row by row, or as mnemonic words with
+/// `--mnemonic`. Keywords are printed followed by the square they generate.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * A usage error if the count or the keyword length is zero or the
+///   variant is invalid.
+pub(crate) fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let mut out: StdoutLock = std::io::stdout().lock();
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    let count: usize = *matches.get_one::<usize>("count").unwrap();
+    let length: usize = *matches.get_one::<usize>("length").unwrap();
+    if count == 0 || length == 0 {
+        return Err(Error::Usage(String::from(
+            "the count and the keyword length must be at least 1",
+        )));
+    }
+    let seed: u64 = matches
+        .get_one::<u64>("seed")
+        .copied()
+        .unwrap_or_else(default_seed);
+    let mut rng: Rng = Rng::new(seed);
+    for _ in 0..count {
+        if matches.get_flag("keyword") {
+            let keyword: String = random_keyword(&mut rng, &variant, length)?;
+            let table: Vec<Vec<char>> = crate::generate_playfair_table(&keyword, &variant);
+            writeln!(
+                out,
+                "{} {}",
+                keyword,
+                square_line(&KeySquare::from_rows(table))
+            )?;
+        } else if matches.get_flag("mnemonic") {
+            let square: KeySquare = random_square(&mut rng, &variant);
+            writeln!(out, "{}", crate::mnemonic::encode(&square, &variant))?;
+        } else {
+            writeln!(out, "{}", square_line(&random_square(&mut rng, &variant)))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_square_is_reproducible() {
+        let variant: Variant = Variant::default();
+        let square: KeySquare = random_square(&mut Rng::new(7), &variant);
+        assert_eq!(square, random_square(&mut Rng::new(7), &variant));
+        assert_ne!(square, random_square(&mut Rng::new(8), &variant));
+        assert_eq!(
+            KeySquare::from_letters(&square_line(&square), &variant),
+            Ok(square)
+        );
+    }
+
+    #[test]
+    fn test_random_keyword_alternates() {
+        let variant: Variant = Variant::default();
+        let keyword: String = random_keyword(&mut Rng::new(7), &variant, 8).unwrap();
+        assert_eq!(keyword.len(), 8);
+        for (i, c) in keyword.chars().enumerate() {
+            assert_eq!(VOWELS.contains(c), i % 2 == 1, "{}", keyword);
+        }
+        let digits: Variant = Variant {
+            alphabet: crate::parse_alphabet("0123456789ABCDEFGHIJKLMNO").unwrap(),
+            ..Variant::default()
+        };
+        assert!(random_keyword(&mut Rng::new(7), &digits, 8).is_ok());
+        let no_vowels: Variant = Variant {
+            alphabet: crate::parse_alphabet("0123456789BCDFGHJKLMNPQRS").unwrap(),
+            ..Variant::default()
+        };
+        assert!(random_keyword(&mut Rng::new(7), &no_vowels, 8).is_err());
+    }
+}
@@ -0,0 +1,2989 @@
+//! The Playfair cipher and its relatives, and the tools to break them.
+//!
+//! The cipher itself lives at the root: a `Variant` decides the symbols and
+//! the shape of the table, `generate_playfair_table` and `KeySquare` build
+//! the table from a keyword, a passphrase or its letters, and
+//! `playfair_cipher` encrypts or decrypts a text with it through
+//! `Playfair`, the cipher of one table behind `squares::DigramCipher`. The
+//! modules hold the rest:
+//!
+//! - `squares` - the two-square, four-square and double Playfair ciphers,
+//!   behind the common `DigramCipher` trait.
+//! - `compiled` - a table compiled into a digram substitution table.
+//! - `analysis` - the n-gram statistics and the search of `crack`.
+//! - `solver` - the trait for search strategies of one's own.
+//! - `fitness` - how much a text reads like a language.
+//! - `analyze`, `cribs`, `structure` and `keygen` - statistics of
+//!   ciphertexts, known plaintext, metrics of tables and random tables.
+//!
+//! The `playfair` binary is a thin wrapper around `build_cli` and `run`.
+
+pub mod analysis;
+pub mod analyze;
+mod auto;
+mod bench;
+mod binary;
+mod checkpoint;
+mod cli;
+mod clipboard;
+mod color;
+mod compare;
+pub mod compiled;
+mod config;
+mod crackview;
+pub mod cribs;
+#[cfg(unix)]
+mod daemon;
+mod encoding;
+mod envelope;
+pub mod error;
+mod export;
+mod filter;
+pub mod fitness;
+mod gpu;
+mod info;
+mod keycheck;
+pub mod keygen;
+mod mask;
+mod mnemonic;
+mod morse;
+mod nato;
+mod normalize;
+mod polybius;
+mod qr;
+mod repl;
+mod report;
+mod selftest;
+mod serve;
+pub mod solver;
+pub mod squares;
+pub mod structure;
+mod transposition;
+mod tui;
+mod vectors;
+mod watch;
+
+pub use cli::{build_cli, build_cli_with, run, run_with};
+
+use compiled::CompiledCipher;
+use config::Config;
+use error::Error;
+use sha2::{Digest, Sha256};
+use squares::DigramCipher;
+
+/// Environment variable consulted for the key when no flag provides one
+const KEY_ENV_VAR: &str = "PLAYFAIR_KEY";
+
+/// Enum to represent encryption or decryption mode
+#[derive(Debug, Copy, Clone)]
+pub enum CipherMode {
+    Encrypt,
+    Decrypt,
+}
+
+/// Enum to represent how the result is printed
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+    /// Text with the ciphertext spelled in the NATO phonetic alphabet
+    Nato,
+    /// Text with the ciphertext written in Morse code
+    Morse,
+}
+
+/// How results are written out
+#[derive(Debug, Clone, PartialEq)]
+struct OutputStyle {
+    /// Size of the letter groups, if grouped
+    group: Option<usize>,
+    /// Character written between the groups
+    separator: char,
+    /// Whether the result is written in lowercase
+    lower: bool,
+}
+
+impl Default for OutputStyle {
+    fn default() -> Self {
+        OutputStyle {
+            group: None,
+            separator: ' ',
+            lower: false,
+        }
+    }
+}
+
+impl OutputStyle {
+    /// Writes a result in the style.
+    fn apply(&self, text: &str) -> String {
+        let text: String = match self.group {
+            Some(size) => group_text(text, size, self.separator),
+            None => text.to_string(),
+        };
+        if self.lower {
+            text.to_lowercase()
+        } else {
+            text
+        }
+    }
+}
+
+/// Enum to represent the symbols the table is built from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alphabet {
+    /// The 25 letters left after the merge, in a 5x5 table
+    Letters,
+    /// The 26 letters and the 10 digits, in a 6x6 table
+    Alphanumeric,
+    /// Uppercase symbols given by the user in their fill order, filling a
+    /// grid such as 5x5, 5x6 or 4x7
+    Custom(Vec<char>),
+}
+
+impl Alphabet {
+    /// Returns the number of rows and columns of the table.
+    fn dimensions(&self) -> (usize, usize) {
+        match self {
+            Alphabet::Letters => (5, 5),
+            Alphabet::Alphanumeric => (6, 6),
+            Alphabet::Custom(symbols) => {
+                grid_dimensions(symbols.len()).expect("custom alphabets fill a grid")
+            }
+        }
+    }
+}
+
+/// Finds the grid a number of symbols fills.
+///
+/// The grid is the one closest to a square, with at least 2 rows and at
+/// most twice as many columns as rows, e.g. 5x6 for 30 symbols and 4x7 for
+/// 28.
+///
+/// # Returns
+///
+/// * The number of rows and columns, or `None` if no such grid holds
+///   exactly `count` symbols.
+fn grid_dimensions(count: usize) -> Option<(usize, usize)> {
+    (2..=count.isqrt())
+        .rev()
+        .find(|rows| count.is_multiple_of(*rows))
+        .map(|rows| (rows, count / rows))
+        .filter(|&(rows, cols)| cols <= 2 * rows)
+}
+
+/// Enum to represent the language conventions used to spell out letters
+/// missing from the table
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Locale {
+    /// `Ä` to `AE`, `Ö` to `OE`, `Ü` to `UE` and `ß` to `SS`
+    German,
+    /// `İ` and `ı` to `I`, and `Ç`, `Ğ`, `Ö`, `Ş`, `Ü` without their marks
+    Turkish,
+    /// `Ñ` to `N` and the accented vowels without their accents
+    Spanish,
+}
+
+impl Locale {
+    /// Returns the setting name of the locale, e.g. `de`.
+    fn name(self) -> &'static str {
+        match self {
+            Locale::German => "de",
+            Locale::Turkish => "tr",
+            Locale::Spanish => "es",
+        }
+    }
+
+    /// Returns the letters an uppercase character is spelled with, if the
+    /// locale has a rule for it.
+    fn transliterate(self, c: char) -> Option<&'static str> {
+        match (self, c) {
+            (Locale::German, 'Ä') => Some("AE"),
+            (Locale::German, 'Ö') => Some("OE"),
+            (Locale::German, 'Ü') => Some("UE"),
+            (Locale::German, 'ß' | 'ẞ') => Some("SS"),
+            // The dotless ı uppercases to I
+            (Locale::Turkish, 'İ' | 'I') => Some("I"),
+            (Locale::Turkish, 'Ç') => Some("C"),
+            (Locale::Turkish, 'Ğ') => Some("G"),
+            (Locale::Turkish, 'Ö') => Some("O"),
+            (Locale::Turkish, 'Ş') => Some("S"),
+            (Locale::Turkish, 'Ü') => Some("U"),
+            (Locale::Spanish, 'Ñ') => Some("N"),
+            (Locale::Spanish, 'Á') => Some("A"),
+            (Locale::Spanish, 'É') => Some("E"),
+            (Locale::Spanish, 'Í') => Some("I"),
+            (Locale::Spanish, 'Ó') => Some("O"),
+            (Locale::Spanish, 'Ú' | 'Ü') => Some("U"),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a locale setting such as `de`.
+///
+/// # Arguments
+///
+/// * `spec` - One of `de`, `tr` or `es`.
+///
+/// # Returns
+///
+/// * The locale, or a usage error for any other value.
+fn parse_locale(spec: &str) -> Result<Locale, Error> {
+    match spec {
+        "de" => Ok(Locale::German),
+        "tr" => Ok(Locale::Turkish),
+        "es" => Ok(Locale::Spanish),
+        _ => Err(Error::Usage(format!(
+            "invalid locale '{}': expected 'de', 'tr' or 'es'",
+            spec
+        ))),
+    }
+}
+
+/// Enum to represent the order in which the table cells are filled
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Route {
+    /// Row by row, left to right
+    Rows,
+    /// Column by column, top to bottom
+    Columns,
+    /// Clockwise from the top left corner towards the center
+    Spiral,
+    /// Row by row, alternating left to right and right to left (boustrophedon)
+    Zigzag,
+    /// Row by row starting from the bottom right corner, right to left
+    Reversed,
+}
+
+impl Route {
+    /// Returns the setting name of the route, e.g. `spiral`.
+    fn name(self) -> &'static str {
+        match self {
+            Route::Rows => "rows",
+            Route::Columns => "columns",
+            Route::Spiral => "spiral",
+            Route::Zigzag => "zigzag",
+            Route::Reversed => "reversed",
+        }
+    }
+
+    /// Lists the cells of a table in the order the route fills them.
+    ///
+    /// # Arguments
+    ///
+    /// * `(rows, cols)` - The number of rows and columns of the table.
+    ///
+    /// # Returns
+    ///
+    /// * Every `(row, col)` of the table exactly once.
+    fn cells(self, (rows, cols): (usize, usize)) -> Vec<(usize, usize)> {
+        let by_rows = (0..rows).flat_map(move |i| (0..cols).map(move |j| (i, j)));
+        match self {
+            Route::Rows => by_rows.collect(),
+            Route::Columns => (0..cols)
+                .flat_map(|j| (0..rows).map(move |i| (i, j)))
+                .collect(),
+            Route::Zigzag => by_rows
+                .map(|(i, j)| {
+                    if i % 2 == 0 {
+                        (i, j)
+                    } else {
+                        (i, cols - 1 - j)
+                    }
+                })
+                .collect(),
+            Route::Reversed => by_rows.map(|(i, j)| (rows - 1 - i, cols - 1 - j)).collect(),
+            Route::Spiral => {
+                let mut cells: Vec<(usize, usize)> = Vec::with_capacity(rows * cols);
+                // Walk the rings from the outside in, each one clockwise
+                for ring in 0..rows.min(cols).div_ceil(2) {
+                    let (bottom, right): (usize, usize) = (rows - 1 - ring, cols - 1 - ring);
+                    if ring == bottom {
+                        // A single row is left in the middle
+                        cells.extend((ring..=right).map(|j| (ring, j)));
+                        break;
+                    }
+                    if ring == right {
+                        cells.extend((ring..=bottom).map(|i| (i, ring)));
+                        break;
+                    }
+                    cells.extend((ring..right).map(|j| (ring, j)));
+                    cells.extend((ring..bottom).map(|i| (i, right)));
+                    cells.extend((ring + 1..=right).rev().map(|j| (bottom, j)));
+                    cells.extend((ring + 1..=bottom).rev().map(|i| (i, ring)));
+                }
+                cells
+            }
+        }
+    }
+}
+
+/// Parses a route setting such as `spiral`.
+///
+/// # Arguments
+///
+/// * `spec` - One of `rows`, `columns`, `spiral`, `zigzag` or `reversed`.
+///
+/// # Returns
+///
+/// * The route, or a usage error for any other value.
+fn parse_route(spec: &str) -> Result<Route, Error> {
+    match spec {
+        "rows" => Ok(Route::Rows),
+        "columns" => Ok(Route::Columns),
+        "spiral" => Ok(Route::Spiral),
+        "zigzag" => Ok(Route::Zigzag),
+        "reversed" => Ok(Route::Reversed),
+        _ => Err(Error::Usage(format!(
+            "invalid route '{}': expected 'rows', 'columns', 'spiral', 'zigzag' or 'reversed'",
+            spec
+        ))),
+    }
+}
+
+/// Enum to represent how repeated letters are kept out of the pairs
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Doubles {
+    /// Inserts the filler when both letters of a digram are the same
+    Digram,
+    /// Inserts the filler between any two equal adjacent letters of the text
+    All,
+    /// Drops the second letter of a doubled digram, shifting the rest left
+    Skip,
+    /// Rejects texts with a doubled digram
+    Error,
+}
+
+impl Doubles {
+    /// Returns the setting name of the strategy, e.g. `skip`.
+    fn name(self) -> &'static str {
+        match self {
+            Doubles::Digram => "digram",
+            Doubles::All => "all",
+            Doubles::Skip => "skip",
+            Doubles::Error => "error",
+        }
+    }
+}
+
+/// Parses a double-letter setting such as `skip`.
+///
+/// # Arguments
+///
+/// * `spec` - One of `digram`, `all`, `skip` or `error`.
+///
+/// # Returns
+///
+/// * The strategy, or a usage error for any other value.
+fn parse_doubles(spec: &str) -> Result<Doubles, Error> {
+    match spec {
+        "digram" => Ok(Doubles::Digram),
+        "all" => Ok(Doubles::All),
+        "skip" => Ok(Doubles::Skip),
+        "error" => Ok(Doubles::Error),
+        _ => Err(Error::Usage(format!(
+            "invalid doubles '{}': expected 'digram', 'all', 'skip' or 'error'",
+            spec
+        ))),
+    }
+}
+
+/// Parses a compatibility preset such as `cryptool`.
+///
+/// Each preset bundles the conventions of a popular implementation, so that
+/// its ciphertexts match. Explicit settings still override the preset.
+///
+/// # Arguments
+///
+/// * `name` - One of `dcode`, `cryptool` or `practicalcryptography`.
+///
+/// # Returns
+///
+/// * The variant of the preset, or a usage error for any other name.
+fn parse_compat(name: &str) -> Result<Variant, Error> {
+    let alt_filler: Option<char> = match name {
+        // dCode separates a doubled X with a Q
+        "dcode" => Some('Q'),
+        // CrypTool 2 replaces the separator by Y where it is doubled itself
+        "cryptool" => Some('Y'),
+        // practicalcryptography.com always inserts an X
+        "practicalcryptography" => None,
+        _ => {
+            return Err(Error::Usage(format!(
+                "invalid compat '{}': expected 'dcode', 'cryptool' or 'practicalcryptography'",
+                name
+            )));
+        }
+    };
+    // All three merge J into I, pad with X and split doubled digrams only
+    Ok(Variant {
+        alt_filler,
+        ..Variant::default()
+    })
+}
+
+/// Settings selecting the flavor of the Playfair cipher
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// Letter inserted between repeated characters and used to pad odd-length text
+    pub filler: char,
+    /// Letter inserted instead of the filler next to a filler, such as
+    /// between two Xs or after a final X, if any
+    pub alt_filler: Option<char>,
+    /// Letter left out of the table and the letter it is replaced with, as
+    /// `(from, into)`; without a replacement the letter is omitted from texts
+    pub merge: (char, Option<char>),
+    /// The symbols of the table; the merge only applies to letters missing from it
+    pub alphabet: Alphabet,
+    /// The conventions spelling out letters that are not in the table, if any
+    pub locale: Option<Locale>,
+    /// The order in which the key and the alphabet fill the table
+    pub route: Route,
+    /// How many times the cipher is applied, shifting the pairs between rounds
+    pub rounds: usize,
+    /// Length of the two rows the letters are written in when seriated, if
+    /// pairs are taken vertically
+    pub period: Option<usize>,
+    /// How repeated letters falling into the same digram are handled
+    pub doubles: Doubles,
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Variant {
+            filler: 'X',
+            alt_filler: None,
+            merge: ('J', Some('I')),
+            alphabet: Alphabet::Letters,
+            locale: None,
+            route: Route::Rows,
+            rounds: 1,
+            period: None,
+            doubles: Doubles::Digram,
+        }
+    }
+}
+
+impl Variant {
+    /// Returns the letter inserted after `c`, the alternative filler if `c`
+    /// is the filler itself.
+    fn filler_after(&self, c: char) -> char {
+        match self.alt_filler {
+            Some(alt) if c == self.filler => alt,
+            _ => self.filler,
+        }
+    }
+
+    /// Returns the symbols of the table in their alphabetical fill order.
+    pub fn symbols(&self) -> Vec<char> {
+        match &self.alphabet {
+            Alphabet::Letters => ('A'..='Z').filter(|&c| c != self.merge.0).collect(),
+            Alphabet::Alphanumeric => ('A'..='Z').chain('0'..='9').collect(),
+            Alphabet::Custom(symbols) => symbols.clone(),
+        }
+    }
+
+    /// Returns how the letter left out of the table is handled.
+    ///
+    /// # Returns
+    ///
+    /// * The setting name and its value, `("merge", "J=I")` or `("omit", "Q")`.
+    fn reduction(&self) -> (&'static str, String) {
+        match self.merge {
+            (from, Some(into)) => ("merge", format!("{}={}", from, into)),
+            (letter, None) => ("omit", letter.to_string()),
+        }
+    }
+
+    /// Returns how the letter left out of the table is handled, or `None`
+    /// if the table holds it, as 6x6 tables and custom alphabets with J do.
+    fn applied_reduction(&self) -> Option<(&'static str, String)> {
+        (!self.symbols().contains(&self.merge.0)).then(|| self.reduction())
+    }
+
+    /// Returns the grid setting of the variant, e.g. `5x5` or `4x7`.
+    fn grid(&self) -> String {
+        let (rows, cols) = self.alphabet.dimensions();
+        format!("{}x{}", rows, cols)
+    }
+}
+
+/// Parses a grid setting such as `6x6`.
+///
+/// # Arguments
+///
+/// * `spec` - Either `5x5` for letters only or `6x6` for letters and digits.
+///
+/// # Returns
+///
+/// * The alphabet of the grid, or a usage error for any other size.
+pub fn parse_grid(spec: &str) -> Result<Alphabet, Error> {
+    match spec {
+        "5x5" => Ok(Alphabet::Letters),
+        "6x6" => Ok(Alphabet::Alphanumeric),
+        _ => Err(Error::Usage(format!(
+            "invalid grid '{}': expected '5x5' or '6x6'",
+            spec
+        ))),
+    }
+}
+
+/// Converts a character to uppercase, keeping it when the uppercase form
+/// takes several characters (e.g. `ß`).
+fn to_upper(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+/// The Russian alphabet and three punctuation marks filling a 6x6 table
+const RUSSIAN_ALPHABET: &str = "АБВГДЕЁЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯ.,-";
+/// The Ukrainian alphabet and three punctuation marks filling a 6x6 table
+const UKRAINIAN_ALPHABET: &str = "АБВГҐДЕЄЖЗИІЇЙКЛМНОПРСТУФХЦЧШЩЬЮЯ.,-";
+
+/// Parses an alphabet setting listing every symbol of the table.
+///
+/// # Arguments
+///
+/// * `spec` - Distinct symbols filling a grid, e.g. 25 such as
+///   `ABCDEFGHIKLMNOPQRSTUVWXYZ`, 30 for 5x6 or 28 for 4x7, or `ru` or `uk`
+///   for the Cyrillic 6x6 tables. Letters are uppercased.
+///
+/// # Returns
+///
+/// * The custom alphabet, or a usage error if the symbols don't fill a grid
+///   or a symbol is repeated or whitespace.
+pub fn parse_alphabet(spec: &str) -> Result<Alphabet, Error> {
+    let spec: &str = match spec {
+        "ru" => RUSSIAN_ALPHABET,
+        "uk" => UKRAINIAN_ALPHABET,
+        _ => spec,
+    };
+    let symbols: Vec<char> = spec.chars().map(to_upper).collect();
+    if grid_dimensions(symbols.len()).is_none() {
+        return Err(Error::Usage(format!(
+            "invalid alphabet: {} symbols don't fill a grid such as 5x5 (25), 5x6 (30) or 4x7 (28)",
+            symbols.len()
+        )));
+    }
+    if let Some(&c) = symbols.iter().find(|c| c.is_whitespace()) {
+        return Err(Error::Usage(format!(
+            "invalid alphabet: whitespace {:?} can't be a symbol",
+            c
+        )));
+    }
+    for (i, c) in symbols.iter().enumerate() {
+        if symbols[..i].contains(c) {
+            return Err(Error::Usage(format!(
+                "invalid alphabet: '{}' appears more than once",
+                c
+            )));
+        }
+    }
+    Ok(Alphabet::Custom(symbols))
+}
+
+/// Parses a merge setting written as `J=I`.
+///
+/// # Arguments
+///
+/// * `spec` - The letter left out of the table, `=`, and the letter replacing it.
+///
+/// # Returns
+///
+/// * The `(from, into)` pair, or a usage error if the setting is malformed.
+fn parse_merge(spec: &str) -> Result<(char, Option<char>), Error> {
+    let letters: Vec<char> = spec.chars().collect();
+    match letters[..] {
+        [from, '=', into]
+            if from.is_ascii_alphabetic()
+                && into.is_ascii_alphabetic()
+                && !from.eq_ignore_ascii_case(&into) =>
+        {
+            Ok((from.to_ascii_uppercase(), Some(into.to_ascii_uppercase())))
+        }
+        _ => Err(Error::Usage(format!(
+            "invalid merge '{}': expected two different letters such as 'J=I'",
+            spec
+        ))),
+    }
+}
+
+/// Parses an omit setting naming the letter dropped from the table and texts.
+///
+/// # Arguments
+///
+/// * `spec` - A single letter such as `Q`.
+///
+/// # Returns
+///
+/// * The `(letter, None)` merge, or a usage error if the setting is not a letter.
+fn parse_omit(spec: &str) -> Result<(char, Option<char>), Error> {
+    let letters: Vec<char> = spec.chars().collect();
+    match letters[..] {
+        [letter] if letter.is_ascii_alphabetic() => Ok((letter.to_ascii_uppercase(), None)),
+        _ => Err(Error::Usage(format!(
+            "invalid omit '{}': expected a single letter such as 'Q'",
+            spec
+        ))),
+    }
+}
+
+/// Checks that the letters of a variant exist in its table.
+///
+/// # Arguments
+///
+/// * `variant` - The variant to check.
+///
+/// # Returns
+///
+/// * A usage error if the filler is not in the table, e.g. because it is
+///   the merged-away letter.
+pub fn validate_variant(variant: &Variant) -> Result<(), Error> {
+    if variant.symbols().contains(&variant.filler) {
+        return match variant.alt_filler {
+            Some(alt) if alt == variant.filler || !variant.symbols().contains(&alt) => {
+                Err(Error::Usage(format!(
+                    "invalid alternative filler '{}': expected a symbol of the table other than the filler",
+                    alt
+                )))
+            }
+            _ => Ok(()),
+        };
+    }
+    if variant.alphabet != Alphabet::Alphanumeric && variant.filler == variant.merge.0 {
+        return Err(Error::Usage(format!(
+            "invalid filler '{}': the letter is {} and not in the table",
+            variant.filler,
+            match variant.merge.1 {
+                Some(into) => format!("merged into '{}'", into),
+                None => String::from("omitted"),
+            }
+        )));
+    }
+    Err(Error::Usage(format!(
+        "invalid filler '{}': expected a {} of the table",
+        variant.filler,
+        match variant.alphabet {
+            Alphabet::Letters => "letter",
+            Alphabet::Alphanumeric => "letter or digit",
+            Alphabet::Custom(_) => "symbol",
+        }
+    )))
+}
+
+/// Normalizes a character to the uppercase letter it stands for in the table.
+///
+/// # Arguments
+///
+/// * `c` - The character to normalize.
+/// * `variant` - The variant providing the merge rule.
+///
+/// # Returns
+///
+/// * The uppercase table letter, or `None` if `c` is not in the table.
+fn normalize_char(c: char, variant: &Variant) -> Option<char> {
+    match &variant.alphabet {
+        Alphabet::Letters if c.is_ascii_alphabetic() => {
+            let c: char = c.to_ascii_uppercase();
+            if c == variant.merge.0 {
+                variant.merge.1
+            } else {
+                Some(c)
+            }
+        }
+        Alphabet::Alphanumeric if c.is_ascii_alphanumeric() => Some(c.to_ascii_uppercase()),
+        Alphabet::Custom(symbols) => {
+            let c: char = to_upper(c);
+            if symbols.contains(&c) {
+                Some(c)
+            } else if c == variant.merge.0 {
+                variant.merge.1.filter(|into| symbols.contains(into))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Converts a text to the table letters it contains.
+///
+/// Characters that are not in the table are spelled out with the rules of
+/// the locale, if any, and skipped otherwise.
+///
+/// # Arguments
+///
+/// * `text` - The text to convert.
+/// * `variant` - The variant providing the table symbols, merge and locale.
+///
+/// # Returns
+///
+/// * The uppercase table letters, in order.
+fn table_letters(text: &str, variant: &Variant) -> Vec<char> {
+    let mut letters: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if let Some(letter) = normalize_char(c, variant) {
+            letters.push(letter);
+        } else if let Some(spelled) = variant.locale.and_then(|l| l.transliterate(to_upper(c))) {
+            letters.extend(spelled.chars().filter_map(|c| normalize_char(c, variant)));
+        }
+    }
+    letters
+}
+
+/// Records the case of every table letter of a text.
+///
+/// # Arguments
+///
+/// * `text` - The text to inspect.
+/// * `variant` - The variant providing the table symbols, merge and locale.
+///
+/// # Returns
+///
+/// * For each letter `table_letters` returns, whether it was written in
+///   lowercase. Letters spelled out by the locale take the case of the
+///   character they replace.
+fn letter_case(text: &str, variant: &Variant) -> Vec<bool> {
+    let mut lower: Vec<bool> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if normalize_char(c, variant).is_some() {
+            lower.push(c.is_lowercase());
+        } else if let Some(spelled) = variant.locale.and_then(|l| l.transliterate(to_upper(c))) {
+            let count: usize = spelled
+                .chars()
+                .filter_map(|c| normalize_char(c, variant))
+                .count();
+            lower.extend(std::iter::repeat_n(c.is_lowercase(), count));
+        }
+    }
+    lower
+}
+
+/// Writes the letters of a result in the case recorded for their positions.
+///
+/// # Arguments
+///
+/// * `result` - The uppercase result.
+/// * `lower` - Whether each letter is lowercase; letters past its end stay
+///   uppercase.
+///
+/// # Returns
+///
+/// * The result in the recorded case.
+fn apply_case(result: &str, lower: &[bool]) -> String {
+    result
+        .chars()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            // Symbols without case, like digits, come out unchanged
+            if lower.get(i) == Some(&true) {
+                c.to_lowercase().collect::<Vec<char>>()
+            } else {
+                vec![c]
+            }
+        })
+        .collect()
+}
+
+/// Lists the letters of a text that are dropped because they are not in the table.
+///
+/// # Arguments
+///
+/// * `text` - The text to check.
+/// * `variant` - The variant providing the table symbols, merge and locale.
+///
+/// # Returns
+///
+/// * Each skipped letter once, in order of appearance.
+fn skipped_letters(text: &str, variant: &Variant) -> Vec<char> {
+    let mut skipped: Vec<char> = Vec::new();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        let c: char = to_upper(c);
+        if table_letters(&c.to_string(), variant).is_empty()
+            && c != variant.merge.0
+            && !skipped.contains(&c)
+        {
+            skipped.push(c);
+        }
+    }
+    skipped
+}
+
+/// Places the letters of a result into the layout of the input text.
+///
+/// Characters that are neither table symbols nor letters stay where they
+/// are, and letters missing from the table are dropped as usual. Every
+/// symbol is replaced by the next result letter, and a filler is placed
+/// right after the letter it follows.
+///
+/// # Arguments
+///
+/// * `text` - The input text providing the layout.
+/// * `variant` - The variant providing the table symbols, merge and locale.
+/// * `result` - The result letters, in order.
+/// * `fillers` - The positions of the fillers among the result letters.
+///
+/// # Returns
+///
+/// * The result laid out like the input.
+fn restore_layout(text: &str, variant: &Variant, result: &str, fillers: &[usize]) -> String {
+    let mut letters = result.chars();
+    let mut fillers = fillers.iter().peekable();
+    let mut position: usize = 0;
+    let mut laid_out: String = String::with_capacity(text.len() + fillers.len());
+    for c in text.chars() {
+        // A locale may spell one character with several letters
+        let count: usize = table_letters(&c.to_string(), variant).len();
+        if count == 0 {
+            if !c.is_alphabetic() {
+                laid_out.push(c);
+            }
+            continue;
+        }
+        for _ in 0..count {
+            laid_out.extend(letters.next());
+            position += 1;
+            while fillers.next_if(|&&i| i == position).is_some() {
+                laid_out.extend(letters.next());
+                position += 1;
+            }
+        }
+    }
+    laid_out.extend(letters);
+    laid_out
+}
+
+/// Removes the fillers a decrypted text most likely contains.
+///
+/// A filler between two equal letters is taken as inserted, as is one at
+/// the end of the text; after a filler, the alternative filler is looked
+/// for instead. Without a period, the `digram` strategy only
+/// inserts fillers as the second letter of a pair, so other positions are
+/// left alone; `skip` and `error` insert none. A filler is kept where
+/// the letters around it spell a word of the dictionary containing the
+/// filler at that place, such as the X of `EXEMPT`.
+///
+/// # Arguments
+///
+/// * `text` - The decrypted text, possibly laid out or in mixed case.
+/// * `variant` - The variant providing the filler and the strategy.
+/// * `dictionary` - Words in table letters that may contain the filler.
+///
+/// # Returns
+///
+/// * The text without the fillers. Characters that are not table symbols
+///   are kept and don't count as letters.
+fn strip_fillers(text: &str, variant: &Variant, dictionary: &[Vec<char>]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    // The table letters of the text and where they are in `chars`
+    let (indices, letters): (Vec<usize>, Vec<char>) = chars
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &c)| normalize_char(c, variant).map(|letter| (i, letter)))
+        .unzip();
+    let inserted: Vec<bool> = inserted_fillers(&letters, variant, dictionary);
+    let removed: Vec<usize> = (0..letters.len())
+        .filter(|&i| inserted[i])
+        .map(|i| indices[i])
+        .collect();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !removed.contains(i))
+        .map(|(_, &c)| c)
+        .collect()
+}
+
+/// Finds the letters of a decrypted text that are most likely fillers, by
+/// the rules of `strip_fillers`.
+///
+/// # Arguments
+///
+/// * `letters` - The table letters of the text.
+/// * `variant` - The variant providing the filler and the strategy.
+/// * `dictionary` - Words in table letters that may contain the filler.
+///
+/// # Returns
+///
+/// * Whether every letter is a filler the cipher inserted.
+fn inserted_fillers(letters: &[char], variant: &Variant, dictionary: &[Vec<char>]) -> Vec<bool> {
+    let in_word = |i: usize| {
+        dictionary.iter().any(|word| {
+            (0..word.len())
+                .filter(|&k| word[k] == letters[i])
+                .any(|k| i >= k && letters.get(i - k..i - k + word.len()) == Some(word.as_slice()))
+        })
+    };
+    let inserted = |i: usize| {
+        let between: bool = i > 0
+            && i + 1 < letters.len()
+            && letters[i - 1] == letters[i + 1]
+            && variant.period.is_none()
+            && match variant.doubles {
+                Doubles::Digram => i % 2 == 1,
+                Doubles::All => true,
+                Doubles::Skip | Doubles::Error => false,
+            };
+        let filler: char = match i {
+            0 => variant.filler,
+            _ => variant.filler_after(letters[i - 1]),
+        };
+        letters[i] == filler && (between || i + 1 == letters.len()) && !in_word(i)
+    };
+    (0..letters.len()).map(inserted).collect()
+}
+
+/// Finds the characters of a text that the cipher would drop.
+///
+/// Whitespace only separates words and is never reported. Other characters
+/// count when they are not table symbols, nor spelled out by the locale,
+/// unless the layout is kept and they are not letters.
+///
+/// # Arguments
+///
+/// * `text` - The text to check.
+/// * `variant` - The variant providing the table symbols, merge and locale.
+/// * `keep_layout` - Whether characters other than letters are kept in place.
+///
+/// # Returns
+///
+/// * The position of every dropped character in the text, counted in
+///   characters from 0, and the character itself.
+fn dropped_chars(text: &str, variant: &Variant, keep_layout: bool) -> Vec<(usize, char)> {
+    text.chars()
+        .enumerate()
+        .filter(|&(_, c)| {
+            !c.is_whitespace()
+                && (c.is_alphabetic() || !keep_layout)
+                && table_letters(&c.to_string(), variant).is_empty()
+        })
+        .collect()
+}
+
+/// Splits a text into groups of letters.
+///
+/// # Arguments
+///
+/// * `text` - The text to group.
+/// * `size` - The number of letters per group.
+/// * `separator` - The character written between the groups.
+///
+/// # Returns
+///
+/// * The grouped text.
+fn group_text(text: &str, size: usize, separator: char) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(size)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join(&separator.to_string())
+}
+
+/// Generates a Playfair encryption table based on the provided key.
+///
+/// # Arguments
+///
+/// * `key` - A string slice that holds the encryption key.
+/// * `variant` - The variant deciding which symbols make up the table.
+///
+/// # Returns
+///
+/// * The Playfair encryption table, e.g. 5x5, 6x6 or 5x6 depending on the
+///   alphabet.
+pub fn generate_playfair_table(key: &str, variant: &Variant) -> Vec<Vec<char>> {
+    let symbols: Vec<char> = variant.symbols();
+    let (rows, cols) = variant.alphabet.dimensions();
+    // Keeps track of symbols already added to the table, by index in `symbols`
+    let mut seen: Vec<bool> = vec![false; symbols.len()];
+    // The table that will be generated, filled along the route
+    let mut table: Vec<Vec<char>> = vec![vec![' '; cols]; rows];
+    let mut cells = variant.route.cells((rows, cols)).into_iter();
+
+    // The key letters are already merged and uppercase, and so are the symbols
+    for c in table_letters(key, variant)
+        .into_iter()
+        .chain(symbols.iter().copied())
+    {
+        let Some(index) = symbols.iter().position(|&s| s == c) else {
+            continue;
+        };
+        // Add the character to the table if it hasn't been added already
+        if !seen[index] {
+            seen[index] = true;
+            let (i, j) = cells.next().expect("the table has a cell for every symbol");
+            table[i][j] = c;
+        }
+    }
+    table
+}
+
+/// A table given cell by cell instead of being derived from a keyword
+///
+/// The rows can only be read, so the index of positions never goes stale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySquare {
+    /// The rows of the table
+    rows: Vec<Vec<char>>,
+    /// The positions of the symbols in the rows
+    positions: Positions,
+}
+
+impl KeySquare {
+    /// Wraps the rows of a table, indexing where its symbols are.
+    fn from_rows(rows: Vec<Vec<char>>) -> Self {
+        KeySquare {
+            positions: Positions::new(&rows),
+            rows,
+        }
+    }
+
+    /// The rows of the table.
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+
+    /// Generates the square of a keyword, like the Playfair table.
+    pub fn from_key(key: &str, variant: &Variant) -> Self {
+        KeySquare::from_rows(generate_playfair_table(key, variant))
+    }
+
+    /// Builds a square from its symbols, row by row.
+    ///
+    /// # Arguments
+    ///
+    /// * `letters` - Every symbol of the table exactly once, e.g.
+    ///   `KEYWORDABCFGHILMNPQSTUVXZ`. Whitespace is ignored so the rows may
+    ///   be separated, and letters are uppercased.
+    /// * `variant` - The variant deciding which symbols make up the table.
+    ///
+    /// # Returns
+    ///
+    /// * The square, or an invalid key error if a symbol is missing, repeated
+    ///   or not in the table.
+    pub fn from_letters(letters: &str, variant: &Variant) -> Result<Self, Error> {
+        let symbols: Vec<char> = variant.symbols();
+        let mut cells: Vec<char> = Vec::new();
+        for c in letters.chars().filter(|c| !c.is_whitespace()).map(to_upper) {
+            if !symbols.contains(&c) {
+                return Err(Error::InvalidKey(format!(
+                    "invalid key square: '{}' is not a symbol of the table",
+                    c
+                )));
+            }
+            if cells.contains(&c) {
+                return Err(Error::InvalidKey(format!(
+                    "invalid key square: '{}' appears more than once",
+                    c
+                )));
+            }
+            cells.push(c);
+        }
+        if cells.len() != symbols.len() {
+            return Err(Error::InvalidKey(format!(
+                "invalid key square: expected {} symbols, got {}",
+                symbols.len(),
+                cells.len()
+            )));
+        }
+        let rows: Vec<Vec<char>> = cells
+            .chunks(variant.alphabet.dimensions().1)
+            .map(<[char]>::to_vec)
+            .collect();
+        Ok(KeySquare::from_rows(rows))
+    }
+
+    /// Derives a square from a passphrase of any length.
+    ///
+    /// The symbols are shuffled (Fisher-Yates) with indices drawn from
+    /// SHA-256 digests of the passphrase and a counter, so every symbol of
+    /// the passphrase affects the whole square instead of only its first
+    /// cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `passphrase` - The passphrase, used byte for byte.
+    /// * `variant` - The variant deciding which symbols make up the table.
+    ///
+    /// # Returns
+    ///
+    /// * The same square for the same passphrase and variant.
+    pub fn from_passphrase(passphrase: &str, variant: &Variant) -> Self {
+        let mut symbols: Vec<char> = variant.symbols();
+        for i in (1..symbols.len()).rev() {
+            let digest = Sha256::new()
+                .chain_update(passphrase.as_bytes())
+                .chain_update((i as u64).to_be_bytes())
+                .finalize();
+            let value: u64 = u64::from_be_bytes(digest[..8].try_into().unwrap());
+            // Scales the 64-bit value to an index in 0..=i
+            let j: usize = ((u128::from(value) * (i as u128 + 1)) >> 64) as usize;
+            symbols.swap(i, j);
+        }
+        let rows: Vec<Vec<char>> = symbols
+            .chunks(variant.alphabet.dimensions().1)
+            .map(<[char]>::to_vec)
+            .collect();
+        KeySquare::from_rows(rows)
+    }
+
+    /// Computes the fingerprint of the square, see `table_fingerprint`.
+    pub fn fingerprint(&self) -> String {
+        table_fingerprint(self.rows())
+    }
+
+    /// Reads the symbols of the square along a route.
+    ///
+    /// Used as a keyword with the same route they generate exactly this
+    /// square, since every symbol appears once, in the order it is placed.
+    pub fn key(&self, route: Route) -> String {
+        route
+            .cells((self.rows().len(), self.rows()[0].len()))
+            .into_iter()
+            .map(|(i, j)| self.rows()[i][j])
+            .collect()
+    }
+}
+
+/// Where every symbol of a Playfair table is, found without scanning its
+/// rows
+#[derive(Debug, Clone, PartialEq)]
+struct Positions {
+    /// The cell of every letter from A to Z, `None` for those not in the
+    /// table
+    letters: [Option<(usize, usize)>; 26],
+    /// The cells of the other symbols, such as the digits of a 6x6 table or
+    /// the letters of a custom alphabet
+    others: Vec<(char, (usize, usize))>,
+}
+
+impl Positions {
+    /// Indexes the cells of a table.
+    fn new(table: &[Vec<char>]) -> Self {
+        let mut positions: Positions = Positions {
+            letters: [None; 26],
+            others: Vec::new(),
+        };
+        for (i, row) in table.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                match c {
+                    'A'..='Z' => positions.letters[c as usize - 'A' as usize] = Some((i, j)),
+                    _ => positions.others.push((c, (i, j))),
+                }
+            }
+        }
+        positions
+    }
+
+    /// Finds the position of a character in the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - The character to find.
+    ///
+    /// # Returns
+    ///
+    /// * A tuple `(row, col)` representing the position of the character in the table.
+    fn find(&self, c: char) -> (usize, usize) {
+        let cell: Option<(usize, usize)> = match c {
+            'A'..='Z' => self.letters[c as usize - 'A' as usize],
+            _ => self
+                .others
+                .iter()
+                .find(|&&(symbol, _)| symbol == c)
+                .map(|&(_, cell)| cell),
+        };
+        cell.expect("Character not found in table")
+    }
+}
+
+/// Computes a short fingerprint of a Playfair table.
+///
+/// The fingerprint identifies the table without revealing the key, so two
+/// parties can check that they derived the same square.
+///
+/// # Arguments
+///
+/// * `table` - The Playfair encryption table.
+///
+/// # Returns
+///
+/// * The first 8 bytes of the SHA-256 digest of the table letters, as hex.
+fn table_fingerprint(table: &[Vec<char>]) -> String {
+    let letters: String = table.iter().flatten().collect();
+    let digest = Sha256::digest(letters.as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Normalizes a text into an even-length sequence of letters ready to be split into pairs.
+///
+/// # Arguments
+///
+/// * `text` - The input text to encrypt or decrypt.
+/// * `variant` - The variant providing the merge rule and the filler letter.
+///
+/// # Returns
+///
+/// * A tuple `(letters, fillers)` with the prepared letters and the positions
+///   in `letters` where the filler letter was inserted. With the `error`
+///   strategy doubled digrams are left as they are, see `doubled_digrams`.
+pub fn prepare_text(text: &str, variant: &Variant) -> (Vec<char>, Vec<usize>) {
+    // Filter alphabetic characters and convert them to table letters
+    let mut chars: Vec<char> = table_letters(text, variant);
+    // Positions of the inserted filler letters
+    let mut fillers: Vec<usize> = Vec::new();
+
+    if variant.doubles == Doubles::All {
+        // Insert the filler between every two equal letters
+        let mut i: usize = 1;
+        while i < chars.len() {
+            let filler: char = variant.filler_after(chars[i]);
+            if chars[i - 1] == chars[i] && chars[i] != filler {
+                chars.insert(i, filler);
+                fillers.push(i);
+            }
+            i += 1;
+        }
+    } else if variant.doubles != Doubles::Error {
+        // Insert the filler between repeated characters in a pair, or drop
+        // the repeated one
+        let mut i: usize = 0;
+        while i < chars.len() {
+            if i + 1 < chars.len() && chars[i] == chars[i + 1] {
+                if variant.doubles == Doubles::Skip {
+                    chars.remove(i + 1);
+                    continue;
+                }
+                chars.insert(i + 1, variant.filler_after(chars[i]));
+                fillers.push(i + 1);
+            }
+            i += 2;
+        }
+    }
+
+    // If the text length is odd, make it even by appending the filler
+    if let Some(&last) = chars.last().filter(|_| !chars.len().is_multiple_of(2)) {
+        fillers.push(chars.len());
+        chars.push(variant.filler_after(last));
+    }
+
+    (chars, fillers)
+}
+
+/// Finds the digrams of a text made of the same letter twice.
+///
+/// # Returns
+///
+/// * The position of the first letter of every doubled digram among the
+///   table letters of the text, counted from 0.
+fn doubled_digrams(text: &str, variant: &Variant) -> Vec<usize> {
+    table_letters(text, variant)
+        .chunks(2)
+        .enumerate()
+        .filter(|(_, pair)| pair.len() == 2 && pair[0] == pair[1])
+        .map(|(i, _)| 2 * i)
+        .collect()
+}
+
+/// Checks a text against the `error` strategy for repeated letters.
+///
+/// # Returns
+///
+/// * An invalid input error listing the doubled digrams, if the strategy
+///   rejects them.
+fn check_doubles(text: &str, variant: &Variant) -> Result<(), Error> {
+    if variant.doubles != Doubles::Error {
+        return Ok(());
+    }
+    let doubled: Vec<usize> = doubled_digrams(text, variant);
+    if doubled.is_empty() {
+        return Ok(());
+    }
+    Err(Error::InvalidInput(format!(
+        "the text has doubled digrams at letter positions {}: rephrase it or choose another --doubles strategy",
+        doubled
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    )))
+}
+
+/// Enum to represent the rule used to transform a pair
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Rule {
+    Row,
+    Column,
+    Rectangle,
+}
+
+impl Rule {
+    /// Returns the name of the rule shown to the user.
+    fn name(self) -> &'static str {
+        match self {
+            Rule::Row => "same row",
+            Rule::Column => "same column",
+            Rule::Rectangle => "rectangle",
+        }
+    }
+}
+
+/// Encrypts or decrypts a single pair of letters.
+///
+/// # Arguments
+///
+/// * `table` - The Playfair encryption table.
+/// * `positions` - The positions of the symbols of the table.
+/// * `pair` - The two letters to transform; both must be in the table.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * The transformed pair and the rule that was applied.
+fn transform_pair(
+    table: &[Vec<char>],
+    positions: &Positions,
+    pair: (char, char),
+    mode: CipherMode,
+) -> ((char, char), Rule) {
+    let (r1, c1) = positions.find(pair.0);
+    let (r2, c2) = positions.find(pair.1);
+    let (rows, cols) = (table.len(), table[0].len());
+    // Encryption moves right/down by one, decryption left/up (n - 1 = -1 mod n)
+    let (row_shift, col_shift) = match mode {
+        CipherMode::Encrypt => (1, 1),
+        CipherMode::Decrypt => (rows - 1, cols - 1),
+    };
+
+    if r1 == r2 {
+        // Same row: shift columns
+        (
+            (
+                table[r1][(c1 + col_shift) % cols],
+                table[r2][(c2 + col_shift) % cols],
+            ),
+            Rule::Row,
+        )
+    } else if c1 == c2 {
+        // Same column: shift rows
+        (
+            (
+                table[(r1 + row_shift) % rows][c1],
+                table[(r2 + row_shift) % rows][c2],
+            ),
+            Rule::Column,
+        )
+    } else {
+        // Rectangle swap
+        ((table[r1][c2], table[r2][c1]), Rule::Rectangle)
+    }
+}
+
+/// Lists the letters entering every round of the cipher.
+///
+/// Before each round after the first, encryption rotates the letters left by
+/// one position, so the new pairs straddle the digrams of the previous round.
+/// Decryption runs the rounds backwards and rotates right after each round
+/// but the last.
+///
+/// # Arguments
+///
+/// * `chars` - The even-length letters produced by `prepare_text`, or the
+///   ciphertext letters when decrypting.
+/// * `table` - The Playfair encryption table.
+/// * `rounds` - How many times the cipher is applied.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * The input of every round in order, followed by the result.
+fn round_inputs(
+    chars: &[char],
+    table: &[Vec<char>],
+    rounds: usize,
+    mode: CipherMode,
+) -> Vec<Vec<char>> {
+    let mut inputs: Vec<Vec<char>> = vec![chars.to_vec()];
+    for round in 0..rounds {
+        let mut next: Vec<char> = process_pairs(&inputs[round], table, mode).chars().collect();
+        if round + 1 < rounds && !next.is_empty() {
+            match mode {
+                CipherMode::Encrypt => next.rotate_left(1),
+                CipherMode::Decrypt => next.rotate_right(1),
+            }
+        }
+        inputs.push(next);
+    }
+    inputs
+}
+
+/// Encrypts or decrypts letters for every round of the variant.
+///
+/// # Returns
+///
+/// * The result of the last round.
+fn process_rounds(chars: &[char], table: &[Vec<char>], rounds: usize, mode: CipherMode) -> String {
+    match rounds {
+        1 => process_pairs(chars, table, mode),
+        _ => round_inputs(chars, table, rounds, mode)
+            .pop()
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Encrypts or decrypts prepared letters pair by pair.
+///
+/// A text of more digrams than the table has pairs of cells is transformed
+/// by a `CompiledCipher`, which works every pair out once.
+///
+/// # Arguments
+///
+/// * `chars` - The even-length letters produced by `prepare_text`.
+/// * `table` - The Playfair encryption table.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * The encrypted or decrypted text.
+fn process_pairs(chars: &[char], table: &[Vec<char>], mode: CipherMode) -> String {
+    let cells: usize = table.len() * table[0].len();
+    if chars.len() / 2 > cells * cells {
+        return CompiledCipher::new(table, mode).process(chars);
+    }
+    let positions: Positions = Positions::new(table);
+    let mut result: String = String::new();
+
+    // Process pairs of characters
+    for chunk in chars.chunks(2) {
+        let ((a, b), _) = transform_pair(table, &positions, (chunk[0], chunk[1]), mode);
+        result.push(a);
+        result.push(b);
+    }
+
+    result
+}
+
+/// Describes how each pair of prepared letters is transformed.
+///
+/// # Arguments
+///
+/// * `chars` - The even-length letters produced by `prepare_text`.
+/// * `table` - The Playfair encryption table.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * One line per pair with the rule applied and the (row, column)
+///   coordinates, counted from 1, of the input and output letters.
+fn trace_pairs(chars: &[char], table: &[Vec<char>], mode: CipherMode) -> Vec<String> {
+    let positions: Positions = Positions::new(table);
+    let coordinates = |c: char| {
+        let (row, col) = positions.find(c);
+        format!("{}({},{})", c, row + 1, col + 1)
+    };
+    chars
+        .chunks(2)
+        .map(|chunk| {
+            let ((a, b), rule) = transform_pair(table, &positions, (chunk[0], chunk[1]), mode);
+            format!(
+                "{}{} -> {}{}  {}: {} {} -> {} {}",
+                chunk[0],
+                chunk[1],
+                a,
+                b,
+                rule.name(),
+                coordinates(chunk[0]),
+                coordinates(chunk[1]),
+                coordinates(a),
+                coordinates(b)
+            )
+        })
+        .collect()
+}
+
+/// Extracts the letters the cipher is applied to.
+///
+/// Seriated texts and ciphertexts of several rounds may contain doubled
+/// pairs, and ciphertexts may repeat a letter across two digrams, so they
+/// are only padded and not split by fillers.
+///
+/// # Returns
+///
+/// * A tuple `(letters, fillers)` with the even-length letters of the text
+///   and the positions where the filler letter was inserted.
+fn prepare_letters(text: &str, variant: &Variant, mode: CipherMode) -> (Vec<char>, Vec<usize>) {
+    let splits: bool = match mode {
+        CipherMode::Encrypt => true,
+        CipherMode::Decrypt => variant.rounds == 1 && variant.doubles != Doubles::All,
+    };
+    if variant.period.is_none() && splits {
+        return prepare_text(text, variant);
+    }
+    let mut chars: Vec<char> = table_letters(text, variant);
+    let mut fillers: Vec<usize> = Vec::new();
+    if let Some(&last) = chars.last().filter(|_| !chars.len().is_multiple_of(2)) {
+        fillers.push(chars.len());
+        chars.push(variant.filler_after(last));
+    }
+    (chars, fillers)
+}
+
+/// Orders prepared letters so that every pair the cipher transforms is
+/// adjacent.
+///
+/// Without a period the letters are already paired in order. With one, each
+/// block of `2 * period` letters is written in two rows of `period` letters
+/// and paired by columns; a shorter last block is split into two equal rows.
+fn pair_order<T: Copy>(chars: &[T], variant: &Variant) -> Vec<T> {
+    let Some(period) = variant.period else {
+        return chars.to_vec();
+    };
+    chars
+        .chunks(2 * period)
+        .flat_map(|block| {
+            let (top, bottom) = block.split_at(block.len() / 2);
+            top.iter().zip(bottom).flat_map(|(&a, &b)| [a, b])
+        })
+        .collect()
+}
+
+/// Reverses `pair_order`, writing the transformed pairs back into their rows.
+fn unpair_order<T: Copy>(pairs: &[T], variant: &Variant) -> Vec<T> {
+    let Some(period) = variant.period else {
+        return pairs.to_vec();
+    };
+    pairs
+        .chunks(2 * period)
+        .flat_map(|block| {
+            let top = block.iter().step_by(2);
+            let bottom = block.iter().skip(1).step_by(2);
+            top.chain(bottom).copied().collect::<Vec<T>>()
+        })
+        .collect()
+}
+
+/// Encrypts or decrypts prepared letters with every setting of the variant.
+///
+/// # Arguments
+///
+/// * `chars` - The even-length letters produced by `prepare_letters`.
+/// * `table` - The Playfair encryption table.
+/// * `variant` - The variant deciding the pairing and the number of rounds.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * The encrypted or decrypted text.
+fn process_letters(
+    chars: &[char],
+    table: &[Vec<char>],
+    variant: &Variant,
+    mode: CipherMode,
+) -> String {
+    let pairs: Vec<char> = pair_order(chars, variant);
+    let result: String = process_rounds(&pairs, table, variant.rounds, mode);
+    if variant.period.is_none() {
+        return result;
+    }
+    let result: Vec<char> = result.chars().collect();
+    unpair_order(&result, variant).into_iter().collect()
+}
+
+/// Encrypts or decrypts a text using the Playfair cipher.
+///
+/// # Arguments
+///
+/// * `text` - The input text to encrypt or decrypt.
+/// * `table` - The Playfair encryption table.
+/// * `variant` - The variant the table was generated with.
+/// * `mode` - The encryption or decryption mode.
+///
+/// # Returns
+///
+/// * The encrypted or decrypted text.
+pub fn playfair_cipher(
+    text: &str,
+    table: &[Vec<char>],
+    variant: &Variant,
+    mode: CipherMode,
+) -> String {
+    Playfair::from_rows(table.to_vec()).process(text, variant, mode)
+}
+
+/// The table of the Playfair cipher, a digram cipher of a single table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playfair {
+    /// The table filled from the key
+    square: KeySquare,
+}
+
+impl Playfair {
+    /// Generates the table of a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The keyword filling the table.
+    /// * `variant` - The variant the table is generated with.
+    pub fn new(key: &str, variant: &Variant) -> Self {
+        Playfair {
+            square: KeySquare::from_key(key, variant),
+        }
+    }
+
+    /// Wraps the rows of a table.
+    fn from_rows(rows: Vec<Vec<char>>) -> Self {
+        Playfair {
+            square: KeySquare::from_rows(rows),
+        }
+    }
+
+    /// The table filled from the key.
+    pub fn square(&self) -> &KeySquare {
+        &self.square
+    }
+}
+
+impl DigramCipher for Playfair {
+    fn name(&self) -> &'static str {
+        "playfair"
+    }
+
+    fn transform_pair(&self, pair: (char, char), mode: CipherMode) -> (char, char) {
+        transform_pair(self.square.rows(), &self.square.positions, pair, mode).0
+    }
+
+    fn keyed_squares(&self) -> Vec<&KeySquare> {
+        vec![&self.square]
+    }
+
+    fn rows(&self) -> Vec<String> {
+        self.square
+            .rows()
+            .iter()
+            .map(|cells| format!("{:?}", cells))
+            .collect()
+    }
+
+    /// Splits doubled letters with the filler before pairing the letters,
+    /// then pairs, seriates and repeats the rounds as the variant says.
+    fn process(&self, text: &str, variant: &Variant, mode: CipherMode) -> String {
+        let (chars, _) = prepare_letters(text, variant, mode);
+        process_letters(&chars, self.square.rows(), variant, mode)
+    }
+}
+
+/// A generated table together with the settings used to process texts
+#[derive(Debug, Clone)]
+struct CipherSettings {
+    /// The Playfair table generated from the key
+    cipher: Playfair,
+    /// The variant the table was generated with
+    variant: Variant,
+    /// Whether texts are encrypted or decrypted
+    mode: CipherMode,
+    /// How the result is grouped
+    style: OutputStyle,
+    /// Column order of the transposition applied after the cipher, if any
+    transposition: Option<Vec<usize>>,
+    /// Whether the result keeps the case pattern of the input letters
+    preserve_case: bool,
+    /// Whether the result keeps the punctuation, digits and whitespace of the
+    /// input in place, instead of being grouped
+    keep_layout: bool,
+    /// The dictionary words in which a filler is genuine, if fillers are
+    /// removed from decrypted texts
+    strip_filler: Option<Vec<Vec<char>>>,
+}
+
+impl CipherSettings {
+    /// Generates the table for a key and bundles it with the settings.
+    fn new(key: &str, variant: Variant, mode: CipherMode, style: OutputStyle) -> Self {
+        CipherSettings {
+            cipher: Playfair::new(key, &variant),
+            variant,
+            mode,
+            style,
+            transposition: None,
+            preserve_case: false,
+            keep_layout: false,
+            strip_filler: None,
+        }
+    }
+
+    /// Returns the text the Playfair stage is applied to.
+    ///
+    /// When decrypting with a transposition, it is undone first.
+    fn playfair_input(&self, text: &str) -> String {
+        match (&self.transposition, self.mode) {
+            (Some(order), CipherMode::Decrypt) => {
+                transposition::decrypt(&table_letters(text, &self.variant), order)
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// Works out which letters of the result are written in lowercase.
+    ///
+    /// Encryption takes the case of the plaintext letter at the same
+    /// position, an inserted filler that of the letter before it. The
+    /// ciphertext then carries the pattern, so decryption reads it back from
+    /// the ciphertext position by position.
+    fn case_pattern(&self, text: &str) -> Vec<bool> {
+        let mut lower: Vec<bool> = letter_case(text, &self.variant);
+        if matches!(self.mode, CipherMode::Encrypt) {
+            let (_, fillers) = prepare_letters(text, &self.variant, self.mode);
+            for i in fillers {
+                let before: bool = i > 0 && lower[i - 1];
+                lower.insert(i, before);
+            }
+        }
+        lower
+    }
+
+    /// Encrypts or decrypts a text and groups or lays out the result.
+    ///
+    /// # Returns
+    ///
+    /// * The result, or an invalid input error if the text contains no letters.
+    fn process(&self, text: &str) -> Result<String, Error> {
+        if table_letters(text, &self.variant).is_empty() {
+            return Err(Error::InvalidInput(String::from(
+                "the input contains no letters to process",
+            )));
+        }
+        if matches!(self.mode, CipherMode::Encrypt) && self.variant.period.is_none() {
+            check_doubles(text, &self.variant)?;
+        }
+        let input: String = self.playfair_input(text);
+        let result: String = self.cipher.process(&input, &self.variant, self.mode);
+        let result: String = match (&self.transposition, self.mode) {
+            (Some(order), CipherMode::Encrypt) => {
+                transposition::encrypt(&result.chars().collect::<Vec<char>>(), order)
+            }
+            _ => result,
+        };
+        let result: String = if self.preserve_case {
+            apply_case(&result, &self.case_pattern(text))
+        } else {
+            result
+        };
+        let result: String = if self.keep_layout {
+            let (_, fillers) = prepare_letters(text, &self.variant, self.mode);
+            restore_layout(text, &self.variant, &result, &fillers)
+        } else {
+            result
+        };
+        let result: String = match (&self.strip_filler, self.mode) {
+            (Some(words), CipherMode::Decrypt) => strip_fillers(&result, &self.variant, words),
+            _ => result,
+        };
+        if self.keep_layout {
+            return Ok(result);
+        }
+        Ok(self.style.apply(&result))
+    }
+
+    /// Decrypts an encryption result again and checks the round trip.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The plaintext that was encrypted.
+    /// * `result` - The ciphertext produced for it.
+    ///
+    /// # Returns
+    ///
+    /// * Notes on what the reader has to undo by hand: the inserted fillers,
+    ///   filler letters in the plaintext that look like inserted ones, and
+    ///   merged letters. An internal error if decryption doesn't restore the
+    ///   prepared text.
+    fn verify(&self, text: &str, result: &str) -> Result<Vec<String>, Error> {
+        let (prepared, fillers) = prepare_letters(text, &self.variant, CipherMode::Encrypt);
+        // Decrypt the way -d does, like the recipient would
+        let decrypter: CipherSettings = CipherSettings {
+            mode: CipherMode::Decrypt,
+            ..self.clone()
+        };
+        let decrypted: String = self.cipher.process(
+            &decrypter.playfair_input(result),
+            &self.variant,
+            CipherMode::Decrypt,
+        );
+        if decrypted != prepared.iter().collect::<String>() {
+            return Err(Error::Internal(format!(
+                "round trip failed: decryption gives {}",
+                decrypted
+            )));
+        }
+
+        let filler: char = self.variant.filler;
+        let letters: Vec<char> = table_letters(text, &self.variant);
+        let mut notes: Vec<String> = Vec::new();
+        if !fillers.is_empty() {
+            notes.push(format!(
+                "{} filler '{}' inserted at {} {} must be removed by the reader",
+                fillers.len(),
+                filler,
+                if fillers.len() == 1 {
+                    "position"
+                } else {
+                    "positions"
+                },
+                fillers
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        // A filler letter of the plaintext between equal letters or at the
+        // end is indistinguishable from an inserted one
+        let lookalikes: Vec<String> = (0..letters.len())
+            .filter(|&i| {
+                letters[i] == filler
+                    && (i + 1 == letters.len() || (i > 0 && letters[i - 1] == letters[i + 1]))
+            })
+            .map(|i| i.to_string())
+            .collect();
+        if !lookalikes.is_empty() {
+            notes.push(format!(
+                "warning: the plaintext has '{}' at letter positions {} where it looks like a filler",
+                filler,
+                lookalikes.join(", ")
+            ));
+        }
+        if let Some(pair) = pair_order(&prepared, &self.variant)
+            .chunks(2)
+            .find(|pair| pair[0] == pair[1])
+        {
+            notes.push(format!(
+                "warning: {} produced the doubled digram '{1}{1}', which some implementations reject",
+                if self.variant.period.is_some() {
+                    "seriation"
+                } else {
+                    "padding"
+                },
+                pair[0]
+            ));
+        }
+        let merged: usize = if self.variant.symbols().contains(&self.variant.merge.0) {
+            0
+        } else {
+            text.chars()
+                .filter(|&c| to_upper(c) == self.variant.merge.0)
+                .count()
+        };
+        if merged > 0 {
+            notes.push(match self.variant.merge {
+                (from, Some(into)) => {
+                    format!("warning: {} '{}' will decrypt as '{}'", merged, from, into)
+                }
+                (letter, None) => format!("warning: {} '{}' omitted from the text", merged, letter),
+            });
+        }
+        Ok(notes)
+    }
+}
+
+/// Reads a key from a file.
+///
+/// The file holds either a keyword or a raw 25-letter square on a single
+/// line. Trailing whitespace and newlines are stripped.
+///
+/// # Arguments
+///
+/// * `path` - Path of the key file.
+///
+/// # Returns
+///
+/// * The key, an I/O error if the file can't be read, or an invalid key
+///   error if it is empty or spans several lines.
+fn read_key_file(path: &str) -> Result<String, Error> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read key file '{}': {}", path, e)))?;
+    let key: &str = contents.trim_end();
+    if key.is_empty() {
+        return Err(Error::InvalidKey(format!("key file '{}' is empty", path)));
+    }
+    if key.contains('\n') {
+        return Err(Error::InvalidKey(format!(
+            "key file '{}' must contain the key on a single line",
+            path
+        )));
+    }
+    Ok(key.to_string())
+}
+
+/// Reads a list of keys from a file, one key per line.
+///
+/// Blank lines and lines starting with `#` are skipped, so a key schedule
+/// can carry comments. Surrounding whitespace is stripped from every key.
+///
+/// # Arguments
+///
+/// * `path` - Path of the key list.
+///
+/// # Returns
+///
+/// * The keys in file order, an I/O error if the file can't be read, or an
+///   invalid key error if it holds no keys.
+fn read_key_list(path: &str) -> Result<Vec<String>, Error> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read key list '{}': {}", path, e)))?;
+    let keys: Vec<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if keys.is_empty() {
+        return Err(Error::InvalidKey(format!("key list '{}' is empty", path)));
+    }
+    Ok(keys)
+}
+
+/// Reads the word list used by `--strip-filler`.
+///
+/// # Arguments
+///
+/// * `path` - Path of the word list, one word per line.
+/// * `variant` - The variant the words are converted to table letters with.
+///
+/// # Returns
+///
+/// * The words containing the filler, in table letters, or an I/O error if
+///   the file can't be read.
+fn read_dictionary(path: &str, variant: &Variant) -> Result<Vec<Vec<char>>, Error> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read dictionary '{}': {}", path, e)))?;
+    Ok(contents
+        .lines()
+        .map(|word| table_letters(word, variant))
+        .filter(|word| {
+            word.contains(&variant.filler)
+                || variant.alt_filler.is_some_and(|alt| word.contains(&alt))
+        })
+        .collect())
+}
+
+/// Picks the key from the available sources.
+///
+/// The precedence is `--key` first, then `--key-file`, then the
+/// `PLAYFAIR_KEY` environment variable. An empty variable is ignored.
+///
+/// # Arguments
+///
+/// * `flag` - The value of `--key`, if given.
+/// * `file` - The path given to `--key-file`, if any.
+/// * `env` - The value of `PLAYFAIR_KEY`, if set.
+///
+/// # Returns
+///
+/// * The key, `None` if no source provides one, or an error if the key file
+///   can't be used.
+fn resolve_key(
+    flag: Option<&String>,
+    file: Option<&String>,
+    env: Option<String>,
+) -> Result<Option<String>, Error> {
+    if let Some(key) = flag {
+        return Ok(Some(key.clone()));
+    }
+    if let Some(path) = file {
+        return read_key_file(path).map(Some);
+    }
+    Ok(env.filter(|key| !key.is_empty()))
+}
+
+/// Prompts for the key on the terminal with echo disabled.
+///
+/// # Arguments
+///
+/// * `confirm` - Whether to ask a second time and require both entries to match.
+///
+/// # Returns
+///
+/// * The key, or an invalid key error if there is no terminal to prompt on,
+///   the key is empty or the confirmation doesn't match.
+fn prompt_key(confirm: bool) -> Result<String, Error> {
+    let no_terminal = |e: std::io::Error| {
+        Error::InvalidKey(format!(
+            "no key given and cannot prompt for one ({}): use --key, --key-file or set {}",
+            e, KEY_ENV_VAR
+        ))
+    };
+    let key: String = rpassword::prompt_password("Key: ").map_err(no_terminal)?;
+    if key.is_empty() {
+        return Err(Error::InvalidKey(String::from("the key must not be empty")));
+    }
+    if confirm {
+        let again: String = rpassword::prompt_password("Confirm key: ").map_err(no_terminal)?;
+        if again != key {
+            return Err(Error::InvalidKey(String::from("the keys do not match")));
+        }
+    }
+    Ok(key)
+}
+
+/// Combines the config file with the command-line flags.
+///
+/// # Arguments
+///
+/// * `config` - The settings read from the config file.
+/// * `format_flag` - The value of `--format`, which overrides the config file.
+///
+/// # Returns
+///
+/// * The variant, the output style and the output format, or a usage error
+///   if a setting is invalid.
+fn settings_from_config(
+    config: &Config,
+    format_flag: Option<&String>,
+) -> Result<(Variant, OutputStyle, OutputFormat), Error> {
+    let mut variant: Variant = match &config.compat {
+        Some(name) => parse_compat(name)?,
+        None => Variant::default(),
+    };
+    if let Some(filler) = config.filler.map(to_upper) {
+        // A filler chosen as the alternative filler of the preset swaps
+        // places with the filler of the preset
+        if variant.alt_filler == Some(filler) {
+            variant.alt_filler = Some(variant.filler);
+        }
+        variant.filler = filler;
+    }
+    if let Some(alt) = config.alt_filler {
+        variant.alt_filler = Some(to_upper(alt));
+    }
+    match (&config.merge, &config.omit) {
+        (Some(_), Some(_)) => {
+            return Err(Error::Usage(String::from(
+                "merge and omit can't both be set",
+            )));
+        }
+        (Some(merge), None) => variant.merge = parse_merge(merge)?,
+        (None, Some(omit)) => variant.merge = parse_omit(omit)?,
+        (None, None) => {}
+    }
+    match (&config.grid, &config.alphabet) {
+        (Some(_), Some(_)) => {
+            return Err(Error::Usage(String::from(
+                "grid and alphabet can't both be set",
+            )));
+        }
+        (Some(grid), None) => variant.alphabet = parse_grid(grid)?,
+        (None, Some(alphabet)) => variant.alphabet = parse_alphabet(alphabet)?,
+        (None, None) => {}
+    }
+    if let Some(locale) = &config.locale {
+        variant.locale = Some(parse_locale(locale)?);
+    }
+    if let Some(route) = &config.route {
+        variant.route = parse_route(route)?;
+    }
+    match config.rounds {
+        Some(0) => {
+            return Err(Error::Usage(String::from(
+                "invalid number of rounds 0: expected at least 1",
+            )));
+        }
+        Some(rounds) => variant.rounds = rounds,
+        None => {}
+    }
+    if config.period == Some(0) {
+        return Err(Error::Usage(String::from(
+            "invalid period 0: expected at least 1",
+        )));
+    }
+    variant.period = config.period;
+    if let Some(doubles) = &config.doubles {
+        variant.doubles = parse_doubles(doubles)?;
+    }
+    // The Cyrillic presets have no X, so they default to its look-alike
+    if config.filler.is_none() && matches!(config.alphabet.as_deref(), Some("ru" | "uk")) {
+        variant.filler = 'Х';
+    }
+    validate_variant(&variant)?;
+
+    if config.group == Some(0) {
+        return Err(Error::Usage(String::from(
+            "invalid group size 0: expected at least 1",
+        )));
+    }
+    let mut style: OutputStyle = OutputStyle {
+        group: config.group,
+        lower: config.lower.unwrap_or(false),
+        ..OutputStyle::default()
+    };
+    if let Some(separator) = config.separator {
+        // Decryption skips the separator, so it can't be a symbol of the table
+        if normalize_char(separator, &variant).is_some() {
+            return Err(Error::Usage(format!(
+                "invalid separator '{}': it is a symbol of the table",
+                separator
+            )));
+        }
+        style.separator = separator;
+    }
+
+    let format: OutputFormat = match format_flag.or(config.format.as_ref()).map(String::as_str) {
+        None | Some("text") => OutputFormat::Text,
+        Some("json") => OutputFormat::Json,
+        Some("nato") => OutputFormat::Nato,
+        Some("morse") => OutputFormat::Morse,
+        Some(other) => {
+            return Err(Error::Usage(format!(
+                "invalid format '{}': expected 'text', 'json', 'nato' or 'morse'",
+                other
+            )));
+        }
+    };
+
+    Ok((variant, style, format))
+}
+
+/// Writes settings back in the form of the config file.
+///
+/// # Arguments
+///
+/// * `variant` - The variant to write.
+/// * `style` - The output style to write.
+///
+/// # Returns
+///
+/// * The config that `settings_from_config` turns back into the same
+///   variant and grouping.
+fn config_from_settings(variant: &Variant, style: &OutputStyle) -> Config {
+    let (reduction, letters) = variant.reduction();
+    Config {
+        filler: Some(variant.filler),
+        alt_filler: variant.alt_filler,
+        merge: (reduction == "merge").then(|| letters.clone()),
+        omit: (reduction == "omit").then_some(letters),
+        grid: match variant.alphabet {
+            Alphabet::Letters => Some(String::from("5x5")),
+            Alphabet::Alphanumeric => Some(String::from("6x6")),
+            Alphabet::Custom(_) => None,
+        },
+        alphabet: match &variant.alphabet {
+            Alphabet::Custom(symbols) => Some(symbols.iter().collect()),
+            _ => None,
+        },
+        locale: variant.locale.map(|locale| locale.name().to_string()),
+        route: Some(variant.route.name().to_string()),
+        rounds: Some(variant.rounds),
+        period: variant.period,
+        doubles: Some(variant.doubles.name().to_string()),
+        group: style.group,
+        separator: style.group.map(|_| style.separator),
+        ..Config::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_playfair_table() {
+        let key: &str = "KEYWORD";
+        let table: Vec<Vec<char>> = generate_playfair_table(key, &Variant::default());
+        assert_eq!(table.len(), 5);
+        assert_eq!(table[0].len(), 5);
+        assert!(table.iter().flatten().all(|&c| c != 'J'));
+    }
+
+    #[test]
+    fn test_playfair_encrypt() {
+        let key: &str = "KEYWORD";
+        let table: Vec<Vec<char>> = generate_playfair_table(key, &Variant::default());
+        let plaintext: &str = "HELLO";
+        let encrypted: String =
+            playfair_cipher(plaintext, &table, &Variant::default(), CipherMode::Encrypt);
+        assert_eq!(encrypted, "GYIZSC");
+    }
+
+    #[test]
+    fn test_playfair_decrypt() {
+        let key: &str = "KEYWORD";
+        let table: Vec<Vec<char>> = generate_playfair_table(key, &Variant::default());
+        let encrypted: &str = "GYIZSC";
+        let decrypted: String =
+            playfair_cipher(encrypted, &table, &Variant::default(), CipherMode::Decrypt);
+        assert_eq!(decrypted, "HELXLO");
+    }
+
+    #[test]
+    fn test_playfair_with_repeated_characters() {
+        let key: &str = "KEYWORD";
+        let table: Vec<Vec<char>> = generate_playfair_table(key, &Variant::default());
+        let plaintext: &str = "BALLOON";
+        let encrypted: String =
+            playfair_cipher(plaintext, &table, &Variant::default(), CipherMode::Encrypt);
+        assert_eq!(encrypted, "CBIZSCES");
+    }
+
+    #[test]
+    fn test_doubles_strategies() {
+        let prepare = |text: &str, doubles: Doubles| {
+            let variant: Variant = Variant {
+                doubles,
+                ..Variant::default()
+            };
+            let (chars, fillers) = prepare_text(text, &variant);
+            (chars.into_iter().collect::<String>(), fillers)
+        };
+        assert_eq!(
+            prepare("BALLOON", Doubles::Digram),
+            (String::from("BALXLOON"), vec![3])
+        );
+        assert_eq!(
+            prepare("BOOK", Doubles::Digram),
+            (String::from("BOOK"), vec![])
+        );
+        assert_eq!(
+            prepare("BOOK", Doubles::All),
+            (String::from("BOXOKX"), vec![2, 5])
+        );
+        assert_eq!(
+            prepare("BALLOON", Doubles::Skip),
+            (String::from("BALOON"), vec![])
+        );
+        assert_eq!(prepare("LLL", Doubles::Skip), (String::from("LX"), vec![1]));
+
+        let error: Variant = Variant {
+            doubles: Doubles::Error,
+            ..Variant::default()
+        };
+        assert_eq!(doubled_digrams("BALLOON", &error), vec![2, 4]);
+        assert!(check_doubles("BALLOON", &error).is_err());
+        assert!(check_doubles("BOOK", &error).is_ok());
+        assert!(check_doubles("BALLOON", &Variant::default()).is_ok());
+        assert!(parse_doubles("twice").is_err());
+
+        // Ciphertexts repeating a letter across digrams are not split
+        let all: Variant = Variant {
+            doubles: Doubles::All,
+            ..Variant::default()
+        };
+        let table: Vec<Vec<char>> = generate_playfair_table("KEYWORD", &all);
+        let encrypted: String = playfair_cipher("HELLO BALLOON", &table, &all, CipherMode::Encrypt);
+        assert_eq!(encrypted, "GYIZSCCBIZSCZWQU");
+        assert_eq!(
+            playfair_cipher(&encrypted, &table, &all, CipherMode::Decrypt),
+            "HELXLOBALXLOXONX"
+        );
+    }
+
+    #[test]
+    fn test_playfair_with_odd_length() {
+        let key: &str = "KEYWORD";
+        let table: Vec<Vec<char>> = generate_playfair_table(key, &Variant::default());
+        let plaintext: &str = "TEST";
+        let encrypted: String =
+            playfair_cipher(plaintext, &table, &Variant::default(), CipherMode::Encrypt);
+        assert!(encrypted.len().is_multiple_of(2));
+    }
+
+    #[test]
+    fn test_prepare_text_filler_positions() {
+        let (chars, fillers) = prepare_text("BALLOON", &Variant::default());
+        let prepared: String = chars.iter().collect();
+        assert_eq!(prepared, "BALXLOON");
+        assert_eq!(fillers, vec![3]);
+
+        let (chars, fillers) = prepare_text("HELLO WORLD", &Variant::default());
+        assert_eq!(chars.len(), 12);
+        assert_eq!(fillers, vec![3, 11]);
+    }
+
+    #[test]
+    fn test_trace_pairs() {
+        let table: Vec<Vec<char>> = generate_playfair_table("KEYWORD", &Variant::default());
+        let (chars, _) = prepare_text("HELLO", &Variant::default());
+        let trace: Vec<String> = trace_pairs(&chars, &table, CipherMode::Encrypt);
+        assert_eq!(
+            trace,
+            vec![
+                "HE -> GY  rectangle: H(3,3) E(1,2) -> G(3,2) Y(1,3)",
+                "LX -> IZ  rectangle: L(3,5) X(5,4) -> I(3,4) Z(5,5)",
+                "LO -> SC  same column: L(3,5) O(1,5) -> S(4,5) C(2,5)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_round_trip() {
+        let settings: CipherSettings = CipherSettings::new(
+            "KEYWORD",
+            Variant::default(),
+            CipherMode::Encrypt,
+            OutputStyle {
+                group: Some(5),
+                ..OutputStyle::default()
+            },
+        );
+        let result: String = settings.process("HELLO WORLD").unwrap();
+        assert_eq!(
+            settings.verify("HELLO WORLD", &result).unwrap(),
+            vec!["2 filler 'X' inserted at positions 3, 11 must be removed by the reader"]
+        );
+        // The padded XX encrypts to a doubled digram, which -d splits again
+        assert!(
+            settings
+                .verify("AXA jinx", &settings.process("AXA jinx").unwrap())
+                .is_err()
+        );
+        let all: CipherSettings = CipherSettings::new(
+            "KEYWORD",
+            Variant {
+                doubles: Doubles::All,
+                ..Variant::default()
+            },
+            CipherMode::Encrypt,
+            OutputStyle::default(),
+        );
+        let notes: Vec<String> = settings
+            .verify("AXA jinxes", &settings.process("AXA jinxes").unwrap())
+            .unwrap();
+        assert_eq!(
+            notes,
+            vec![
+                "1 filler 'X' inserted at position 9 must be removed by the reader",
+                "warning: the plaintext has 'X' at letter positions 1 where it looks like a filler",
+                "warning: 1 'J' will decrypt as 'I'",
+            ]
+        );
+        assert_eq!(
+            all.verify("FOX", &all.process("FOX").unwrap()).unwrap(),
+            vec![
+                "1 filler 'X' inserted at position 3 must be removed by the reader",
+                "warning: the plaintext has 'X' at letter positions 2 where it looks like a filler",
+                "warning: padding produced the doubled digram 'XX', which some implementations reject",
+            ]
+        );
+        assert!(settings.verify("HELLO", "GYIZSD").is_err());
+    }
+
+    #[test]
+    fn test_variant_merge_and_filler() {
+        let variant: Variant = Variant {
+            filler: 'Q',
+            alt_filler: None,
+            merge: parse_merge("V=U").unwrap(),
+            alphabet: Alphabet::Letters,
+            locale: None,
+            route: Route::Rows,
+            rounds: 1,
+            period: None,
+            doubles: Doubles::Digram,
+        };
+        let table: Vec<Vec<char>> = generate_playfair_table("jovial", &variant);
+        assert!(table.iter().flatten().all(|&c| c != 'V'));
+        assert_eq!(table[0][0], 'J');
+        let (chars, fillers) = prepare_text("VOLLEY", &variant);
+        let prepared: String = chars.iter().collect();
+        assert_eq!(prepared, "UOLQLEYQ");
+        assert_eq!(fillers, vec![3, 7]);
+    }
+
+    #[test]
+    fn test_config_from_settings() {
+        let variants: [Variant; 3] = [
+            Variant::default(),
+            Variant {
+                filler: 'Q',
+                alt_filler: Some('Z'),
+                merge: ('K', None),
+                alphabet: parse_grid("6x6").unwrap(),
+                locale: Some(Locale::German),
+                route: Route::Spiral,
+                rounds: 2,
+                period: Some(5),
+                doubles: Doubles::All,
+            },
+            Variant {
+                filler: 'Х',
+                alphabet: parse_alphabet("ru").unwrap(),
+                ..Variant::default()
+            },
+        ];
+        let style: OutputStyle = OutputStyle {
+            group: Some(5),
+            separator: '/',
+            lower: false,
+        };
+        for variant in variants {
+            let config: Config = config_from_settings(&variant, &style);
+            let (back, back_style, _) = settings_from_config(&config, None).unwrap();
+            assert_eq!((back, back_style), (variant, style.clone()));
+        }
+    }
+
+    #[test]
+    fn test_alphanumeric_grid() {
+        let variant: Variant = Variant {
+            alphabet: parse_grid("6x6").unwrap(),
+            ..Variant::default()
+        };
+        let table: Vec<Vec<char>> = generate_playfair_table("playfair 2024", &variant);
+        assert_eq!(table.len(), 6);
+        assert!(table.iter().all(|row| row.len() == 6));
+        assert_eq!(table[1], vec!['R', '2', '0', '4', 'B', 'C']);
+        assert!(table.iter().flatten().any(|&c| c == 'J'));
+
+        let encrypted: String = playfair_cipher("10pm", &table, &variant, CipherMode::Encrypt);
+        assert_eq!(encrypted, "WCRU");
+        let decrypted: String = playfair_cipher(&encrypted, &table, &variant, CipherMode::Decrypt);
+        assert_eq!(decrypted, "10PM");
+        assert!(parse_grid("7x7").is_err());
+    }
+
+    #[test]
+    fn test_locale_spells_out_letters() {
+        let german: Variant = Variant {
+            locale: Some(parse_locale("de").unwrap()),
+            ..Variant::default()
+        };
+        let letters: String = table_letters("Grüße, Jörg", &german).into_iter().collect();
+        assert_eq!(letters, "GRUESSEIOERG");
+        assert_eq!(skipped_letters("Grüße", &german), Vec::<char>::new());
+        assert_eq!(
+            skipped_letters("Grüße", &Variant::default()),
+            vec!['Ü', 'ß']
+        );
+
+        let turkish: Variant = Variant {
+            locale: Some(parse_locale("tr").unwrap()),
+            ..Variant::default()
+        };
+        let letters: String = table_letters("İstanbul ılık", &turkish)
+            .into_iter()
+            .collect();
+        assert_eq!(letters, "ISTANBULILIK");
+
+        let spanish: Variant = Variant {
+            locale: Some(parse_locale("es").unwrap()),
+            ..Variant::default()
+        };
+        let table: Vec<Vec<char>> = generate_playfair_table("Señor", &spanish);
+        assert_eq!(table[0], vec!['S', 'E', 'N', 'O', 'R']);
+        assert!(parse_locale("fr").is_err());
+    }
+
+    #[test]
+    fn test_cyrillic_alphabet() {
+        let config: Config = config::parse("alphabet = \"ru\"\n").unwrap();
+        let (variant, _, _) = settings_from_config(&config, None).unwrap();
+        assert_eq!(variant.alphabet.dimensions(), (6, 6));
+        assert_eq!(variant.filler, 'Х');
+        let table: Vec<Vec<char>> = generate_playfair_table("Шифровка", &variant);
+        assert_eq!(table[0], vec!['Ш', 'И', 'Ф', 'Р', 'О', 'В']);
+        assert_eq!(table[5], vec!['Э', 'Ю', 'Я', '.', ',', '-']);
+
+        let encrypted: String = playfair_cipher("ёлка", &table, &variant, CipherMode::Encrypt);
+        let decrypted: String = playfair_cipher(&encrypted, &table, &variant, CipherMode::Decrypt);
+        assert_eq!(decrypted, "ЁЛКА");
+        assert!(matches!(parse_alphabet("uk"), Ok(Alphabet::Custom(s)) if s.contains(&'Ґ')));
+        // Other tables without X keep the filler and report it as omitted
+        let config: Config = config::parse("omit = \"X\"\n").unwrap();
+        let Err(Error::Usage(e)) = settings_from_config(&config, None) else {
+            panic!("an omitted filler is an error");
+        };
+        assert!(!e.contains('Х'), "{}", e);
+    }
+
+    #[test]
+    fn test_preserve_case() {
+        let german: Variant = Variant {
+            locale: Some(Locale::German),
+            ..Variant::default()
+        };
+        assert_eq!(
+            letter_case("Grüße!", &german),
+            vec![false, true, true, true, true, true, true]
+        );
+        assert_eq!(apply_case("AB7CD", &[true, false, true, true]), "aB7cD");
+
+        let mut settings: CipherSettings = CipherSettings::new(
+            "KEYWORD",
+            Variant::default(),
+            CipherMode::Encrypt,
+            OutputStyle::default(),
+        );
+        settings.preserve_case = true;
+        // The filler inserted between the two l takes the case of the first
+        let encrypted: String = settings.process("Hello World").unwrap();
+        assert_eq!(encrypted, "GyizscOkcfbu");
+        settings.mode = CipherMode::Decrypt;
+        assert_eq!(settings.process(&encrypted).unwrap(), "HelxloWorldx");
+    }
+
+    #[test]
+    fn test_keep_layout() {
+        let variant: Variant = Variant::default();
+        // The fillers follow the letters they were inserted after
+        assert_eq!(
+            restore_layout("Hello, World!", &variant, "GYIZSCOKCFBU", &[3, 11]),
+            "GYIZSC, OKCFBU!"
+        );
+        // Letters missing from the table are dropped, other characters kept
+        let german: Variant = Variant {
+            merge: ('Q', None),
+            locale: Some(Locale::German),
+            ..Variant::default()
+        };
+        assert_eq!(
+            restore_layout("ß-Quiz 1", &german, "ABCDEF", &[5]),
+            "AB-CDEF 1"
+        );
+
+        let mut settings: CipherSettings = CipherSettings::new(
+            "KEYWORD",
+            variant,
+            CipherMode::Encrypt,
+            OutputStyle {
+                group: Some(5),
+                ..OutputStyle::default()
+            },
+        );
+        settings.keep_layout = true;
+        let encrypted: String = settings.process("Hello, World!").unwrap();
+        assert_eq!(encrypted, "GYIZSC, OKCFBU!");
+        settings.mode = CipherMode::Decrypt;
+        assert_eq!(settings.process(&encrypted).unwrap(), "HELXLO, WORLDX!");
+    }
+
+    #[test]
+    fn test_dropped_chars() {
+        let variant: Variant = Variant::default();
+        assert_eq!(
+            dropped_chars("Meet me at 10, Jo", &variant, false),
+            vec![(11, '1'), (12, '0'), (13, ',')]
+        );
+        assert_eq!(dropped_chars("Meet me at 10, Jo", &variant, true), vec![]);
+        assert_eq!(
+            dropped_chars("Grüße", &variant, true),
+            vec![(2, 'ü'), (3, 'ß')]
+        );
+
+        let variant: Variant = Variant {
+            alphabet: Alphabet::Alphanumeric,
+            locale: Some(Locale::German),
+            ..Variant::default()
+        };
+        assert_eq!(dropped_chars("Grüße 10", &variant, false), vec![]);
+    }
+
+    #[test]
+    fn test_strip_fillers() {
+        let variant: Variant = Variant::default();
+        assert_eq!(strip_fillers("HELXLOWORLDX", &variant, &[]), "HELLOWORLD");
+        // Only the second letter of a pair can be an inserted filler
+        assert_eq!(strip_fillers("BAXAEF", &variant, &[]), "BAXAEF");
+        assert_eq!(
+            strip_fillers("Helxlo, Worldx!", &variant, &[]),
+            "Hello, World!"
+        );
+
+        let dictionary: Vec<Vec<char>> = vec!["EXEMPT".chars().collect(), "BOX".chars().collect()];
+        assert_eq!(strip_fillers("ABEXEMPT", &variant, &dictionary), "ABEXEMPT");
+        assert_eq!(strip_fillers("ABEXEMPT", &variant, &[]), "ABEEMPT");
+        assert_eq!(strip_fillers("THEBOX", &variant, &dictionary), "THEBOX");
+
+        let all: Variant = Variant {
+            doubles: Doubles::All,
+            ..Variant::default()
+        };
+        assert_eq!(strip_fillers("BOXOKX", &all, &[]), "BOOK");
+    }
+
+    #[test]
+    fn test_custom_alphabet() {
+        let variant: Variant = Variant {
+            alphabet: parse_alphabet("abcdefghijklmnoprstuvwxyz").unwrap(),
+            ..Variant::default()
+        };
+        assert_eq!(variant.alphabet.dimensions(), (5, 5));
+        let table: Vec<Vec<char>> = generate_playfair_table("jumbo", &variant);
+        assert_eq!(table[0], vec!['J', 'U', 'M', 'B', 'O']);
+        assert!(table.iter().flatten().all(|&c| c != 'Q'));
+        assert_eq!(normalize_char('q', &variant), None);
+        assert!(validate_variant(&variant).is_ok());
+
+        assert!(parse_alphabet("ABC").is_err());
+        assert!(parse_alphabet("ABCDEFGHIKLMNOPQRSTUVWXYA").is_err());
+        assert!(parse_alphabet("ABCDEFGHIKLMNOPQRSTUVWXY ").is_err());
+        let digits: Alphabet = parse_alphabet("0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap();
+        assert_eq!(digits.dimensions(), (6, 6));
+    }
+
+    #[test]
+    fn test_rectangular_grid() {
+        assert_eq!(grid_dimensions(30), Some((5, 6)));
+        assert_eq!(grid_dimensions(28), Some((4, 7)));
+        assert_eq!(grid_dimensions(36), Some((6, 6)));
+        assert_eq!(grid_dimensions(29), None);
+        assert_eq!(grid_dimensions(27), None);
+
+        let variant: Variant = Variant {
+            alphabet: parse_alphabet("ABCDEFGHIJKLMNOPQRSTUVWXYZ.,?!").unwrap(),
+            ..Variant::default()
+        };
+        assert_eq!(variant.grid(), "5x6");
+        // J is in the table, so the default merge doesn't apply
+        assert_eq!(variant.applied_reduction(), None);
+        assert_eq!(
+            Variant::default().applied_reduction(),
+            Some(("merge", String::from("J=I")))
+        );
+        let table: Vec<Vec<char>> = generate_playfair_table("KEYWORD", &variant);
+        assert_eq!(table[0], vec!['K', 'E', 'Y', 'W', 'O', 'R']);
+        assert_eq!(table[4], vec!['X', 'Z', '.', ',', '?', '!']);
+        let positions: Positions = Positions::new(&table);
+        assert_eq!(positions.find('K'), (0, 0));
+        assert_eq!(positions.find('!'), (4, 5));
+        // Rows wrap after six columns and columns after five rows
+        assert_eq!(
+            transform_pair(&table, &positions, ('K', 'R'), CipherMode::Encrypt).0,
+            ('E', 'K')
+        );
+        assert_eq!(
+            transform_pair(&table, &positions, ('K', 'X'), CipherMode::Encrypt).0,
+            ('D', 'K')
+        );
+        assert_eq!(
+            transform_pair(&table, &positions, ('E', 'K'), CipherMode::Decrypt).0,
+            ('K', 'R')
+        );
+
+        for (spec, dimensions) in [
+            ("ABCDEFGHIJKLMNOPQRSTUVWXYZ.,?!", (5, 6)),
+            ("ABCDEFGHIJKLMNOPQRSTUVWXYZ.,", (4, 7)),
+        ] {
+            for route in [
+                Route::Rows,
+                Route::Columns,
+                Route::Spiral,
+                Route::Zigzag,
+                Route::Reversed,
+            ] {
+                let mut cells: Vec<(usize, usize)> = route.cells(dimensions);
+                cells.sort_unstable();
+                cells.dedup();
+                assert_eq!(cells.len(), dimensions.0 * dimensions.1, "{}", route.name());
+
+                let variant: Variant = Variant {
+                    alphabet: parse_alphabet(spec).unwrap(),
+                    route,
+                    ..Variant::default()
+                };
+                let table: Vec<Vec<char>> = generate_playfair_table("Meet me, now!", &variant);
+                assert_eq!((table.len(), table[0].len()), dimensions);
+                let encrypted: String =
+                    playfair_cipher("Meet me, now!", &table, &variant, CipherMode::Encrypt);
+                assert_eq!(
+                    playfair_cipher(&encrypted, &table, &variant, CipherMode::Decrypt),
+                    prepare_text("Meet me, now!", &variant)
+                        .0
+                        .into_iter()
+                        .collect::<String>()
+                );
+                let square: KeySquare = KeySquare::from_rows(table);
+                assert_eq!(
+                    KeySquare::from_letters(&square.key(Route::Rows), &variant),
+                    Ok(square.clone())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_merge() {
+        assert_eq!(parse_merge("j=i").unwrap(), ('J', Some('I')));
+        assert_eq!(parse_omit("q").unwrap(), ('Q', None));
+        assert!(parse_omit("QU").is_err());
+        assert!(parse_merge("J=J").is_err());
+        assert!(parse_merge("JI").is_err());
+        assert!(parse_merge("J=1").is_err());
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let config: Config =
+            config::parse("filler = \"q\"\ngroup = 5\nformat = \"json\"\n").unwrap();
+        let (variant, style, format) = settings_from_config(&config, None).unwrap();
+        assert_eq!(variant.filler, 'Q');
+        assert_eq!(style.group, Some(5));
+        assert_eq!(format, OutputFormat::Json);
+
+        let text: String = String::from("text");
+        let (_, _, format) = settings_from_config(&config, Some(&text)).unwrap();
+        assert_eq!(format, OutputFormat::Text);
+
+        let config: Config = config::parse("filler = \"J\"\n").unwrap();
+        assert!(settings_from_config(&config, None).is_err());
+
+        let config: Config = config::parse("omit = \"Q\"\n").unwrap();
+        let (variant, _, _) = settings_from_config(&config, None).unwrap();
+        assert_eq!(variant.merge, ('Q', None));
+        assert!(!variant.symbols().contains(&'Q'));
+        assert_eq!(normalize_char('q', &variant), None);
+        let config: Config = config::parse("omit = \"Q\"\nmerge = \"J=I\"\n").unwrap();
+        assert!(settings_from_config(&config, None).is_err());
+    }
+
+    #[test]
+    fn test_read_key_file() {
+        let dir: std::path::PathBuf = std::env::temp_dir();
+        let single: std::path::PathBuf = dir.join("playfair_test_key_single.txt");
+        std::fs::write(&single, "KEYWORD \n\n").unwrap();
+        assert_eq!(read_key_file(single.to_str().unwrap()).unwrap(), "KEYWORD");
+
+        let multi: std::path::PathBuf = dir.join("playfair_test_key_multi.txt");
+        std::fs::write(&multi, "KEY\nWORD\n").unwrap();
+        assert!(read_key_file(multi.to_str().unwrap()).is_err());
+
+        let empty: std::path::PathBuf = dir.join("playfair_test_key_empty.txt");
+        std::fs::write(&empty, "\n").unwrap();
+        assert!(read_key_file(empty.to_str().unwrap()).is_err());
+
+        assert!(
+            read_key_file(dir.join("playfair_test_key_missing.txt").to_str().unwrap()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_resolve_key_precedence() {
+        let flag: String = String::from("FLAG");
+        let path: std::path::PathBuf =
+            std::env::temp_dir().join("playfair_test_key_precedence.txt");
+        std::fs::write(&path, "FILE\n").unwrap();
+        let file: String = path.to_str().unwrap().to_string();
+        let env: Option<String> = Some(String::from("ENV"));
+
+        assert_eq!(
+            resolve_key(Some(&flag), Some(&file), env.clone()).unwrap(),
+            Some(flag.clone())
+        );
+        assert_eq!(
+            resolve_key(None, Some(&file), env.clone()).unwrap(),
+            Some(String::from("FILE"))
+        );
+        assert_eq!(resolve_key(None, None, env.clone()).unwrap(), env);
+        assert_eq!(resolve_key(None, None, Some(String::new())).unwrap(), None);
+        assert_eq!(resolve_key(None, None, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_fill_routes() {
+        let table = |route: Route| {
+            let variant: Variant = Variant {
+                route,
+                ..Variant::default()
+            };
+            generate_playfair_table("KEYWORD", &variant)
+        };
+        assert_eq!(table(Route::Columns)[0], vec!['K', 'R', 'F', 'M', 'T']);
+        assert_eq!(table(Route::Columns)[1], vec!['E', 'D', 'G', 'N', 'U']);
+        assert_eq!(table(Route::Spiral)[0], vec!['K', 'E', 'Y', 'W', 'O']);
+        assert_eq!(table(Route::Spiral)[2], vec!['L', 'X', 'Z', 'S', 'D']);
+        assert_eq!(table(Route::Zigzag)[1], vec!['C', 'B', 'A', 'D', 'R']);
+        assert_eq!(table(Route::Reversed)[4], vec!['O', 'W', 'Y', 'E', 'K']);
+        assert_eq!(Route::Spiral.cells((6, 6)).len(), 36);
+
+        // The route only changes the table, so decryption still inverts encryption
+        let variant: Variant = Variant {
+            route: Route::Spiral,
+            ..Variant::default()
+        };
+        let spiral: Vec<Vec<char>> = table(Route::Spiral);
+        let encrypted: String =
+            playfair_cipher("HELLO WORLD", &spiral, &variant, CipherMode::Encrypt);
+        assert_ne!(encrypted, "GYIZSCOKCFBU");
+        assert_eq!(
+            playfair_cipher(&encrypted, &spiral, &variant, CipherMode::Decrypt),
+            "HELXLOWORLDX"
+        );
+    }
+
+    #[test]
+    fn test_rounds_round_trip() {
+        let variant: Variant = Variant {
+            rounds: 3,
+            ..Variant::default()
+        };
+        let table: Vec<Vec<char>> = generate_playfair_table("KEYWORD", &variant);
+        let encrypted: String =
+            playfair_cipher("HELLO WORLD", &table, &variant, CipherMode::Encrypt);
+        assert_ne!(encrypted, "GYIZSCOKCFBU");
+        assert_eq!(
+            playfair_cipher(&encrypted, &table, &variant, CipherMode::Decrypt),
+            "HELXLOWORLDX"
+        );
+        // Shifting the pairs of SOS gives the doubled pair ZZ in the second round
+        let twice: Variant = Variant {
+            rounds: 2,
+            ..Variant::default()
+        };
+        let encrypted: String = playfair_cipher("SOS", &table, &twice, CipherMode::Encrypt);
+        assert_eq!(
+            playfair_cipher(&encrypted, &table, &twice, CipherMode::Decrypt),
+            "SOSX"
+        );
+
+        // The second round pairs the letters across the digrams of the first
+        let chars: Vec<char> = "GYIZ".chars().collect();
+        let inputs: Vec<Vec<char>> = round_inputs(&chars, &table, 2, CipherMode::Encrypt);
+        let once: String = process_pairs(&chars, &table, CipherMode::Encrypt);
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(
+            inputs[1].iter().collect::<String>(),
+            once[1..].to_string() + &once[..1]
+        );
+    }
+
+    #[test]
+    fn test_seriated_pairs() {
+        let variant: Variant = Variant {
+            period: Some(3),
+            ..Variant::default()
+        };
+        let (chars, fillers) = prepare_letters("BALLOONIST", &variant, CipherMode::Encrypt);
+        // Doubled letters are not split, so only the length is padded
+        assert_eq!(chars.iter().collect::<String>(), "BALLOONIST");
+        assert!(fillers.is_empty());
+        // BAL over LOO and NI over ST
+        let pairs: String = pair_order(&chars, &variant).into_iter().collect();
+        assert_eq!(pairs, "BLAOLONSIT");
+        assert_eq!(
+            unpair_order(&pairs.chars().collect::<Vec<char>>(), &variant),
+            chars
+        );
+
+        let table: Vec<Vec<char>> = generate_playfair_table("KEYWORD", &variant);
+        let encrypted: String =
+            playfair_cipher("BALLOONIST", &table, &variant, CipherMode::Encrypt);
+        assert_ne!(
+            encrypted,
+            playfair_cipher(
+                "BALLOONIST",
+                &table,
+                &Variant::default(),
+                CipherMode::Encrypt
+            )
+        );
+        assert_eq!(
+            playfair_cipher(&encrypted, &table, &variant, CipherMode::Decrypt),
+            "BALLOONIST"
+        );
+    }
+
+    #[test]
+    fn test_playfair_columnar() {
+        let mut settings: CipherSettings = CipherSettings::new(
+            "KEYWORD",
+            Variant::default(),
+            CipherMode::Encrypt,
+            OutputStyle::default(),
+        );
+        settings.transposition = Some(transposition::column_order("ZEBRAS").unwrap());
+        // GYIZSCOKCFBU written under ZEBRAS and read by columns
+        let encrypted: String = settings.process("HELLO WORLD").unwrap();
+        assert_eq!(encrypted, "SBICYKZFCUGO");
+        assert_eq!(settings.verify("HELLO WORLD", &encrypted).unwrap().len(), 1);
+
+        settings.mode = CipherMode::Decrypt;
+        assert_eq!(settings.playfair_input(&encrypted), "GYIZSCOKCFBU");
+        assert_eq!(settings.process(&encrypted).unwrap(), "HELXLOWORLDX");
+    }
+
+    #[test]
+    fn test_key_square_from_letters() {
+        let variant: Variant = Variant::default();
+        let square: KeySquare =
+            KeySquare::from_letters("keywo rdabc fghil mnpqs tuvxz", &variant).unwrap();
+        assert_eq!(square.rows(), generate_playfair_table("KEYWORD", &variant));
+        assert_eq!(
+            generate_playfair_table(&square.key(variant.route), &variant),
+            square.rows()
+        );
+        // The fingerprint depends on the square, not on how it was given
+        assert_eq!(square.fingerprint(), "f0b7d3c4f5bd1e9d");
+        assert_eq!(
+            KeySquare::from_key("KEYWORD", &variant).fingerprint(),
+            square.fingerprint()
+        );
+        let spiral: Variant = Variant {
+            route: Route::Spiral,
+            ..Variant::default()
+        };
+        assert_eq!(
+            generate_playfair_table(&square.key(Route::Spiral), &spiral),
+            square.rows()
+        );
+
+        let err = |letters: &str| KeySquare::from_letters(letters, &variant).unwrap_err();
+        assert!(err("KEYWORDABCFGHILMNPQSTUVXJ").to_string().contains("'J'"));
+        assert!(
+            err("KEYWORDABCFGHILMNPQSTUVXK")
+                .to_string()
+                .contains("more than once")
+        );
+        assert!(
+            err("KEYWORDABCFGHILMNPQSTUVX")
+                .to_string()
+                .contains("got 24")
+        );
+    }
+
+    #[test]
+    fn test_key_square_from_passphrase() {
+        let variant: Variant = Variant::default();
+        let square: KeySquare =
+            KeySquare::from_passphrase("correct horse battery staple", &variant);
+        assert_eq!(
+            square,
+            KeySquare::from_passphrase("correct horse battery staple", &variant)
+        );
+        assert_eq!(
+            KeySquare::from_letters(&square.key(Route::Rows), &variant),
+            Ok(square.clone())
+        );
+        // A change at the end of the passphrase reshuffles the first cells too
+        let other: KeySquare =
+            KeySquare::from_passphrase("correct horse battery staples", &variant);
+        assert_ne!(square.rows()[0], other.rows()[0]);
+        assert_eq!(
+            KeySquare::from_passphrase(
+                "x",
+                &Variant {
+                    alphabet: Alphabet::Alphanumeric,
+                    ..Variant::default()
+                }
+            )
+            .rows()
+            .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_table_fingerprint() {
+        let table: Vec<Vec<char>> = generate_playfair_table("KEYWORD", &Variant::default());
+        let fingerprint: String = table_fingerprint(&table);
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(
+            fingerprint,
+            table_fingerprint(&generate_playfair_table("KEYWORD", &Variant::default()))
+        );
+        assert_ne!(
+            fingerprint,
+            table_fingerprint(&generate_playfair_table("PLAYFAIR", &Variant::default()))
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Masks recording what the cipher loses, for exact restoration.
+//!
+//! The Playfair cipher keeps only the table letters of a text. A mask holds
+//! the rest: the positions of the inserted fillers and the layout of the
+//! original text, in which every letter is replaced by `a` (`A` when
+//! uppercase) or `m` (`M`) when it was the merged-away letter. Other
+//! characters are kept as they are; placeholder letters that are not table
+//! letters themselves, and backslashes, are escaped with a backslash.
+//! Masks are written next to the ciphertext with `--mask FILE`, or into an
+//! envelope with `--with-mask`:
+//!
+//! ```toml
+//! fillers = [3, 11]
+//! layout = "Aaaaa, aaaaa!"
+//! ```
+//!
+//! The git filter stores the same layout, escaped only for custom alphabets.
+
+use crate::Variant;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+
+/// Characters escaped with a backslash in an escaped layout
+const ESCAPED: [char; 5] = ['a', 'A', 'm', 'M', '\\'];
+
+/// The filler positions and the layout of a plaintext
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Mask {
+    /// Positions of the inserted fillers among the prepared letters
+    pub fillers: Vec<usize>,
+    /// The plaintext with its table letters replaced by placeholders
+    pub layout: String,
+}
+
+/// Writes the layout of a text.
+///
+/// # Arguments
+///
+/// * `text` - The plaintext.
+/// * `variant` - The variant deciding which characters are table letters.
+/// * `escaping` - Whether placeholder letters and backslashes kept as they
+///   are get escaped.
+///
+/// # Returns
+///
+/// * The layout of the text.
+pub fn layout(text: &str, variant: &Variant, escaping: bool) -> String {
+    let mut layout: String = String::with_capacity(text.len());
+    for c in text.chars() {
+        match crate::normalize_char(c, variant) {
+            None if escaping && ESCAPED.contains(&c) => {
+                layout.push('\\');
+                layout.push(c);
+            }
+            None => layout.push(c),
+            Some(n) if n != crate::to_upper(c) => {
+                layout.push(if c.is_uppercase() { 'M' } else { 'm' })
+            }
+            Some(_) => layout.push(if c.is_uppercase() { 'A' } else { 'a' }),
+        }
+    }
+    layout
+}
+
+/// Fills the letters back into a layout.
+///
+/// # Arguments
+///
+/// * `layout` - The layout from `layout`.
+/// * `letters` - The uppercase table letters of the text, without fillers.
+/// * `variant` - The variant providing the merged-away letter.
+/// * `escaping` - Whether the layout was written with escaping.
+///
+/// # Returns
+///
+/// * The original text, or a short description of the mismatch if the
+///   letters don't fit the layout.
+pub fn restore(
+    layout: &str,
+    letters: impl IntoIterator<Item = char>,
+    variant: &Variant,
+    escaping: bool,
+) -> Result<String, &'static str> {
+    let mut letters = letters.into_iter();
+    let mut text: String = String::with_capacity(layout.len());
+    let mut layout = layout.chars();
+    while let Some(c) = layout.next() {
+        match c {
+            '\\' if escaping => text.push(layout.next().ok_or("bad escape")?),
+            'a' | 'A' => {
+                let letter: char = letters.next().ok_or("layout too long")?;
+                if c == 'a' {
+                    text.extend(letter.to_lowercase());
+                } else {
+                    text.push(letter);
+                }
+            }
+            'm' => {
+                letters.next().ok_or("layout too long")?;
+                text.extend(variant.merge.0.to_lowercase());
+            }
+            'M' => {
+                letters.next().ok_or("layout too long")?;
+                text.push(variant.merge.0);
+            }
+            _ => text.push(c),
+        }
+    }
+    if letters.next().is_some() {
+        return Err("layout too short");
+    }
+    Ok(text)
+}
+
+/// Removes the fillers from prepared letters.
+///
+/// # Returns
+///
+/// * The letters that are not at one of the filler positions, or an error
+///   if a position is out of range.
+pub fn without_fillers(letters: &[char], fillers: &[usize]) -> Result<Vec<char>, &'static str> {
+    let mut is_filler: Vec<bool> = vec![false; letters.len()];
+    for &i in fillers {
+        *is_filler.get_mut(i).ok_or("bad filler position")? = true;
+    }
+    Ok(letters
+        .iter()
+        .zip(is_filler)
+        .filter(|&(_, filler)| !filler)
+        .map(|(&c, _)| c)
+        .collect())
+}
+
+impl Mask {
+    /// Records the mask of a plaintext.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The plaintext.
+    /// * `variant` - The variant the text is encrypted with.
+    /// * `fillers` - The positions of the fillers inserted in the text.
+    ///
+    /// # Returns
+    ///
+    /// * The mask, or a usage error if the variant changes letters in a way
+    ///   that can't be restored.
+    pub fn record(text: &str, variant: &Variant, fillers: Vec<usize>) -> Result<Mask, Error> {
+        check(variant)?;
+        Ok(Mask {
+            fillers,
+            layout: layout(text, variant, true),
+        })
+    }
+
+    /// Restores the plaintext from the decrypted letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `decrypted` - The decrypted text, fillers included. Characters that
+    ///   are not table symbols, such as group separators, are skipped.
+    /// * `variant` - The variant the text was encrypted with.
+    ///
+    /// # Returns
+    ///
+    /// * The original text, or an invalid input error if the mask doesn't
+    ///   fit the ciphertext.
+    pub fn apply(&self, decrypted: &str, variant: &Variant) -> Result<String, Error> {
+        check(variant)?;
+        let invalid = |what: &str| {
+            Error::InvalidInput(format!("the mask doesn't fit the ciphertext: {}", what))
+        };
+        let letters: Vec<char> = crate::table_letters(decrypted, variant);
+        let letters: Vec<char> = without_fillers(&letters, &self.fillers).map_err(invalid)?;
+        restore(&self.layout, letters, variant, true).map_err(invalid)
+    }
+
+    /// Reads a mask file.
+    ///
+    /// # Returns
+    ///
+    /// * The mask, an I/O error if the file can't be read, or an invalid
+    ///   input error if it isn't a valid mask.
+    pub fn read(path: &str) -> Result<Mask, Error> {
+        let contents: String = std::fs::read_to_string(path)
+            .map_err(|e| Error::Io(format!("cannot read mask '{}': {}", path, e)))?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::InvalidInput(format!("invalid mask '{}': {}", path, e)))
+    }
+
+    /// Writes a mask file, replacing any previous one.
+    ///
+    /// # Returns
+    ///
+    /// * An I/O error if the file can't be written.
+    pub fn write(&self, path: &str) -> Result<(), Error> {
+        let contents: String = toml::to_string(self).map_err(|e| Error::Internal(e.to_string()))?;
+        std::fs::write(path, contents)
+            .map_err(|e| Error::Io(format!("cannot write mask '{}': {}", path, e)))
+    }
+}
+
+/// Checks that every letter of the variant's texts can be restored.
+fn check(variant: &Variant) -> Result<(), Error> {
+    if variant.locale.is_some() || variant.doubles == crate::Doubles::Skip {
+        return Err(Error::Usage(String::from(
+            "a mask can't restore letters spelled out by --locale or dropped by --doubles skip",
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_round_trip() {
+        let variant: Variant = Variant::default();
+        let mask: Mask = Mask::record("Hello, World!", &variant, vec![3, 11]).unwrap();
+        assert_eq!(mask.layout, "Aaaaa, Aaaaa!");
+        assert_eq!(
+            mask.apply("HE LX LO WO RL DX", &variant).unwrap(),
+            "Hello, World!"
+        );
+        assert!(mask.apply("HELXLOWORLD", &variant).is_err());
+        assert!(mask.apply("HELXLOWORLDXAB", &variant).is_err());
+
+        // Omitted letters are kept as they are, escaped when they look like placeholders
+        let omit: Variant = Variant {
+            merge: ('A', None),
+            ..Variant::default()
+        };
+        let mask: Mask = Mask::record("a Jam\\", &omit, vec![]).unwrap();
+        assert_eq!(mask.layout, "\\a A\\aa\\\\");
+        assert_eq!(mask.apply("JM", &omit).unwrap(), "a Jam\\");
+        let jam: Mask = Mask::record("Jam", &variant, vec![]).unwrap();
+        assert_eq!(jam.layout, "Maa");
+        assert_eq!(jam.apply("IAM", &variant).unwrap(), "Jam");
+
+        let locale: Variant = Variant {
+            locale: Some(crate::Locale::German),
+            ..Variant::default()
+        };
+        assert!(Mask::record("Straße", &locale, vec![]).is_err());
+    }
+}
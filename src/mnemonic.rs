@@ -0,0 +1,251 @@
+//! Mnemonic words for key squares, so they can be dictated reliably.
+//!
+//! The cells of a square are numbered by their rank among the symbols that
+//! are still free (a Lehmer code), which makes every square a number below
+//! `n!` for a table of `n` symbols. The number is written in base 256 with
+//! one word per digit, followed by a checksum word: the first byte of the
+//! SHA-256 digest of the square. A 5x5 square takes 12 words and a 6x6
+//! square 19. The words of the list differ in their first four letters, so
+//! only those have to be heard right:
+//!
+//! ```text
+//! agent ginger robot hotel orbit angle owl pumpkin finger ocean hunter engine
+//! ```
+//!
+//! The same variant settings are needed to decode the words, since they
+//! decide the symbols of the table.
+
+use crate::error::Error;
+use crate::{KeySquare, Variant};
+use sha2::{Digest, Sha256};
+
+/// The words, one per byte value, in alphabetical order
+const WORDS: [&str; 256] = [
+    "able", "acid", "acorn", "actor", "adult", "agent", "alarm", "album", "alley", "amber",
+    "anchor", "angle", "ankle", "apple", "april", "arena", "armor", "arrow", "atlas", "attic",
+    "august", "autumn", "awake", "axis", "bacon", "badge", "bamboo", "banana", "banjo", "barrel",
+    "basket", "beach", "beaver", "bench", "berry", "bishop", "border", "bottle", "bounce",
+    "breeze", "bridge", "brother", "bubble", "bucket", "butter", "cabin", "cactus", "camera",
+    "canal", "candle", "canyon", "carbon", "carpet", "castle", "cattle", "cedar", "cement",
+    "chalk", "cherry", "circle", "clock", "cloud", "coffee", "comet", "copper", "cotton", "cousin",
+    "crayon", "curtain", "daisy", "dancer", "dawn", "deer", "desert", "desk", "diamond", "dinner",
+    "dolphin", "donkey", "dragon", "drum", "duck", "dust", "eagle", "earth", "echo", "elbow",
+    "empire", "engine", "evening", "exit", "fabric", "falcon", "feather", "fence", "fiber",
+    "finger", "flame", "flower", "forest", "fossil", "fox", "frog", "fruit", "funnel", "galaxy",
+    "garden", "garlic", "gecko", "giant", "ginger", "globe", "glove", "goat", "gold", "grape",
+    "gravel", "guitar", "hammer", "harbor", "hazel", "helmet", "hero", "hill", "honey", "hotel",
+    "hunter", "icon", "igloo", "impact", "index", "ink", "insect", "island", "ivory", "jacket",
+    "jaguar", "jazz", "jelly", "jewel", "jungle", "kayak", "kernel", "kettle", "kingdom", "kitten",
+    "kiwi", "knee", "knot", "ladder", "lagoon", "lamp", "lantern", "laptop", "lava", "lemon",
+    "letter", "lizard", "lotus", "lunar", "magnet", "mango", "maple", "marble", "meadow", "melon",
+    "mirror", "monkey", "motor", "muffin", "museum", "napkin", "needle", "nest", "nickel",
+    "noodle", "north", "number", "oasis", "ocean", "olive", "onion", "opera", "orange", "orbit",
+    "orchid", "otter", "oven", "owl", "paddle", "palace", "panda", "paper", "parrot", "peanut",
+    "pencil", "pepper", "piano", "pillow", "planet", "pocket", "potato", "pumpkin", "puzzle",
+    "queen", "quiet", "quilt", "rabbit", "radar", "radio", "raven", "ribbon", "river", "robot",
+    "rocket", "rubber", "ruby", "saddle", "salmon", "sandal", "scarf", "shadow", "sheep", "shovel",
+    "silver", "sister", "socket", "spider", "sponge", "sugar", "summer", "table", "tiger",
+    "tomato", "trumpet", "tulip", "tunnel", "turtle", "uncle", "valley", "velvet", "violin",
+    "volcano", "wagon", "walnut", "water", "whale", "window", "winter", "wizard", "wolf", "yacht",
+    "yellow", "yogurt", "zebra", "zipper",
+];
+
+/// Multiplies a little-endian base 256 number by a factor and adds a value.
+fn mul_add(number: &mut Vec<u8>, factor: u32, addend: u32) {
+    let mut carry: u32 = addend;
+    for byte in number.iter_mut() {
+        let value: u32 = u32::from(*byte) * factor + carry;
+        *byte = value as u8;
+        carry = value >> 8;
+    }
+    while carry > 0 {
+        number.push(carry as u8);
+        carry >>= 8;
+    }
+}
+
+/// Divides a little-endian base 256 number in place.
+///
+/// # Returns
+///
+/// * The remainder.
+fn div_rem(number: &mut [u8], divisor: u32) -> u32 {
+    let mut remainder: u32 = 0;
+    for byte in number.iter_mut().rev() {
+        let value: u32 = remainder << 8 | u32::from(*byte);
+        *byte = (value / divisor) as u8;
+        remainder = value % divisor;
+    }
+    remainder
+}
+
+/// Combines the ranks of the cells, the first of `n` possible values, the
+/// next of `n - 1` and so on, into a single number.
+fn combine(ranks: &[usize]) -> Vec<u8> {
+    let mut number: Vec<u8> = Vec::new();
+    for (i, &rank) in ranks.iter().enumerate() {
+        mul_add(&mut number, (ranks.len() - i) as u32, rank as u32);
+    }
+    number
+}
+
+/// Returns the number of digit words for a table of `n` symbols.
+fn digit_count(n: usize) -> usize {
+    // The largest number, n! - 1, ranks every cell last
+    let last: Vec<usize> = (0..n).rev().collect();
+    combine(&last).len()
+}
+
+/// Computes the checksum word of a square.
+fn checksum(square: &KeySquare) -> &'static str {
+    let letters: String = square.rows().iter().flatten().collect();
+    WORDS[usize::from(Sha256::digest(letters.as_bytes())[0])]
+}
+
+/// Finds a word of the list, comparing only the first four letters.
+fn find_word(word: &str) -> Option<usize> {
+    let prefix: String = word.to_lowercase().chars().take(4).collect();
+    WORDS
+        .iter()
+        .position(|w| w.chars().take(4).collect::<String>() == prefix)
+}
+
+/// Encodes a square as mnemonic words.
+///
+/// # Arguments
+///
+/// * `square` - The square, every symbol of the table exactly once.
+/// * `variant` - The variant deciding which symbols make up the table.
+///
+/// # Returns
+///
+/// * The words, separated by spaces.
+pub fn encode(square: &KeySquare, variant: &Variant) -> String {
+    let mut free: Vec<char> = variant.symbols();
+    let ranks: Vec<usize> = square
+        .rows()
+        .iter()
+        .flatten()
+        .map(|c| {
+            let rank: usize = free
+                .iter()
+                .position(|s| s == c)
+                .expect("a square holds every symbol once");
+            free.remove(rank);
+            rank
+        })
+        .collect();
+    let mut number: Vec<u8> = combine(&ranks);
+    number.resize(digit_count(ranks.len()), 0);
+    number
+        .iter()
+        .rev()
+        .map(|&byte| WORDS[usize::from(byte)])
+        .chain([checksum(square)])
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Decodes mnemonic words into a square.
+///
+/// # Arguments
+///
+/// * `words` - The words from `encode`, separated by whitespace. Case is
+///   ignored and only the first four letters of each word are compared.
+/// * `variant` - The variant the words were encoded with.
+///
+/// # Returns
+///
+/// * The square, or an invalid key error if a word is unknown, the number
+///   of words is wrong or the checksum word doesn't match.
+pub fn decode(words: &str, variant: &Variant) -> Result<KeySquare, Error> {
+    let mut free: Vec<char> = variant.symbols();
+    let n: usize = free.len();
+    let words: Vec<&str> = words.split_whitespace().collect();
+    if words.len() != digit_count(n) + 1 {
+        return Err(Error::InvalidKey(format!(
+            "invalid key mnemonic: expected {} words for a table of {} symbols, got {}",
+            digit_count(n) + 1,
+            n,
+            words.len()
+        )));
+    }
+    let mut number: Vec<u8> = Vec::new();
+    for word in &words[..words.len() - 1] {
+        let byte: usize = find_word(word).ok_or_else(|| {
+            Error::InvalidKey(format!("invalid key mnemonic: unknown word '{}'", word))
+        })?;
+        number.insert(0, byte as u8);
+    }
+    let mut ranks: Vec<usize> = vec![0; n];
+    for (i, rank) in ranks.iter_mut().enumerate().rev() {
+        *rank = div_rem(&mut number, (n - i) as u32) as usize;
+    }
+    if number.iter().any(|&byte| byte != 0) {
+        return Err(Error::InvalidKey(String::from(
+            "invalid key mnemonic: the words don't encode a square of this table",
+        )));
+    }
+    let cells: Vec<char> = ranks.into_iter().map(|rank| free.remove(rank)).collect();
+    let square: KeySquare = KeySquare::from_rows(
+        cells
+            .chunks(variant.alphabet.dimensions().1)
+            .map(<[char]>::to_vec)
+            .collect(),
+    );
+    let last: &str = words[words.len() - 1];
+    if find_word(last) != find_word(checksum(&square)) {
+        return Err(Error::InvalidKey(format!(
+            "invalid key mnemonic: the checksum word is '{}', not '{}'; a word was misheard",
+            checksum(&square),
+            last
+        )));
+    }
+    Ok(square)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_are_distinct() {
+        let mut prefixes: Vec<String> = WORDS.iter().map(|w| w.chars().take(4).collect()).collect();
+        assert!(WORDS.is_sorted());
+        prefixes.dedup();
+        assert_eq!(prefixes.len(), WORDS.len());
+    }
+
+    #[test]
+    fn test_mnemonic_round_trip() {
+        let variant: Variant = Variant::default();
+        let square: KeySquare = KeySquare::from_key("KEYWORD", &variant);
+        let words: String = encode(&square, &variant);
+        assert_eq!(words.split(' ').count(), 12);
+        assert_eq!(decode(&words, &variant), Ok(square.clone()));
+        // Only the first four letters count
+        let shouted: String = words
+            .split(' ')
+            .map(|w| w.to_uppercase() + "S")
+            .collect::<Vec<String>>()
+            .join("  ");
+        assert_eq!(decode(&shouted, &variant), Ok(square.clone()));
+
+        let alphabetical: KeySquare = KeySquare::from_key("A", &variant);
+        assert!(encode(&alphabetical, &variant).starts_with("able able"));
+        let six: Variant = Variant {
+            alphabet: crate::Alphabet::Alphanumeric,
+            ..Variant::default()
+        };
+        let square: KeySquare = KeySquare::from_passphrase("six", &six);
+        assert_eq!(decode(&encode(&square, &six), &six), Ok(square));
+
+        let mut swapped: Vec<&str> = words.split(' ').collect();
+        swapped.swap(0, 1);
+        assert!(decode(&swapped.join(" "), &variant).is_err());
+        assert!(decode("tulip carbon", &variant).is_err());
+        assert!(decode(&words.replacen(' ', " xyzzy ", 1)[..], &variant).is_err());
+        let too_large: String = ["zipper"; 11].join(" ") + " able";
+        assert!(decode(&too_large, &variant).is_err());
+    }
+}
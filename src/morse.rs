@@ -0,0 +1,225 @@
+//! International Morse code, for ciphertexts sent over the key.
+//!
+//! `--format morse` writes every character of an encrypted text as dots and
+//! dashes, and reads decrypted input in the same form. Codes are separated
+//! by `--letter-separator` (a space by default) and the letter groups by
+//! `--word-separator` (` / ` by default):
+//!
+//! ```text
+//! --. -.-- .. --.. / ... -.-. --- -.- / -.-. ..-. -... ..-
+//! ```
+
+use crate::error::Error;
+
+/// The codes of the letters, digits and punctuation marks
+const CODES: [(char, &str); 54] = [
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('!', "-.-.--"),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('&', ".-..."),
+    (':', "---..."),
+    (';', "-.-.-."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('-', "-....-"),
+    ('_', "..--.-"),
+    ('"', ".-..-."),
+    ('$', "...-..-"),
+    ('@', ".--.-."),
+];
+
+/// The separators written between the codes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Separators {
+    /// Written between the codes of a letter group
+    pub letter: String,
+    /// Written between the letter groups
+    pub word: String,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Separators {
+            letter: String::from(" "),
+            word: String::from(" / "),
+        }
+    }
+}
+
+impl Separators {
+    /// Checks that the separators can be told apart from the codes and
+    /// from each other.
+    ///
+    /// # Returns
+    ///
+    /// * A usage error if a separator is empty or holds a dot or a dash, or
+    ///   if the word separator is part of the letter separator.
+    pub fn check(&self) -> Result<(), Error> {
+        for separator in [&self.letter, &self.word] {
+            if separator.is_empty() || separator.contains(['.', '-']) {
+                return Err(Error::Usage(format!(
+                    "invalid Morse separator '{}': it must be non-empty and hold no '.' or '-'",
+                    separator
+                )));
+            }
+        }
+        if self.letter.contains(self.word.as_str()) {
+            return Err(Error::Usage(format!(
+                "invalid Morse separators: the word separator '{}' is part of the letter separator '{}'",
+                self.word, self.letter
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Writes a text in Morse code.
+///
+/// # Arguments
+///
+/// * `text` - The text to write, usually an uppercase ciphertext. Its
+///   whitespace separates the words.
+/// * `separators` - The separators written between the codes.
+///
+/// # Returns
+///
+/// * The codes, or a usage error naming a character without a code.
+pub fn encode(text: &str, separators: &Separators) -> Result<String, Error> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .map(|c| {
+                    let upper: char = c.to_ascii_uppercase();
+                    CODES
+                        .iter()
+                        .find(|&&(symbol, _)| symbol == upper)
+                        .map(|&(_, code)| code)
+                        .ok_or_else(|| {
+                            Error::Usage(format!(
+                                "'{}' has no Morse code: --format morse needs Latin letters and digits",
+                                c
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<&str>, Error>>()
+                .map(|codes| codes.join(&separators.letter))
+        })
+        .collect::<Result<Vec<String>, Error>>()?;
+    Ok(words.join(&separators.word))
+}
+
+/// Reads a text written in Morse code.
+///
+/// # Arguments
+///
+/// * `text` - The codes. Whitespace around them is ignored.
+/// * `separators` - The separators written between the codes.
+///
+/// # Returns
+///
+/// * The text, with its words separated by spaces, or an invalid input
+///   error naming the first code that is not known.
+pub fn decode(text: &str, separators: &Separators) -> Result<String, Error> {
+    let words: Vec<String> = text
+        .split(separators.word.as_str())
+        .map(|word| {
+            word.split(separators.letter.as_str())
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(|code| {
+                    CODES
+                        .iter()
+                        .find(|&&(_, c)| c == code)
+                        .map(|&(symbol, _)| symbol)
+                        .ok_or_else(|| {
+                            Error::InvalidInput(format!("'{}' is not a Morse code", code))
+                        })
+                })
+                .collect::<Result<String, Error>>()
+        })
+        .collect::<Result<Vec<String>, Error>>()?;
+    Ok(words
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<String>>()
+        .join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_morse_round_trip() {
+        let separators: Separators = Separators::default();
+        let encoded: String = encode("GYIZ SCOK-4", &separators).unwrap();
+        assert_eq!(encoded, "--. -.-- .. --.. / ... -.-. --- -.- -....- ....-");
+        assert_eq!(decode(&encoded, &separators).unwrap(), "GYIZ SCOK-4");
+        assert!(encode("ШИФР", &separators).is_err());
+        assert!(decode("--. ---.-.-", &separators).is_err());
+
+        let custom: Separators = Separators {
+            letter: String::from("||"),
+            word: String::from("|"),
+        };
+        assert!(custom.check().is_err());
+        let custom: Separators = Separators {
+            letter: String::from("/"),
+            word: String::from(" "),
+        };
+        assert!(custom.check().is_ok());
+        let encoded: String = encode("GY IZ", &custom).unwrap();
+        assert_eq!(encoded, "--./-.-- ../--..");
+        assert_eq!(decode(&encoded, &custom).unwrap(), "GY IZ");
+        assert!(
+            Separators {
+                letter: String::from("-"),
+                ..Separators::default()
+            }
+            .check()
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! The NATO phonetic alphabet, for ciphertexts read aloud.
+//!
+//! `--format nato` spells every letter and digit of an encrypted text with
+//! its code word, `GOLF YANKEE INDIA ...`, and reads decrypted input in the
+//! same form. Whitespace between letter groups is written as `/`, and other
+//! characters, such as a group separator, are kept as they are.
+
+use crate::error::Error;
+
+/// The code words of the letters A to Z
+const LETTERS: [&str; 26] = [
+    "ALFA", "BRAVO", "CHARLIE", "DELTA", "ECHO", "FOXTROT", "GOLF", "HOTEL", "INDIA", "JULIETT",
+    "KILO", "LIMA", "MIKE", "NOVEMBER", "OSCAR", "PAPA", "QUEBEC", "ROMEO", "SIERRA", "TANGO",
+    "UNIFORM", "VICTOR", "WHISKEY", "XRAY", "YANKEE", "ZULU",
+];
+
+/// The code words of the digits 0 to 9
+const DIGITS: [&str; 10] = [
+    "ZERO", "ONE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT", "NINER",
+];
+
+/// Other spellings that are common enough to be accepted when reading
+const ALIASES: [(&str, char); 5] = [
+    ("ALPHA", 'A'),
+    ("JULIET", 'J'),
+    ("X-RAY", 'X'),
+    ("WHISKY", 'W'),
+    ("NINE", '9'),
+];
+
+/// Spells a text with code words.
+///
+/// # Arguments
+///
+/// * `text` - The text to spell, usually an uppercase ciphertext.
+///
+/// # Returns
+///
+/// * The code words separated by spaces. Letters outside A to Z and other
+///   characters are kept as they are; whitespace becomes `/`.
+pub fn encode(text: &str) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    for c in text.chars() {
+        let upper: char = c.to_ascii_uppercase();
+        let token: String = match upper {
+            'A'..='Z' => LETTERS[(upper as u8 - b'A') as usize].to_string(),
+            '0'..='9' => DIGITS[(upper as u8 - b'0') as usize].to_string(),
+            _ if c.is_whitespace() => {
+                // Consecutive whitespace is a single break
+                if tokens.last().is_some_and(|last| last == "/") {
+                    continue;
+                }
+                String::from("/")
+            }
+            _ => c.to_string(),
+        };
+        tokens.push(token);
+    }
+    tokens.join(" ")
+}
+
+/// Reads a text spelled with code words.
+///
+/// # Arguments
+///
+/// * `text` - The code words, separated by whitespace. Case is ignored.
+///
+/// # Returns
+///
+/// * The spelled letters, with `/` read back as a space and tokens without
+///   letters or digits kept as they are, or an invalid input error naming
+///   the first word that is not a code word.
+pub fn decode(text: &str) -> Result<String, Error> {
+    let mut decoded: String = String::new();
+    for token in text.split_whitespace() {
+        let word: String = token.to_uppercase();
+        let letter: Option<char> = LETTERS
+            .iter()
+            .zip('A'..='Z')
+            .chain(DIGITS.iter().zip('0'..='9'))
+            .find(|(w, _)| **w == word)
+            .map(|(_, c)| c)
+            .or_else(|| ALIASES.iter().find(|(w, _)| *w == word).map(|&(_, c)| c));
+        match letter {
+            Some(c) => decoded.push(c),
+            None if token == "/" => decoded.push(' '),
+            None if !token.chars().any(char::is_alphanumeric) => decoded.push_str(token),
+            None => {
+                return Err(Error::InvalidInput(format!(
+                    "'{}' is not a NATO code word",
+                    token
+                )));
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nato_round_trip() {
+        assert_eq!(
+            encode("GYIZ SC-4"),
+            "GOLF YANKEE INDIA ZULU / SIERRA CHARLIE - FOUR"
+        );
+        assert_eq!(encode("AB  C"), "ALFA BRAVO / CHARLIE");
+        assert_eq!(
+            decode("golf Yankee INDIA zulu / sierra charlie - four").unwrap(),
+            "GYIZ SC-4"
+        );
+        assert_eq!(decode("alpha juliet x-ray nine").unwrap(), "AJX9");
+        assert!(decode("GOLF YANKE").is_err());
+        assert!(decode("GYIZSC").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Compatibility decomposition of the input for `--normalize`.
+//!
+//! Letters with diacritics are replaced by their base letter (`é` by `e`,
+//! `Ç` by `C`), ligatures and digraphs are spelled out (`ﬁ` as `fi`) and
+//! fullwidth forms become their ASCII counterparts. This is what NFKD
+//! followed by the removal of combining marks gives for the precomposed
+//! Latin and Cyrillic letters; combining marks of already decomposed text
+//! are dropped. Symbols of the table and characters the locale spells out
+//! are left alone, so `Й` stays in the Russian table and `ü` still becomes
+//! `UE` with `--locale de`.
+
+use crate::Variant;
+
+/// Base letters and the precomposed letters decomposing to them, uppercase
+/// and lowercase
+const BASE_LETTERS: [(char, &str); 44] = [
+    (
+        'A',
+        "ÀÁÂÃÄÅàáâãäåĀāĂăĄąǍǎǞǟǠǡǺǻȀȁȂȃȦȧḀḁẠạẢảẤấẦầẨẩẪẫẬậẮắẰằẲẳẴẵẶặ",
+    ),
+    ('C', "ÇçĆćĈĉĊċČčḈḉ"),
+    ('E', "ÈÉÊËèéêëĒēĔĕĖėĘęĚěȄȅȆȇȨȩḔḕḖḗḘḙḚḛḜḝẸẹẺẻẼẽẾếỀềỂểỄễỆệ"),
+    ('I', "ÌÍÎÏìíîïĨĩĪīĬĭĮįİǏǐȈȉȊȋḬḭḮḯỈỉỊị"),
+    ('N', "ÑñŃńŅņŇňǸǹṄṅṆṇṈṉṊṋ"),
+    (
+        'O',
+        "ÒÓÔÕÖòóôõöŌōŎŏŐőƠơǑǒǪǫǬǭȌȍȎȏȪȫȬȭȮȯȰȱṌṍṎṏṐṑṒṓỌọỎỏỐốỒồỔổỖỗỘộỚớỜờỞởỠỡỢợ",
+    ),
+    (
+        'U',
+        "ÙÚÛÜùúûüŨũŪūŬŭŮůŰűŲųƯưǓǔǕǖǗǘǙǚǛǜȔȕȖȗṲṳṴṵṶṷṸṹṺṻỤụỦủỨứỪừỬửỮữỰự",
+    ),
+    ('Y', "ÝýÿŶŷŸȲȳẎẏẙỲỳỴỵỶỷỸỹ"),
+    ('D', "ĎďḊḋḌḍḎḏḐḑḒḓ"),
+    ('G', "ĜĝĞğĠġĢģǦǧǴǵḠḡ"),
+    ('H', "ĤĥȞȟḢḣḤḥḦḧḨḩḪḫẖ"),
+    ('J', "Ĵĵǰ"),
+    ('K', "ĶķǨǩḰḱḲḳḴḵ"),
+    ('L', "ĹĺĻļĽľḶḷḸḹḺḻḼḽ"),
+    ('R', "ŔŕŖŗŘřȐȑȒȓṘṙṚṛṜṝṞṟ"),
+    ('S', "ŚśŜŝŞşŠšſȘșṠṡṢṣṤṥṦṧṨṩẛ"),
+    ('T', "ŢţŤťȚțṪṫṬṭṮṯṰṱẗ"),
+    ('W', "ŴŵẀẁẂẃẄẅẆẇẈẉẘ"),
+    ('Z', "ŹźŻżŽžẐẑẒẓẔẕ"),
+    ('Æ', "ǢǣǼǽ"),
+    ('Ʒ', "Ǯǯ"),
+    ('Ø', "Ǿǿ"),
+    ('B', "ḂḃḄḅḆḇ"),
+    ('F', "Ḟḟ"),
+    ('M', "ḾḿṀṁṂṃ"),
+    ('P', "ṔṕṖṗ"),
+    ('V', "ṼṽṾṿ"),
+    ('X', "ẊẋẌẍ"),
+    ('Е', "ЀЁѐёӖӗ"),
+    ('Г', "Ѓѓ"),
+    ('І', "Її"),
+    ('К', "Ќќ"),
+    ('И', "ЍЙйѝӢӣӤӥ"),
+    ('У', "ЎўӮӯӰӱӲӳ"),
+    ('Ѵ', "Ѷѷ"),
+    ('Ж', "ӁӂӜӝ"),
+    ('А', "ӐӑӒӓ"),
+    ('Ә', "Ӛӛ"),
+    ('З', "Ӟӟ"),
+    ('О', "Ӧӧ"),
+    ('Ө', "Ӫӫ"),
+    ('Э', "Ӭӭ"),
+    ('Ч', "Ӵӵ"),
+    ('Ы', "Ӹӹ"),
+];
+
+/// Ligatures and digraphs decomposing to several letters
+const EXPANSIONS: [(char, &str); 23] = [
+    ('Ĳ', "IJ"),
+    ('ĳ', "ij"),
+    ('Ŀ', "L·"),
+    ('ŀ', "l·"),
+    ('Ǆ', "DZ"),
+    ('ǅ', "Dz"),
+    ('ǆ', "dz"),
+    ('Ǉ', "LJ"),
+    ('ǈ', "Lj"),
+    ('ǉ', "lj"),
+    ('Ǌ', "NJ"),
+    ('ǋ', "Nj"),
+    ('ǌ', "nj"),
+    ('Ǳ', "DZ"),
+    ('ǲ', "Dz"),
+    ('ǳ', "dz"),
+    ('ﬀ', "ff"),
+    ('ﬁ', "fi"),
+    ('ﬂ', "fl"),
+    ('ﬃ', "ffi"),
+    ('ﬄ', "ffl"),
+    ('ﬅ', "st"),
+    ('ﬆ', "st"),
+];
+
+/// Decomposes a character, keeping its case.
+///
+/// # Returns
+///
+/// * The replacement, an empty string for a combining mark, or `None` if
+///   the character has no decomposition.
+fn decompose(c: char) -> Option<String> {
+    if ('\u{0300}'..='\u{036F}').contains(&c) {
+        return Some(String::new());
+    }
+    // Fullwidth forms sit at a fixed offset from ASCII
+    if ('\u{FF01}'..='\u{FF5E}').contains(&c) {
+        return char::from_u32(c as u32 - 0xFEE0).map(String::from);
+    }
+    if let Some((_, expansion)) = EXPANSIONS.iter().find(|(from, _)| *from == c) {
+        return Some(expansion.to_string());
+    }
+    let (base, _) = BASE_LETTERS
+        .iter()
+        .find(|(_, letters)| letters.contains(c))?;
+    Some(if c.is_lowercase() {
+        base.to_lowercase().collect()
+    } else {
+        base.to_string()
+    })
+}
+
+/// Replaces the characters of a text by their decomposition.
+///
+/// # Arguments
+///
+/// * `text` - The text to normalize.
+/// * `variant` - The variant whose table symbols and locale are left alone.
+///
+/// # Returns
+///
+/// * The normalized text and the number of characters that were replaced
+///   or dropped.
+pub fn normalize(text: &str, variant: &Variant) -> (String, usize) {
+    let mut normalized: String = String::with_capacity(text.len());
+    let mut changed: usize = 0;
+    for c in text.chars() {
+        let kept: bool = crate::normalize_char(c, variant).is_some()
+            || variant
+                .locale
+                .is_some_and(|l| l.transliterate(crate::to_upper(c)).is_some());
+        match decompose(c) {
+            Some(replacement) if !kept => {
+                normalized.push_str(&replacement);
+                changed += 1;
+            }
+            _ => normalized.push(c),
+        }
+    }
+    (normalized, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_strips_diacritics() {
+        let variant: Variant = Variant::default();
+        assert_eq!(
+            normalize("Façade, café!", &variant),
+            (String::from("Facade, cafe!"), 2)
+        );
+        assert_eq!(
+            normalize("Tiê\u{0301}ng Việt", &variant),
+            (String::from("Tieng Viet"), 3)
+        );
+        assert_eq!(
+            normalize("ﬁne ＡＢＣ", &variant),
+            (String::from("fine ABC"), 4)
+        );
+
+        // The locale spells out its own letters and the table keeps its symbols
+        let german: Variant = Variant {
+            locale: Some(crate::Locale::German),
+            ..Variant::default()
+        };
+        assert_eq!(
+            normalize("Müller é", &german),
+            (String::from("Müller e"), 1)
+        );
+        let russian: Variant = Variant {
+            alphabet: crate::parse_alphabet("ru").unwrap(),
+            ..Variant::default()
+        };
+        assert_eq!(normalize("Йогурт", &russian), (String::from("Йогурт"), 0));
+    }
+}
@@ -0,0 +1,220 @@
+//! The Polybius square and the ADFGVX cipher, fractionating ciphers built on
+//! the key table.
+//!
+//! Instead of substituting digrams, every letter is replaced by the labels
+//! of its row and column. The `polybius` variant labels them with the
+//! digits 1 to 9; the `adfgvx` variant labels them with the letters `ADFGX`
+//! (5x5) or `ADFGVX` (6x6), whose Morse codes are hard to confuse, as the
+//! German army did in 1918. A keyed columnar transposition of the
+//! coordinates then separates the two halves of every letter. It is
+//! required by `adfgvx` and optional for `polybius`:
+//!
+//! ```text
+//! HELLO under KEYWORD, 5x5: H = row 3, column 3 -> 33 12 35 35 15
+//! ```
+
+use crate::error::Error;
+use crate::{KeySquare, Variant};
+
+/// The labels of the 5x5 ADFGX cipher
+const ADFGX: [char; 5] = ['A', 'D', 'F', 'G', 'X'];
+
+/// The labels of the 6x6 ADFGVX cipher
+const ADFGVX: [char; 6] = ['A', 'D', 'F', 'G', 'V', 'X'];
+
+/// A Polybius square with its coordinate labels
+pub struct Polybius {
+    /// The key table
+    pub square: KeySquare,
+    /// The labels of the rows and the columns, in order
+    pub labels: Vec<char>,
+    /// Column order of the transposition of the coordinates, if any
+    pub transposition: Option<Vec<usize>>,
+}
+
+impl Polybius {
+    /// Builds the square of a cipher.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Either `polybius` or `adfgvx`.
+    /// * `key` - The key of the table.
+    /// * `variant` - The variant deciding the symbols and the shape of the
+    ///   table.
+    /// * `transposition` - The column order of the transposition, if any.
+    ///
+    /// # Returns
+    ///
+    /// * The square, or a usage error if the table has no labels in the
+    ///   cipher or `adfgvx` has no transposition.
+    pub fn new(
+        name: &str,
+        key: &str,
+        variant: &Variant,
+        transposition: Option<Vec<usize>>,
+    ) -> Result<Self, Error> {
+        let (rows, columns) = variant.alphabet.dimensions();
+        let labels: Vec<char> = match (name, rows.max(columns)) {
+            ("adfgvx", _) if transposition.is_none() => {
+                return Err(Error::Usage(String::from(
+                    "the adfgvx variant needs a transposition key: use -K",
+                )));
+            }
+            ("adfgvx", _) if (rows, columns) == (5, 5) => ADFGX.to_vec(),
+            ("adfgvx", _) if (rows, columns) == (6, 6) => ADFGVX.to_vec(),
+            ("adfgvx", _) => {
+                return Err(Error::Usage(format!(
+                    "the adfgvx variant needs a 5x5 or 6x6 table, not {}x{}",
+                    rows, columns
+                )));
+            }
+            (_, size) if size <= 9 => ('1'..='9').take(size).collect(),
+            (_, size) => {
+                return Err(Error::Usage(format!(
+                    "the polybius variant labels at most 9 rows and columns, not {}",
+                    size
+                )));
+            }
+        };
+        Ok(Polybius {
+            square: KeySquare::from_key(key, variant),
+            labels,
+            transposition,
+        })
+    }
+
+    /// Returns the name of the cipher for its labels.
+    pub fn name(&self) -> &'static str {
+        if self.labels[0] == 'A' {
+            "adfgvx"
+        } else {
+            "polybius"
+        }
+    }
+
+    /// Replaces every letter of a text by its coordinates.
+    ///
+    /// # Returns
+    ///
+    /// * The transposed coordinates, two labels per letter.
+    pub fn encrypt(&self, text: &str, variant: &Variant) -> String {
+        let coordinates: Vec<char> = crate::table_letters(text, variant)
+            .into_iter()
+            .flat_map(|c| {
+                let (row, column) = self.square.positions.find(c);
+                [self.labels[row], self.labels[column]]
+            })
+            .collect();
+        match &self.transposition {
+            Some(order) => crate::transposition::encrypt(&coordinates, order),
+            None => coordinates.into_iter().collect(),
+        }
+    }
+
+    /// Reads the letters back from their coordinates.
+    ///
+    /// Characters that are not labels, such as group separators, are
+    /// skipped.
+    ///
+    /// # Returns
+    ///
+    /// * The letters, or an invalid input error if there are no coordinates
+    ///   or they don't pair up or point outside the table.
+    pub fn decrypt(&self, text: &str) -> Result<String, Error> {
+        let coordinates: Vec<char> = text
+            .chars()
+            .map(crate::to_upper)
+            .filter(|c| self.labels.contains(c))
+            .collect();
+        if coordinates.is_empty() {
+            return Err(Error::InvalidInput(String::from(
+                "the input contains no coordinates to decrypt",
+            )));
+        }
+        let coordinates: Vec<char> = match &self.transposition {
+            Some(order) => crate::transposition::decrypt(&coordinates, order)
+                .chars()
+                .collect(),
+            None => coordinates,
+        };
+        if !coordinates.len().is_multiple_of(2) {
+            return Err(Error::InvalidInput(String::from(
+                "the coordinates don't pair up: the input has an odd number of them",
+            )));
+        }
+        let index = |c: char| self.labels.iter().position(|&l| l == c).unwrap();
+        coordinates
+            .chunks(2)
+            .map(|pair| {
+                self.square
+                    .rows()
+                    .get(index(pair[0]))
+                    .and_then(|row| row.get(index(pair[1])))
+                    .copied()
+                    .ok_or_else(|| {
+                        Error::InvalidInput(format!(
+                            "the coordinates {}{} point outside the table",
+                            pair[0], pair[1]
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Lays the table out with its labels.
+    ///
+    /// # Returns
+    ///
+    /// * A header line with the column labels, then one line per row in the
+    ///   `['K', 'E', ...]` form, behind the row label.
+    pub fn rows(&self) -> Vec<String> {
+        let columns: usize = self.square.rows()[0].len();
+        // Every label sits above the letter of its column
+        let header: String = self.labels[..columns]
+            .iter()
+            .map(|label| format!("{:>5}", label))
+            .collect::<String>();
+        let mut rows: Vec<String> = vec![header];
+        for (label, cells) in self.labels.iter().zip(self.square.rows()) {
+            rows.push(format!("{} {:?}", label, cells));
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polybius_and_adfgvx() {
+        let variant: Variant = Variant::default();
+        let polybius: Polybius = Polybius::new("polybius", "KEYWORD", &variant, None).unwrap();
+        assert_eq!(polybius.encrypt("Hello", &variant), "3312353515");
+        assert_eq!(polybius.decrypt("33 12 35 35 15").unwrap(), "HELLO");
+        assert!(polybius.decrypt("331").is_err());
+        assert!(polybius.decrypt("HELLO").is_err());
+        assert_eq!(polybius.rows()[0], "    1    2    3    4    5");
+        assert_eq!(polybius.rows()[1], "1 ['K', 'E', 'Y', 'W', 'O']");
+
+        // The textbook ADFGVX example, with its 6x6 square given cell by cell
+        let six: Variant = Variant {
+            alphabet: crate::Alphabet::Alphanumeric,
+            ..Variant::default()
+        };
+        let order: Vec<usize> = crate::transposition::column_order("PRIVACY").unwrap();
+        let adfgvx: Polybius = Polybius::new(
+            "adfgvx",
+            "NA1C3H8TB2OME5WRPD4F6G7I9J0KLQSUVXYZ",
+            &six,
+            Some(order),
+        )
+        .unwrap();
+        let encrypted: String = adfgvx.encrypt("ATTACK AT 1200AM", &six);
+        assert_eq!(encrypted, "DGDDDAGDDGAFADDFDADVDVFAADVX");
+        assert_eq!(adfgvx.decrypt(&encrypted).unwrap(), "ATTACKAT1200AM");
+        assert_eq!(adfgvx.name(), "adfgvx");
+
+        assert!(Polybius::new("adfgvx", "KEYWORD", &variant, None).is_err());
+    }
+}
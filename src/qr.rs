@@ -0,0 +1,195 @@
+//! QR codes of ciphertexts, written as PNG images or drawn on the terminal.
+//!
+//! `--qr PATH.png` writes the encrypted text as a QR code image, and
+//! `--qr -` draws it on stderr with half-block characters. The `qrcode`
+//! crate builds the symbol with error correction level M, in the most
+//! compact modes and the smallest of the 40 versions the text fits. The
+//! image is a 1-bit grayscale PNG compressed with `flate2`.
+
+use crate::error::Error;
+use flate2::Compression;
+use flate2::Crc;
+use flate2::write::ZlibEncoder;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{Color, EcLevel, QrCode};
+use std::io::Write;
+
+/// Pixels per module of the PNG image
+const SCALE: usize = 8;
+
+/// Light modules around the symbol, as the standard requires
+const QUIET_ZONE: usize = 4;
+
+/// Builds the QR code of a text.
+///
+/// # Returns
+///
+/// * The symbol, or an invalid input error if the text is too long.
+fn encode(data: &[u8]) -> Result<QrCode, Error> {
+    QrCode::with_error_correction_level(data, EcLevel::M).map_err(|e| {
+        Error::InvalidInput(format!(
+            "the text is too long for a QR code: {} bytes ({})",
+            data.len(),
+            e
+        ))
+    })
+}
+
+/// Draws the symbol with half-block characters, two rows of modules per
+/// line, for a dark terminal background.
+fn to_text(qr: &QrCode) -> String {
+    let text: String = qr
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    text + "\n"
+}
+
+/// Renders the symbol as a PNG image with a quiet zone.
+fn to_png(qr: &QrCode) -> Vec<u8> {
+    let size: usize = qr.width();
+    let colors: Vec<Color> = qr.to_colors();
+    let width: usize = (size + 2 * QUIET_ZONE) * SCALE;
+    // One filter byte and then one bit per pixel, 1 for white
+    let mut raw: Vec<u8> = Vec::with_capacity((width.div_ceil(8) + 1) * width);
+    for y in 0..width {
+        raw.push(0);
+        let mut row: Vec<u8> = vec![0; width.div_ceil(8)];
+        for x in 0..width {
+            let (module_y, module_x) = (y / SCALE, x / SCALE);
+            let dark: bool = (QUIET_ZONE..size + QUIET_ZONE).contains(&module_y)
+                && (QUIET_ZONE..size + QUIET_ZONE).contains(&module_x)
+                && colors[(module_y - QUIET_ZONE) * size + module_x - QUIET_ZONE] == Color::Dark;
+            if !dark {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        raw.extend(row);
+    }
+    let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::best());
+    // Writing to memory can't fail
+    encoder.write_all(&raw).unwrap();
+    let compressed: Vec<u8> = encoder.finish().unwrap();
+
+    let mut header: Vec<u8> = Vec::new();
+    header.extend((width as u32).to_be_bytes());
+    header.extend((width as u32).to_be_bytes());
+    // Bit depth 1, grayscale, deflate, standard filters, no interlace
+    header.extend([1, 0, 0, 0, 0]);
+    let mut png: Vec<u8> = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &compressed);
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Appends a chunk with its length and CRC to a PNG image.
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let mut crc: Crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend(kind);
+    png.extend(data);
+    png.extend(crc.sum().to_be_bytes());
+}
+
+/// Writes the QR code of a text as `--qr` asks.
+///
+/// # Arguments
+///
+/// * `path` - The PNG file to write, or `-` to draw the code on stderr.
+/// * `text` - The text to encode.
+///
+/// # Returns
+///
+/// * An invalid input error if the text is too long, or an I/O error if
+///   the image can't be written.
+pub fn write(path: &str, text: &str) -> Result<(), Error> {
+    let qr: QrCode = encode(text.as_bytes())?;
+    if path == "-" {
+        eprint!("{}", to_text(&qr));
+        return Ok(());
+    }
+    std::fs::write(path, to_png(&qr))
+        .map_err(|e| Error::Io(format!("cannot write QR code '{}': {}", path, e)))?;
+    eprintln!("Wrote QR code '{}'", path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    /// Reads back the pixels of a PNG image `to_png` wrote, `true` for
+    /// white, checking the CRC of every chunk.
+    fn read_png(png: &[u8]) -> (usize, Vec<Vec<bool>>) {
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let (mut width, mut compressed) = (0, Vec::new());
+        let mut rest: &[u8] = &png[8..];
+        while !rest.is_empty() {
+            let length: usize = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+            let mut crc: Crc = Crc::new();
+            crc.update(&rest[4..8 + length]);
+            assert_eq!(rest[8 + length..12 + length], crc.sum().to_be_bytes());
+            match kind {
+                b"IHDR" => width = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize,
+                b"IDAT" => compressed.extend(data),
+                _ => {}
+            }
+            rest = &rest[12 + length..];
+        }
+        let mut raw: Vec<u8> = Vec::new();
+        ZlibDecoder::new(&compressed[..])
+            .read_to_end(&mut raw)
+            .unwrap();
+        let pixels: Vec<Vec<bool>> = raw
+            .chunks(width.div_ceil(8) + 1)
+            .map(|row| {
+                assert_eq!(row[0], 0);
+                (0..width)
+                    .map(|x| row[1 + x / 8] & (0x80 >> (x % 8)) != 0)
+                    .collect()
+            })
+            .collect();
+        (width, pixels)
+    }
+
+    #[test]
+    fn test_qr_decodes() {
+        for text in [
+            "GYIZSCOKCFBU",
+            "gyiz sc-okcf bu!",
+            &"BMODZBXDNABEKUDMUIXMMOUVIF".repeat(20),
+        ] {
+            let qr: QrCode = encode(text.as_bytes()).unwrap();
+            let (width, pixels) = read_png(&to_png(&qr));
+            assert_eq!(width, (qr.width() + 2 * QUIET_ZONE) * SCALE);
+            let mut image = rqrr::PreparedImage::prepare_from_greyscale(width, width, |x, y| {
+                if pixels[y][x] { 255 } else { 0 }
+            });
+            let grids = image.detect_grids();
+            assert_eq!(grids.len(), 1);
+            let (meta, decoded) = grids[0].decode().unwrap();
+            assert_eq!(decoded, text);
+            assert_eq!(meta.ecc_level, 0, "level M");
+        }
+        assert_eq!(encode(b"GYIZSCOKCFBU").unwrap().width(), 21);
+        assert!(matches!(encode(&[b'a'; 2400]), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_qr_text() {
+        let qr: QrCode = encode(b"GYIZSCOKCFBU").unwrap();
+        let text: String = to_text(&qr);
+        // 21 modules and the quiet zones, two rows to a line
+        assert_eq!(text.lines().count(), 15);
+        assert!(text.lines().all(|line| line.chars().count() == 29));
+        // The quiet zone is drawn light, as full blocks
+        assert!(text.lines().next().unwrap().chars().all(|c| c == '█'));
+    }
+}
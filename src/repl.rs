@@ -0,0 +1,266 @@
+//! Interactive session keeping the table in memory between messages.
+//!
+//! Each line typed is encrypted or decrypted with the current settings.
+//! Lines starting with `:` are commands:
+//!
+//! * `:mode [encrypt|decrypt]` - shows or switches the mode
+//! * `:key KEY` - generates a new table from `KEY`
+//! * `:table` - prints the current table
+//! * `:help` - lists the commands
+//! * `:quit` - ends the session (so does end of input)
+//!
+//! With `--key-list` every message is processed with the next key of the
+//! list, starting at `--message-index` and wrapping around at the end.
+
+use crate::error::Error;
+use crate::squares::DigramCipher;
+use crate::{CipherMode, CipherSettings};
+use clap::ArgMatches;
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Text printed by `:help`
+const HELP: &str = "\
+Type a line to encrypt or decrypt it, or one of these commands:
+  :mode [encrypt|decrypt]  show or switch the mode
+  :key KEY                 generate a new table from KEY
+  :table                   print the current table
+  :help                    show this help
+  :quit                    end the session";
+
+/// What the session does after handling a line
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// Print this text and wait for the next line
+    Output(String),
+    /// Print nothing and wait for the next line
+    Nothing,
+    /// End the session
+    Quit,
+}
+
+/// The state of an interactive session
+pub struct Session {
+    /// The table and settings used for the next line
+    settings: CipherSettings,
+    /// The keys used in turn for each message, empty for a fixed key
+    keys: Vec<String>,
+    /// The index of the key of the next message in `keys`
+    next: usize,
+}
+
+impl Session {
+    /// Starts a session with the given table and settings.
+    pub fn new(settings: CipherSettings) -> Self {
+        Session {
+            settings,
+            keys: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// Starts a session switching to the next key of a list after every message.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings, whose table is replaced for each message.
+    /// * `keys` - The key schedule, which must not be empty.
+    /// * `first` - The index of the key of the first message.
+    pub fn with_key_list(settings: CipherSettings, keys: Vec<String>, first: usize) -> Self {
+        let mut session: Session = Session {
+            settings,
+            next: first % keys.len(),
+            keys,
+        };
+        session.set_key(&session.keys[session.next].clone());
+        session
+    }
+
+    /// Generates the table of the next line from `key`.
+    fn set_key(&mut self, key: &str) {
+        self.settings = CipherSettings::new(
+            key,
+            self.settings.variant.clone(),
+            self.settings.mode,
+            self.settings.style.clone(),
+        );
+    }
+
+    /// Handles one line of input.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line typed by the user, without the newline.
+    ///
+    /// # Returns
+    ///
+    /// * What to do next, or an error to report before reading the next line.
+    pub fn handle(&mut self, line: &str) -> Result<Outcome, Error> {
+        let line: &str = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            if line.is_empty() {
+                return Ok(Outcome::Nothing);
+            }
+            let result: String = self.settings.process(line)?;
+            if !self.keys.is_empty() {
+                self.next = (self.next + 1) % self.keys.len();
+                self.set_key(&self.keys[self.next].clone());
+            }
+            return Ok(Outcome::Output(result));
+        };
+
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+        match (name, argument) {
+            ("mode", "") => Ok(Outcome::Output(String::from(match self.settings.mode {
+                CipherMode::Encrypt => "encrypt",
+                CipherMode::Decrypt => "decrypt",
+            }))),
+            ("mode", "encrypt" | "e") => {
+                self.settings.mode = CipherMode::Encrypt;
+                Ok(Outcome::Nothing)
+            }
+            ("mode", "decrypt" | "d") => {
+                self.settings.mode = CipherMode::Decrypt;
+                Ok(Outcome::Nothing)
+            }
+            ("mode", other) => Err(Error::Usage(format!(
+                "unknown mode '{}': expected 'encrypt' or 'decrypt'",
+                other
+            ))),
+            ("key", "") => Err(Error::Usage(String::from("usage: :key KEY"))),
+            ("key", key) => {
+                crate::cli::validate_key(key, &self.settings.variant)?;
+                // An explicit key ends the rotation through the key list
+                self.keys.clear();
+                self.set_key(key);
+                Ok(Outcome::Nothing)
+            }
+            ("table", _) => Ok(Outcome::Output(self.settings.cipher.rows().join("\n"))),
+            ("help", _) => Ok(Outcome::Output(String::from(HELP))),
+            ("quit" | "q" | "exit", _) => Ok(Outcome::Quit),
+            (other, _) => Err(Error::Usage(format!(
+                "unknown command ':{}', type :help for a list",
+                other
+            ))),
+        }
+    }
+}
+
+/// Runs the `repl` subcommand until `:quit` or end of input.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings are invalid or the terminal can't be used.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let mode: CipherMode = crate::cli::mode_from_matches(matches);
+    let (variant, style, _) = crate::cli::settings_from_matches(matches)?;
+    let key: String = crate::cli::key_from_matches(matches, mode, &variant)?;
+    let keys: Option<Vec<String>> = crate::cli::key_list_from_matches(matches, &variant)?;
+    let settings: CipherSettings = CipherSettings::new(&key, variant, mode, style);
+    let mut session: Session = match keys {
+        Some(keys) => Session::with_key_list(
+            settings,
+            keys,
+            crate::cli::message_index_from_matches(matches)?,
+        ),
+        None => Session::new(settings),
+    };
+
+    let interactive: bool = std::io::stdin().is_terminal();
+    if interactive {
+        eprintln!("Playfair session, type :help for commands");
+    }
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        if interactive {
+            eprint!("> ");
+            std::io::stderr()
+                .flush()
+                .map_err(|e| Error::Io(e.to_string()))?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line: String = line.map_err(|e| Error::Io(format!("cannot read input: {}", e)))?;
+        match session.handle(&line) {
+            Ok(Outcome::Output(text)) => println!("{}", text),
+            Ok(Outcome::Nothing) => {}
+            Ok(Outcome::Quit) => break,
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutputStyle, Variant};
+
+    #[test]
+    fn test_session_commands() {
+        let mut session: Session = Session::new(CipherSettings::new(
+            "KEYWORD",
+            Variant::default(),
+            CipherMode::Encrypt,
+            OutputStyle::default(),
+        ));
+        assert_eq!(
+            session.handle("hello").unwrap(),
+            Outcome::Output(String::from("GYIZSC"))
+        );
+        assert_eq!(session.handle(":mode decrypt").unwrap(), Outcome::Nothing);
+        assert_eq!(
+            session.handle("GYIZSC").unwrap(),
+            Outcome::Output(String::from("HELXLO"))
+        );
+        assert_eq!(session.handle(":key PLAYFAIR").unwrap(), Outcome::Nothing);
+        match session.handle(":table").unwrap() {
+            Outcome::Output(table) => assert!(table.starts_with("['P', 'L', 'A', 'Y', 'F']")),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(session.handle(":mode sideways").is_err());
+        assert!(session.handle(":bogus").is_err());
+        assert_eq!(session.handle("").unwrap(), Outcome::Nothing);
+        assert_eq!(session.handle(":quit").unwrap(), Outcome::Quit);
+    }
+
+    #[test]
+    fn test_session_rotates_key_list() {
+        let keys: Vec<String> = vec![String::from("KEYWORD"), String::from("PLAYFAIR")];
+        let settings: CipherSettings = CipherSettings::new(
+            "unused",
+            Variant::default(),
+            CipherMode::Encrypt,
+            OutputStyle::default(),
+        );
+        let mut session: Session = Session::with_key_list(settings, keys, 1);
+        let playfair: Outcome = session.handle("hello").unwrap();
+        assert_eq!(
+            session.handle("hello").unwrap(),
+            Outcome::Output(String::from("GYIZSC"))
+        );
+        assert_eq!(session.handle("hello").unwrap(), playfair);
+        assert_ne!(playfair, Outcome::Output(String::from("GYIZSC")));
+
+        // Commands are not messages and don't advance the list
+        session.handle(":table").unwrap();
+        assert_eq!(
+            session.handle("hello").unwrap(),
+            Outcome::Output(String::from("GYIZSC"))
+        );
+        assert_eq!(session.handle(":key KEYWORD").unwrap(), Outcome::Nothing);
+        session.handle("hello").unwrap();
+        assert_eq!(
+            session.handle("hello").unwrap(),
+            Outcome::Output(String::from("GYIZSC"))
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Reports of `crack` runs for write-ups.
+//!
+//! `crack --report PATH` writes what a write-up of the solve needs: the
+//! ciphertext, its letter and digram statistics, the settings of the
+//! search, the best tables with their scores and the plaintext they
+//! recover. The report is Markdown, or a standalone HTML page if `PATH`
+//! ends in `.html` or `.htm`. It is written whether or not a key was found,
+//! so a failed run can be shared too.
+
+use crate::analysis::{Solution, Solver, Strategy};
+use crate::analyze::{Level, Statistics};
+use crate::error::Error;
+use crate::export;
+use crate::keygen;
+use std::time::Duration;
+
+/// Letters per line of the ciphertext and the plaintext in a report
+const LINE_LETTERS: usize = 60;
+
+/// What a report says about a `crack` run
+pub struct Report<'a> {
+    /// The ciphertext, as the symbols of the table
+    pub ciphertext: String,
+    /// The statistics of the ciphertext
+    pub statistics: Statistics,
+    /// The strategy of the search
+    pub strategy: &'a Strategy,
+    /// The seed of the search
+    pub seed: u64,
+    /// What the decryptions were scored as, such as `English text`
+    pub scoring: String,
+    /// The name of the n-grams the scores are counted in
+    pub ngram_name: &'static str,
+    /// The best tables, best first, with their decryptions
+    pub solutions: Vec<(&'a Solution, String)>,
+    /// Whether the best decryption reads as the language
+    pub found: bool,
+    /// How long the search took
+    pub elapsed: Duration,
+}
+
+/// Names a solver with its settings.
+fn solver_name(solver: &Solver) -> String {
+    match solver {
+        Solver::Hill => String::from("hill"),
+        Solver::Anneal(schedule) => format!(
+            "anneal, from temperature {} with {} cooling{}",
+            schedule.start,
+            schedule.cooling.name(),
+            match schedule.reheat {
+                Some(changes) => format!(
+                    ", reheating after {} changes without a better table",
+                    changes
+                ),
+                None => String::new(),
+            }
+        ),
+        Solver::Genetic(genetics) => format!(
+            "genetic, population {}, crossover {}, mutation {}",
+            genetics.population, genetics.crossover, genetics.mutation
+        ),
+        Solver::Beam(width) => format!("beam of width {}", width),
+        Solver::Exact => String::from("exact"),
+        Solver::Custom(name) => name.to_string(),
+    }
+}
+
+impl Report<'_> {
+    /// Lists the settings of the search as names and values.
+    fn settings(&self) -> Vec<(&'static str, String)> {
+        let strategy: &Strategy = self.strategy;
+        let mut settings: Vec<(&'static str, String)> = vec![
+            ("Solver", solver_name(&strategy.solver)),
+            (
+                "Changes",
+                strategy
+                    .moves
+                    .iter()
+                    .map(|change| change.name())
+                    .collect::<Vec<&str>>()
+                    .join(","),
+            ),
+            ("Iterations", strategy.iterations.to_string()),
+            ("Restarts", strategy.restarts.to_string()),
+            ("Seed", self.seed.to_string()),
+            (
+                "Scoring",
+                format!("{} by {}", self.scoring, self.ngram_name),
+            ),
+            ("Time", format!("{:.1} s", self.elapsed.as_secs_f64())),
+        ];
+        if !strategy.cribs.is_empty() {
+            settings.push(("Crib digrams", strategy.cribs.len().to_string()));
+        }
+        settings
+    }
+
+    /// Lists the statistics as rows of the measure and the values of the
+    /// ciphertext, the language and random symbols.
+    fn statistics_rows(&self) -> Vec<[String; 4]> {
+        let mut rows: Vec<[String; 4]> = Vec::new();
+        for (name, levels) in [
+            ("Letters", &self.statistics.letters),
+            ("Digrams", &self.statistics.digrams),
+        ] {
+            // The label, the value and its places, as `analyze stats` shows them
+            type Measure = (&'static str, fn(&Level) -> f64, usize);
+            let measures: [Measure; 3] = [
+                ("index of coincidence", |level| level.coincidence, 4),
+                ("entropy (bits)", |level| level.entropy, 2),
+                ("chi-squared", |level| level.chi_squared, 1),
+            ];
+            for (measure, value, places) in measures {
+                let shown = |level: &Level| format!("{:.*}", places, value(level));
+                // The chi-squared statistic is only measured on the ciphertext
+                let (language, random): (String, String) = if measure == "chi-squared" {
+                    (String::from("-"), String::from("-"))
+                } else {
+                    (shown(&levels.language), shown(&levels.random))
+                };
+                rows.push([
+                    format!("{}: {}", name, measure),
+                    shown(&levels.ciphertext),
+                    language,
+                    random,
+                ]);
+            }
+        }
+        rows
+    }
+
+    /// Writes the report as Markdown.
+    pub fn markdown(&self) -> String {
+        let mut md: String = String::from("# Playfair Cracking Report\n\n");
+        md.push_str(&format!(
+            "**Result:** {}\n\n",
+            if self.found {
+                "key found"
+            } else {
+                "no key found"
+            }
+        ));
+        md.push_str(&format!(
+            "## Ciphertext\n\n{} letters:\n\n```text\n{}\n```\n\n",
+            self.ciphertext.chars().count(),
+            lines(&self.ciphertext)
+        ));
+        md.push_str(&format!(
+            "## Statistics\n\n| Measure | Ciphertext | {} | Random |\n|---|---:|---:|---:|\n",
+            self.statistics.language
+        ));
+        for row in self.statistics_rows() {
+            md.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        md.push_str("\n## Settings\n\n| Setting | Value |\n|---|---|\n");
+        for (name, value) in self.settings() {
+            md.push_str(&format!("| {} | {} |\n", name, value.replace('|', "\\|")));
+        }
+        for (rank, (solution, plaintext)) in self.solutions.iter().enumerate() {
+            md.push_str(&format!("\n## Key {}\n\n", rank + 1));
+            md.push_str(&format!(
+                "- Key square: `{}`\n- Fingerprint: `{}`\n- Score: {:.2} per {}\n",
+                keygen::square_line(&solution.square),
+                solution.square.fingerprint(),
+                solution.score,
+                self.ngram_name
+            ));
+            if let Some(words) = solution.words {
+                md.push_str(&format!("- Words: {:.0}% of the letters\n", words * 100.0));
+            }
+            md.push_str("\n```text\n");
+            for row in solution.square.rows() {
+                let row: Vec<String> = row.iter().map(char::to_string).collect();
+                md.push_str(&format!("{}\n", row.join(" ")));
+            }
+            md.push_str(&format!(
+                "```\n\nPlaintext:\n\n```text\n{}\n```\n",
+                lines(plaintext)
+            ));
+        }
+        md
+    }
+
+    /// Writes the report as a standalone HTML page.
+    pub fn html(&self) -> String {
+        let escape = |text: &str| -> String { text.chars().map(export::xml_escape).collect() };
+        let mut html: String = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Playfair Cracking Report</title>\n\
+             <style>\n\
+             body { font-family: sans-serif; max-width: 50em; margin: 2em auto; }\n\
+             pre { background: #f4f4f4; padding: 0.5em; }\n\
+             table.report { border-collapse: collapse; }\n\
+             table.report td, table.report th { border: 1px solid #999; padding: 0.2em 0.6em; }\n\
+             </style>\n</head>\n<body>\n<h1>Playfair Cracking Report</h1>\n",
+        );
+        html.push_str(&format!(
+            "<p><strong>Result:</strong> {}</p>\n",
+            if self.found {
+                "key found"
+            } else {
+                "no key found"
+            }
+        ));
+        html.push_str(&format!(
+            "<h2>Ciphertext</h2>\n<p>{} letters:</p>\n<pre>{}</pre>\n",
+            self.ciphertext.chars().count(),
+            escape(&lines(&self.ciphertext))
+        ));
+        html.push_str(&format!(
+            "<h2>Statistics</h2>\n<table class=\"report\">\n  <tr><th>Measure</th><th>Ciphertext</th><th>{}</th><th>Random</th></tr>\n",
+            escape(&self.statistics.language)
+        ));
+        for row in self.statistics_rows() {
+            let cells: String = row
+                .iter()
+                .map(|cell| format!("<td>{}</td>", escape(cell)))
+                .collect();
+            html.push_str(&format!("  <tr>{}</tr>\n", cells));
+        }
+        html.push_str("</table>\n<h2>Settings</h2>\n<table class=\"report\">\n");
+        for (name, value) in self.settings() {
+            html.push_str(&format!(
+                "  <tr><th>{}</th><td>{}</td></tr>\n",
+                name,
+                escape(&value)
+            ));
+        }
+        html.push_str("</table>\n");
+        for (rank, (solution, plaintext)) in self.solutions.iter().enumerate() {
+            html.push_str(&format!("<h2>Key {}</h2>\n<ul>\n", rank + 1));
+            html.push_str(&format!(
+                "  <li>Key square: <code>{}</code></li>\n  <li>Fingerprint: <code>{}</code></li>\n  <li>Score: {:.2} per {}</li>\n",
+                escape(&keygen::square_line(&solution.square)),
+                solution.square.fingerprint(),
+                solution.score,
+                self.ngram_name
+            ));
+            if let Some(words) = solution.words {
+                html.push_str(&format!(
+                    "  <li>Words: {:.0}% of the letters</li>\n",
+                    words * 100.0
+                ));
+            }
+            html.push_str("</ul>\n");
+            html.push_str(&export::html(solution.square.rows()));
+            html.push_str(&format!(
+                "<p>Plaintext:</p>\n<pre>{}</pre>\n",
+                escape(&lines(plaintext))
+            ));
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Writes the report to a file, as HTML if its name ends in `.html` or
+    /// `.htm` and as Markdown otherwise.
+    ///
+    /// # Returns
+    ///
+    /// * An I/O error if the file can't be written.
+    pub fn write(&self, path: &str) -> Result<(), Error> {
+        let lower: String = path.to_lowercase();
+        let contents: String = if lower.ends_with(".html") || lower.ends_with(".htm") {
+            self.html()
+        } else {
+            self.markdown()
+        };
+        std::fs::write(path, contents)
+            .map_err(|e| Error::Io(format!("cannot write report '{}': {}", path, e)))
+    }
+}
+
+/// Breaks a text into lines of `LINE_LETTERS` characters.
+fn lines(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(LINE_LETTERS)
+        .map(|line| line.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Language;
+    use crate::{KeySquare, Variant};
+
+    #[test]
+    fn test_reports() {
+        let variant: Variant = Variant::default();
+        let symbols: Vec<char> = variant.symbols();
+        let rows: Vec<Vec<char>> = crate::generate_playfair_table("PLAYFAIR", &variant);
+        let ciphertext: String = String::from("BMODZBXDNABEKUDMUIXMMOUVIF");
+        let indices: Vec<usize> = ciphertext
+            .chars()
+            .map(|c| symbols.iter().position(|&s| s == c).unwrap())
+            .collect();
+        let solution: Solution = Solution {
+            square: KeySquare::from_rows(rows),
+            plaintext: String::from("HIDETHEGOLDINTHETREXESTUMP"),
+            score: -4.5,
+            words: Some(0.8),
+            fitness: -4.5,
+        };
+        let strategy: Strategy = Strategy::default();
+        let report: Report = Report {
+            ciphertext,
+            statistics: crate::analyze::statistics(&indices, &symbols, Language::English),
+            strategy: &strategy,
+            seed: 7,
+            scoring: String::from("English <text> & more"),
+            ngram_name: "quadgram",
+            solutions: vec![(&solution, solution.plaintext.clone())],
+            found: true,
+            elapsed: Duration::from_millis(1500),
+        };
+
+        let markdown: String = report.markdown();
+        assert!(markdown.contains("**Result:** key found"));
+        assert!(markdown.contains("BMODZBXDNABEKUDMUIXMMOUVIF"));
+        assert!(markdown.contains("- Key square: `PLAYF IRBCD EGHKM NOQST UVWXZ`"));
+        assert!(markdown.contains("HIDETHEGOLDINTHETREXESTUMP"));
+        assert!(markdown.contains("| Seed | 7 |"));
+        assert!(markdown.contains(" with linear cooling |"));
+        assert!(markdown.contains("| Changes | swap,rows,columns,reflect |"));
+        assert!(markdown.contains("| Time | 1.5 s |"));
+        assert!(markdown.contains("| Digrams: chi-squared |"));
+
+        let html: String = report.html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<table class=\"playfair\">"));
+        assert!(html.contains("<td>English &lt;text&gt; &amp; more by quadgram</td>"));
+        assert!(html.contains("<pre>HIDETHEGOLDINTHETREXESTUMP</pre>"));
+        assert!(html.ends_with("</html>\n"));
+
+        // Long texts are broken into lines
+        assert_eq!(lines(&"A".repeat(61)), format!("{}\nA", "A".repeat(60)));
+    }
+}
@@ -0,0 +1,205 @@
+//! Built-in known-answer tests checking the binary at runtime.
+//!
+//! The vectors come from textbook examples and from configurations that
+//! exercise the variant settings. Every encryption vector is also decrypted
+//! again to check the round trip.
+
+use crate::error::Error;
+use crate::{Alphabet, CipherMode, Variant};
+
+/// A known-answer test vector
+struct Vector {
+    /// Short description printed in the report
+    name: &'static str,
+    /// The key the table is generated from
+    key: &'static str,
+    /// The filler letter
+    filler: char,
+    /// The merged letter and its replacement, if not omitted
+    merge: (char, Option<char>),
+    /// The grid or alphabet setting of the table, e.g. `5x5` or `ru`
+    alphabet: &'static str,
+    /// Whether `input` is encrypted or decrypted
+    mode: CipherMode,
+    /// The text to process
+    input: &'static str,
+    /// The expected result
+    expected: &'static str,
+}
+
+/// The embedded known-answer vectors
+const VECTORS: [Vector; 10] = [
+    Vector {
+        name: "wikipedia example",
+        key: "playfair example",
+        filler: 'X',
+        merge: ('J', Some('I')),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "Hide the gold in the tree stump",
+        expected: "BMODZBXDNABEKUDMUIXMMOUVIF",
+    },
+    Vector {
+        name: "readme example",
+        key: "KEYWORD",
+        filler: 'X',
+        merge: ('J', Some('I')),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "HELLO WORLD",
+        expected: "GYIZSCOKCFBU",
+    },
+    Vector {
+        name: "readme decryption",
+        key: "KEYWORD",
+        filler: 'X',
+        merge: ('J', Some('I')),
+        alphabet: "5x5",
+        mode: CipherMode::Decrypt,
+        input: "GYIZSCOKCFBU",
+        expected: "HELXLOWORLDX",
+    },
+    Vector {
+        name: "stallings example, filler Z",
+        key: "MONARCHY",
+        filler: 'Z',
+        merge: ('J', Some('I')),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "instruments",
+        expected: "GATLMZCLRQTX",
+    },
+    Vector {
+        name: "filler Q between doubles",
+        key: "KEYWORD",
+        filler: 'Q',
+        merge: ('J', Some('I')),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "balloon",
+        expected: "CBISSCES",
+    },
+    Vector {
+        name: "merge V into U",
+        key: "jovial",
+        filler: 'X',
+        merge: ('V', Some('U')),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "vivid javelin",
+        expected: "IAIALIJILBJR",
+    },
+    Vector {
+        name: "omit Q",
+        key: "KEYWORD",
+        filler: 'X',
+        merge: ('Q', None),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "quick jump",
+        expected: "XGROGZNS",
+    },
+    Vector {
+        name: "lowercase key and j merge",
+        key: "jam",
+        filler: 'X',
+        merge: ('J', Some('I')),
+        alphabet: "5x5",
+        mode: CipherMode::Encrypt,
+        input: "jump",
+        expected: "CQCN",
+    },
+    Vector {
+        name: "6x6 grid with digits",
+        key: "playfair 2024",
+        filler: 'X',
+        merge: ('J', Some('I')),
+        alphabet: "6x6",
+        mode: CipherMode::Encrypt,
+        input: "Meet at 10pm by gate 7",
+        expected: "NDKNIOWCRU4FO0NKY7",
+    },
+    Vector {
+        name: "cyrillic 6x6 grid",
+        key: "шифровка",
+        filler: 'Х',
+        merge: ('J', Some('I')),
+        alphabet: "ru",
+        mode: CipherMode::Encrypt,
+        input: "Привет, мир",
+        expected: "ТИФШГХ-ЛФО",
+    },
+];
+
+/// Runs one vector and, for encryption vectors, the round trip back.
+///
+/// # Returns
+///
+/// * A description of the mismatch if the vector fails.
+fn check(vector: &Vector) -> Result<(), String> {
+    let alphabet: Alphabet = crate::parse_grid(vector.alphabet)
+        .or_else(|_| crate::parse_alphabet(vector.alphabet))
+        .map_err(|e| e.to_string())?;
+    let variant: Variant = Variant {
+        filler: vector.filler,
+        merge: vector.merge,
+        alphabet,
+        ..Variant::default()
+    };
+    let table: Vec<Vec<char>> = crate::generate_playfair_table(vector.key, &variant);
+    let result: String = crate::playfair_cipher(vector.input, &table, &variant, vector.mode);
+    if result != vector.expected {
+        return Err(format!("expected {}, got {}", vector.expected, result));
+    }
+    if matches!(vector.mode, CipherMode::Encrypt) {
+        let (prepared, _) = crate::prepare_text(vector.input, &variant);
+        let prepared: String = prepared.into_iter().collect();
+        let back: String = crate::playfair_cipher(&result, &table, &variant, CipherMode::Decrypt);
+        if back != prepared {
+            return Err(format!("round trip expected {}, got {}", prepared, back));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `selftest` subcommand, printing a line per vector.
+///
+/// # Returns
+///
+/// * An internal error if any vector fails.
+pub fn run() -> Result<(), Error> {
+    let mut failures: usize = 0;
+    for vector in &VECTORS {
+        match check(vector) {
+            Ok(()) => println!("PASS {}", vector.name),
+            Err(e) => {
+                failures += 1;
+                println!("FAIL {}: {}", vector.name, e);
+            }
+        }
+    }
+    println!(
+        "{} of {} vectors passed",
+        VECTORS.len() - failures,
+        VECTORS.len()
+    );
+    if failures > 0 {
+        return Err(Error::Internal(format!(
+            "{} self-test vectors failed",
+            failures
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_vectors_pass() {
+        for vector in &VECTORS {
+            assert_eq!(check(vector), Ok(()), "{}", vector.name);
+        }
+    }
+}
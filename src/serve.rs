@@ -0,0 +1,273 @@
+//! HTTP server exposing the cipher as JSON endpoints.
+//!
+//! `POST /encrypt` and `POST /decrypt` take a body such as
+//! `{"text": "HELLO", "key": "KEYWORD"}` and answer with
+//! `{"result": "GYIZSC", "key_fingerprint": "..."}`. The key may be left out
+//! when one was preloaded at startup with `--key`, `--key-file` or
+//! `PLAYFAIR_KEY`. Errors are answered with a 4xx status and `{"error": "..."}`.
+//!
+//! Requests are handled by a pool of worker threads. The preloaded table is
+//! shared read-only; everything else is built per request.
+
+use crate::error::Error;
+use crate::{CipherMode, CipherSettings, OutputStyle, Playfair, Variant};
+use clap::ArgMatches;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::io::Read;
+use std::sync::Arc;
+
+/// Largest request body accepted, in bytes
+const MAX_BODY: u64 = 1 << 20;
+
+/// Body of an `/encrypt` or `/decrypt` request
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CipherRequest {
+    /// The text to encrypt or decrypt
+    text: String,
+    /// The key, required unless one was preloaded
+    key: Option<String>,
+}
+
+/// The immutable state shared by all worker threads
+pub struct Service {
+    /// The variant used for every request
+    variant: Variant,
+    /// How the results are grouped
+    style: OutputStyle,
+    /// The table generated from the preloaded key, if any
+    preloaded: Option<Vec<Vec<char>>>,
+}
+
+impl Service {
+    /// Creates the service, generating the table of the preloaded key.
+    pub fn new(variant: Variant, style: OutputStyle, key: Option<&str>) -> Self {
+        let preloaded: Option<Vec<Vec<char>>> =
+            key.map(|key| crate::generate_playfair_table(key, &variant));
+        Service {
+            variant,
+            style,
+            preloaded,
+        }
+    }
+
+    /// Answers one request.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The HTTP method, e.g. `POST`.
+    /// * `path` - The request path, e.g. `/encrypt`.
+    /// * `body` - The request body.
+    ///
+    /// # Returns
+    ///
+    /// * The HTTP status code and the JSON body of the response.
+    pub fn respond(&self, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mode: CipherMode = match path.split('?').next().unwrap_or_default() {
+            "/encrypt" => CipherMode::Encrypt,
+            "/decrypt" => CipherMode::Decrypt,
+            _ => return (404, json!({ "error": "not found" })),
+        };
+        if method != "POST" {
+            return (405, json!({ "error": "use POST" }));
+        }
+        match self.process(body, mode) {
+            Ok(response) => (200, response),
+            Err(e) => (400, json!({ "error": e.to_string() })),
+        }
+    }
+
+    /// Parses a request body and processes its text.
+    fn process(&self, body: &str, mode: CipherMode) -> Result<Value, Error> {
+        let request: CipherRequest = serde_json::from_str(body)
+            .map_err(|e| Error::InvalidInput(format!("invalid request: {}", e)))?;
+        self.process_text(&request.text, request.key.as_deref(), mode)
+    }
+
+    /// Encrypts or decrypts a text with the request key or the preloaded one.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to process.
+    /// * `key` - The key of the request, which takes precedence over the preloaded key.
+    /// * `mode` - The encryption or decryption mode.
+    ///
+    /// # Returns
+    ///
+    /// * A JSON object with the result and the key fingerprint.
+    pub fn process_text(
+        &self,
+        text: &str,
+        key: Option<&str>,
+        mode: CipherMode,
+    ) -> Result<Value, Error> {
+        let table: Vec<Vec<char>> = match (key, &self.preloaded) {
+            (Some(key), _) => {
+                crate::cli::validate_key(key, &self.variant)?;
+                crate::generate_playfair_table(key, &self.variant)
+            }
+            (None, Some(table)) => table.clone(),
+            (None, None) => {
+                return Err(Error::InvalidKey(String::from(
+                    "no key given and none preloaded",
+                )));
+            }
+        };
+        let settings: CipherSettings = CipherSettings {
+            cipher: Playfair::from_rows(table),
+            variant: self.variant.clone(),
+            mode,
+            style: self.style.clone(),
+            transposition: None,
+            preserve_case: false,
+            keep_layout: false,
+            strip_filler: None,
+        };
+        let result: String = settings.process(text)?;
+        Ok(json!({
+            "result": result,
+            "key_fingerprint": settings.cipher.square().fingerprint(),
+        }))
+    }
+}
+
+/// Loads the variant and the optional preloaded key shared by the servers.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * The service, or an error if the settings or the key are invalid.
+pub fn service_from_matches(matches: &ArgMatches) -> Result<Service, Error> {
+    let (variant, style, _) = crate::cli::settings_from_matches(matches)?;
+    // Keys are optional here, so never prompt for one
+    let key: Option<String> = match crate::cli::square_from_matches(matches, &variant)? {
+        Some(key) => Some(key),
+        None => crate::resolve_key(
+            matches.get_one::<String>("key"),
+            matches.get_one::<String>("key-file"),
+            std::env::var(crate::KEY_ENV_VAR).ok(),
+        )?
+        .map(|key| crate::cli::derive_from_matches(matches, key, &variant)),
+    };
+    if let Some(key) = &key {
+        crate::cli::validate_key(key, &variant)?;
+    }
+    Ok(Service::new(variant, style, key.as_deref()))
+}
+
+/// Describes where the keys of a service come from, for the startup message.
+pub fn key_source(service: &Service) -> &'static str {
+    if service.preloaded.is_some() {
+        "key preloaded"
+    } else {
+        "keys per request"
+    }
+}
+
+/// Runs the `serve` subcommand until the process is stopped.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings are invalid or the address can't be bound.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let service: Arc<Service> = Arc::new(service_from_matches(matches)?);
+
+    let bind: &String = matches.get_one::<String>("bind").unwrap();
+    let server: Arc<tiny_http::Server> = Arc::new(
+        tiny_http::Server::http(bind.as_str())
+            .map_err(|e| Error::Io(format!("cannot listen on {}: {}", bind, e)))?,
+    );
+    eprintln!("Listening on http://{} ({})", bind, key_source(&service));
+
+    let workers: usize = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let handles: Vec<std::thread::JoinHandle<()>> = (0..workers)
+        .map(|_| {
+            let server: Arc<tiny_http::Server> = Arc::clone(&server);
+            let service: Arc<Service> = Arc::clone(&service);
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(request, &service);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle
+            .join()
+            .map_err(|_| Error::Internal(String::from("a server thread panicked")))?;
+    }
+
+    Ok(())
+}
+
+/// Reads one HTTP request, answers it and logs failures to stderr.
+fn handle_request(mut request: tiny_http::Request, service: &Service) {
+    let mut body: String = String::new();
+    let (status, response) = match request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut body)
+    {
+        Ok(_) if body.len() as u64 > MAX_BODY => {
+            (413, json!({ "error": "request body too large" }))
+        }
+        Ok(_) => service.respond(request.method().as_str(), request.url(), &body),
+        Err(e) => (
+            400,
+            json!({ "error": format!("cannot read request: {}", e) }),
+        ),
+    };
+    let header: tiny_http::Header =
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    let reply = tiny_http::Response::from_string(response.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(reply) {
+        eprintln!("Error: cannot send response: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond_with_request_key() {
+        let service: Service = Service::new(Variant::default(), OutputStyle::default(), None);
+        let (status, body) =
+            service.respond("POST", "/encrypt", r#"{"text": "hello", "key": "KEYWORD"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["result"], "GYIZSC");
+
+        let (status, body) = service.respond("POST", "/decrypt", r#"{"text": "GYIZSC"}"#);
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("no key"));
+    }
+
+    #[test]
+    fn test_respond_with_preloaded_key() {
+        let service: Service = Service::new(
+            Variant::default(),
+            OutputStyle {
+                group: Some(2),
+                ..OutputStyle::default()
+            },
+            Some("KEYWORD"),
+        );
+        let (status, body) = service.respond("POST", "/decrypt", r#"{"text": "GYIZSC"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["result"], "HE LX LO");
+
+        assert_eq!(service.respond("GET", "/decrypt", "").0, 405);
+        assert_eq!(service.respond("POST", "/other", "").0, 404);
+        assert_eq!(service.respond("POST", "/encrypt", "not json").0, 400);
+    }
+}
@@ -0,0 +1,213 @@
+//! Solvers that walk from table to table one change at a time.
+//!
+//! `--solver hill` and `anneal` are two implementations of the `Solver`
+//! trait: it proposes a neighbour of the table the search is at, sets the
+//! temperature over a restart, decides which neighbours are kept and when a
+//! restart may stop early. A new optimizer implements the trait and is
+//! given to `analysis::crack_with`, or to `build_cli_with` and `run_with`
+//! of a binary of one's own, after which `--solver NAME` picks it, and it
+//! runs with the restarts, threads, cribs, checkpoints, time limit and
+//! progress reports of the built-in solvers. `SOLVERS` are the solvers
+//! offered by default.
+//!
+//! `threshold` is listed as an example: threshold accepting, which keeps
+//! every neighbour less worse than a threshold falling over the restart,
+//! with no random draws, and stops a restart once a quarter of its changes
+//! went by without a better table.
+
+use crate::analysis::{self, Move};
+use crate::keygen::Rng;
+
+/// A search strategy climbing from a table through its neighbours
+pub trait Solver: Sync {
+    /// Returns the name `--solver` picks the solver by.
+    fn name(&self) -> &'static str;
+
+    /// Changes a table into one of its neighbours, in place.
+    ///
+    /// The default draws one of the `--moves` by its weight.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The generator of the restart.
+    /// * `moves` - The kinds of changes `--moves` allows; not empty.
+    /// * `shape` - Rows and columns of the table.
+    /// * `cells` - The symbol in every cell, row by row.
+    fn propose(&self, rng: &mut Rng, moves: &[Move], shape: (usize, usize), cells: &mut [usize]) {
+        analysis::change(rng, moves, shape, cells);
+    }
+
+    /// Returns the temperature after a fraction of the changes of a
+    /// restart, in log10 probability. The default is 0.
+    fn temperature(&self, progress: f64) -> f64 {
+        let _ = progress;
+        0.0
+    }
+
+    /// Decides whether the search moves to a neighbour.
+    ///
+    /// The default keeps neighbours at least as good, which lets the search
+    /// walk across plateaus, and worse ones with the probability
+    /// `e^(delta / temperature)` of simulated annealing.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The generator of the restart.
+    /// * `delta` - The score of the neighbour minus the score of the table
+    ///   the search is at.
+    /// * `temperature` - The temperature at this change.
+    fn accept(&self, rng: &mut Rng, delta: f64, temperature: f64) -> bool {
+        delta >= 0.0 || (temperature > 0.0 && analysis::unit(rng) < (delta / temperature).exp())
+    }
+
+    /// Decides whether a restart ends before its last change. The default
+    /// never ends one early.
+    ///
+    /// # Arguments
+    ///
+    /// * `iteration` - How many changes the restart tried.
+    /// * `iterations` - How many changes it may try.
+    /// * `since_best` - How many changes ago its best table was found, or
+    ///   since it was resumed.
+    fn stop(&self, iteration: usize, iterations: usize, since_best: usize) -> bool {
+        let _ = (iteration, iterations, since_best);
+        false
+    }
+}
+
+/// Hill climbing, keeping only neighbours at least as good
+pub struct Hill;
+
+impl Solver for Hill {
+    fn name(&self) -> &'static str {
+        "hill"
+    }
+}
+
+/// Threshold accepting, keeping every neighbour that is at most a threshold
+/// worse, as the threshold falls linearly to 0
+pub struct Threshold {
+    /// The threshold at the first change, in log10 probability
+    pub start: f64,
+}
+
+impl Solver for Threshold {
+    fn name(&self) -> &'static str {
+        "threshold"
+    }
+
+    fn temperature(&self, progress: f64) -> f64 {
+        self.start * (1.0 - progress)
+    }
+
+    fn accept(&self, _rng: &mut Rng, delta: f64, temperature: f64) -> bool {
+        delta >= -temperature
+    }
+
+    fn stop(&self, iteration: usize, iterations: usize, since_best: usize) -> bool {
+        // Early on the threshold is too high for the best table to improve
+        iteration > iterations / 2 && since_best > iterations / 4
+    }
+}
+
+/// The solvers `--solver` offers besides the built-in ones, unless others
+/// are given
+pub const SOLVERS: &[&dyn Solver] = &[&Threshold { start: 50.0 }];
+
+/// Finds a solver of a list by its name.
+pub fn find<'a>(solvers: &[&'a dyn Solver], name: &str) -> Option<&'a dyn Solver> {
+    solvers.iter().copied().find(|solver| solver.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{Language, Ngrams, Strategy};
+    use crate::error::Error;
+    use crate::{CipherMode, Variant, keygen};
+
+    // A solver of one's own, keeping every neighbour
+    struct Walk;
+
+    impl Solver for Walk {
+        fn name(&self) -> &'static str {
+            "walk"
+        }
+
+        fn accept(&self, _rng: &mut Rng, _delta: f64, _temperature: f64) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_solver_defaults() {
+        let mut rng: Rng = Rng::new(1);
+        assert_eq!(Hill.temperature(0.5), 0.0);
+        assert!(Hill.accept(&mut rng, 0.0, 0.0));
+        assert!(!Hill.accept(&mut rng, -0.01, 0.0));
+        assert!(!Hill.stop(10, 10, 10));
+
+        let threshold: &dyn Solver = find(SOLVERS, "threshold").unwrap();
+        assert_eq!(threshold.temperature(0.5), 25.0);
+        assert!(threshold.accept(&mut rng, -1.0, 1.0));
+        assert!(!threshold.accept(&mut rng, -1.5, 1.0));
+        assert!(threshold.stop(900, 1000, 251));
+        assert!(!threshold.stop(400, 1000, 400));
+        assert!(find(SOLVERS, "hill").is_none());
+
+        // The default change keeps the symbols of the table
+        let mut cells: Vec<usize> = (0..25).collect();
+        for _ in 0..100 {
+            Hill.propose(&mut rng, &[Move::Swap, Move::Rows], (5, 5), &mut cells);
+        }
+        let mut sorted: Vec<usize> = cells.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..25).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_solvers_of_ones_own() {
+        let variant: Variant = Variant::default();
+        let ngrams: Ngrams = Ngrams::builtin(Language::English, 4);
+        let square = keygen::random_square(&mut Rng::new(3), &variant);
+        let ciphertext: Vec<char> = crate::playfair_cipher(
+            "IT WAS THE BEST OF TIMES IT WAS THE WORST OF TIMES",
+            square.rows(),
+            &variant,
+            CipherMode::Encrypt,
+        )
+        .chars()
+        .collect();
+        let strategy: Strategy = Strategy {
+            solver: analysis::Solver::Custom("walk"),
+            iterations: 1000,
+            restarts: 1,
+            threads: 1,
+            ..Strategy::default()
+        };
+        let solvers: &[&dyn Solver] = &[&Walk, SOLVERS[0]];
+        // The defaults don't know the solver
+        assert!(matches!(
+            analysis::crack(&ciphertext, &variant, &ngrams, &strategy, &mut Rng::new(1)),
+            Err(Error::Usage(_))
+        ));
+        let solutions = analysis::crack_with(
+            &ciphertext,
+            &variant,
+            &ngrams,
+            &strategy,
+            &mut Rng::new(1),
+            solvers,
+        )
+        .unwrap();
+        assert!(solutions[0].score.is_finite());
+        // The command line offers it once given
+        let args: [&str; 5] = ["playfair", "crack", "--solver", "walk", "ABCD"];
+        assert!(crate::build_cli().try_get_matches_from(args).is_err());
+        assert!(
+            crate::build_cli_with(solvers)
+                .try_get_matches_from(args)
+                .is_ok()
+        );
+    }
+}
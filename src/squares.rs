@@ -0,0 +1,471 @@
+//! The two-square, four-square and double Playfair ciphers, digram ciphers
+//! built from several tables.
+//!
+//! All of them implement [`DigramCipher`], which splits a text into pairs and
+//! transforms each of them with the tables; unlike the Playfair cipher they
+//! look the two letters of a pair up in different tables, so doubled letters
+//! need no filler and odd texts are only padded. `crate::Playfair`
+//! implements it too, its `process` splitting doubled letters first.
+//!
+//! With the `vertical` arrangement of the two-square cipher the first key
+//! fills the top table and the second key the bottom one. The first letter
+//! of a pair is looked up in the top table and the second in the bottom one;
+//! the result is the other two corners of the rectangle they span, the first
+//! letter taken from the row of the first plaintext letter. Pairs in the
+//! same column are left unchanged. The `horizontal` arrangement places the tables side by side
+//! and reverses pairs in the same row.
+//!
+//! The four-square cipher places two keyed tables in the top right and
+//! bottom left corners and two plain alphabetical ones in the other corners.
+//! The letters of a pair are looked up in the plain tables (top left, then
+//! bottom right), and replaced by the letters of the keyed tables at the
+//! other corners of their rectangle.
+//!
+//! The double Playfair (Doppelkasten) of the German forces in WWII places two
+//! keyed tables side by side and enciphers every pair twice. The first
+//! letter is looked up in the left table and the second in the right one.
+//! Letters in different rows are replaced by the corners of their rectangle,
+//! the first taken from the right table. Letters in the same row, which the
+//! two-square cipher would only reverse, are replaced by their right
+//! neighbours instead, so no pair ever passes through unchanged.
+
+use crate::error::Error;
+use crate::{CipherMode, KeySquare, Variant};
+
+/// A cipher transforming texts pair by pair with keyed tables
+pub trait DigramCipher {
+    /// Returns the name of the cipher, e.g. `two-square`.
+    fn name(&self) -> &'static str;
+
+    /// Encrypts or decrypts one pair of letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `pair` - The two letters, both in the table alphabet.
+    /// * `mode` - The encryption or decryption mode.
+    ///
+    /// # Returns
+    ///
+    /// * The transformed pair.
+    fn transform_pair(&self, pair: (char, char), mode: CipherMode) -> (char, char);
+
+    /// Returns the keyed tables, in the order of their keys.
+    fn keyed_squares(&self) -> Vec<&KeySquare>;
+
+    /// Lays the tables out the way they are arranged.
+    ///
+    /// # Returns
+    ///
+    /// * One line per row, in the `['K', 'E', ...]` form of the Playfair table.
+    fn rows(&self) -> Vec<String>;
+
+    /// Encrypts or decrypts a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The input text.
+    /// * `variant` - The variant the tables were generated with.
+    /// * `mode` - The encryption or decryption mode.
+    ///
+    /// # Returns
+    ///
+    /// * The result, padded with the filler to an even length.
+    fn process(&self, text: &str, variant: &Variant, mode: CipherMode) -> String {
+        let mut chars: Vec<char> = crate::table_letters(text, variant);
+        if !chars.len().is_multiple_of(2) {
+            chars.push(variant.filler);
+        }
+        chars
+            .chunks(2)
+            .flat_map(|pair| {
+                let (a, b) = self.transform_pair((pair[0], pair[1]), mode);
+                [a, b]
+            })
+            .collect()
+    }
+}
+
+/// Formats a table row in the `['K', 'E', ...]` form.
+fn row(cells: &[char]) -> String {
+    format!("{:?}", cells)
+}
+
+/// Enum to represent how the two tables of a two-square cipher are placed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Arrangement {
+    /// The first table above the second
+    Vertical,
+    /// The first table left of the second
+    Horizontal,
+}
+
+impl Arrangement {
+    /// Returns the setting name of the arrangement, e.g. `vertical`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Arrangement::Vertical => "vertical",
+            Arrangement::Horizontal => "horizontal",
+        }
+    }
+}
+
+/// Parses an arrangement setting such as `horizontal`.
+///
+/// # Arguments
+///
+/// * `spec` - Either `vertical` or `horizontal`.
+///
+/// # Returns
+///
+/// * The arrangement, or a usage error for any other value.
+pub fn parse_arrangement(spec: &str) -> Result<Arrangement, Error> {
+    match spec {
+        "vertical" => Ok(Arrangement::Vertical),
+        "horizontal" => Ok(Arrangement::Horizontal),
+        _ => Err(Error::Usage(format!(
+            "invalid arrangement '{}': expected 'vertical' or 'horizontal'",
+            spec
+        ))),
+    }
+}
+
+/// The two tables of a two-square cipher and how they are placed
+pub struct TwoSquare {
+    /// The table filled from the first key, top or left
+    pub first: KeySquare,
+    /// The table filled from the second key, bottom or right
+    pub second: KeySquare,
+    /// How the tables are placed
+    pub arrangement: Arrangement,
+}
+
+impl TwoSquare {
+    /// Generates both tables from their keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The key of the top or left table.
+    /// * `second` - The key of the bottom or right table.
+    /// * `variant` - The variant both tables are generated with.
+    /// * `arrangement` - How the tables are placed.
+    pub fn new(first: &str, second: &str, variant: &Variant, arrangement: Arrangement) -> Self {
+        TwoSquare {
+            first: KeySquare::from_key(first, variant),
+            second: KeySquare::from_key(second, variant),
+            arrangement,
+        }
+    }
+}
+
+impl DigramCipher for TwoSquare {
+    fn name(&self) -> &'static str {
+        "two-square"
+    }
+
+    fn transform_pair(&self, pair: (char, char), mode: CipherMode) -> (char, char) {
+        let (first, second) = (self.first.rows(), self.second.rows());
+        match self.arrangement {
+            // The corner in the row of the first letter stays in the top
+            // table, so the vertical cipher is its own inverse
+            Arrangement::Vertical => {
+                let (r1, c1) = self.first.positions.find(pair.0);
+                let (r2, c2) = self.second.positions.find(pair.1);
+                (first[r1][c2], second[r2][c1])
+            }
+            // The corner in the row of the first letter is in the right
+            // table, so decryption looks the letters up the other way round
+            Arrangement::Horizontal => match mode {
+                CipherMode::Encrypt => {
+                    let (r1, c1) = self.first.positions.find(pair.0);
+                    let (r2, c2) = self.second.positions.find(pair.1);
+                    (second[r1][c2], first[r2][c1])
+                }
+                CipherMode::Decrypt => {
+                    let (r1, c2) = self.second.positions.find(pair.0);
+                    let (r2, c1) = self.first.positions.find(pair.1);
+                    (first[r1][c1], second[r2][c2])
+                }
+            },
+        }
+    }
+
+    fn keyed_squares(&self) -> Vec<&KeySquare> {
+        vec![&self.first, &self.second]
+    }
+
+    fn rows(&self) -> Vec<String> {
+        let (first, second) = (self.first.rows(), self.second.rows());
+        match self.arrangement {
+            Arrangement::Vertical => first.iter().chain(second).map(|cells| row(cells)).collect(),
+            Arrangement::Horizontal => first
+                .iter()
+                .zip(second)
+                .map(|(left, right)| format!("{}  {}", row(left), row(right)))
+                .collect(),
+        }
+    }
+}
+
+/// The four tables of a four-square cipher
+pub struct FourSquare {
+    /// The alphabetical table in the top left and bottom right corners
+    pub plain: KeySquare,
+    /// The table filled from the first key, in the top right corner
+    pub first: KeySquare,
+    /// The table filled from the second key, in the bottom left corner
+    pub second: KeySquare,
+}
+
+impl FourSquare {
+    /// Generates the keyed tables from their keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The key of the top right table.
+    /// * `second` - The key of the bottom left table.
+    /// * `variant` - The variant all tables are generated with.
+    pub fn new(first: &str, second: &str, variant: &Variant) -> Self {
+        FourSquare {
+            plain: KeySquare::from_key("", variant),
+            first: KeySquare::from_key(first, variant),
+            second: KeySquare::from_key(second, variant),
+        }
+    }
+}
+
+impl DigramCipher for FourSquare {
+    fn name(&self) -> &'static str {
+        "four-square"
+    }
+
+    fn transform_pair(&self, pair: (char, char), mode: CipherMode) -> (char, char) {
+        let (plain, first, second) = (self.plain.rows(), self.first.rows(), self.second.rows());
+        match mode {
+            CipherMode::Encrypt => {
+                let (r1, c1) = self.plain.positions.find(pair.0);
+                let (r2, c2) = self.plain.positions.find(pair.1);
+                (first[r1][c2], second[r2][c1])
+            }
+            CipherMode::Decrypt => {
+                let (r1, c2) = self.first.positions.find(pair.0);
+                let (r2, c1) = self.second.positions.find(pair.1);
+                (plain[r1][c1], plain[r2][c2])
+            }
+        }
+    }
+
+    fn keyed_squares(&self) -> Vec<&KeySquare> {
+        vec![&self.first, &self.second]
+    }
+
+    fn rows(&self) -> Vec<String> {
+        let side_by_side = |left: &[Vec<char>], right: &[Vec<char>]| {
+            left.iter()
+                .zip(right)
+                .map(|(l, r)| format!("{}  {}", row(l), row(r)))
+                .collect::<Vec<String>>()
+        };
+        let mut rows: Vec<String> = side_by_side(self.plain.rows(), self.first.rows());
+        rows.extend(side_by_side(self.second.rows(), self.plain.rows()));
+        rows
+    }
+}
+
+/// The two tables of a double Playfair cipher
+pub struct DoublePlayfair {
+    /// The table filled from the first key, on the left
+    pub left: KeySquare,
+    /// The table filled from the second key, on the right
+    pub right: KeySquare,
+}
+
+impl DoublePlayfair {
+    /// Generates both tables from their keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The key of the left table.
+    /// * `right` - The key of the right table.
+    /// * `variant` - The variant both tables are generated with.
+    pub fn new(left: &str, right: &str, variant: &Variant) -> Self {
+        DoublePlayfair {
+            left: KeySquare::from_key(left, variant),
+            right: KeySquare::from_key(right, variant),
+        }
+    }
+
+    /// Enciphers or deciphers a pair once.
+    fn step(&self, pair: (char, char), mode: CipherMode) -> (char, char) {
+        let (left, right) = (self.left.rows(), self.right.rows());
+        let cols: usize = left[0].len();
+        match mode {
+            CipherMode::Encrypt => {
+                let (r1, c1) = self.left.positions.find(pair.0);
+                let (r2, c2) = self.right.positions.find(pair.1);
+                if r1 == r2 {
+                    (right[r1][(c2 + 1) % cols], left[r1][(c1 + 1) % cols])
+                } else {
+                    (right[r1][c2], left[r2][c1])
+                }
+            }
+            CipherMode::Decrypt => {
+                let (r1, c2) = self.right.positions.find(pair.0);
+                let (r2, c1) = self.left.positions.find(pair.1);
+                if r1 == r2 {
+                    (
+                        left[r1][(c1 + cols - 1) % cols],
+                        right[r1][(c2 + cols - 1) % cols],
+                    )
+                } else {
+                    (left[r1][c1], right[r2][c2])
+                }
+            }
+        }
+    }
+}
+
+impl DigramCipher for DoublePlayfair {
+    fn name(&self) -> &'static str {
+        "double-playfair"
+    }
+
+    fn transform_pair(&self, pair: (char, char), mode: CipherMode) -> (char, char) {
+        self.step(self.step(pair, mode), mode)
+    }
+
+    fn keyed_squares(&self) -> Vec<&KeySquare> {
+        vec![&self.left, &self.right]
+    }
+
+    fn rows(&self) -> Vec<String> {
+        self.left
+            .rows()
+            .iter()
+            .zip(self.right.rows())
+            .map(|(left, right)| format!("{}  {}", row(left), row(right)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playfair() {
+        let variant: Variant = Variant::default();
+        let cipher: Box<dyn DigramCipher> =
+            Box::new(crate::Playfair::new("PLAYFAIREXAMPLE", &variant));
+        assert_eq!(cipher.name(), "playfair");
+        // H and I span a rectangle, D and E share a column
+        assert_eq!(
+            cipher.transform_pair(('H', 'I'), CipherMode::Encrypt),
+            ('B', 'M')
+        );
+        assert_eq!(
+            cipher.transform_pair(('D', 'E'), CipherMode::Encrypt),
+            ('O', 'D')
+        );
+        let encrypted: String = cipher.process(
+            "Hide the gold in the tree stump",
+            &variant,
+            CipherMode::Encrypt,
+        );
+        assert_eq!(encrypted, "BMODZBXDNABEKUDMUIXMMOUVIF");
+        assert_eq!(
+            cipher.process(&encrypted, &variant, CipherMode::Decrypt),
+            "HIDETHEGOLDINTHETREXESTUMP"
+        );
+        assert_eq!(cipher.keyed_squares().len(), 1);
+        assert_eq!(cipher.rows()[0], "['P', 'L', 'A', 'Y', 'F']");
+    }
+
+    #[test]
+    fn test_vertical_two_square() {
+        let variant: Variant = Variant::default();
+        let squares: TwoSquare =
+            TwoSquare::new("EXAMPLE", "KEYWORD", &variant, Arrangement::Vertical);
+        // H and E share a column, L and P span a rectangle
+        assert_eq!(
+            squares.transform_pair(('H', 'E'), CipherMode::Encrypt),
+            ('H', 'E')
+        );
+        assert_eq!(
+            squares.transform_pair(('L', 'P'), CipherMode::Encrypt),
+            ('C', 'M')
+        );
+        let encrypted: String = squares.process("Help me obi wan", &variant, CipherMode::Encrypt);
+        assert_eq!(
+            squares.process(&encrypted, &variant, CipherMode::Decrypt),
+            "HELPMEOBIWAN"
+        );
+        // Doubled letters need no filler
+        assert_eq!(
+            squares.process("LL", &variant, CipherMode::Encrypt).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_four_square() {
+        // The textbook example leaves out Q instead of merging J
+        let variant: Variant = Variant {
+            merge: ('Q', None),
+            ..Variant::default()
+        };
+        let squares: FourSquare = FourSquare::new("EXAMPLE", "KEYWORD", &variant);
+        let encrypted: String =
+            squares.process("help me obi wan kenobi", &variant, CipherMode::Encrypt);
+        assert_eq!(encrypted, "FYGMKYHOBXMFKKKIMD");
+        assert_eq!(
+            squares.process(&encrypted, &variant, CipherMode::Decrypt),
+            "HELPMEOBIWANKENOBI"
+        );
+        assert_eq!(squares.rows().len(), 10);
+        assert_eq!(
+            squares.rows()[0],
+            "['A', 'B', 'C', 'D', 'E']  ['E', 'X', 'A', 'M', 'P']"
+        );
+    }
+
+    #[test]
+    fn test_double_playfair() {
+        let variant: Variant = Variant::default();
+        let squares: DoublePlayfair = DoublePlayfair::new("EXAMPLE", "KEYWORD", &variant);
+        // E and Y share the top row, so both move to their right neighbours
+        assert_eq!(squares.step(('E', 'Y'), CipherMode::Encrypt), ('W', 'X'));
+        assert_eq!(squares.step(('W', 'X'), CipherMode::Decrypt), ('E', 'Y'));
+        let encrypted: String = squares.process("attack at dawn", &variant, CipherMode::Encrypt);
+        assert_eq!(
+            squares.process(&encrypted, &variant, CipherMode::Decrypt),
+            "ATTACKATDAWN"
+        );
+        // Every pair of the alphabet round-trips
+        let letters: Vec<char> = variant.symbols();
+        for &a in &letters {
+            for &b in &letters {
+                let pair: (char, char) = squares.transform_pair((a, b), CipherMode::Encrypt);
+                assert_eq!(squares.transform_pair(pair, CipherMode::Decrypt), (a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_horizontal_two_square() {
+        let variant: Variant = Variant::default();
+        let squares: TwoSquare =
+            TwoSquare::new("EXAMPLE", "KEYWORD", &variant, Arrangement::Horizontal);
+        // E and Y share the top row and are reversed
+        assert_eq!(
+            squares.transform_pair(('E', 'Y'), CipherMode::Encrypt),
+            ('Y', 'E')
+        );
+        let encrypted: String = squares.process("attack at dawn", &variant, CipherMode::Encrypt);
+        assert_eq!(
+            squares.process(&encrypted, &variant, CipherMode::Decrypt),
+            "ATTACKATDAWN"
+        );
+        assert_eq!(squares.rows().len(), 5);
+        assert!(parse_arrangement("diagonal").is_err());
+    }
+}
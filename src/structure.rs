@@ -0,0 +1,231 @@
+//! How far a key square is from the table of no key.
+//!
+//! The cells are read along the route of the variant, the order a keyword
+//! and the alphabet fill them in, and compared with the symbols in
+//! alphabetical order:
+//!
+//! - The displacement of a symbol is how many cells it sits from its place
+//!   in the table of no key; a shuffled table averages `(n² - 1) / 3n` for
+//!   `n` symbols.
+//! - A run is a stretch of cells holding consecutive symbols, such as
+//!   `STU`, which a keyword table keeps after its keyword.
+//! - The permutation entropy counts the orders of every three cells in a
+//!   row, largest, middle and smallest: a table in alphabetical order only
+//!   has one of the six, a shuffled one all of them about as often. It is
+//!   given as a share of the most there is, from 0 to 1.
+//! - The alphabetical tail is the longest stretch of cells at the end in
+//!   alphabetical order. The cells before it are the shortest keyword that
+//!   fills the table, which is the keyword itself or a part of it for a
+//!   table made from one.
+//!
+//! `table --stats` prints them, and `keycheck` judges keywords by them.
+
+use crate::Variant;
+
+/// The structure of a key square
+#[derive(Debug, Clone, PartialEq)]
+pub struct Structure {
+    /// The average number of cells a symbol sits from its place in the
+    /// table of no key
+    pub displacement: f64,
+    /// The average displacement of a shuffled table
+    pub random_displacement: f64,
+    /// How many symbols sit in their place in the table of no key
+    pub fixed: usize,
+    /// The lengths of the runs of consecutive symbols of two or more cells,
+    /// in the order of the route
+    pub runs: Vec<usize>,
+    /// The permutation entropy, from 0 for a table in order to 1
+    pub entropy: f64,
+    /// How many cells at the end are in alphabetical order
+    pub tail: usize,
+    /// The shortest keyword that fills the table
+    pub keyword: String,
+}
+
+impl Structure {
+    /// Returns the length of the longest run, 1 if no symbols are in a run.
+    pub fn longest_run(&self) -> usize {
+        self.runs.iter().copied().max().unwrap_or(1)
+    }
+}
+
+/// Measures the structure of a key square.
+///
+/// # Arguments
+///
+/// * `table` - The rows of the square.
+/// * `variant` - The variant deciding the symbols and the route of the table.
+pub fn measure(table: &[Vec<char>], variant: &Variant) -> Structure {
+    let symbols: Vec<char> = variant.symbols();
+    let shape: (usize, usize) = (table.len(), table[0].len());
+    // The place of every cell in the alphabet, along the route
+    let order: Vec<usize> = variant
+        .route
+        .cells(shape)
+        .into_iter()
+        .map(|(i, j)| symbols.iter().position(|&s| s == table[i][j]).unwrap())
+        .collect();
+    let n: usize = order.len();
+    let displacement: f64 = order
+        .iter()
+        .enumerate()
+        .map(|(cell, &symbol)| cell.abs_diff(symbol))
+        .sum::<usize>() as f64
+        / n as f64;
+    let fixed: usize = order
+        .iter()
+        .enumerate()
+        .filter(|&(cell, &symbol)| cell == symbol)
+        .count();
+
+    let mut runs: Vec<usize> = Vec::new();
+    let mut run: usize = 1;
+    for pair in order.windows(2) {
+        if pair[1] == pair[0] + 1 {
+            run += 1;
+        } else {
+            if run > 1 {
+                runs.push(run);
+            }
+            run = 1;
+        }
+    }
+    if run > 1 {
+        runs.push(run);
+    }
+
+    // The six orders of three cells, counted by which is largest and how
+    // the other two compare
+    let mut patterns: [usize; 6] = [0; 6];
+    for window in order.windows(3) {
+        let (a, b, c) = (window[0], window[1], window[2]);
+        let pattern: usize = match (a < b, b < c, a < c) {
+            (true, true, _) => 0,
+            (true, false, true) => 1,
+            (true, false, false) => 2,
+            (false, true, true) => 3,
+            (false, true, false) => 4,
+            (false, false, _) => 5,
+        };
+        patterns[pattern] += 1;
+    }
+    let windows: f64 = n.saturating_sub(2).max(1) as f64;
+    let entropy: f64 = patterns
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p: f64 = count as f64 / windows;
+            -p * p.log2()
+        })
+        .sum::<f64>()
+        / 6f64.log2();
+
+    let tail: usize = 1 + order
+        .windows(2)
+        .rev()
+        .take_while(|pair| pair[0] < pair[1])
+        .count();
+    Structure {
+        displacement,
+        random_displacement: (n * n - 1) as f64 / (3 * n) as f64,
+        fixed,
+        runs,
+        entropy,
+        tail,
+        keyword: order[..n - tail]
+            .iter()
+            .map(|&symbol| symbols[symbol])
+            .collect(),
+    }
+}
+
+/// Recovers the keyword of a table found up to cyclic shifts of its rows
+/// and columns, as `crack` finds them.
+///
+/// # Returns
+///
+/// * The shortest keyword of the shift with the longest alphabetical tail,
+///   or `None` if no shift leaves at least half the cells to the alphabet,
+///   so the table was likely not made from a keyword.
+pub fn keyword(table: &[Vec<char>], variant: &Variant) -> Option<String> {
+    let (rows, cols) = (table.len(), table[0].len());
+    let mut best: Option<Structure> = None;
+    for down in 0..rows {
+        for right in 0..cols {
+            let shifted: Vec<Vec<char>> = (0..rows)
+                .map(|i| {
+                    (0..cols)
+                        .map(|j| table[(i + down) % rows][(j + right) % cols])
+                        .collect()
+                })
+                .collect();
+            let structure: Structure = measure(&shifted, variant);
+            if best.as_ref().is_none_or(|best| structure.tail > best.tail) {
+                best = Some(structure);
+            }
+        }
+    }
+    best.filter(|best| 2 * best.tail >= rows * cols)
+        .map(|best| best.keyword)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structure() {
+        let variant: Variant = Variant::default();
+        let plain: Structure = measure(&crate::generate_playfair_table("", &variant), &variant);
+        assert_eq!((plain.displacement, plain.fixed), (0.0, 25));
+        assert_eq!(plain.runs, vec![25]);
+        assert_eq!((plain.entropy, plain.tail), (0.0, 25));
+        assert_eq!(plain.keyword, "");
+        assert_eq!(plain.random_displacement, 8.32);
+
+        let keyed: Vec<Vec<char>> = crate::generate_playfair_table("PLAYFAIR", &variant);
+        let structure: Structure = measure(&keyed, &variant);
+        // PLAYFIR, then BCDEGHKMNOQSTUVWXZ
+        assert_eq!(structure.tail, 18);
+        assert_eq!(structure.keyword, "PLAYFIR");
+        assert_eq!(
+            crate::generate_playfair_table(&structure.keyword, &variant),
+            keyed
+        );
+        assert_eq!(structure.runs, vec![4, 2, 3, 6]);
+        assert_eq!(structure.longest_run(), 6);
+        assert_eq!(structure.fixed, 1);
+        assert!(structure.entropy > 0.0 && structure.entropy < 0.7);
+
+        // The route decides the order the cells are read in
+        let columns: Variant = Variant {
+            route: crate::Route::Columns,
+            ..Variant::default()
+        };
+        let structure: Structure = measure(
+            &crate::generate_playfair_table("PLAYFAIR", &columns),
+            &columns,
+        );
+        assert_eq!(structure.keyword, "PLAYFIR");
+
+        // A shift of a keyword table gives the keyword back
+        let shifted: Vec<Vec<char>> = keyed[2..]
+            .iter()
+            .chain(&keyed[..2])
+            .map(|row| row[1..].iter().chain(&row[..1]).copied().collect())
+            .collect();
+        assert_eq!(keyword(&shifted, &variant).as_deref(), Some("PLAYFIR"));
+        let found: Vec<Vec<char>> =
+            crate::KeySquare::from_letters("SKNOQ ZTUVW FPLAY MIREX HBCDG", &variant)
+                .unwrap()
+                .rows()
+                .to_vec();
+        assert_eq!(keyword(&found, &variant).as_deref(), Some("PLAYFIREXM"));
+        let random: Vec<Vec<char>> =
+            crate::keygen::random_square(&mut crate::keygen::Rng::new(1), &variant)
+                .rows()
+                .to_vec();
+        assert_eq!(keyword(&random, &variant), None);
+    }
+}
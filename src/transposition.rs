@@ -0,0 +1,100 @@
+//! Keyed columnar transposition, applied after the Playfair cipher.
+//!
+//! The letters are written row by row under the key, and the columns are
+//! read off top to bottom in the alphabetical order of the key letters;
+//! repeated key letters are read left to right. The last row may be short,
+//! so decryption first works out how long each column is.
+
+use crate::error::Error;
+
+/// Works out the order in which the columns are read.
+///
+/// # Arguments
+///
+/// * `key` - The transposition key. Only its letters and digits count, and
+///   case is ignored.
+///
+/// # Returns
+///
+/// * The column indices in reading order, or an invalid key error if the
+///   key contains no letters or digits.
+pub fn column_order(key: &str) -> Result<Vec<usize>, Error> {
+    let letters: Vec<char> = key
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(crate::to_upper)
+        .collect();
+    if letters.is_empty() {
+        return Err(Error::InvalidKey(String::from(
+            "the transposition key contains no letters or digits",
+        )));
+    }
+    let mut order: Vec<usize> = (0..letters.len()).collect();
+    // The sort is stable, so repeated letters keep their left to right order
+    order.sort_by_key(|&i| letters[i]);
+    Ok(order)
+}
+
+/// Transposes letters by columns.
+///
+/// # Arguments
+///
+/// * `chars` - The letters to transpose.
+/// * `order` - The column order from `column_order`.
+///
+/// # Returns
+///
+/// * The columns read off in key order.
+pub fn encrypt(chars: &[char], order: &[usize]) -> String {
+    let cols: usize = order.len();
+    order
+        .iter()
+        .flat_map(|&col| chars.iter().skip(col).step_by(cols))
+        .collect()
+}
+
+/// Undoes the transposition.
+///
+/// # Arguments
+///
+/// * `chars` - The transposed letters.
+/// * `order` - The column order from `column_order`.
+///
+/// # Returns
+///
+/// * The letters in their original order.
+pub fn decrypt(chars: &[char], order: &[usize]) -> String {
+    let (len, cols): (usize, usize) = (chars.len(), order.len());
+    let mut text: Vec<char> = vec![' '; len];
+    let mut rest: &[char] = chars;
+    for &col in order {
+        // Columns left of the short last row hold one more letter
+        let height: usize = (len + cols - 1 - col) / cols;
+        let (column, tail) = rest.split_at(height.min(rest.len()));
+        for (row, &c) in column.iter().enumerate() {
+            text[row * cols + col] = c;
+        }
+        rest = tail;
+    }
+    text.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columnar_transposition() {
+        // The textbook example: ZEBRAS reads its columns in the order A B E R S Z
+        let order: Vec<usize> = column_order("zebras").unwrap();
+        assert_eq!(order, vec![4, 2, 1, 3, 5, 0]);
+        let plaintext: Vec<char> = "WEAREDISCOVEREDFLEEATONCE".chars().collect();
+        let transposed: String = encrypt(&plaintext, &order);
+        assert_eq!(transposed, "EVLNACDTESEAROFODEECWIREE");
+        let chars: Vec<char> = transposed.chars().collect();
+        assert_eq!(decrypt(&chars, &order), "WEAREDISCOVEREDFLEEATONCE");
+
+        assert_eq!(column_order("ABA").unwrap(), vec![0, 2, 1]);
+        assert!(column_order("--").is_err());
+    }
+}
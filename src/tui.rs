@@ -0,0 +1,412 @@
+//! Full-screen interface showing the cipher's geometry.
+//!
+//! The key square is drawn at the top; the cells of the selected digram are
+//! highlighted together with the cells it turns into. Below, the input text
+//! and the result are shown side by side and recomputed on every keystroke.
+//!
+//! Keys: typing edits the focused field, `Tab` switches between the text and
+//! the key, `Left`/`Right` select a digram, `Ctrl-T` toggles the mode and
+//! `Esc` quits.
+
+use crate::error::Error;
+use crate::{CipherMode, Rule, Variant};
+use clap::ArgMatches;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+/// Style of the cells holding the selected input digram
+const INPUT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+/// Style of the cells holding the result of the selected digram
+const OUTPUT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Green);
+
+/// Enum to represent the field receiving typed characters
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Focus {
+    Text,
+    Key,
+}
+
+/// A digram of the prepared input together with its result
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    /// The prepared input pair
+    input: (char, char),
+    /// The transformed pair
+    output: (char, char),
+    /// The rule that was applied
+    rule: Rule,
+}
+
+/// The state of the interface
+struct App {
+    /// The key the table is generated from
+    key: String,
+    /// The text being encrypted or decrypted
+    text: String,
+    /// The variant used for the table and the text
+    variant: Variant,
+    /// Whether the text is encrypted or decrypted
+    mode: CipherMode,
+    /// The table generated from the key
+    table: Vec<Vec<char>>,
+    /// The digrams of the text and their results
+    steps: Vec<Step>,
+    /// Index of the highlighted digram
+    selected: usize,
+    /// The field receiving typed characters
+    focus: Focus,
+}
+
+impl App {
+    /// Creates the state for a key and variant with an empty text.
+    fn new(key: String, variant: Variant, mode: CipherMode) -> Self {
+        let mut app: App = App {
+            key,
+            text: String::new(),
+            variant,
+            mode,
+            table: Vec::new(),
+            steps: Vec::new(),
+            selected: 0,
+            focus: Focus::Text,
+        };
+        app.recompute();
+        app
+    }
+
+    /// Regenerates the table and the digrams after an edit.
+    fn recompute(&mut self) {
+        self.table = crate::generate_playfair_table(&self.key, &self.variant);
+        let positions: crate::Positions = crate::Positions::new(&self.table);
+        let (chars, _) = crate::prepare_text(&self.text, &self.variant);
+        self.steps = chars
+            .chunks(2)
+            .map(|pair| {
+                let (output, rule) =
+                    crate::transform_pair(&self.table, &positions, (pair[0], pair[1]), self.mode);
+                Step {
+                    input: (pair[0], pair[1]),
+                    output,
+                    rule,
+                }
+            })
+            .collect();
+        self.selected = self.selected.min(self.steps.len().saturating_sub(1));
+    }
+
+    /// Applies a key press.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if the interface should close.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl: bool = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return true,
+            KeyCode::Char('c') if ctrl => return true,
+            KeyCode::Char('t') if ctrl => {
+                self.mode = match self.mode {
+                    CipherMode::Encrypt => CipherMode::Decrypt,
+                    CipherMode::Decrypt => CipherMode::Encrypt,
+                };
+            }
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Text => Focus::Key,
+                    Focus::Key => Focus::Text,
+                };
+                return false;
+            }
+            KeyCode::Left => {
+                self.selected = self.selected.saturating_sub(1);
+                return false;
+            }
+            KeyCode::Right => {
+                if self.selected + 1 < self.steps.len() {
+                    self.selected += 1;
+                }
+                return false;
+            }
+            KeyCode::Backspace => {
+                self.field_mut().pop();
+            }
+            KeyCode::Enter if self.focus == Focus::Text => self.text.push('\n'),
+            KeyCode::Char(c) if !ctrl => {
+                self.field_mut().push(c);
+                // Follow the digram being typed
+                if self.focus == Focus::Text {
+                    self.selected = usize::MAX;
+                }
+            }
+            _ => return false,
+        }
+        self.recompute();
+        false
+    }
+
+    /// Returns the field receiving typed characters.
+    fn field_mut(&mut self) -> &mut String {
+        match self.focus {
+            Focus::Text => &mut self.text,
+            Focus::Key => &mut self.key,
+        }
+    }
+
+    /// Draws the whole interface.
+    fn draw(&self, frame: &mut Frame) {
+        // The square needs three columns per cell plus the border
+        let (rows, cols): (u16, u16) = (self.table.len() as u16, self.table[0].len() as u16);
+        let [top, panes, help] = Layout::vertical([
+            Constraint::Length(rows.max(5) + 2),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [square, info] =
+            Layout::horizontal([Constraint::Length(cols * 3 + 2), Constraint::Min(20)]).areas(top);
+        let [input, output] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(panes);
+
+        self.draw_square(frame, square);
+        self.draw_info(frame, info);
+        self.draw_panes(frame, input, output);
+        frame.render_widget(
+            Paragraph::new(
+                "Tab: switch text/key   Left/Right: select digram   Ctrl-T: toggle mode   Esc: quit",
+            )
+            .style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+    }
+
+    /// Draws the key square, highlighting the cells of the selected digram.
+    fn draw_square(&self, frame: &mut Frame, area: Rect) {
+        let step: Option<&Step> = self.steps.get(self.selected);
+        let lines: Vec<Line> = self
+            .table
+            .iter()
+            .map(|row| {
+                Line::from(
+                    row.iter()
+                        .map(|&c| {
+                            let style: Style = match step {
+                                Some(step) if c == step.input.0 || c == step.input.1 => INPUT_STYLE,
+                                Some(step) if c == step.output.0 || c == step.output.1 => {
+                                    OUTPUT_STYLE
+                                }
+                                _ => Style::new(),
+                            };
+                            Span::styled(format!(" {} ", c), style)
+                        })
+                        .collect::<Vec<Span>>(),
+                )
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Square ")),
+            area,
+        );
+    }
+
+    /// Draws the mode, the key and the rule applied to the selected digram.
+    fn draw_info(&self, frame: &mut Frame, area: Rect) {
+        let key_style: Style = if self.focus == Focus::Key {
+            Style::new().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::new()
+        };
+        let mut lines: Vec<Line> = vec![
+            Line::from(format!(
+                "Mode: {}",
+                match self.mode {
+                    CipherMode::Encrypt => "encrypt",
+                    CipherMode::Decrypt => "decrypt",
+                }
+            )),
+            Line::from(vec![
+                Span::raw("Key:  "),
+                Span::styled(self.key.clone(), key_style),
+            ]),
+        ];
+        if let Some(step) = self.steps.get(self.selected) {
+            lines.push(Line::from(format!(
+                "Digram {}/{}: ",
+                self.selected + 1,
+                self.steps.len()
+            )));
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}{}", step.input.0, step.input.1), INPUT_STYLE),
+                Span::raw(" -> "),
+                Span::styled(format!("{}{}", step.output.0, step.output.1), OUTPUT_STYLE),
+                Span::raw(format!("  ({})", step.rule.name())),
+            ]));
+        }
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Digram ")),
+            area,
+        );
+    }
+
+    /// Draws the input text and the result side by side.
+    fn draw_panes(&self, frame: &mut Frame, input: Rect, output: Rect) {
+        let (input_title, output_title) = match self.mode {
+            CipherMode::Encrypt => (" Plaintext ", " Ciphertext "),
+            CipherMode::Decrypt => (" Ciphertext ", " Plaintext "),
+        };
+        let text_style: Style = if self.focus == Focus::Text {
+            Style::new().add_modifier(Modifier::BOLD)
+        } else {
+            Style::new()
+        };
+
+        let mut input_lines: Vec<Line> = self
+            .text
+            .split('\n')
+            .map(|line| Line::styled(line.to_string(), text_style))
+            .collect();
+        input_lines.push(Line::default());
+        input_lines.push(self.digram_line(|step| step.input));
+        frame.render_widget(
+            Paragraph::new(input_lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(input_title)),
+            input,
+        );
+        frame.render_widget(
+            Paragraph::new(self.digram_line(|step| step.output))
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(output_title)),
+            output,
+        );
+    }
+
+    /// Lists one side of the digrams, highlighting the selected one.
+    fn digram_line(&self, side: impl Fn(&Step) -> (char, char)) -> Line<'static> {
+        let spans: Vec<Span> = self
+            .steps
+            .iter()
+            .enumerate()
+            .flat_map(|(i, step)| {
+                let (a, b) = side(step);
+                let style: Style = if i == self.selected {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new()
+                };
+                [Span::styled(format!("{}{}", a, b), style), Span::raw(" ")]
+            })
+            .collect();
+        Line::from(spans)
+    }
+}
+
+/// Runs the `tui` subcommand until the user quits.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings are invalid or the terminal can't be used.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let mode: CipherMode = crate::cli::mode_from_matches(matches);
+    let (variant, _, _) = crate::cli::settings_from_matches(matches)?;
+    if variant.rounds > 1 || variant.period.is_some() {
+        return Err(Error::Usage(String::from(
+            "the full-screen view shows a single round of adjacent pairs: drop --rounds and --period",
+        )));
+    }
+    let key: String = crate::cli::key_from_matches(matches, mode, &variant)?;
+    let mut app: App = App::new(key, variant, mode);
+
+    let mut terminal: DefaultTerminal = ratatui::try_init()
+        .map_err(|e| Error::Io(format!("cannot start the terminal interface: {}", e)))?;
+    let result: Result<(), Error> = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+/// Draws the interface and applies key presses until the user quits.
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<(), Error> {
+    loop {
+        terminal
+            .draw(|frame| app.draw(frame))
+            .map_err(|e| Error::Io(e.to_string()))?;
+        if let Event::Key(key) = event::read().map_err(|e| Error::Io(e.to_string()))?
+            && key.kind == KeyEventKind::Press
+            && app.handle_key(key)
+        {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    /// Builds a key press without modifiers
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_app_live_editing() {
+        let mut app: App = App::new(
+            String::from("KEYWORD"),
+            Variant::default(),
+            CipherMode::Encrypt,
+        );
+        for c in "hello".chars() {
+            app.handle_key(press(KeyCode::Char(c)));
+        }
+        assert_eq!(app.steps.len(), 3);
+        assert_eq!(app.selected, 2);
+        assert_eq!(app.steps[1].input, ('L', 'X'));
+        assert_eq!(app.steps[1].output, ('I', 'Z'));
+
+        app.handle_key(press(KeyCode::Left));
+        assert_eq!(app.selected, 1);
+
+        app.handle_key(press(KeyCode::Tab));
+        app.handle_key(press(KeyCode::Backspace));
+        assert_eq!(app.key, "KEYWOR");
+        assert_eq!(app.text, "hello");
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        assert!(matches!(app.mode, CipherMode::Decrypt));
+        assert!(app.handle_key(press(KeyCode::Esc)));
+    }
+
+    #[test]
+    fn test_app_draw() {
+        let mut app: App = App::new(
+            String::from("KEYWORD"),
+            Variant::default(),
+            CipherMode::Encrypt,
+        );
+        for c in "hello".chars() {
+            app.handle_key(press(KeyCode::Char(c)));
+        }
+        let mut terminal: Terminal<TestBackend> = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains(" K  E  Y  W  O "));
+        assert!(screen.contains("GY IZ SC"));
+        assert!(screen.contains("(same column)"));
+    }
+}
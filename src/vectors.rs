@@ -0,0 +1,402 @@
+//! Checking and regenerating external files of test vectors.
+//!
+//! A vector file lists keys, variants and plaintexts together with the
+//! ciphertexts another implementation produced, in TOML or, when the file
+//! name ends in `.json`, in JSON with the same structure:
+//!
+//! ```toml
+//! [[vectors]]
+//! name = "readme example"
+//! key = "KEYWORD"
+//! plaintext = "HELLO WORLD"
+//! ciphertext = "GYIZSCOKCFBU"
+//! variant = { filler = "X", merge = "J=I", grid = "5x5" }
+//! ```
+//!
+//! `name` and `variant` are optional. Ciphertexts are compared letter by
+//! letter, so grouping and case don't matter, but a character that is not a
+//! symbol of the table fails the vector, naming its line in the file.
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::{CipherMode, Variant};
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+
+/// The contents of a vector file
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct VectorFile {
+    /// The vectors, in file order
+    vectors: Vec<TestVector>,
+}
+
+/// One vector of a vector file
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct TestVector {
+    /// Short description printed in the report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// The key the table is generated from
+    key: String,
+    /// The variant settings, defaulting to filler `X` and merge `J=I`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variant: Option<VectorVariant>,
+    /// The text to encrypt
+    plaintext: String,
+    /// The expected ciphertext, filled in by `generate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ciphertext: Option<String>,
+}
+
+/// The variant settings of a vector, written like the config file keys
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct VectorVariant {
+    /// Conventions of another implementation, e.g. `cryptool`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compat: Option<String>,
+    /// Letter inserted between repeated characters and used as padding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filler: Option<char>,
+    /// Letter inserted instead of the filler after a filler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alt_filler: Option<char>,
+    /// Letter merged into another one, written as `J=I`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    merge: Option<String>,
+    /// Table size, `5x5` or `6x6`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grid: Option<String>,
+    /// The symbols of the table, e.g. 25 for 5x5 or 30 for 5x6, instead of a grid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alphabet: Option<String>,
+    /// Locale spelling out letters missing from the table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    locale: Option<String>,
+    /// Order in which the table is filled, e.g. `spiral`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    route: Option<String>,
+    /// How many times the cipher is applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rounds: Option<usize>,
+    /// Row length of the seriated text, if pairs are taken vertically
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    period: Option<usize>,
+    /// How repeated letters in a digram are handled, e.g. `skip`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    doubles: Option<String>,
+}
+
+impl TestVector {
+    /// Returns the name of the vector, or its position when it has none.
+    fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("vector {}", index + 1))
+    }
+
+    /// Builds the variant of the vector from its settings.
+    fn variant(&self) -> Result<Variant, Error> {
+        let config: Config = Config {
+            compat: self.variant.as_ref().and_then(|v| v.compat.clone()),
+            filler: self.variant.as_ref().and_then(|v| v.filler),
+            alt_filler: self.variant.as_ref().and_then(|v| v.alt_filler),
+            merge: self.variant.as_ref().and_then(|v| v.merge.clone()),
+            grid: self.variant.as_ref().and_then(|v| v.grid.clone()),
+            alphabet: self.variant.as_ref().and_then(|v| v.alphabet.clone()),
+            locale: self.variant.as_ref().and_then(|v| v.locale.clone()),
+            route: self.variant.as_ref().and_then(|v| v.route.clone()),
+            rounds: self.variant.as_ref().and_then(|v| v.rounds),
+            period: self.variant.as_ref().and_then(|v| v.period),
+            doubles: self.variant.as_ref().and_then(|v| v.doubles.clone()),
+            ..Config::default()
+        };
+        let (variant, _, _) = crate::settings_from_config(&config, None)?;
+        Ok(variant)
+    }
+
+    /// Encrypts the plaintext of the vector.
+    ///
+    /// # Returns
+    ///
+    /// * The ciphertext, or an error if the key or variant is unusable.
+    fn encrypt(&self) -> Result<String, Error> {
+        let variant: Variant = self.variant()?;
+        crate::cli::validate_key(&self.key, &variant)?;
+        let table: Vec<Vec<char>> = crate::generate_playfair_table(&self.key, &variant);
+        Ok(crate::playfair_cipher(
+            &self.plaintext,
+            &table,
+            &variant,
+            CipherMode::Encrypt,
+        ))
+    }
+}
+
+/// Whether a vector file is written in JSON rather than TOML
+fn is_json(path: &str) -> bool {
+    path.to_ascii_lowercase().ends_with(".json")
+}
+
+/// Parses the contents of a vector file.
+///
+/// # Arguments
+///
+/// * `contents` - The text of the file.
+/// * `json` - Whether the file is JSON rather than TOML.
+///
+/// # Returns
+///
+/// * The parsed file, or an error message if it is malformed.
+fn parse(contents: &str, json: bool) -> Result<VectorFile, String> {
+    if json {
+        serde_json::from_str(contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+}
+
+/// Reads and parses a vector file.
+///
+/// # Returns
+///
+/// * The parsed file and its text.
+fn load(path: &str) -> Result<(VectorFile, String), Error> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read vector file '{}': {}", path, e)))?;
+    let file: VectorFile = parse(&contents, is_json(path))
+        .map_err(|e| Error::InvalidInput(format!("invalid vector file '{}': {}", path, e)))?;
+    Ok((file, contents))
+}
+
+/// Finds the line of every `ciphertext` key of a vector file.
+///
+/// # Arguments
+///
+/// * `contents` - The text of the file.
+/// * `json` - Whether the file is JSON rather than TOML.
+///
+/// # Returns
+///
+/// * The lines, counted from 1, in file order, so the nth belongs to the
+///   nth vector with a ciphertext.
+fn ciphertext_lines(contents: &str, json: bool) -> Vec<usize> {
+    let (key, separator): (&str, char) = if json {
+        ("\"ciphertext\"", ':')
+    } else {
+        ("ciphertext", '=')
+    };
+    let mut lines: Vec<usize> = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        for (start, _) in line.match_indices(key) {
+            let before: Option<char> = line[..start].chars().next_back();
+            let after: &str = line[start + key.len()..].trim_start();
+            // A key, not the word inside a string or another key
+            let bounded: bool = if json {
+                before != Some('\\')
+            } else {
+                before.is_none_or(|c| c.is_whitespace() || c == '{' || c == ',')
+            };
+            if bounded && after.starts_with(separator) {
+                lines.push(number + 1);
+            }
+        }
+    }
+    lines
+}
+
+/// Checks one vector against its expected ciphertext.
+///
+/// # Arguments
+///
+/// * `vector` - The vector.
+/// * `line` - The line of its ciphertext in the file, if known.
+///
+/// # Returns
+///
+/// * A description of the mismatch if the vector fails.
+fn check(vector: &TestVector, line: Option<usize>) -> Result<(), String> {
+    let Some(expected) = &vector.ciphertext else {
+        return Err(String::from("no ciphertext to compare with"));
+    };
+    let result: String = vector.encrypt().map_err(|e| e.to_string())?;
+    let variant: Variant = vector.variant().map_err(|e| e.to_string())?;
+    let symbols: Vec<char> = variant.symbols();
+    if let Some(c) = expected
+        .chars()
+        .find(|&c| !c.is_whitespace() && !symbols.contains(&crate::to_upper(c)))
+    {
+        let at: String = line.map_or_else(String::new, |line| format!("line {}: ", line));
+        return Err(format!(
+            "{}the ciphertext holds '{}', which is not a symbol of the table",
+            at, c
+        ));
+    }
+    let expected: String = expected
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(crate::to_upper)
+        .collect();
+    if result != expected {
+        return Err(format!("expected {}, got {}", expected, result));
+    }
+    Ok(())
+}
+
+/// Fills in the ciphertext of every vector.
+///
+/// # Returns
+///
+/// * The file with the computed ciphertexts, or an error naming the first
+///   vector that can't be encrypted.
+fn generate(mut file: VectorFile) -> Result<VectorFile, Error> {
+    for (index, vector) in file.vectors.iter_mut().enumerate() {
+        let ciphertext: String = vector.encrypt().map_err(|e| match e {
+            Error::InvalidKey(message) => {
+                Error::InvalidKey(format!("{}: {}", vector.label(index), message))
+            }
+            Error::Usage(message) => Error::Usage(format!("{}: {}", vector.label(index), message)),
+            other => other,
+        })?;
+        vector.ciphertext = Some(ciphertext);
+    }
+    Ok(file)
+}
+
+/// Runs the `vectors` subcommand.
+///
+/// `check` prints a line per vector and fails if any doesn't match;
+/// `generate` prints the file with the ciphertexts filled in to stdout.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the file can't be used or, for `check`, a vector fails.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("check", sub)) => {
+            let path: &String = sub.get_one::<String>("file").unwrap();
+            let (file, contents) = load(path)?;
+            let mut lines = ciphertext_lines(&contents, is_json(path)).into_iter();
+            let mut failures: usize = 0;
+            for (index, vector) in file.vectors.iter().enumerate() {
+                let line: Option<usize> = vector.ciphertext.as_ref().and_then(|_| lines.next());
+                match check(vector, line) {
+                    Ok(()) => println!("PASS {}", vector.label(index)),
+                    Err(e) => {
+                        failures += 1;
+                        println!("FAIL {}: {}", vector.label(index), e);
+                    }
+                }
+            }
+            let total: usize = file.vectors.len();
+            println!("{} of {} vectors passed", total - failures, total);
+            if failures > 0 {
+                return Err(Error::InvalidInput(format!(
+                    "{} of {} vectors did not match",
+                    failures, total
+                )));
+            }
+            Ok(())
+        }
+        Some(("generate", sub)) => {
+            let path: &String = sub.get_one::<String>("file").unwrap();
+            let file: VectorFile = generate(load(path)?.0)?;
+            let output: String = if is_json(path) {
+                serde_json::to_string_pretty(&file)
+                    .map(|json| json + "\n")
+                    .map_err(|e| e.to_string())
+            } else {
+                toml::to_string(&file).map_err(|e| e.to_string())
+            }
+            .map_err(|e| Error::Internal(format!("cannot serialize vectors: {}", e)))?;
+            print!("{}", output);
+            Ok(())
+        }
+        _ => unreachable!("clap requires a vectors subcommand"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_FILE: &str = r#"
+[[vectors]]
+name = "readme example"
+key = "KEYWORD"
+plaintext = "HELLO WORLD"
+ciphertext = "gyiz scok cfbu"
+
+[[vectors]]
+key = "KEYWORD"
+plaintext = "balloon"
+ciphertext = "CBIZSCES"
+variant = { filler = "Q" }
+"#;
+
+    #[test]
+    fn test_check_vectors() {
+        let file: VectorFile = parse(TOML_FILE, false).unwrap();
+        assert_eq!(check(&file.vectors[0], Some(6)), Ok(()));
+        assert_eq!(
+            check(&file.vectors[1], Some(12)),
+            Err(String::from("expected CBIZSCES, got CBISSCES"))
+        );
+        assert!(parse(r#"{"vectors": [{"key": "K"}]}"#, true).is_err());
+    }
+
+    #[test]
+    fn test_check_rejects_foreign_symbols() {
+        let contents: &str = r#"
+[[vectors]]
+key = "KEYWORD"
+plaintext = "HELLO WORLD"
+ciphertext = "GYIZSCOKCFBU"
+
+[[vectors]]
+name = "no ciphertext here"
+key = "KEYWORD"
+plaintext = "WRONG"
+  ciphertext =   "WRONG1"
+"#;
+        let file: VectorFile = parse(contents, false).unwrap();
+        let lines: Vec<usize> = ciphertext_lines(contents, false);
+        assert_eq!(lines, vec![5, 11]);
+        assert_eq!(
+            check(&file.vectors[1], Some(lines[1])),
+            Err(String::from(
+                "line 11: the ciphertext holds '1', which is not a symbol of the table"
+            ))
+        );
+        // Digits are symbols of the 6x6 table, and a value spelling the key
+        // is not the key
+        let json: &str = r#"{"vectors": [
+  {"name": "ciphertext", "key": "KEY", "plaintext": "k1",
+   "ciphertext": "x", "variant": {"grid": "6x6"}}
+]}"#;
+        let file: VectorFile = parse(json, true).unwrap();
+        assert_eq!(ciphertext_lines(json, true), vec![3]);
+        assert!(
+            check(&file.vectors[0], Some(3))
+                .unwrap_err()
+                .starts_with("expected ")
+        );
+    }
+
+    #[test]
+    fn test_generate_vectors() {
+        let json: &str = r#"{"vectors": [{"key": "jovial", "plaintext": "vivid", "variant": {"merge": "V=U"}}]}"#;
+        let file: VectorFile = generate(parse(json, true).unwrap()).unwrap();
+        assert_eq!(file.vectors[0].ciphertext.as_deref(), Some("IAIACY"));
+        assert_eq!(
+            parse(&toml::to_string(&file).unwrap(), false).unwrap(),
+            file
+        );
+    }
+}
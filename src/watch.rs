@@ -0,0 +1,197 @@
+//! Watch mode: processes files as they appear or change in a directory.
+//!
+//! Every new or modified file in the watched directory is read as text,
+//! encrypted (or decrypted with `--decrypt`) and written under the same name
+//! to the output directory. Failures on single files are reported on stderr
+//! and the watcher keeps running. The output directory may not be the watched
+//! directory or lie inside it, or the watcher would process its own output.
+
+use crate::error::Error;
+use crate::{CipherMode, CipherSettings};
+use clap::ArgMatches;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// Runs the `watch` subcommand until the watcher stops.
+///
+/// # Arguments
+///
+/// * `matches` - The parsed arguments of the subcommand.
+///
+/// # Returns
+///
+/// * An error if the settings are invalid or the directories can't be watched.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let mode: CipherMode = crate::cli::mode_from_matches(matches);
+    let (variant, style, _) = crate::cli::settings_from_matches(matches)?;
+    let key: String = crate::cli::key_from_matches(matches, mode, &variant)?;
+    let settings: CipherSettings = CipherSettings::new(&key, variant, mode, style);
+
+    let in_dir: PathBuf = PathBuf::from(matches.get_one::<String>("in").unwrap());
+    let out_dir: PathBuf = PathBuf::from(matches.get_one::<String>("out").unwrap());
+    if !in_dir.is_dir() {
+        return Err(Error::Io(format!(
+            "'{}' is not a directory",
+            in_dir.display()
+        )));
+    }
+    std::fs::create_dir_all(&out_dir).map_err(|e| {
+        Error::Io(format!(
+            "cannot create output directory '{}': {}",
+            out_dir.display(),
+            e
+        ))
+    })?;
+    check_dirs(&in_dir, &out_dir)?;
+
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| Error::Io(format!("cannot start watcher: {}", e)))?;
+    watcher
+        .watch(&in_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| Error::Io(format!("cannot watch '{}': {}", in_dir.display(), e)))?;
+    eprintln!(
+        "Watching {} (writing to {}), press Ctrl-C to stop",
+        in_dir.display(),
+        out_dir.display()
+    );
+
+    for event in rx {
+        let event: Event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("Error: watcher: {}", e);
+                continue;
+            }
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            continue;
+        }
+        for path in event.paths {
+            if !path.is_file() || path.starts_with(&out_dir) {
+                continue;
+            }
+            // A freshly created file is often still empty; its content arrives
+            // with a later modify event
+            if std::fs::metadata(&path).is_ok_and(|m| m.len() == 0) {
+                continue;
+            }
+            match process_file(&path, &out_dir, &settings) {
+                Ok(output) => eprintln!("{} -> {}", path.display(), output.display()),
+                Err(e) => eprintln!("Error: {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the output directory is neither the watched directory nor
+/// inside it.
+///
+/// # Arguments
+///
+/// * `in_dir` - The watched directory.
+/// * `out_dir` - The output directory, which must exist.
+///
+/// # Returns
+///
+/// * A usage error if the output would land in the watched directory.
+pub fn check_dirs(in_dir: &Path, out_dir: &Path) -> Result<(), Error> {
+    let canonical = |dir: &Path| -> Result<PathBuf, Error> {
+        dir.canonicalize()
+            .map_err(|e| Error::Io(format!("cannot resolve '{}': {}", dir.display(), e)))
+    };
+    let (in_dir, out_dir): (PathBuf, PathBuf) = (canonical(in_dir)?, canonical(out_dir)?);
+    if out_dir.starts_with(&in_dir) {
+        return Err(Error::Usage(format!(
+            "--out '{}' must not be inside --in '{}'",
+            out_dir.display(),
+            in_dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Processes one file and writes the result to the output directory.
+///
+/// # Arguments
+///
+/// * `path` - The file to read.
+/// * `out_dir` - The directory the result is written to, under the same file name.
+/// * `settings` - The table and settings used to process the text.
+///
+/// # Returns
+///
+/// * The path of the written file.
+pub fn process_file(
+    path: &Path,
+    out_dir: &Path,
+    settings: &CipherSettings,
+) -> Result<PathBuf, Error> {
+    let text: String = std::fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("cannot read '{}': {}", path.display(), e)))?;
+    let result: String = settings.process(&text)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::Io(format!("'{}' has no file name", path.display())))?;
+    let output: PathBuf = out_dir.join(name);
+    std::fs::write(&output, result + "\n")
+        .map_err(|e| Error::Io(format!("cannot write '{}': {}", output.display(), e)))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutputStyle, Variant};
+
+    #[test]
+    fn test_process_file() {
+        let dir: PathBuf = std::env::temp_dir().join("playfair_test_watch");
+        let in_dir: PathBuf = dir.join("in");
+        let out_dir: PathBuf = dir.join("out");
+        std::fs::create_dir_all(&in_dir).unwrap();
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let settings: CipherSettings = CipherSettings::new(
+            "KEYWORD",
+            Variant::default(),
+            CipherMode::Encrypt,
+            OutputStyle::default(),
+        );
+
+        let input: PathBuf = in_dir.join("message.txt");
+        std::fs::write(&input, "Hello\n").unwrap();
+        let output: PathBuf = process_file(&input, &out_dir, &settings).unwrap();
+        assert_eq!(output, out_dir.join("message.txt"));
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "GYIZSC\n");
+
+        let empty: PathBuf = in_dir.join("empty.txt");
+        std::fs::write(&empty, "1234\n").unwrap();
+        assert!(matches!(
+            process_file(&empty, &out_dir, &settings),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_dirs() {
+        let dir: PathBuf = std::env::temp_dir().join("playfair_test_watch_dirs");
+        let in_dir: PathBuf = dir.join("in");
+        let nested: PathBuf = in_dir.join("out");
+        let out_dir: PathBuf = dir.join("out");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        assert!(check_dirs(&in_dir, &out_dir).is_ok());
+        assert!(matches!(check_dirs(&in_dir, &in_dir), Err(Error::Usage(_))));
+        assert!(matches!(check_dirs(&in_dir, &nested), Err(Error::Usage(_))));
+        // Spelled differently, the same directory is still refused
+        assert!(matches!(
+            check_dirs(&in_dir, &nested.join("..")),
+            Err(Error::Usage(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}